@@ -1,5 +1,8 @@
 pub mod provider;
 pub mod zhipu;
 
-pub use provider::{AIProvider, AIRequest, AISettings};
+pub use provider::{AIProvider, AIRequest, AISettings, TitleSummary};
 pub use zhipu::ZhipuProvider;
+
+#[cfg(test)]
+mod tests;
@@ -18,6 +18,34 @@ pub struct AIResponse {
     pub tokens_used: Option<u32>,
 }
 
+/// Title and one-sentence summary proposed for a diary entry
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct TitleSummary {
+    pub title: String,
+    pub summary: String,
+}
+
+impl TitleSummary {
+    /// Parse a model reply, tolerating code fences or prose around the JSON object
+    pub fn parse(reply: &str) -> Result<Self, AIError> {
+        let invalid = || AIError::ProviderError(format!("Unexpected title/summary reply: {reply}"));
+        let start = reply.find('{').ok_or_else(invalid)?;
+        let end = reply.rfind('}').ok_or_else(invalid)?;
+        if end < start {
+            return Err(invalid());
+        }
+
+        let parsed: Self = serde_json::from_str(&reply[start..=end]).map_err(|_| invalid())?;
+        let title = parsed.title.trim().trim_matches('"').trim().to_string();
+        let summary = parsed.summary.trim().to_string();
+        if title.is_empty() || summary.is_empty() {
+            return Err(invalid());
+        }
+
+        Ok(Self { title, summary })
+    }
+}
+
 /// Error types for AI operations
 #[derive(Debug, thiserror::Error)]
 #[allow(dead_code)]
@@ -0,0 +1,17 @@
+use super::TitleSummary;
+
+#[test]
+fn title_summary_replies_are_parsed_leniently() {
+    let fenced =
+        "```json\n{\"title\": \" \\\"Lake day\\\" \", \"summary\": \"A picnic by the lake.\"}\n```";
+    assert_eq!(
+        TitleSummary::parse(fenced).expect("fenced"),
+        TitleSummary {
+            title: "Lake day".to_string(),
+            summary: "A picnic by the lake.".to_string(),
+        }
+    );
+
+    assert!(TitleSummary::parse("Lake day: a picnic").is_err());
+    assert!(TitleSummary::parse("{\"title\": \"\", \"summary\": \"x\"}").is_err());
+}
@@ -94,6 +94,12 @@ impl ZhipuProvider {
                     text
                 )
             }
+            "title_summary" => {
+                format!(
+                    "Propose a short title (at most 8 words) and a one-sentence summary for the following diary entry, in the same language as the entry. Reply with JSON only, in the form {{\"title\": \"...\", \"summary\": \"...\"}}.\n\n{}",
+                    text
+                )
+            }
             _ => text.to_string(),
         }
     }
@@ -0,0 +1,250 @@
+//! App lock: a passphrase gate in front of the entry, AI and TTS commands that
+//! closes again after a period of inactivity.
+//!
+//! Unlike encryption at rest this only guards the running app; data on disk is
+//! untouched. The passphrase is kept as an Argon2 hash in the OS keychain, so
+//! the lock is on exactly when a hash is stored there.
+
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+use argon2::password_hash::{rand_core::OsRng, PasswordHash, SaltString};
+use argon2::{Argon2, PasswordHasher, PasswordVerifier};
+use sqlx::SqlitePool;
+
+use crate::crypto;
+use crate::db::queries::{get_setting, save_setting};
+use crate::error::AppError;
+use crate::keychain;
+use crate::models::{LockSettings, LockStatus};
+
+/// Default minutes of inactivity before the app locks itself
+pub const DEFAULT_IDLE_MINUTES: i64 = 5;
+
+/// Longest idle timeout that can be set, in minutes (a day)
+pub const MAX_IDLE_MINUTES: i64 = 24 * 60;
+
+/// How often the backend checks whether the idle timeout has passed
+pub const IDLE_CHECK_INTERVAL: Duration = Duration::from_secs(15);
+
+const IDLE_MINUTES_SETTING: &str = "app_lock_idle_minutes";
+
+struct LockState {
+    enabled: bool,
+    locked: bool,
+    last_activity: Instant,
+    /// `None` disables the idle timeout
+    idle_timeout: Option<Duration>,
+}
+
+/// Lock state shared by all commands (managed as Tauri state)
+pub struct AppLock {
+    state: Mutex<LockState>,
+}
+
+fn idle_timeout(idle_minutes: i64) -> Option<Duration> {
+    (idle_minutes > 0).then(|| Duration::from_secs((idle_minutes as u64).saturating_mul(60)))
+}
+
+impl AppLock {
+    /// An enabled lock starts out locked
+    pub fn new(enabled: bool, idle_minutes: i64) -> Self {
+        Self {
+            state: Mutex::new(LockState {
+                enabled,
+                locked: enabled,
+                last_activity: Instant::now(),
+                idle_timeout: idle_timeout(idle_minutes),
+            }),
+        }
+    }
+
+    fn state(&self) -> std::sync::MutexGuard<'_, LockState> {
+        self.state.lock().expect("app lock poisoned")
+    }
+
+    /// Gate for a command: fails with [`AppError::Locked`] while locked
+    /// (including when the idle timeout just passed), otherwise counts as activity
+    pub fn check(&self) -> Result<(), AppError> {
+        self.check_at(Instant::now())
+    }
+
+    pub fn check_at(&self, now: Instant) -> Result<(), AppError> {
+        self.lock_if_idle(now);
+
+        let mut state = self.state();
+        if state.locked {
+            return Err(AppError::Locked);
+        }
+        state.last_activity = now;
+        Ok(())
+    }
+
+    /// Lock if nothing happened for the idle timeout. Returns whether it locked.
+    pub fn lock_if_idle(&self, now: Instant) -> bool {
+        let mut state = self.state();
+        let Some(timeout) = state.idle_timeout else {
+            return false;
+        };
+        if !state.enabled || state.locked || now.duration_since(state.last_activity) < timeout {
+            return false;
+        }
+        state.locked = true;
+        true
+    }
+
+    pub fn lock(&self) {
+        let mut state = self.state();
+        state.locked = state.enabled;
+    }
+
+    fn unlock_at(&self, now: Instant) {
+        let mut state = self.state();
+        state.locked = false;
+        state.last_activity = now;
+    }
+
+    fn set_enabled(&self, enabled: bool) {
+        let mut state = self.state();
+        state.enabled = enabled;
+        state.locked = false;
+        state.last_activity = Instant::now();
+    }
+
+    fn set_idle_minutes(&self, idle_minutes: i64) {
+        self.state().idle_timeout = idle_timeout(idle_minutes);
+    }
+
+    pub fn status(&self) -> LockStatus {
+        let state = self.state();
+        LockStatus {
+            enabled: state.enabled,
+            locked: state.locked,
+            idle_minutes: state
+                .idle_timeout
+                .map_or(0, |timeout| (timeout.as_secs() / 60) as i64),
+        }
+    }
+}
+
+/// Hash a passphrase for storage (Argon2id, PHC string format)
+pub fn hash_passphrase(passphrase: &str) -> Result<String, AppError> {
+    let salt = SaltString::generate(&mut OsRng);
+    Argon2::default()
+        .hash_password(passphrase.as_bytes(), &salt)
+        .map(|hash| hash.to_string())
+        .map_err(|e| AppError::Encryption(format!("Failed to hash passphrase: {e}")))
+}
+
+/// Check a passphrase against a stored hash; a malformed hash never matches
+pub fn verify_passphrase(passphrase: &str, hash: &str) -> bool {
+    PasswordHash::new(hash).is_ok_and(|hash| {
+        Argon2::default()
+            .verify_password(passphrase.as_bytes(), &hash)
+            .is_ok()
+    })
+}
+
+/// Build the lock state at startup from the keychain and settings.
+///
+/// Fails closed: if the keychain can't say whether a passphrase is set, the
+/// app starts locked rather than unguarded.
+pub async fn load(pool: &SqlitePool) -> AppLock {
+    let idle_minutes = match get_lock_settings(pool).await {
+        Ok(settings) => settings.idle_minutes,
+        Err(e) => {
+            println!("App lock: Failed to load settings, using defaults: {}", e);
+            DEFAULT_IDLE_MINUTES
+        }
+    };
+    let enabled = keychain::get_lock_hash().map_or_else(
+        |e| {
+            println!(
+                "App lock: Failed to read the passphrase, starting locked: {}",
+                e
+            );
+            true
+        },
+        |hash| hash.is_some(),
+    );
+    AppLock::new(enabled, idle_minutes)
+}
+
+/// Unlock with the passphrase
+pub fn unlock(lock: &AppLock, passphrase: &str) -> Result<(), AppError> {
+    let Some(hash) = keychain::get_lock_hash()? else {
+        lock.set_enabled(false);
+        return Ok(());
+    };
+    if !verify_passphrase(passphrase, &hash) {
+        return Err(AppError::IncorrectPassphrase);
+    }
+    lock.unlock_at(Instant::now());
+    Ok(())
+}
+
+/// Turn the lock on or change its passphrase. Changing requires the current one.
+pub fn set_passphrase(
+    lock: &AppLock,
+    current: Option<&str>,
+    passphrase: &str,
+) -> Result<(), AppError> {
+    if let Some(hash) = keychain::get_lock_hash()? {
+        if !current.is_some_and(|current| verify_passphrase(current, &hash)) {
+            return Err(AppError::IncorrectPassphrase);
+        }
+    }
+    crypto::validate_passphrase(passphrase)?;
+
+    keychain::set_lock_hash(&hash_passphrase(passphrase)?)?;
+    lock.set_enabled(true);
+    Ok(())
+}
+
+/// Turn the lock off
+pub fn disable(lock: &AppLock, passphrase: &str) -> Result<(), AppError> {
+    if let Some(hash) = keychain::get_lock_hash()? {
+        if !verify_passphrase(passphrase, &hash) {
+            return Err(AppError::IncorrectPassphrase);
+        }
+        keychain::delete_lock_hash()?;
+    }
+    lock.set_enabled(false);
+    Ok(())
+}
+
+/// Get app lock settings
+pub async fn get_lock_settings(pool: &SqlitePool) -> Result<LockSettings, AppError> {
+    Ok(LockSettings {
+        idle_minutes: get_setting(pool, IDLE_MINUTES_SETTING)
+            .await?
+            .and_then(|value| value.parse::<i64>().ok())
+            .unwrap_or(DEFAULT_IDLE_MINUTES),
+    })
+}
+
+/// Save app lock settings and apply the new idle timeout right away
+pub async fn save_lock_settings(
+    pool: &SqlitePool,
+    lock: &AppLock,
+    settings: &LockSettings,
+) -> Result<(), AppError> {
+    if !(0..=MAX_IDLE_MINUTES).contains(&settings.idle_minutes) {
+        return Err(AppError::InvalidSettings(format!(
+            "Idle timeout must be between 0 and {MAX_IDLE_MINUTES} minutes, got {}",
+            settings.idle_minutes
+        )));
+    }
+
+    save_setting(
+        pool,
+        IDLE_MINUTES_SETTING,
+        &settings.idle_minutes.to_string(),
+    )
+    .await?;
+    lock.set_idle_minutes(settings.idle_minutes);
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests;
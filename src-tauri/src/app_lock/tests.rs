@@ -0,0 +1,78 @@
+use std::time::{Duration, Instant};
+
+use super::{hash_passphrase, verify_passphrase, AppLock};
+use crate::db::migrations;
+use crate::error::AppError;
+use crate::models::LockSettings;
+
+#[test]
+fn idle_timeout_relocks_and_activity_postpones_it() {
+    let lock = AppLock::new(true, 5);
+    assert!(matches!(lock.check(), Err(AppError::Locked)));
+
+    let start = Instant::now();
+    lock.unlock_at(start);
+    lock.check_at(start + Duration::from_secs(4 * 60))
+        .expect("active");
+    // Activity at minute 4 pushes the timeout to minute 9
+    assert!(!lock.lock_if_idle(start + Duration::from_secs(8 * 60)));
+    lock.check_at(start + Duration::from_secs(8 * 60))
+        .expect("still active");
+
+    assert!(matches!(
+        lock.check_at(start + Duration::from_secs(14 * 60)),
+        Err(AppError::Locked)
+    ));
+    assert!(lock.status().locked);
+
+    // 0 minutes disables the timeout, and a disabled lock never engages
+    let lock = AppLock::new(true, 0);
+    lock.unlock_at(start);
+    assert!(!lock.lock_if_idle(start + Duration::from_secs(24 * 60 * 60)));
+    let lock = AppLock::new(false, 5);
+    lock.lock();
+    lock.check_at(start + Duration::from_secs(60 * 60))
+        .expect("disabled");
+}
+
+#[test]
+fn passphrase_hashes_verify() {
+    let hash = hash_passphrase("correct horse").expect("hash");
+    assert!(!hash.contains("correct horse"));
+    assert!(verify_passphrase("correct horse", &hash));
+    assert!(!verify_passphrase("wrong horse", &hash));
+    assert!(!verify_passphrase("correct horse", "not a hash"));
+}
+
+#[tokio::test]
+async fn idle_minutes_setting_round_trips() {
+    let pool = sqlx::SqlitePool::connect("sqlite::memory:")
+        .await
+        .expect("connect");
+    migrations::run(&pool).await.expect("migrate");
+
+    let settings = super::get_lock_settings(&pool).await.expect("settings");
+    assert_eq!(settings.idle_minutes, super::DEFAULT_IDLE_MINUTES);
+
+    let lock = AppLock::new(true, settings.idle_minutes);
+    for idle_minutes in [-1, super::MAX_IDLE_MINUTES + 1] {
+        assert!(
+            super::save_lock_settings(&pool, &lock, &LockSettings { idle_minutes })
+                .await
+                .is_err()
+        );
+    }
+    // A huge stored value saturates instead of overflowing
+    assert!(AppLock::new(true, i64::MAX).status().idle_minutes > 0);
+    super::save_lock_settings(&pool, &lock, &LockSettings { idle_minutes: 15 })
+        .await
+        .expect("save");
+    assert_eq!(lock.status().idle_minutes, 15);
+    assert_eq!(
+        super::get_lock_settings(&pool)
+            .await
+            .expect("settings")
+            .idle_minutes,
+        15
+    );
+}
@@ -0,0 +1,271 @@
+//! Files attached to entries.
+//!
+//! Files are stored once per content under `attachments/{key[..2]}/{key}` in
+//! the app data dir, named by the [`Vault::fingerprint`] of their bytes, so
+//! attaching the same photo twice stores it once. Images also get a PNG
+//! thumbnail under `attachments/thumbs/`. With encryption on, both are sealed
+//! and the fingerprint is keyed, so file names don't reveal what is stored.
+//! Files no longer referenced by any `attachments` row are removed by
+//! [`collect_garbage`]. Callers hold [`STORE_LOCK`] around both, since a
+//! freshly stored file has no row yet.
+
+use std::collections::HashSet;
+use std::path::{Path, PathBuf};
+
+use image::ImageFormat;
+use sqlx::SqlitePool;
+
+use crate::crypto::Vault;
+use crate::db::queries;
+use crate::error::AppError;
+
+/// Directory (relative to the app data dir) holding attachment files
+pub const ATTACHMENTS_DIR: &str = "attachments";
+
+const THUMBS_DIR: &str = "thumbs";
+
+/// Largest file that can be attached
+pub const MAX_ATTACHMENT_BYTES: usize = 50 * 1024 * 1024;
+
+/// Thumbnails fit in a square of this many pixels
+const THUMBNAIL_SIZE: u32 = 320;
+
+/// Held from storing a file until its `attachments` row is written, and while
+/// collecting garbage, so a file is never removed just before it is referenced
+pub static STORE_LOCK: tokio::sync::Mutex<()> = tokio::sync::Mutex::const_new(());
+
+/// A file written to the store
+#[derive(Debug)]
+pub struct StoredFile {
+    pub sha256: String,
+    pub mime_type: String,
+    pub size_bytes: i64,
+    pub width: Option<i64>,
+    pub height: Option<i64>,
+    pub has_thumbnail: bool,
+}
+
+/// Hashes come from the database, so check them before building paths
+fn validate_hash(sha256: &str) -> Result<(), AppError> {
+    if sha256.len() != 64 || !sha256.bytes().all(|b| b.is_ascii_hexdigit()) {
+        return Err(AppError::Attachment(format!(
+            "Invalid attachment hash: {sha256}"
+        )));
+    }
+    Ok(())
+}
+
+fn file_path(app_data_dir: &Path, sha256: &str) -> PathBuf {
+    app_data_dir
+        .join(ATTACHMENTS_DIR)
+        .join(&sha256[..2])
+        .join(sha256)
+}
+
+fn thumbnail_path(app_data_dir: &Path, sha256: &str) -> PathBuf {
+    app_data_dir
+        .join(ATTACHMENTS_DIR)
+        .join(THUMBS_DIR)
+        .join(format!("{sha256}.png"))
+}
+
+/// MIME type from a file name's extension
+pub fn mime_type_for(file_name: &str) -> &'static str {
+    let extension = Path::new(file_name)
+        .extension()
+        .and_then(|e| e.to_str())
+        .unwrap_or("")
+        .to_ascii_lowercase();
+    match extension.as_str() {
+        "png" => "image/png",
+        "jpg" | "jpeg" => "image/jpeg",
+        "gif" => "image/gif",
+        "webp" => "image/webp",
+        "heic" => "image/heic",
+        "pdf" => "application/pdf",
+        "txt" | "md" => "text/plain",
+        "mp3" => "audio/mpeg",
+        "m4a" => "audio/mp4",
+        "wav" => "audio/wav",
+        "mp4" => "video/mp4",
+        "mov" => "video/quicktime",
+        _ => "application/octet-stream",
+    }
+}
+
+/// Write a file into the store (a no-op for content already there) and
+/// thumbnail it if it is an image we can decode
+pub fn store(
+    app_data_dir: &Path,
+    vault: &Vault,
+    file_name: &str,
+    bytes: &[u8],
+) -> Result<StoredFile, AppError> {
+    if bytes.is_empty() {
+        return Err(AppError::Attachment("File is empty".to_string()));
+    }
+    if bytes.len() > MAX_ATTACHMENT_BYTES {
+        return Err(AppError::Attachment(format!(
+            "File is larger than {} MB",
+            MAX_ATTACHMENT_BYTES / (1024 * 1024)
+        )));
+    }
+
+    let sha256 = vault.fingerprint(bytes)?;
+    let path = file_path(app_data_dir, &sha256);
+    if !path.exists() {
+        write_file(&path, vault.seal_bytes(bytes.to_vec())?)?;
+    }
+
+    // Prefer the decoded format over the extension, which may be missing or wrong
+    let decoded_format = image::guess_format(bytes).ok();
+    let mime_type = decoded_format
+        .map(|format| format.to_mime_type())
+        .unwrap_or_else(|| mime_type_for(file_name))
+        .to_string();
+
+    let mut stored = StoredFile {
+        sha256,
+        mime_type,
+        size_bytes: bytes.len() as i64,
+        width: None,
+        height: None,
+        has_thumbnail: false,
+    };
+
+    // Anything that fails to decode is still attached, just without a thumbnail
+    if let Some(Ok(image)) =
+        decoded_format.map(|format| image::load_from_memory_with_format(bytes, format))
+    {
+        stored.width = Some(image.width() as i64);
+        stored.height = Some(image.height() as i64);
+
+        let thumb_path = thumbnail_path(app_data_dir, &stored.sha256);
+        if !thumb_path.exists() {
+            let mut png = std::io::Cursor::new(Vec::new());
+            image
+                .thumbnail(THUMBNAIL_SIZE, THUMBNAIL_SIZE)
+                .write_to(&mut png, ImageFormat::Png)
+                .map_err(|e| AppError::Attachment(format!("Failed to create thumbnail: {e}")))?;
+            write_file(&thumb_path, vault.seal_bytes(png.into_inner())?)?;
+        }
+        stored.has_thumbnail = true;
+    }
+
+    Ok(stored)
+}
+
+fn write_file(path: &Path, bytes: Vec<u8>) -> Result<(), AppError> {
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent)?;
+    }
+    // Write then rename so a crash never leaves a truncated file under a valid hash
+    let partial = path.with_extension("partial");
+    std::fs::write(&partial, bytes)?;
+    std::fs::rename(&partial, path)?;
+    Ok(())
+}
+
+/// Path of a stored file (or its thumbnail) on disk
+pub fn locate(app_data_dir: &Path, sha256: &str, thumbnail: bool) -> Result<PathBuf, AppError> {
+    validate_hash(sha256)?;
+    Ok(if thumbnail {
+        thumbnail_path(app_data_dir, sha256)
+    } else {
+        file_path(app_data_dir, sha256)
+    })
+}
+
+/// Read a stored file (or its thumbnail), decrypted
+pub fn read(
+    app_data_dir: &Path,
+    vault: &Vault,
+    sha256: &str,
+    thumbnail: bool,
+) -> Result<Vec<u8>, AppError> {
+    let path = locate(app_data_dir, sha256, thumbnail)?;
+    vault.open_bytes(std::fs::read(path)?)
+}
+
+/// Rename stored files from their plain SHA-256 to the keyed fingerprint of
+/// `vault` and point their rows at the new name (used when encryption is
+/// turned on). Files already named by their fingerprint are left alone, so
+/// this can be repeated. Returns the number of files renamed.
+pub async fn rekey(
+    app_data_dir: &Path,
+    pool: &SqlitePool,
+    vault: &Vault,
+) -> Result<usize, AppError> {
+    let _store = STORE_LOCK.lock().await;
+
+    let mut renamed = 0;
+    for old in queries::referenced_attachment_hashes(pool).await? {
+        let path = locate(app_data_dir, &old, false)?;
+        // A missing file has nothing to rename; its attachment stays broken
+        if !path.is_file() {
+            continue;
+        }
+        let bytes = vault.open_bytes(std::fs::read(&path)?)?;
+        let new = vault.fingerprint(&bytes)?;
+        if new == old {
+            continue;
+        }
+
+        // Write the new files before the rows point at them, and remove the
+        // old ones only after, so a crash leaves garbage at worst
+        write_file(&file_path(app_data_dir, &new), vault.seal_bytes(bytes)?)?;
+        let old_thumb = thumbnail_path(app_data_dir, &old);
+        if old_thumb.is_file() {
+            let thumb = vault.open_bytes(std::fs::read(&old_thumb)?)?;
+            write_file(
+                &thumbnail_path(app_data_dir, &new),
+                vault.seal_bytes(thumb)?,
+            )?;
+        }
+        queries::rename_attachment_hash(pool, &old, &new).await?;
+        std::fs::remove_file(&path)?;
+        if old_thumb.is_file() {
+            std::fs::remove_file(&old_thumb)?;
+        }
+        renamed += 1;
+    }
+
+    Ok(renamed)
+}
+
+/// Remove stored files and thumbnails whose hash is not in `referenced`.
+/// Returns the number of files removed.
+pub fn collect_garbage(
+    app_data_dir: &Path,
+    referenced: &HashSet<String>,
+) -> Result<usize, AppError> {
+    let root = app_data_dir.join(ATTACHMENTS_DIR);
+    if !root.is_dir() {
+        return Ok(0);
+    }
+
+    let mut removed = 0;
+    for dir in std::fs::read_dir(&root)? {
+        let dir = dir?.path();
+        if !dir.is_dir() {
+            continue;
+        }
+        for file in std::fs::read_dir(&dir)? {
+            let path = file?.path();
+            let Some(stem) = path.file_stem().and_then(|s| s.to_str()) else {
+                continue;
+            };
+            // Leftovers of interrupted writes are always garbage
+            let partial = path.extension().is_some_and(|e| e == "partial");
+            if partial || !referenced.contains(stem) {
+                std::fs::remove_file(&path)?;
+                removed += 1;
+            }
+        }
+    }
+
+    Ok(removed)
+}
+
+#[cfg(test)]
+mod tests;
@@ -0,0 +1,91 @@
+use std::collections::HashSet;
+use std::sync::Arc;
+
+use sha2::{Digest, Sha256};
+
+use super::{collect_garbage, locate, read, store};
+use crate::crypto::{SecretKey, Vault, BLOB_MAGIC};
+
+fn png(width: u32, height: u32) -> Vec<u8> {
+    let mut bytes = std::io::Cursor::new(Vec::new());
+    image::DynamicImage::new_rgb8(width, height)
+        .write_to(&mut bytes, image::ImageFormat::Png)
+        .expect("encode png");
+    bytes.into_inner()
+}
+
+#[test]
+fn files_are_stored_once_thumbnailed_and_collected() {
+    let dir = std::env::temp_dir().join(format!("echo-daily-attachments-{}", uuid::Uuid::new_v4()));
+    let vault = Vault::Plain;
+
+    // The decoded format wins over a misleading extension
+    let photo = png(800, 400);
+    let stored = store(&dir, &vault, "photo.jpg", &photo).expect("store photo");
+    assert_eq!(stored.mime_type, "image/png");
+    assert_eq!((stored.width, stored.height), (Some(800), Some(400)));
+    assert!(stored.has_thumbnail);
+    let again = store(&dir, &vault, "copy.png", &photo).expect("store again");
+    assert_eq!(again.sha256, stored.sha256);
+    assert_eq!(
+        read(&dir, &vault, &stored.sha256, false).expect("read"),
+        photo
+    );
+
+    let thumbnail = read(&dir, &vault, &stored.sha256, true).expect("read thumbnail");
+    let thumbnail = image::load_from_memory(&thumbnail).expect("decode thumbnail");
+    assert_eq!((thumbnail.width(), thumbnail.height()), (320, 160));
+
+    // Files that aren't images are kept as they are, without a thumbnail
+    let notes = store(&dir, &vault, "notes.PDF", b"%PDF-1.4 not really").expect("store pdf");
+    assert_eq!(notes.mime_type, "application/pdf");
+    assert!(!notes.has_thumbnail);
+    assert!(store(&dir, &vault, "empty.txt", b"").is_err());
+
+    // Hashes come from the database and must not escape the store
+    assert!(locate(&dir, "../../etc/passwd", false).is_err());
+
+    // Only the photo is still referenced, so the PDF goes
+    let referenced = HashSet::from([stored.sha256.clone()]);
+    assert_eq!(collect_garbage(&dir, &referenced).expect("gc"), 1);
+    assert!(read(&dir, &vault, &notes.sha256, false).is_err());
+    assert!(read(&dir, &vault, &stored.sha256, true).is_ok());
+
+    // Nothing referenced: the photo and its thumbnail go too
+    assert_eq!(collect_garbage(&dir, &HashSet::new()).expect("gc"), 2);
+
+    std::fs::remove_dir_all(&dir).ok();
+}
+
+#[test]
+fn encrypted_files_are_not_named_by_their_plain_hash() {
+    let dir = std::env::temp_dir().join(format!("echo-daily-attachments-{}", uuid::Uuid::new_v4()));
+    let vault = Vault::Unlocked(Arc::new(SecretKey::generate()));
+
+    let photo = png(40, 20);
+    let stored = store(&dir, &vault, "photo.png", &photo).expect("store photo");
+    let plain_hash: String = Sha256::digest(&photo)
+        .iter()
+        .map(|b| format!("{b:02x}"))
+        .collect();
+    assert_ne!(stored.sha256, plain_hash);
+    assert_eq!(
+        stored.sha256,
+        vault.fingerprint(&photo).expect("fingerprint")
+    );
+
+    // Neither the file nor its thumbnail can be found by the plain hash
+    for thumbnail in [false, true] {
+        assert!(!locate(&dir, &plain_hash, thumbnail)
+            .expect("locate")
+            .exists());
+        let path = locate(&dir, &stored.sha256, thumbnail).expect("locate");
+        assert!(std::fs::read(path).expect("read").starts_with(BLOB_MAGIC));
+    }
+    assert_eq!(
+        read(&dir, &vault, &stored.sha256, false).expect("read"),
+        photo
+    );
+
+    std::fs::remove_dir_all(&dir).ok();
+}
@@ -0,0 +1,176 @@
+//! Helpers for the ProseMirror JSON stored in `entries.content_json`.
+
+use std::collections::{BTreeSet, HashMap};
+
+use serde_json::Value;
+
+use crate::search::segment::is_cjk;
+
+/// Block nodes whose text is separated from the next block by a line break
+const BLOCK_NODES: &[&str] = &[
+    "paragraph",
+    "heading",
+    "blockquote",
+    "codeBlock",
+    "listItem",
+    "taskItem",
+    "bulletList",
+    "orderedList",
+    "taskList",
+    "horizontalRule",
+    "table",
+    "tableRow",
+];
+
+/// Extract the readable text of a ProseMirror document.
+///
+/// Blocks are separated by newlines, inline text is concatenated as written, and
+/// node types, marks and attributes are dropped. Content that is not valid JSON is
+/// returned trimmed as-is so legacy plain-text entries stay searchable.
+pub fn extract_plain_text(content_json: &str) -> String {
+    let Ok(doc) = serde_json::from_str::<Value>(content_json) else {
+        return content_json.trim().to_string();
+    };
+
+    let mut out = String::new();
+    collect_text(&doc, &mut out);
+
+    // Collapse the blank lines produced by nested blocks
+    out.lines()
+        .map(str::trim_end)
+        .filter(|line| !line.trim().is_empty())
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+fn collect_text(node: &Value, out: &mut String) {
+    match node.get("type").and_then(Value::as_str) {
+        Some("text") => {
+            if let Some(text) = node.get("text").and_then(Value::as_str) {
+                out.push_str(text);
+            }
+        }
+        Some("hardBreak") => out.push('\n'),
+        Some("tableCell") | Some("tableHeader") => {
+            collect_children(node, out);
+            out.push(' ');
+        }
+        Some(kind) if BLOCK_NODES.contains(&kind) => {
+            collect_children(node, out);
+            out.push('\n');
+        }
+        _ => collect_children(node, out),
+    }
+}
+
+fn collect_children(node: &Value, out: &mut String) {
+    if let Some(children) = node.get("content").and_then(Value::as_array) {
+        for child in children {
+            collect_text(child, out);
+        }
+    }
+}
+
+/// Words and characters in a piece of plain text
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct TextCounts {
+    pub words: i64,
+    /// Characters other than whitespace
+    pub characters: i64,
+}
+
+/// Count the words and characters of extracted plain text.
+///
+/// Every CJK character counts as a word, since those scripts don't put spaces
+/// between words. Elsewhere a word is a whitespace-separated run containing a
+/// letter or digit, so punctuation alone is not a word and "don't" is one.
+pub fn count_text(text: &str) -> TextCounts {
+    let mut counts = TextCounts::default();
+    let mut in_word = false;
+    for c in text.chars() {
+        if c.is_whitespace() {
+            in_word = false;
+            continue;
+        }
+        counts.characters += 1;
+        if is_cjk(c) {
+            counts.words += 1;
+            in_word = false;
+        } else if c.is_alphanumeric() && !in_word {
+            counts.words += 1;
+            in_word = true;
+        }
+    }
+    counts
+}
+
+/// Fill the `{{name}}` placeholders in the text of a ProseMirror document.
+///
+/// Only text nodes are touched, so node types and attributes can't be changed
+/// by a value. Names are matched without surrounding spaces (`{{ date }}` works
+/// too); unknown placeholders are left as written.
+pub fn expand_placeholders(
+    content_json: &str,
+    values: &HashMap<&str, String>,
+) -> Result<String, serde_json::Error> {
+    let mut doc = serde_json::from_str::<Value>(content_json)?;
+    expand_node(&mut doc, values);
+    serde_json::to_string(&doc)
+}
+
+fn expand_node(node: &mut Value, values: &HashMap<&str, String>) {
+    if node.get("type").and_then(Value::as_str) == Some("text") {
+        if let Some(Value::String(text)) = node.get_mut("text") {
+            *text = expand_text(text, values);
+        }
+        return;
+    }
+    if let Some(children) = node.get_mut("content").and_then(Value::as_array_mut) {
+        for child in children {
+            expand_node(child, values);
+        }
+    }
+}
+
+fn expand_text(text: &str, values: &HashMap<&str, String>) -> String {
+    let mut out = String::with_capacity(text.len());
+    let rest = scan_placeholders(text, |before, name, placeholder| {
+        out.push_str(before);
+        match values.get(name) {
+            Some(value) => out.push_str(value),
+            None => out.push_str(placeholder),
+        }
+    });
+    out.push_str(rest);
+    out
+}
+
+/// Names of the `{{placeholders}}` in a ProseMirror document's text
+pub fn placeholder_names(content_json: &str) -> BTreeSet<String> {
+    let mut names = BTreeSet::new();
+    scan_placeholders(&extract_plain_text(content_json), |_, name, _| {
+        names.insert(name.to_string());
+    });
+    names
+}
+
+/// Call `found(text before, trimmed name, whole placeholder)` for each
+/// placeholder in `text` and return the text after the last one
+fn scan_placeholders<'a>(
+    mut text: &'a str,
+    mut found: impl FnMut(&'a str, &'a str, &'a str),
+) -> &'a str {
+    while let Some(start) = text.find("{{") {
+        let Some(len) = text[start + 2..].find("}}") else {
+            break;
+        };
+        let end = start + 2 + len + 2;
+        found(
+            &text[..start],
+            text[start + 2..end - 2].trim(),
+            &text[start..end],
+        );
+        text = &text[end..];
+    }
+    text
+}
@@ -0,0 +1,444 @@
+//! Opt-in encryption at rest for diary content.
+//!
+//! A random data key encrypts entry content, revisions, AI operation text,
+//! audio record text and audio files. The data key is wrapped (AES-GCM) with a
+//! key derived from the user's passphrase, so changing the passphrase only
+//! re-wraps it. The wrapped key is cached in the OS keychain and unlocking
+//! reads it from there; `app_settings` keeps a copy next to the salt and key
+//! check, used to recover when the keychain has no usable copy (another
+//! machine, a restored backup, a reset keychain). Encrypted text is stored as
+//! `enc:v1:<base64(nonce || ciphertext)>` and encrypted files start with
+//! [`BLOB_MAGIC`]; anything without the marker is read back as plaintext.
+//!
+//! While encryption is on, the full-text index lives in an in-memory database
+//! attached to every pooled connection as `secure` and is rebuilt on unlock,
+//! so no plaintext is written to disk.
+
+pub mod cipher;
+
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, LazyLock, Mutex};
+
+use base64::{engine::general_purpose::STANDARD, Engine as _};
+use sha2::{Digest, Sha256};
+use sqlx::SqlitePool;
+
+use crate::attachments;
+use crate::db::{backup, queries};
+use crate::error::AppError;
+use crate::keychain;
+pub use cipher::SecretKey;
+
+const TEXT_PREFIX: &str = "enc:v1:";
+pub const BLOB_MAGIC: &[u8] = b"ECHOENC1";
+
+/// Full-text index used while encryption is on (see [`crate::db::connect`])
+pub const SECURE_FTS_TABLE: &str = "secure.entries_fts";
+
+const SALT_SETTING: &str = "encryption_salt";
+const WRAPPED_KEY_SETTING: &str = "encryption_wrapped_key";
+const KEY_CHECK_SETTING: &str = "encryption_key_check";
+const KEY_CHECK: &[u8] = b"echo-daily";
+/// Set while the file, files and backups written before encryption still
+/// hold plaintext
+const FILES_PENDING_SETTING: &str = "encryption_files_pending";
+
+pub const MIN_PASSPHRASE_LEN: usize = 8;
+
+/// Encryption state of one database
+#[derive(Clone)]
+pub enum Vault {
+    /// Encryption is off
+    Plain,
+    /// Encryption is on and the data key is not loaded
+    Locked,
+    Unlocked(Arc<SecretKey>),
+}
+
+/// Vault per database file, so independent pools (e.g. in tests) never share keys
+static VAULTS: LazyLock<Mutex<HashMap<PathBuf, Vault>>> = LazyLock::new(Default::default);
+
+fn pool_id(pool: &SqlitePool) -> PathBuf {
+    pool.connect_options().get_filename().to_path_buf()
+}
+
+fn locked() -> AppError {
+    AppError::Encryption("Journal is locked".to_string())
+}
+
+impl Vault {
+    /// Current vault of the database behind `pool`
+    pub fn of(pool: &SqlitePool) -> Self {
+        VAULTS
+            .lock()
+            .expect("vault registry poisoned")
+            .get(&pool_id(pool))
+            .cloned()
+            .unwrap_or(Vault::Plain)
+    }
+
+    fn register(self, pool: &SqlitePool) {
+        VAULTS
+            .lock()
+            .expect("vault registry poisoned")
+            .insert(pool_id(pool), self);
+    }
+
+    pub fn is_locked(&self) -> bool {
+        matches!(self, Vault::Locked)
+    }
+
+    pub fn ensure_unlocked(&self) -> Result<(), AppError> {
+        if self.is_locked() {
+            return Err(locked());
+        }
+        Ok(())
+    }
+
+    /// Encrypt text for storage (unchanged when encryption is off)
+    pub fn seal(&self, text: &str) -> Result<String, AppError> {
+        match self {
+            Vault::Plain => Ok(text.to_string()),
+            Vault::Locked => Err(locked()),
+            Vault::Unlocked(key) => Ok(format!(
+                "{TEXT_PREFIX}{}",
+                STANDARD.encode(key.encrypt(text.as_bytes()))
+            )),
+        }
+    }
+
+    /// Decrypt stored text; text that was never encrypted is returned as-is
+    pub fn open(&self, text: String) -> Result<String, AppError> {
+        let Some(encoded) = text.strip_prefix(TEXT_PREFIX) else {
+            return Ok(text);
+        };
+        let Vault::Unlocked(key) = self else {
+            return Err(locked());
+        };
+
+        let data = STANDARD
+            .decode(encoded)
+            .map_err(|_| AppError::Encryption("Encrypted text is corrupted".to_string()))?;
+        String::from_utf8(key.decrypt(&data)?)
+            .map_err(|_| AppError::Encryption("Encrypted text is corrupted".to_string()))
+    }
+
+    /// Encrypt file contents (unchanged when encryption is off)
+    pub fn seal_bytes(&self, data: Vec<u8>) -> Result<Vec<u8>, AppError> {
+        match self {
+            Vault::Plain => Ok(data),
+            Vault::Locked => Err(locked()),
+            Vault::Unlocked(key) => Ok([BLOB_MAGIC, &key.encrypt(&data)].concat()),
+        }
+    }
+
+    /// Decrypt file contents; files that were never encrypted are returned as-is
+    pub fn open_bytes(&self, data: Vec<u8>) -> Result<Vec<u8>, AppError> {
+        let Some(encrypted) = data.strip_prefix(BLOB_MAGIC) else {
+            return Ok(data);
+        };
+        let Vault::Unlocked(key) = self else {
+            return Err(locked());
+        };
+        key.decrypt(encrypted)
+    }
+
+    /// Hex digest identifying plaintext, for lookups by content. Keyed with
+    /// the data key while encryption is on, so a stored digest can't be used to
+    /// confirm a guessed text.
+    pub fn fingerprint(&self, data: &[u8]) -> Result<String, AppError> {
+        let digest: [u8; 32] = match self {
+            Vault::Plain => Sha256::digest(data).into(),
+            Vault::Locked => return Err(locked()),
+            Vault::Unlocked(key) => key.mac(data),
+        };
+        Ok(digest.iter().map(|b| format!("{b:02x}")).collect())
+    }
+
+    /// Full-text table searches and index updates go to
+    pub fn fts_table(&self) -> &'static str {
+        match self {
+            Vault::Plain => "entries_fts",
+            Vault::Locked | Vault::Unlocked(_) => SECURE_FTS_TABLE,
+        }
+    }
+}
+
+/// Whether the database has encryption turned on
+pub async fn is_enabled(pool: &SqlitePool) -> Result<bool, AppError> {
+    Ok(queries::get_setting(pool, WRAPPED_KEY_SETTING)
+        .await?
+        .is_some())
+}
+
+/// Register the database as locked if encryption is on. Runs before migrations,
+/// so a database without `app_settings` yet is simply unencrypted.
+pub async fn init(pool: &SqlitePool) -> Result<(), AppError> {
+    let has_settings: bool = sqlx::query_scalar(
+        "SELECT COUNT(*) > 0 FROM sqlite_master WHERE type = 'table' AND name = 'app_settings'",
+    )
+    .fetch_one(pool)
+    .await?;
+
+    let vault = if has_settings && is_enabled(pool).await? {
+        Vault::Locked
+    } else {
+        Vault::Plain
+    };
+    vault.register(pool);
+
+    Ok(())
+}
+
+pub fn validate_passphrase(passphrase: &str) -> Result<(), AppError> {
+    if passphrase.chars().count() < MIN_PASSPHRASE_LEN {
+        return Err(AppError::InvalidSettings(format!(
+            "Passphrase must be at least {MIN_PASSPHRASE_LEN} characters"
+        )));
+    }
+    Ok(())
+}
+
+fn decode_setting(value: Option<String>) -> Result<Vec<u8>, AppError> {
+    value
+        .and_then(|value| STANDARD.decode(value).ok())
+        .ok_or_else(|| {
+            AppError::Encryption("Encryption settings are missing or corrupted".to_string())
+        })
+}
+
+/// Turn encryption on: encrypt every existing row in one transaction and move
+/// the search index into memory. TTS cache keys are dropped since they were
+/// not keyed; vacuuming and files are left to [`seal_pending_files`].
+pub async fn enable(pool: &SqlitePool, passphrase: &str) -> Result<(), AppError> {
+    if is_enabled(pool).await? {
+        return Err(AppError::Encryption(
+            "Encryption is already enabled".to_string(),
+        ));
+    }
+    validate_passphrase(passphrase)?;
+
+    let key = SecretKey::generate();
+    let salt = cipher::generate_salt();
+    let wrapped = SecretKey::derive(passphrase, &salt)?.encrypt(key.as_bytes());
+    let vault = Vault::Unlocked(Arc::new(key.clone()));
+
+    let mut tx = pool.begin().await?;
+    queries::seal_all_content(&mut tx, &vault).await?;
+    queries::forget_tts_cache_keys(&mut tx).await?;
+    queries::save_setting(&mut *tx, SALT_SETTING, &STANDARD.encode(salt)).await?;
+    queries::save_setting(&mut *tx, WRAPPED_KEY_SETTING, &STANDARD.encode(&wrapped)).await?;
+    queries::save_setting(
+        &mut *tx,
+        KEY_CHECK_SETTING,
+        &STANDARD.encode(key.encrypt(KEY_CHECK)),
+    )
+    .await?;
+    queries::save_setting(&mut *tx, FILES_PENDING_SETTING, "1").await?;
+    tx.commit().await?;
+    cache_wrapped_key(&wrapped);
+
+    // The rows are encrypted now, so nothing below may fail the call; the
+    // index is rebuilt again on unlock
+    vault.clone().register(pool);
+    let rebuilt = match pool.acquire().await {
+        Ok(mut conn) => queries::rebuild_search_index(&mut conn, &vault).await,
+        Err(e) => Err(e.into()),
+    };
+    if let Err(e) = rebuilt {
+        println!("Encryption: Failed to rebuild the search index: {}", e);
+    }
+
+    Ok(())
+}
+
+/// Cache the wrapped data key in the keychain. Failing is not fatal, since
+/// unlocking recovers from the database copy.
+fn cache_wrapped_key(wrapped: &[u8]) {
+    if let Err(e) = keychain::set_wrapped_key(&STANDARD.encode(wrapped)) {
+        println!("Encryption: Failed to cache the wrapped key: {}", e);
+        // Don't leave an outdated copy behind
+        let _ = keychain::delete_wrapped_key();
+    }
+}
+
+/// Unlock with the passphrase, returning the data key
+pub async fn unlock(pool: &SqlitePool, passphrase: &str) -> Result<SecretKey, AppError> {
+    let salt = decode_setting(queries::get_setting(pool, SALT_SETTING).await?)?;
+    let check = decode_setting(queries::get_setting(pool, KEY_CHECK_SETTING).await?)?;
+    let passphrase_key = SecretKey::derive(passphrase, &salt)?;
+    let open_wrapped = |wrapped: &[u8]| {
+        passphrase_key
+            .decrypt(wrapped)
+            .ok()
+            .and_then(|bytes| SecretKey::from_bytes(&bytes))
+            .filter(|key| key.decrypt(&check).ok().as_deref() == Some(KEY_CHECK))
+    };
+
+    let cached = keychain::get_wrapped_key()
+        .ok()
+        .flatten()
+        .and_then(|value| STANDARD.decode(value).ok());
+    let key = match cached.as_deref().and_then(open_wrapped) {
+        Some(key) => key,
+        None => {
+            // No usable copy in the keychain: recover from the database
+            let wrapped = decode_setting(queries::get_setting(pool, WRAPPED_KEY_SETTING).await?)?;
+            let key = open_wrapped(&wrapped).ok_or(AppError::IncorrectPassphrase)?;
+            cache_wrapped_key(&wrapped);
+            key
+        }
+    };
+
+    unlock_with_key(pool, key.clone()).await?;
+    Ok(key)
+}
+
+/// Unlock with the data key, checked against the stored key check
+async fn unlock_with_key(pool: &SqlitePool, key: SecretKey) -> Result<(), AppError> {
+    let check = decode_setting(queries::get_setting(pool, KEY_CHECK_SETTING).await?)?;
+    if key.decrypt(&check).ok().as_deref() != Some(KEY_CHECK) {
+        return Err(AppError::Encryption("Incorrect key".to_string()));
+    }
+
+    let vault = Vault::Unlocked(Arc::new(key));
+    vault.clone().register(pool);
+    let mut conn = pool.acquire().await?;
+    queries::rebuild_search_index(&mut conn, &vault).await?;
+
+    Ok(())
+}
+
+/// Forget the data key and drop the in-memory search index
+pub async fn lock(pool: &SqlitePool) -> Result<(), AppError> {
+    if !is_enabled(pool).await? {
+        return Ok(());
+    }
+
+    Vault::Locked.register(pool);
+    sqlx::query(&format!("DROP TABLE IF EXISTS {SECURE_FTS_TABLE}"))
+        .execute(pool)
+        .await?;
+
+    Ok(())
+}
+
+/// Re-wrap the data key under a new passphrase; content is not re-encrypted
+pub async fn change_passphrase(
+    pool: &SqlitePool,
+    current: &str,
+    new: &str,
+) -> Result<(), AppError> {
+    validate_passphrase(new)?;
+    let key = unlock(pool, current).await?;
+
+    let salt = cipher::generate_salt();
+    let wrapped = SecretKey::derive(new, &salt)?.encrypt(key.as_bytes());
+
+    let mut tx = pool.begin().await?;
+    queries::save_setting(&mut *tx, SALT_SETTING, &STANDARD.encode(salt)).await?;
+    queries::save_setting(&mut *tx, WRAPPED_KEY_SETTING, &STANDARD.encode(&wrapped)).await?;
+    tx.commit().await?;
+    cache_wrapped_key(&wrapped);
+
+    Ok(())
+}
+
+/// Finish turning encryption on: vacuum the plaintext out of the database
+/// file, rename the attachments under `app_data_dir` to keyed fingerprints,
+/// seal the files under `dirs`, remove
+/// `discard_dirs` (files named after unkeyed hashes of their text) and replace
+/// the plaintext backups in `backups_dir` with one of the encrypted database.
+/// Every step can be repeated, so this runs again on unlock until it has
+/// completed once. Returns whether anything was pending.
+pub async fn seal_pending_files(
+    pool: &SqlitePool,
+    app_data_dir: &Path,
+    dirs: &[PathBuf],
+    discard_dirs: &[PathBuf],
+    backups_dir: &Path,
+) -> Result<bool, AppError> {
+    if queries::get_setting(pool, FILES_PENDING_SETTING)
+        .await?
+        .is_none()
+    {
+        return Ok(false);
+    }
+    let vault = Vault::of(pool);
+    vault.ensure_unlocked()?;
+
+    // Free pages still hold the plaintext until the file is rewritten
+    sqlx::query("VACUUM").execute(pool).await?;
+
+    let renamed = attachments::rekey(app_data_dir, pool, &vault).await?;
+    println!("Encryption: Renamed {} attachment files", renamed);
+
+    let mut sealed = 0;
+    for dir in dirs {
+        sealed += seal_files(dir, &vault)?;
+    }
+    println!(
+        "Encryption: Encrypted {} audio and attachment files",
+        sealed
+    );
+    for dir in discard_dirs {
+        if dir.is_dir() {
+            std::fs::remove_dir_all(dir)?;
+        }
+    }
+
+    let removed = backup::delete_all_backups(backups_dir)?;
+    let fresh = backup::create_backup(pool, backups_dir).await?;
+    println!(
+        "Encryption: Replaced {} unencrypted backup files with {}",
+        removed, fresh.file_name
+    );
+
+    sqlx::query("DELETE FROM app_settings WHERE key = ?")
+        .bind(FILES_PENDING_SETTING)
+        .execute(pool)
+        .await?;
+
+    Ok(true)
+}
+
+/// Encrypt every not yet encrypted file under `dir` in place.
+/// Returns the number of files encrypted.
+pub fn seal_files(dir: &Path, vault: &Vault) -> Result<usize, AppError> {
+    if !dir.is_dir() {
+        return Ok(0);
+    }
+
+    // Clear leftovers of an interrupted run first; their originals are
+    // still in place and get sealed below
+    let mut paths = Vec::new();
+    for item in std::fs::read_dir(dir)? {
+        let path = item?.path();
+        if path.is_file() && path.extension().is_some_and(|e| e == "partial") {
+            std::fs::remove_file(&path)?;
+        } else {
+            paths.push(path);
+        }
+    }
+
+    let mut sealed = 0;
+    for path in paths {
+        if path.is_dir() {
+            sealed += seal_files(&path, vault)?;
+            continue;
+        }
+
+        let data = std::fs::read(&path)?;
+        if data.starts_with(BLOB_MAGIC) {
+            continue;
+        }
+        // Write then rename so a crash never leaves a half-sealed file
+        let partial = path.with_extension("partial");
+        std::fs::write(&partial, vault.seal_bytes(data)?)?;
+        std::fs::rename(&partial, &path)?;
+        sealed += 1;
+    }
+
+    Ok(sealed)
+}
@@ -0,0 +1,348 @@
+//! Rotating database snapshots under `backups/` in the app data dir.
+//!
+//! Snapshots are written with `VACUUM INTO`, which produces a consistent copy
+//! while the pool is open. Restoring is staged: the chosen snapshot is copied
+//! to `restore-pending.db` and swapped in by [`apply_pending_restore`] the next
+//! time the database is opened, after the current file has been set aside.
+//!
+//! Separately, `backups/pre-migration/` holds one snapshot per schema version,
+//! taken before pending migrations run so a failed upgrade can be rolled back.
+
+use std::collections::HashSet;
+use std::path::{Path, PathBuf};
+
+use chrono::{Datelike, Local, TimeZone, Utc};
+use sqlx::SqlitePool;
+
+use super::queries::{get_setting, save_setting};
+use crate::error::AppError;
+use crate::models::{BackupInfo, BackupSettings};
+
+pub const BACKUPS_DIR: &str = "backups";
+
+/// Default number of most recent days / ISO weeks that keep a snapshot
+pub const DEFAULT_KEEP_DAILY: i64 = 7;
+pub const DEFAULT_KEEP_WEEKLY: i64 = 4;
+/// Default number of most recent pre-restore copies kept
+pub const DEFAULT_KEEP_PRE_RESTORE: i64 = 3;
+
+/// Minimum time between two scheduled snapshots
+pub const SNAPSHOT_INTERVAL_MS: i64 = 24 * 60 * 60 * 1000;
+
+const KEEP_DAILY_SETTING: &str = "backup_keep_daily";
+const KEEP_WEEKLY_SETTING: &str = "backup_keep_weekly";
+const KEEP_PRE_RESTORE_SETTING: &str = "backup_keep_pre_restore";
+
+const FILE_PREFIX: &str = "echo-daily-";
+const PRE_RESTORE_SUFFIX: &str = "-pre-restore";
+const TIMESTAMP_FORMAT: &str = "%Y%m%d-%H%M%S-%3f";
+const PENDING_RESTORE_FILE: &str = "restore-pending.db";
+const PRE_MIGRATION_DIR: &str = "pre-migration";
+
+pub const KIND_SNAPSHOT: &str = "snapshot";
+pub const KIND_PRE_RESTORE: &str = "pre_restore";
+
+pub fn backups_dir(app_data_dir: &Path) -> PathBuf {
+    app_data_dir.join(BACKUPS_DIR)
+}
+
+fn file_name_for(created_at: i64, pre_restore: bool) -> String {
+    let timestamp = Utc
+        .timestamp_millis_opt(created_at)
+        .single()
+        .unwrap_or_default()
+        .format(TIMESTAMP_FORMAT);
+    let suffix = if pre_restore { PRE_RESTORE_SUFFIX } else { "" };
+    format!("{FILE_PREFIX}{timestamp}{suffix}.db")
+}
+
+/// Parse a backup file name back into (created_at, kind)
+fn parse_file_name(file_name: &str) -> Option<(i64, &'static str)> {
+    let stem = file_name.strip_prefix(FILE_PREFIX)?.strip_suffix(".db")?;
+    let (timestamp, kind) = match stem.strip_suffix(PRE_RESTORE_SUFFIX) {
+        Some(timestamp) => (timestamp, KIND_PRE_RESTORE),
+        None => (stem, KIND_SNAPSHOT),
+    };
+    let created_at = chrono::NaiveDateTime::parse_from_str(timestamp, TIMESTAMP_FORMAT)
+        .ok()?
+        .and_utc()
+        .timestamp_millis();
+    Some((created_at, kind))
+}
+
+/// Write a consistent snapshot of the database into `dir`
+pub async fn create_backup(pool: &SqlitePool, dir: &Path) -> Result<BackupInfo, AppError> {
+    std::fs::create_dir_all(dir)?;
+
+    let created_at = chrono::Utc::now().timestamp_millis();
+    let file_name = file_name_for(created_at, false);
+    let path = dir.join(&file_name);
+
+    sqlx::query("VACUUM INTO ?")
+        .bind(path.to_string_lossy().into_owned())
+        .execute(pool)
+        .await?;
+
+    Ok(BackupInfo {
+        file_name,
+        created_at,
+        size_bytes: std::fs::metadata(&path)?.len() as i64,
+        kind: KIND_SNAPSHOT.to_string(),
+    })
+}
+
+/// List backups in `dir`, newest first
+pub fn list_backups(dir: &Path) -> Result<Vec<BackupInfo>, AppError> {
+    if !dir.is_dir() {
+        return Ok(Vec::new());
+    }
+
+    let mut backups = Vec::new();
+    for item in std::fs::read_dir(dir)? {
+        let item = item?;
+        let file_name = item.file_name().to_string_lossy().into_owned();
+        let Some((created_at, kind)) = parse_file_name(&file_name) else {
+            continue;
+        };
+        backups.push(BackupInfo {
+            file_name,
+            created_at,
+            size_bytes: item.metadata()?.len() as i64,
+            kind: kind.to_string(),
+        });
+    }
+    backups.sort_by_key(|backup| std::cmp::Reverse(backup.created_at));
+
+    Ok(backups)
+}
+
+/// Snapshots to keep: the newest one of each of the last `keep_daily` days and
+/// of each of the last `keep_weekly` ISO weeks (in local time), plus the
+/// newest `keep_pre_restore` pre-restore copies. Expects `backups` newest first.
+pub fn backups_to_keep(backups: &[BackupInfo], settings: &BackupSettings) -> HashSet<String> {
+    let mut keep = HashSet::new();
+    let mut days = HashSet::new();
+    let mut weeks = HashSet::new();
+    let mut pre_restore = 0;
+
+    for backup in backups {
+        if backup.kind != KIND_SNAPSHOT {
+            if pre_restore < settings.keep_pre_restore {
+                keep.insert(backup.file_name.clone());
+                pre_restore += 1;
+            }
+            continue;
+        }
+        let Some(local) = Local.timestamp_millis_opt(backup.created_at).single() else {
+            continue;
+        };
+
+        let day = local.date_naive();
+        if (days.len() as i64) < settings.keep_daily && days.insert(day) {
+            keep.insert(backup.file_name.clone());
+        }
+
+        let week = (local.iso_week().year(), local.iso_week().week());
+        if (weeks.len() as i64) < settings.keep_weekly && weeks.insert(week) {
+            keep.insert(backup.file_name.clone());
+        }
+    }
+
+    keep
+}
+
+/// Delete backups outside the retention policy, returning the removed file names
+pub fn prune_backups(dir: &Path, settings: &BackupSettings) -> Result<Vec<String>, AppError> {
+    let backups = list_backups(dir)?;
+    let keep = backups_to_keep(&backups, settings);
+
+    let mut removed = Vec::new();
+    for backup in backups {
+        if !keep.contains(&backup.file_name) {
+            std::fs::remove_file(dir.join(&backup.file_name))?;
+            removed.push(backup.file_name);
+        }
+    }
+
+    Ok(removed)
+}
+
+/// Delete every backup in `dir`, including pre-restore copies, pre-migration
+/// snapshots and a staged restore. Returns the number of files removed.
+pub fn delete_all_backups(dir: &Path) -> Result<usize, AppError> {
+    if !dir.is_dir() {
+        return Ok(0);
+    }
+
+    let mut removed = 0;
+    for item in std::fs::read_dir(dir)? {
+        let path = item?.path();
+        if path.is_dir() {
+            removed += delete_all_backups(&path)?;
+            std::fs::remove_dir(&path)?;
+        } else {
+            std::fs::remove_file(&path)?;
+            removed += 1;
+        }
+    }
+
+    Ok(removed)
+}
+
+/// Whether the newest snapshot is old enough for the scheduler to take another
+pub fn snapshot_due(dir: &Path, now: i64) -> Result<bool, AppError> {
+    let newest = list_backups(dir)?
+        .into_iter()
+        .find(|backup| backup.kind == KIND_SNAPSHOT);
+
+    Ok(newest.is_none_or(|backup| now - backup.created_at >= SNAPSHOT_INTERVAL_MS))
+}
+
+/// Queue a backup to replace the database the next time it is opened
+pub fn stage_restore(dir: &Path, file_name: &str) -> Result<(), AppError> {
+    // Only accept names we listed, never arbitrary paths
+    if !list_backups(dir)?
+        .iter()
+        .any(|backup| backup.file_name == file_name)
+    {
+        return Err(AppError::InvalidSettings(format!(
+            "Backup not found: {file_name}"
+        )));
+    }
+
+    let source = dir.join(file_name);
+    let mut header = [0u8; 16];
+    std::io::Read::read_exact(&mut std::fs::File::open(&source)?, &mut header)?;
+    if &header != b"SQLite format 3\0" {
+        return Err(AppError::InvalidSettings(format!(
+            "Backup is not a valid database: {file_name}"
+        )));
+    }
+
+    std::fs::copy(&source, dir.join(PENDING_RESTORE_FILE))?;
+
+    Ok(())
+}
+
+/// Swap in a staged restore, if any, moving the current database (and its
+/// WAL/SHM files) into `dir` as a pre-restore copy first.
+/// Must run before the database is opened. Returns whether a restore happened.
+pub fn apply_pending_restore(db_path: &Path, dir: &Path) -> Result<bool, AppError> {
+    let pending = dir.join(PENDING_RESTORE_FILE);
+    if !pending.is_file() {
+        return Ok(false);
+    }
+
+    let set_aside = dir.join(file_name_for(Utc::now().timestamp_millis(), true));
+    if db_path.is_file() {
+        move_file(db_path, &set_aside)?;
+    }
+    for suffix in ["-wal", "-shm"] {
+        let sidecar = PathBuf::from(format!("{}{suffix}", db_path.display()));
+        if sidecar.is_file() {
+            move_file(
+                &sidecar,
+                &PathBuf::from(format!("{}{suffix}", set_aside.display())),
+            )?;
+        }
+    }
+
+    move_file(&pending, db_path)?;
+
+    Ok(true)
+}
+
+/// Snapshot the database before migrating it from `version`, replacing any
+/// earlier snapshot of the same version
+pub async fn create_pre_migration_snapshot(
+    pool: &SqlitePool,
+    dir: &Path,
+    version: i64,
+) -> Result<PathBuf, AppError> {
+    let dir = dir.join(PRE_MIGRATION_DIR);
+    std::fs::create_dir_all(&dir)?;
+
+    let path = dir.join(format!("{FILE_PREFIX}v{version}.db"));
+    if path.exists() {
+        std::fs::remove_file(&path)?;
+    }
+    sqlx::query("VACUUM INTO ?")
+        .bind(path.to_string_lossy().into_owned())
+        .execute(pool)
+        .await?;
+
+    Ok(path)
+}
+
+/// Put a snapshot back in place of the database, discarding its WAL/SHM files.
+/// The database must be closed.
+pub fn restore_snapshot(snapshot: &Path, db_path: &Path) -> Result<(), AppError> {
+    for suffix in ["-wal", "-shm"] {
+        let sidecar = PathBuf::from(format!("{}{suffix}", db_path.display()));
+        if sidecar.is_file() {
+            std::fs::remove_file(sidecar)?;
+        }
+    }
+    std::fs::copy(snapshot, db_path)?;
+
+    Ok(())
+}
+
+/// Rename, falling back to copy + delete across file systems
+fn move_file(from: &Path, to: &Path) -> std::io::Result<()> {
+    if std::fs::rename(from, to).is_ok() {
+        return Ok(());
+    }
+    std::fs::copy(from, to)?;
+    std::fs::remove_file(from)
+}
+
+/// Get backup retention settings
+pub async fn get_backup_settings(pool: &SqlitePool) -> Result<BackupSettings, AppError> {
+    let read = |value: Option<String>, default: i64| {
+        value
+            .and_then(|value| value.parse::<i64>().ok())
+            .unwrap_or(default)
+    };
+
+    Ok(BackupSettings {
+        keep_daily: read(
+            get_setting(pool, KEEP_DAILY_SETTING).await?,
+            DEFAULT_KEEP_DAILY,
+        ),
+        keep_weekly: read(
+            get_setting(pool, KEEP_WEEKLY_SETTING).await?,
+            DEFAULT_KEEP_WEEKLY,
+        ),
+        keep_pre_restore: read(
+            get_setting(pool, KEEP_PRE_RESTORE_SETTING).await?,
+            DEFAULT_KEEP_PRE_RESTORE,
+        ),
+    })
+}
+
+/// Save backup retention settings
+pub async fn save_backup_settings(
+    pool: &SqlitePool,
+    settings: &BackupSettings,
+) -> Result<(), AppError> {
+    if settings.keep_daily < 1 || settings.keep_weekly < 0 {
+        return Err(AppError::InvalidSettings(
+            "Keep at least one daily backup".to_string(),
+        ));
+    }
+    if settings.keep_pre_restore < 0 {
+        return Err(AppError::InvalidSettings(
+            "Pre-restore copies to keep can't be negative".to_string(),
+        ));
+    }
+
+    save_setting(pool, KEEP_DAILY_SETTING, &settings.keep_daily.to_string()).await?;
+    save_setting(pool, KEEP_WEEKLY_SETTING, &settings.keep_weekly.to_string()).await?;
+    save_setting(
+        pool,
+        KEEP_PRE_RESTORE_SETTING,
+        &settings.keep_pre_restore.to_string(),
+    )
+    .await
+}
@@ -1,4 +1,9 @@
-use sqlx::{Executor, SqlitePool};
+use super::queries;
+use crate::crypto::Vault;
+use crate::error::AppError;
+use crate::models::{AppliedMigration, SchemaInfo};
+use sha2::{Digest, Sha256};
+use sqlx::{Connection, Executor, SqliteConnection, SqlitePool};
 
 // Migration: initial schema
 const MIGRATION_001: &str = r#"
@@ -100,84 +105,678 @@ CREATE TRIGGER IF NOT EXISTS entries_au AFTER UPDATE ON entries BEGIN
 END;
 "#;
 
-pub async fn run(pool: &SqlitePool) -> Result<(), sqlx::Error> {
-    let mut conn = pool.begin().await?;
+// Migration: add entry revision history
+const MIGRATION_006: &str = r#"
+-- Snapshots of previous entry content, taken before an update overwrites it
+CREATE TABLE IF NOT EXISTS entry_revisions (
+    id TEXT PRIMARY KEY,
+    entry_id TEXT NOT NULL,
+    entry_date TEXT NOT NULL,
+    content_json TEXT NOT NULL,
+    created_at INTEGER NOT NULL,
+    FOREIGN KEY (entry_id) REFERENCES entries(id) ON DELETE CASCADE
+);
+
+-- Indexes for revision lookups
+CREATE INDEX IF NOT EXISTS idx_entry_revisions_entry_id ON entry_revisions(entry_id, created_at);
+CREATE INDEX IF NOT EXISTS idx_entry_revisions_entry_date ON entry_revisions(entry_date);
+"#;
+
+// Migration: add soft delete (trash) to entries
+const MIGRATION_007: &str = r#"
+-- Entries moved to the trash keep their row until purged
+ALTER TABLE entries ADD COLUMN deleted_at INTEGER;
+
+-- Index for trash listing and purging
+CREATE INDEX IF NOT EXISTS idx_entries_deleted_at ON entries(deleted_at);
+"#;
+
+// Migration: add tags and index them for full-text search
+const MIGRATION_008: &str = r#"
+-- Tags (names are unique regardless of case)
+CREATE TABLE IF NOT EXISTS tags (
+    id TEXT PRIMARY KEY,
+    name TEXT NOT NULL UNIQUE COLLATE NOCASE,
+    created_at INTEGER NOT NULL
+);
+
+-- Entry <-> tag links
+CREATE TABLE IF NOT EXISTS entry_tags (
+    entry_id TEXT NOT NULL,
+    tag_id TEXT NOT NULL,
+    created_at INTEGER NOT NULL,
+    PRIMARY KEY (entry_id, tag_id),
+    FOREIGN KEY (entry_id) REFERENCES entries(id) ON DELETE CASCADE,
+    FOREIGN KEY (tag_id) REFERENCES tags(id) ON DELETE CASCADE
+);
+
+CREATE INDEX IF NOT EXISTS idx_entry_tags_tag_id ON entry_tags(tag_id);
+
+-- Rebuild the FTS table with a tags column
+DROP TRIGGER IF EXISTS entries_ai;
+DROP TRIGGER IF EXISTS entries_ad;
+DROP TRIGGER IF EXISTS entries_au;
+DROP TABLE IF EXISTS entries_fts;
+
+CREATE VIRTUAL TABLE IF NOT EXISTS entries_fts USING fts5(
+    entry_id UNINDEXED,
+    content,
+    mood,
+    tags
+);
+
+INSERT INTO entries_fts(entry_id, content, mood, tags)
+SELECT id, content_json, COALESCE(mood, ''), '' FROM entries;
+
+-- Keep the FTS index in sync with entries
+CREATE TRIGGER IF NOT EXISTS entries_ai AFTER INSERT ON entries BEGIN
+    INSERT INTO entries_fts(entry_id, content, mood, tags)
+    VALUES (NEW.id, NEW.content_json, COALESCE(NEW.mood, ''), '');
+END;
+
+CREATE TRIGGER IF NOT EXISTS entries_ad AFTER DELETE ON entries BEGIN
+    DELETE FROM entries_fts WHERE entry_id = OLD.id;
+END;
+
+CREATE TRIGGER IF NOT EXISTS entries_au AFTER UPDATE ON entries BEGIN
+    DELETE FROM entries_fts WHERE entry_id = OLD.id;
+    INSERT INTO entries_fts(entry_id, content, mood, tags)
+    VALUES (
+        NEW.id,
+        NEW.content_json,
+        COALESCE(NEW.mood, ''),
+        COALESCE((SELECT group_concat(t.name, ' ') FROM entry_tags et
+                  JOIN tags t ON t.id = et.tag_id WHERE et.entry_id = NEW.id), '')
+    );
+END;
+
+-- Keep the tags column in sync with tag links and renames
+CREATE TRIGGER IF NOT EXISTS entry_tags_ai AFTER INSERT ON entry_tags BEGIN
+    UPDATE entries_fts
+    SET tags = COALESCE((SELECT group_concat(t.name, ' ') FROM entry_tags et
+                         JOIN tags t ON t.id = et.tag_id WHERE et.entry_id = NEW.entry_id), '')
+    WHERE entry_id = NEW.entry_id;
+END;
+
+CREATE TRIGGER IF NOT EXISTS entry_tags_ad AFTER DELETE ON entry_tags BEGIN
+    UPDATE entries_fts
+    SET tags = COALESCE((SELECT group_concat(t.name, ' ') FROM entry_tags et
+                         JOIN tags t ON t.id = et.tag_id WHERE et.entry_id = OLD.entry_id), '')
+    WHERE entry_id = OLD.entry_id;
+END;
+
+CREATE TRIGGER IF NOT EXISTS tags_au AFTER UPDATE OF name ON tags BEGIN
+    UPDATE entries_fts
+    SET tags = COALESCE((SELECT group_concat(t.name, ' ') FROM entry_tags et
+                         JOIN tags t ON t.id = et.tag_id WHERE et.entry_id = entries_fts.entry_id), '')
+    WHERE entry_id IN (SELECT entry_id FROM entry_tags WHERE tag_id = NEW.id);
+END;
+"#;
+
+// Migration: add persisted TTS audio records
+const MIGRATION_009: &str = r#"
+-- Synthesized audio clips; files live under the app data dir at audio_relpath
+CREATE TABLE IF NOT EXISTS audio_records (
+    id TEXT PRIMARY KEY,
+    entry_id TEXT NOT NULL,
+    text TEXT NOT NULL,
+    audio_relpath TEXT NOT NULL,
+    format TEXT NOT NULL,
+    provider TEXT NOT NULL,
+    model TEXT NOT NULL,
+    voice TEXT,
+    speed REAL,
+    duration_ms INTEGER,
+    size_bytes INTEGER NOT NULL,
+    created_at INTEGER NOT NULL,
+    FOREIGN KEY (entry_id) REFERENCES entries(id) ON DELETE CASCADE
+);
+
+CREATE INDEX IF NOT EXISTS idx_audio_records_entry_id ON audio_records(entry_id, created_at);
+"#;
+
+// Migration: add content-addressed TTS cache
+const MIGRATION_010: &str = r#"
+-- Index of cached clips; files live under the app data dir at relpath
+CREATE TABLE IF NOT EXISTS tts_cache (
+    cache_key TEXT PRIMARY KEY,
+    relpath TEXT NOT NULL,
+    format TEXT NOT NULL,
+    provider TEXT NOT NULL,
+    model TEXT NOT NULL,
+    voice TEXT NOT NULL,
+    duration_ms INTEGER,
+    size_bytes INTEGER NOT NULL,
+    created_at INTEGER NOT NULL,
+    last_used_at INTEGER NOT NULL
+);
+
+CREATE INDEX IF NOT EXISTS idx_tts_cache_last_used_at ON tts_cache(last_used_at);
+
+-- Remember which cached clip an audio record was made from
+ALTER TABLE audio_records ADD COLUMN cache_key TEXT;
+CREATE INDEX IF NOT EXISTS idx_audio_records_cache_key ON audio_records(entry_id, cache_key);
+"#;
+
+// Migration: index extracted plain text instead of raw ProseMirror JSON
+const MIGRATION_011: &str = r#"
+-- Plain text extracted from content_json, maintained on every write
+ALTER TABLE entries ADD COLUMN content_text TEXT NOT NULL DEFAULT '';
+
+-- The index is now maintained explicitly from db::queries
+DROP TRIGGER IF EXISTS entries_ai;
+DROP TRIGGER IF EXISTS entries_ad;
+DROP TRIGGER IF EXISTS entries_au;
+DROP TRIGGER IF EXISTS entry_tags_ai;
+DROP TRIGGER IF EXISTS entry_tags_ad;
+DROP TRIGGER IF EXISTS tags_au;
+DROP TABLE IF EXISTS entries_fts;
+
+CREATE VIRTUAL TABLE IF NOT EXISTS entries_fts USING fts5(
+    entry_id UNINDEXED,
+    content,
+    mood,
+    tags
+);
+"#;
+
+// CJK characters are wrapped in U+E002 before indexing (see search::segment),
+// so the tokenizer must split on it
+const MIGRATION_012: &str = concat!(
+    r#"
+DROP TABLE IF EXISTS entries_fts;
+
+CREATE VIRTUAL TABLE IF NOT EXISTS entries_fts USING fts5(
+    entry_id UNINDEXED,
+    content,
+    mood,
+    tags,
+    tokenize = "unicode61 separators '"#,
+    "\u{E002}",
+    r#"'"
+);
+"#
+);
+
+// Several entries (sessions) per day, each with an optional title and time.
+// SQLite can't drop a UNIQUE constraint, so the table is rebuilt; the runner
+// turns foreign keys off for this so child rows are not cascade-deleted.
+const MIGRATION_013: &str = r#"
+CREATE TABLE entries_new (
+    id TEXT PRIMARY KEY,
+    entry_date TEXT NOT NULL,
+    entry_time TEXT,
+    title TEXT,
+    content_json TEXT NOT NULL,
+    content_text TEXT NOT NULL DEFAULT '',
+    mood TEXT,
+    mood_emoji TEXT,
+    created_at INTEGER NOT NULL,
+    updated_at INTEGER NOT NULL,
+    deleted_at INTEGER
+);
+
+INSERT INTO entries_new (id, entry_date, content_json, content_text, mood, mood_emoji,
+                         created_at, updated_at, deleted_at)
+SELECT id, entry_date, content_json, content_text, mood, mood_emoji,
+       created_at, updated_at, deleted_at
+FROM entries;
 
-    // Bootstrap schema_migrations so a fresh database can run migrations.
+DROP TABLE entries;
+ALTER TABLE entries_new RENAME TO entries;
+
+CREATE INDEX IF NOT EXISTS idx_entries_entry_date ON entries(entry_date, entry_time);
+CREATE INDEX IF NOT EXISTS idx_entries_created_at ON entries(created_at);
+CREATE INDEX IF NOT EXISTS idx_entries_mood ON entries(mood);
+CREATE INDEX IF NOT EXISTS idx_entries_deleted_at ON entries(deleted_at);
+"#;
+
+// Entry summaries, and titles and summaries in the full-text index
+const MIGRATION_014: &str = concat!(
+    r#"
+ALTER TABLE entries ADD COLUMN summary TEXT;
+
+DROP TABLE IF EXISTS entries_fts;
+
+CREATE VIRTUAL TABLE IF NOT EXISTS entries_fts USING fts5(
+    entry_id UNINDEXED,
+    title,
+    summary,
+    content,
+    mood,
+    tags,
+    tokenize = "unicode61 separators '"#,
+    "\u{E002}",
+    r#"'"
+);
+"#
+);
+
+// Image and file attachments
+const MIGRATION_015: &str = r#"
+-- Files live under the app data dir in a store keyed by sha256, shared
+-- between rows attaching the same content
+CREATE TABLE IF NOT EXISTS attachments (
+    id TEXT PRIMARY KEY,
+    entry_id TEXT NOT NULL,
+    sha256 TEXT NOT NULL,
+    file_name TEXT NOT NULL,
+    mime_type TEXT NOT NULL,
+    size_bytes INTEGER NOT NULL,
+    width INTEGER,
+    height INTEGER,
+    has_thumbnail INTEGER NOT NULL DEFAULT 0,
+    created_at INTEGER NOT NULL,
+    FOREIGN KEY (entry_id) REFERENCES entries(id) ON DELETE CASCADE
+);
+
+CREATE INDEX IF NOT EXISTS idx_attachments_entry_id ON attachments(entry_id, created_at);
+CREATE INDEX IF NOT EXISTS idx_attachments_sha256 ON attachments(sha256);
+"#;
+
+// Per-entry word counts for calendar aggregates. Left NULL here and filled in
+// on write or on first use, since encrypted content can't be read while migrating.
+const MIGRATION_016: &str = r#"
+ALTER TABLE entries ADD COLUMN word_count INTEGER;
+"#;
+
+const MIGRATION_017: &str = r#"
+-- Reusable starting points for entries, with {{placeholders}}
+CREATE TABLE IF NOT EXISTS templates (
+    id TEXT PRIMARY KEY,
+    name TEXT NOT NULL,
+    content_json TEXT NOT NULL,
+    created_at INTEGER NOT NULL,
+    updated_at INTEGER NOT NULL
+);
+"#;
+
+const MIGRATION_018: &str = r#"
+-- Prompts written by the user; the bundled ones ship with the app
+CREATE TABLE IF NOT EXISTS custom_prompts (
+    id TEXT PRIMARY KEY,
+    text TEXT NOT NULL,
+    level TEXT,
+    topic TEXT,
+    created_at INTEGER NOT NULL
+);
+
+-- Favorites and skips of any prompt, bundled or custom
+CREATE TABLE IF NOT EXISTS prompt_marks (
+    prompt_id TEXT PRIMARY KEY,
+    is_favorite INTEGER NOT NULL DEFAULT 0,
+    skipped_on TEXT, -- diary date the prompt was last skipped
+    updated_at INTEGER NOT NULL
+);
+
+-- The prompt an entry answered
+CREATE TABLE IF NOT EXISTS entry_prompts (
+    entry_id TEXT PRIMARY KEY,
+    prompt_id TEXT NOT NULL,
+    answered_on TEXT NOT NULL,
+    created_at INTEGER NOT NULL
+);
+CREATE INDEX IF NOT EXISTS idx_entry_prompts_prompt ON entry_prompts(prompt_id, answered_on);
+"#;
+
+// Revisions are coalesced on when they were taken; the content's own save time
+// moves to saved_at. Word counts are left NULL here and filled in when listed,
+// since encrypted content can't be read while migrating.
+const MIGRATION_019: &str = r#"
+ALTER TABLE entry_revisions ADD COLUMN saved_at INTEGER NOT NULL DEFAULT 0;
+UPDATE entry_revisions SET saved_at = created_at;
+ALTER TABLE entry_revisions ADD COLUMN word_count INTEGER;
+"#;
+
+/// Work a migration needs done in Rust after its SQL
+#[derive(Debug, Clone, Copy)]
+enum Step {
+    /// Recompute `entries.content_text` from `content_json`
+    RefreshContentText,
+    /// Rebuild `entries_fts` from scratch. The rebuild reads the latest schema,
+    /// so only the last pending migration asking for it runs it.
+    RebuildSearchIndex,
+}
+
+struct Migration {
+    version: i64,
+    description: &'static str,
+    sql: &'static str,
+    steps: &'static [Step],
+}
+
+/// Every migration, in the order they are applied. Never edit an entry once
+/// released: its checksum is stored and verified on every start.
+const MIGRATIONS: &[Migration] = &[
+    Migration {
+        version: 1,
+        description: "initial schema",
+        sql: MIGRATION_001,
+        steps: &[],
+    },
+    Migration {
+        version: 2,
+        description: "add AI operations tracking",
+        sql: MIGRATION_002,
+        steps: &[],
+    },
+    Migration {
+        version: 3,
+        description: "add app settings table",
+        sql: MIGRATION_003,
+        steps: &[],
+    },
+    Migration {
+        version: 4,
+        description: "add mood tracking to entries",
+        sql: MIGRATION_004,
+        steps: &[],
+    },
+    Migration {
+        version: 5,
+        description: "add full-text search",
+        sql: MIGRATION_005,
+        steps: &[],
+    },
+    Migration {
+        version: 6,
+        description: "add entry revision history",
+        sql: MIGRATION_006,
+        steps: &[],
+    },
+    Migration {
+        version: 7,
+        description: "add soft delete (trash) to entries",
+        sql: MIGRATION_007,
+        steps: &[],
+    },
+    Migration {
+        version: 8,
+        description: "add tags and index them for full-text search",
+        sql: MIGRATION_008,
+        steps: &[],
+    },
+    Migration {
+        version: 9,
+        description: "add persisted TTS audio records",
+        sql: MIGRATION_009,
+        steps: &[],
+    },
+    Migration {
+        version: 10,
+        description: "add content-addressed TTS cache",
+        sql: MIGRATION_010,
+        steps: &[],
+    },
+    Migration {
+        version: 11,
+        description: "index extracted plain text instead of raw ProseMirror JSON",
+        sql: MIGRATION_011,
+        steps: &[Step::RefreshContentText, Step::RebuildSearchIndex],
+    },
+    Migration {
+        version: 12,
+        description: "segment CJK text in the full-text index",
+        sql: MIGRATION_012,
+        steps: &[Step::RebuildSearchIndex],
+    },
+    Migration {
+        version: 13,
+        description: "allow several entries per day, with optional title and time",
+        sql: MIGRATION_013,
+        steps: &[],
+    },
+    Migration {
+        version: 14,
+        description: "add entry summaries and index titles and summaries",
+        sql: MIGRATION_014,
+        steps: &[Step::RebuildSearchIndex],
+    },
+    Migration {
+        version: 15,
+        description: "add image and file attachments",
+        sql: MIGRATION_015,
+        steps: &[],
+    },
+    Migration {
+        version: 16,
+        description: "add per-entry word counts",
+        sql: MIGRATION_016,
+        steps: &[],
+    },
+    Migration {
+        version: 17,
+        description: "entry templates",
+        sql: MIGRATION_017,
+        steps: &[],
+    },
+    Migration {
+        version: 18,
+        description: "writing prompts",
+        sql: MIGRATION_018,
+        steps: &[],
+    },
+    Migration {
+        version: 19,
+        description: "add revision save times and word counts",
+        sql: MIGRATION_019,
+        steps: &[],
+    },
+];
+
+/// Schema version this build of the app migrates to
+pub fn latest_version() -> i64 {
+    MIGRATIONS.last().map_or(0, |m| m.version)
+}
+
+/// SHA-256 of a migration's SQL, stored when it is applied
+fn checksum(sql: &str) -> String {
+    Sha256::digest(sql.as_bytes())
+        .iter()
+        .map(|b| format!("{b:02x}"))
+        .collect()
+}
+
+/// Create `schema_migrations`, adding the checksum column to databases
+/// created before checksums were recorded
+async fn bootstrap(conn: &mut SqliteConnection) -> Result<(), sqlx::Error> {
     conn.execute(
         r#"
         CREATE TABLE IF NOT EXISTS schema_migrations (
             version INTEGER PRIMARY KEY,
-            applied_at INTEGER NOT NULL
+            applied_at INTEGER NOT NULL,
+            checksum TEXT
         );
         "#,
     )
     .await?;
 
-    let current_version: i64 =
-        sqlx::query_scalar("SELECT COALESCE(MAX(version), 0) FROM schema_migrations")
-            .fetch_one(&mut *conn)
+    let has_checksum: bool = sqlx::query_scalar(
+        "SELECT COUNT(*) > 0 FROM pragma_table_info('schema_migrations') WHERE name = 'checksum'",
+    )
+    .fetch_one(&mut *conn)
+    .await?;
+    if !has_checksum {
+        conn.execute("ALTER TABLE schema_migrations ADD COLUMN checksum TEXT")
             .await?;
+    }
 
-    if current_version < 1 {
-        conn.execute(MIGRATION_001).await?;
+    Ok(())
+}
 
-        let now = chrono::Utc::now().timestamp_millis();
-        sqlx::query("INSERT INTO schema_migrations (version, applied_at) VALUES (?, ?)")
-            .bind(1_i64)
-            .bind(now)
-            .execute(&mut *conn)
-            .await?;
-    }
+async fn applied_migrations(
+    conn: &mut SqliteConnection,
+) -> Result<Vec<AppliedMigration>, sqlx::Error> {
+    let rows: Vec<(i64, i64, Option<String>)> = sqlx::query_as(
+        "SELECT version, applied_at, checksum FROM schema_migrations ORDER BY version ASC",
+    )
+    .fetch_all(&mut *conn)
+    .await?;
 
-    if current_version < 2 {
-        // First, drop the old table if it exists (in case it was created with wrong schema)
-        conn.execute("DROP TABLE IF EXISTS ai_operations;").await?;
+    Ok(rows
+        .into_iter()
+        .map(|(version, applied_at, checksum)| AppliedMigration {
+            version,
+            description: MIGRATIONS
+                .iter()
+                .find(|m| m.version == version)
+                .map(|m| m.description.to_string()),
+            applied_at,
+            checksum,
+        })
+        .collect())
+}
 
-        conn.execute(MIGRATION_002).await?;
+/// Check already-applied migrations against the ones compiled into this build
+fn verify(applied: &[AppliedMigration]) -> Result<(), AppError> {
+    let latest = latest_version();
+    for row in applied {
+        let Some(migration) = MIGRATIONS.iter().find(|m| m.version == row.version) else {
+            return Err(AppError::Migration(if row.version > latest {
+                format!(
+                    "database schema version {} is newer than this app supports ({latest}); \
+                     please update EchoDaily",
+                    row.version
+                )
+            } else {
+                format!("database has unknown migration {}", row.version)
+            }));
+        };
 
-        let now = chrono::Utc::now().timestamp_millis();
-        sqlx::query("INSERT INTO schema_migrations (version, applied_at) VALUES (?, ?)")
-            .bind(2_i64)
-            .bind(now)
-            .execute(&mut *conn)
-            .await?;
+        if let Some(stored) = &row.checksum {
+            if *stored != checksum(migration.sql) {
+                return Err(AppError::Migration(format!(
+                    "migration {} ({}) was changed after it was applied",
+                    migration.version, migration.description
+                )));
+            }
+        }
     }
 
-    if current_version < 3 {
-        conn.execute(MIGRATION_003).await?;
+    Ok(())
+}
 
-        let now = chrono::Utc::now().timestamp_millis();
-        sqlx::query("INSERT INTO schema_migrations (version, applied_at) VALUES (?, ?)")
-            .bind(3_i64)
-            .bind(now)
-            .execute(&mut *conn)
-            .await?;
+/// Apply every pending migration, each in its own transaction
+pub async fn run(pool: &SqlitePool) -> Result<(), AppError> {
+    run_up_to(pool, latest_version()).await
+}
+
+/// Apply pending migrations up to and including `target`, e.g. to set up a
+/// database at an older schema
+pub(crate) async fn run_up_to(pool: &SqlitePool, target: i64) -> Result<(), AppError> {
+    let mut conn = pool.acquire().await?;
+    bootstrap(&mut conn).await?;
+
+    let applied = applied_migrations(&mut conn).await?;
+    verify(&applied)?;
+
+    // Rows recorded before checksums existed are trusted as-is
+    for row in applied.iter().filter(|row| row.checksum.is_none()) {
+        if let Some(migration) = MIGRATIONS.iter().find(|m| m.version == row.version) {
+            sqlx::query("UPDATE schema_migrations SET checksum = ? WHERE version = ?")
+                .bind(checksum(migration.sql))
+                .bind(row.version)
+                .execute(&mut *conn)
+                .await?;
+        }
     }
 
-    if current_version < 4 {
-        conn.execute(MIGRATION_004).await?;
+    // Foreign keys are off while migrating so tables can be rebuilt
+    // (https://sqlite.org/lang_altertable.html#otheralter); they are checked
+    // before each migration commits instead.
+    conn.execute("PRAGMA foreign_keys = OFF").await?;
+    let result = apply_pending(&mut conn, pool, &applied, target).await;
+    conn.execute("PRAGMA foreign_keys = ON").await?;
 
-        let now = chrono::Utc::now().timestamp_millis();
-        sqlx::query("INSERT INTO schema_migrations (version, applied_at) VALUES (?, ?)")
-            .bind(4_i64)
-            .bind(now)
-            .execute(&mut *conn)
-            .await?;
+    result
+}
+
+async fn apply_pending(
+    conn: &mut SqliteConnection,
+    pool: &SqlitePool,
+    applied: &[AppliedMigration],
+    target: i64,
+) -> Result<(), AppError> {
+    let current_version = applied.iter().map(|row| row.version).max().unwrap_or(0);
+    let vault = Vault::of(pool);
+    let pending = || {
+        MIGRATIONS
+            .iter()
+            .filter(|m| m.version > current_version && m.version <= target)
+    };
+    let last_rebuild = pending()
+        .filter(|m| {
+            m.steps
+                .iter()
+                .any(|s| matches!(s, Step::RebuildSearchIndex))
+        })
+        .map(|m| m.version)
+        .max();
+    for migration in pending() {
+        let mut tx = conn.begin().await?;
+
+        tx.execute(migration.sql).await?;
+        for step in migration.steps {
+            match step {
+                Step::RefreshContentText => queries::refresh_content_text(&mut tx, &vault).await?,
+                Step::RebuildSearchIndex if Some(migration.version) == last_rebuild => {
+                    queries::rebuild_search_index(&mut tx, &vault).await?
+                }
+                Step::RebuildSearchIndex => {}
+            }
+        }
+
+        sqlx::query(
+            "INSERT INTO schema_migrations (version, applied_at, checksum) VALUES (?, ?, ?)",
+        )
+        .bind(migration.version)
+        .bind(chrono::Utc::now().timestamp_millis())
+        .bind(checksum(migration.sql))
+        .execute(&mut *tx)
+        .await?;
+
+        let violations: Vec<(String,)> =
+            sqlx::query_as("SELECT \"table\" FROM pragma_foreign_key_check")
+                .fetch_all(&mut *tx)
+                .await?;
+        if let Some((table,)) = violations.first() {
+            return Err(AppError::Migration(format!(
+                "migration {} ({}) left rows in {table} pointing at missing rows",
+                migration.version, migration.description
+            )));
+        }
+
+        tx.commit().await?;
     }
 
-    if current_version < 5 {
-        conn.execute(MIGRATION_005).await?;
+    Ok(())
+}
 
-        let now = chrono::Utc::now().timestamp_millis();
-        sqlx::query("INSERT INTO schema_migrations (version, applied_at) VALUES (?, ?)")
-            .bind(5_i64)
-            .bind(now)
-            .execute(&mut *conn)
-            .await?;
+/// Highest applied migration, or 0 for a database never migrated
+pub async fn current_version(pool: &SqlitePool) -> Result<i64, AppError> {
+    let bootstrapped: bool = sqlx::query_scalar(
+        "SELECT COUNT(*) > 0 FROM sqlite_master WHERE type = 'table' AND name = 'schema_migrations'",
+    )
+    .fetch_one(pool)
+    .await?;
+    if !bootstrapped {
+        return Ok(0);
     }
 
-    conn.commit().await?;
+    let version = sqlx::query_scalar("SELECT COALESCE(MAX(version), 0) FROM schema_migrations")
+        .fetch_one(pool)
+        .await?;
+    Ok(version)
+}
 
-    Ok(())
+/// Current schema version and migration history, for diagnostics
+pub async fn schema_info(pool: &SqlitePool) -> Result<SchemaInfo, AppError> {
+    let mut conn = pool.acquire().await?;
+    let applied = applied_migrations(&mut conn).await?;
+
+    Ok(SchemaInfo {
+        current_version: applied.iter().map(|row| row.version).max().unwrap_or(0),
+        latest_version: latest_version(),
+        applied,
+    })
 }
@@ -1,15 +1,19 @@
+pub mod backup;
 pub mod migrations;
 pub mod queries;
 
-use sqlx::{sqlite::SqliteConnectOptions, SqlitePool};
+use sqlx::sqlite::{SqliteConnectOptions, SqlitePoolOptions};
+use sqlx::{Executor, SqlitePool};
+use std::path::{Path, PathBuf};
+
+use crate::crypto;
+use crate::models::{StartupError, STARTUP_DATABASE_UNAVAILABLE, STARTUP_MIGRATION_FAILED};
 use tauri::Manager;
 
-pub async fn get_pool(app: &tauri::AppHandle) -> Result<SqlitePool, crate::error::AppError> {
-    let app_dir = app.path().app_data_dir().map_err(|e| {
-        crate::error::AppError::Io(std::io::Error::other(format!(
-            "Failed to resolve app data directory: {e}"
-        )))
-    })?;
+/// Location of the database file, creating the app data dir if needed.
+/// A database at the legacy `~/.echo-daily` location takes precedence.
+pub fn resolve_db_path(app: &tauri::AppHandle) -> Result<PathBuf, crate::error::AppError> {
+    let app_dir = app_data_dir(app)?;
 
     let legacy_db_path = std::env::var("HOME")
         .or_else(|_| std::env::var("USERPROFILE"))
@@ -28,15 +32,121 @@ pub async fn get_pool(app: &tauri::AppHandle) -> Result<SqlitePool, crate::error
         }
     };
 
+    Ok(db_path)
+}
+
+pub fn app_data_dir<R: tauri::Runtime>(
+    app: &tauri::AppHandle<R>,
+) -> Result<PathBuf, crate::error::AppError> {
+    app.path().app_data_dir().map_err(|e| {
+        crate::error::AppError::Io(std::io::Error::other(format!(
+            "Failed to resolve app data directory: {e}"
+        )))
+    })
+}
+
+/// Open a pool whose connections all attach the same private in-memory
+/// database as `secure`, which holds the search index while encryption is on.
+/// Connections are never recycled, since the in-memory database only lives as
+/// long as one of them stays open.
+pub async fn connect(options: SqliteConnectOptions) -> Result<SqlitePool, sqlx::Error> {
+    let secure = format!(
+        "file:echo-daily-secure-{}?mode=memory&cache=shared",
+        uuid::Uuid::new_v4()
+    );
+
+    SqlitePoolOptions::new()
+        .min_connections(1)
+        .idle_timeout(None)
+        .max_lifetime(None)
+        .after_connect(move |conn, _| {
+            let attach = format!("ATTACH DATABASE '{secure}' AS secure");
+            Box::pin(async move {
+                conn.execute(attach.as_str()).await?;
+                Ok(())
+            })
+        })
+        .connect_with(options)
+        .await
+}
+
+fn database_unavailable(e: impl ToString) -> StartupError {
+    StartupError {
+        kind: STARTUP_DATABASE_UNAVAILABLE.to_string(),
+        message: e.to_string(),
+        schema_version: None,
+        rolled_back: false,
+        snapshot_path: None,
+    }
+}
+
+pub async fn get_pool(app: &tauri::AppHandle) -> Result<SqlitePool, StartupError> {
+    let db_path = resolve_db_path(app).map_err(database_unavailable)?;
+    let app_dir = app_data_dir(app).map_err(database_unavailable)?;
+
+    open_and_migrate(&db_path, &backup::backups_dir(&app_dir)).await
+}
+
+/// Open the database and bring its schema up to date.
+///
+/// The database is snapshotted before pending migrations run; if one fails,
+/// the file is restored from that snapshot so the app is left on the last
+/// working schema rather than a half-migrated one.
+pub async fn open_and_migrate(
+    db_path: &Path,
+    backups_dir: &Path,
+) -> Result<SqlitePool, StartupError> {
+    // A restore picked in the UI is swapped in before anything opens the file
+    if backup::apply_pending_restore(db_path, backups_dir).map_err(database_unavailable)? {
+        println!("Backup: Restored database from backup");
+    }
+
     let options = SqliteConnectOptions::new()
-        .filename(&db_path)
+        .filename(db_path)
         .create_if_missing(true);
 
-    let pool = SqlitePool::connect_with(options).await?;
+    let pool = connect(options).await.map_err(database_unavailable)?;
+    crypto::init(&pool).await.map_err(database_unavailable)?;
 
-    migrations::run(&pool).await?;
+    let version = migrations::current_version(&pool)
+        .await
+        .map_err(database_unavailable)?;
+
+    // Nothing worth protecting in a database that was never migrated
+    let snapshot = if version > 0 && version < migrations::latest_version() {
+        let snapshot = backup::create_pre_migration_snapshot(&pool, backups_dir, version)
+            .await
+            .map_err(database_unavailable)?;
+        println!("Database: Snapshot before migrating from v{version}");
+        Some(snapshot)
+    } else {
+        None
+    };
+
+    let Err(e) = migrations::run(&pool).await else {
+        return Ok(pool);
+    };
+    println!("Database: Migration from v{version} failed: {e}");
+    pool.close().await;
+
+    let rolled_back = match &snapshot {
+        Some(snapshot) => match backup::restore_snapshot(snapshot, db_path) {
+            Ok(()) => true,
+            Err(restore_err) => {
+                println!("Database: Rollback failed: {restore_err}");
+                false
+            }
+        },
+        None => false,
+    };
 
-    Ok(pool)
+    Err(StartupError {
+        kind: STARTUP_MIGRATION_FAILED.to_string(),
+        message: e.to_string(),
+        schema_version: Some(version),
+        rolled_back,
+        snapshot_path: snapshot.map(|path| path.display().to_string()),
+    })
 }
 
 #[cfg(test)]
@@ -1,174 +1,2091 @@
+use crate::content::{
+    count_text, expand_placeholders, extract_plain_text, placeholder_names, TextCounts,
+};
+use crate::crypto::Vault;
 use crate::error::AppError;
-use crate::models::{AIOperation, DiaryEntry, ExportData, ImportOptions, WritingStats};
+use crate::models::{
+    AIOperation, AIUsage, Attachment, AudioRecord, CalendarDay, DaySettings, DiaryEntry,
+    EntryRevision, EntryRevisionSummary, EntryTag, EntryTextStats, ExportData, ImportOptions,
+    OnThisDayEntry, PeriodStats, PromptSettings, SearchHit, SearchResults, SnippetSegment,
+    TTSCacheEntry, Tag, TagUsage, Template, TemplateSettings, TrashSettings, WeekdayStats,
+    WritingAnalytics, WritingPrompt, WritingStats,
+};
+use crate::prompts;
+use crate::search::parse_query;
+use crate::search::segment::{desegment, segment};
+use chrono::{Datelike, Timelike};
 use serde_json::json;
-use sqlx::SqlitePool;
+use sqlx::{
+    FromRow, QueryBuilder, Sqlite, SqliteConnection, SqliteExecutor, SqlitePool, Transaction,
+};
+use std::collections::{BTreeMap, BTreeSet, HashMap, HashSet};
 use uuid::Uuid;
 
+/// Minimum time between two revision snapshots of the same entry.
+/// Autosave fires every few seconds, so edits inside this window are coalesced
+/// into the snapshot taken at its start.
+const REVISION_COALESCE_WINDOW_MS: i64 = 10 * 60 * 1000;
+
+/// Maximum number of revisions kept per entry; older ones are pruned.
+const MAX_REVISIONS_PER_ENTRY: i64 = 50;
+
+/// Order of entries within a day: untimed entries first in the order they
+/// were written, then timed entries by time
+const DAY_ORDER: &str = "entry_time IS NOT NULL, entry_time, created_at";
+
+/// Decrypt the content of an entry read from the database
+fn open_entry(vault: &Vault, mut entry: DiaryEntry) -> Result<DiaryEntry, AppError> {
+    entry.content_json = vault.open(entry.content_json)?;
+    entry.title = entry.title.map(|title| vault.open(title)).transpose()?;
+    entry.summary = entry
+        .summary
+        .map(|summary| vault.open(summary))
+        .transpose()?;
+    Ok(entry)
+}
+
+fn open_entries(vault: &Vault, entries: Vec<DiaryEntry>) -> Result<Vec<DiaryEntry>, AppError> {
+    entries
+        .into_iter()
+        .map(|entry| open_entry(vault, entry))
+        .collect()
+}
+
+/// The first entry of a day, which date-only callers read and write
+async fn first_entry_of_day(
+    executor: impl SqliteExecutor<'_>,
+    entry_date: &str,
+) -> Result<Option<DiaryEntry>, AppError> {
+    Ok(sqlx::query_as::<_, DiaryEntry>(&format!(
+        "SELECT * FROM entries
+         WHERE entry_date = ? AND deleted_at IS NULL
+         ORDER BY {DAY_ORDER}
+         LIMIT 1"
+    ))
+    .bind(entry_date)
+    .fetch_optional(executor)
+    .await?)
+}
+
+/// Save content to the first entry of a day, creating it if the day has none
 pub async fn upsert_entry(
     pool: &SqlitePool,
     entry_date: &str,
     content_json: &str,
 ) -> Result<DiaryEntry, AppError> {
+    let vault = Vault::of(pool);
+    let mut tx = pool.begin().await?;
+    let existing = first_entry_of_day(&mut *tx, entry_date).await?;
+    let entry = write_entry_in(&mut tx, &vault, existing, entry_date, content_json, false).await?;
+    tx.commit().await?;
+
+    Ok(entry)
+}
+
+/// Save content to a specific entry, which must be on `entry_date` so an
+/// editor still showing another day can't overwrite it
+pub async fn save_entry(
+    pool: &SqlitePool,
+    entry_id: &str,
+    entry_date: &str,
+    content_json: &str,
+) -> Result<DiaryEntry, AppError> {
+    let vault = Vault::of(pool);
+    let mut tx = pool.begin().await?;
+    let existing = sqlx::query_as::<_, DiaryEntry>(
+        "SELECT * FROM entries WHERE id = ? AND deleted_at IS NULL",
+    )
+    .bind(entry_id)
+    .fetch_optional(&mut *tx)
+    .await?
+    .ok_or_else(|| AppError::EntryNotFound(entry_id.to_string()))?;
+    if existing.entry_date != entry_date {
+        return Err(AppError::InvalidEntryDate(format!(
+            "entry {entry_id} is on {}, not {entry_date}",
+            existing.entry_date
+        )));
+    }
+
+    let entry = write_entry_in(
+        &mut tx,
+        &vault,
+        Some(existing),
+        entry_date,
+        content_json,
+        false,
+    )
+    .await?;
+    tx.commit().await?;
+
+    Ok(entry)
+}
+
+/// Add another entry to a day
+pub async fn create_entry(
+    pool: &SqlitePool,
+    entry_date: &str,
+    title: Option<&str>,
+    entry_time: Option<&str>,
+    content_json: &str,
+) -> Result<DiaryEntry, AppError> {
+    let vault = Vault::of(pool);
+    let mut tx = pool.begin().await?;
+    let entry = write_entry_in(&mut tx, &vault, None, entry_date, content_json, false).await?;
+    let details = EntryDetails {
+        title,
+        summary: None,
+        entry_time,
+    };
+    let entry = set_entry_details_in(&mut tx, &vault, entry, details).await?;
+    tx.commit().await?;
+
+    Ok(entry)
+}
+
+/// Title, summary and time of an entry, set together
+pub struct EntryDetails<'a> {
+    pub title: Option<&'a str>,
+    pub summary: Option<&'a str>,
+    pub entry_time: Option<&'a str>,
+}
+
+/// Change the title, summary and time of an entry
+pub async fn update_entry_details(
+    pool: &SqlitePool,
+    entry_id: &str,
+    details: EntryDetails<'_>,
+) -> Result<DiaryEntry, AppError> {
+    let vault = Vault::of(pool);
+    let mut tx = pool.begin().await?;
+    let entry = sqlx::query_as::<_, DiaryEntry>(
+        "SELECT * FROM entries WHERE id = ? AND deleted_at IS NULL",
+    )
+    .bind(entry_id)
+    .fetch_optional(&mut *tx)
+    .await?
+    .ok_or_else(|| AppError::EntryNotFound(entry_id.to_string()))?;
+    let entry = set_entry_details_in(&mut tx, &vault, open_entry(&vault, entry)?, details).await?;
+    tx.commit().await?;
+
+    Ok(entry)
+}
+
+async fn set_entry_details_in(
+    tx: &mut Transaction<'_, Sqlite>,
+    vault: &Vault,
+    entry: DiaryEntry,
+    details: EntryDetails<'_>,
+) -> Result<DiaryEntry, AppError> {
+    let title = details.title.map(str::trim).filter(|t| !t.is_empty());
+    let summary = details.summary.map(str::trim).filter(|s| !s.is_empty());
     let now = chrono::Utc::now().timestamp_millis();
 
-    // Try to update an existing entry first.
-    let result = sqlx::query_as::<_, DiaryEntry>(
-        "UPDATE entries 
-         SET content_json = ?, updated_at = ? 
-         WHERE entry_date = ? 
-         RETURNING *",
+    sqlx::query(
+        "UPDATE entries SET title = ?, summary = ?, entry_time = ?, updated_at = ? WHERE id = ?",
     )
-    .bind(content_json)
+    .bind(title.map(|title| vault.seal(title)).transpose()?)
+    .bind(summary.map(|summary| vault.seal(summary)).transpose()?)
+    .bind(details.entry_time)
     .bind(now)
-    .bind(entry_date)
-    .fetch_optional(pool)
+    .bind(&entry.id)
+    .execute(&mut **tx)
     .await?;
+    reindex_entry(tx, vault, &entry.id).await?;
 
-    if let Some(entry) = result {
-        Ok(entry)
+    Ok(DiaryEntry {
+        title: title.map(str::to_string),
+        summary: summary.map(str::to_string),
+        entry_time: details.entry_time.map(str::to_string),
+        updated_at: now,
+        ..entry
+    })
+}
+
+/// Write content to `existing`, or to a new entry on `entry_date` if `None`,
+/// inside an open transaction, snapshotting the previous content into
+/// `entry_revisions` first. `force_snapshot` bypasses the coalescing window
+/// (used by restore).
+async fn write_entry_in(
+    tx: &mut Transaction<'_, Sqlite>,
+    vault: &Vault,
+    existing: Option<DiaryEntry>,
+    entry_date: &str,
+    content_json: &str,
+    force_snapshot: bool,
+) -> Result<DiaryEntry, AppError> {
+    let now = chrono::Utc::now().timestamp_millis();
+    let sealed_json = vault.seal(content_json)?;
+    let plain_text = extract_plain_text(content_json);
+    let word_count = count_text(&plain_text).words;
+    let content_text = vault.seal(&plain_text)?;
+
+    if let Some(existing) = existing {
+        // Writing to a trashed entry (restoring one of its revisions) takes it
+        // out of the trash; the trashed content is kept in the revision history.
+        let revive = existing.deleted_at.is_some();
+        let previous_json = vault.open(existing.content_json.clone())?;
+        if revive || previous_json != content_json {
+            let previous_words = count_text(&extract_plain_text(&previous_json)).words;
+            snapshot_revision(tx, &existing, previous_words, now, force_snapshot || revive).await?;
+        }
+
+        let entry = sqlx::query_as::<_, DiaryEntry>(
+            "UPDATE entries 
+             SET content_json = ?, content_text = ?, word_count = ?, deleted_at = NULL, updated_at = ? 
+             WHERE id = ? 
+             RETURNING *",
+        )
+        .bind(&sealed_json)
+        .bind(&content_text)
+        .bind(word_count)
+        .bind(now)
+        .bind(&existing.id)
+        .fetch_one(&mut **tx)
+        .await?;
+        reindex_entry(tx, vault, &entry.id).await?;
+
+        Ok(DiaryEntry {
+            content_json: content_json.to_string(),
+            ..open_entry(vault, entry)?
+        })
     } else {
         // Otherwise create a new entry.
         let id = Uuid::new_v4().to_string();
         let entry = DiaryEntry {
             id: id.clone(),
             entry_date: entry_date.to_string(),
+            entry_time: None,
+            title: None,
+            summary: None,
             content_json: content_json.to_string(),
             mood: None,
             mood_emoji: None,
             created_at: now,
             updated_at: now,
+            deleted_at: None,
         };
 
         sqlx::query(
-            "INSERT INTO entries (id, entry_date, content_json, mood, mood_emoji, created_at, updated_at)
-             VALUES (?, ?, ?, ?, ?, ?, ?)"
+            "INSERT INTO entries (id, entry_date, content_json, content_text, word_count, mood, mood_emoji, created_at, updated_at)
+             VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?)"
         )
         .bind(&entry.id)
         .bind(&entry.entry_date)
-        .bind(&entry.content_json)
+        .bind(&sealed_json)
+        .bind(&content_text)
+        .bind(word_count)
         .bind(&entry.mood)
         .bind(&entry.mood_emoji)
         .bind(entry.created_at)
         .bind(entry.updated_at)
-        .execute(pool)
+        .execute(&mut **tx)
         .await?;
+        reindex_entry(tx, vault, &entry.id).await?;
 
         Ok(entry)
     }
 }
 
-pub async fn get_entry(
+/// Store the current content of `entry` as a revision, unless a snapshot was
+/// already taken within the coalescing window.
+async fn snapshot_revision(
+    tx: &mut Transaction<'_, Sqlite>,
+    entry: &DiaryEntry,
+    word_count: i64,
+    now: i64,
+    force: bool,
+) -> Result<(), AppError> {
+    if !force {
+        let last_snapshot: Option<i64> =
+            sqlx::query_scalar("SELECT MAX(created_at) FROM entry_revisions WHERE entry_id = ?")
+                .bind(&entry.id)
+                .fetch_one(&mut **tx)
+                .await?;
+
+        // A snapshot from the same editing session already exists.
+        if let Some(last) = last_snapshot {
+            if now - last < REVISION_COALESCE_WINDOW_MS {
+                return Ok(());
+            }
+        }
+    }
+
+    sqlx::query(
+        "INSERT INTO entry_revisions
+             (id, entry_id, entry_date, content_json, word_count, saved_at, created_at)
+         VALUES (?, ?, ?, ?, ?, ?, ?)",
+    )
+    .bind(Uuid::new_v4().to_string())
+    .bind(&entry.id)
+    .bind(&entry.entry_date)
+    .bind(&entry.content_json)
+    .bind(word_count)
+    .bind(entry.updated_at)
+    .bind(now)
+    .execute(&mut **tx)
+    .await?;
+
+    // Keep only the newest revisions for this entry.
+    sqlx::query(
+        "DELETE FROM entry_revisions
+         WHERE entry_id = ? AND id NOT IN (
+             SELECT id FROM entry_revisions
+             WHERE entry_id = ?
+             ORDER BY created_at DESC
+             LIMIT ?
+         )",
+    )
+    .bind(&entry.id)
+    .bind(&entry.id)
+    .bind(MAX_REVISIONS_PER_ENTRY)
+    .execute(&mut **tx)
+    .await?;
+
+    Ok(())
+}
+
+/// Get a live entry by id
+pub async fn get_entry(pool: &SqlitePool, entry_id: &str) -> Result<Option<DiaryEntry>, AppError> {
+    let entry = sqlx::query_as::<_, DiaryEntry>(
+        "SELECT * FROM entries WHERE id = ? AND deleted_at IS NULL",
+    )
+    .bind(entry_id)
+    .fetch_optional(pool)
+    .await?;
+
+    entry
+        .map(|entry| open_entry(&Vault::of(pool), entry))
+        .transpose()
+}
+
+/// Get every entry of a day, in day order
+pub async fn get_day_entries(
     pool: &SqlitePool,
     entry_date: &str,
-) -> Result<Option<DiaryEntry>, AppError> {
-    let entry = sqlx::query_as::<_, DiaryEntry>("SELECT * FROM entries WHERE entry_date = ?")
-        .bind(entry_date)
-        .fetch_optional(pool)
-        .await?;
+) -> Result<Vec<DiaryEntry>, AppError> {
+    let entries = sqlx::query_as::<_, DiaryEntry>(&format!(
+        "SELECT * FROM entries
+         WHERE entry_date = ? AND deleted_at IS NULL
+         ORDER BY {DAY_ORDER}"
+    ))
+    .bind(entry_date)
+    .fetch_all(pool)
+    .await?;
 
-    Ok(entry)
+    open_entries(&Vault::of(pool), entries)
+}
+
+/// Get the first entry of a day, which date-only commands act on
+pub async fn get_first_entry(
+    pool: &SqlitePool,
+    entry_date: &str,
+) -> Result<Option<DiaryEntry>, AppError> {
+    first_entry_of_day(pool, entry_date)
+        .await?
+        .map(|entry| open_entry(&Vault::of(pool), entry))
+        .transpose()
 }
 
 pub async fn list_entries(
     pool: &SqlitePool,
     month: &str, // YYYY-MM
 ) -> Result<Vec<DiaryEntry>, AppError> {
-    let entries = sqlx::query_as::<_, DiaryEntry>(
+    let entries = sqlx::query_as::<_, DiaryEntry>(&format!(
         "SELECT * FROM entries 
-         WHERE entry_date LIKE ? 
-         ORDER BY entry_date DESC",
-    )
+         WHERE entry_date LIKE ? AND deleted_at IS NULL
+         ORDER BY entry_date DESC, {DAY_ORDER}"
+    ))
     .bind(format!("{}%", month))
     .fetch_all(pool)
     .await?;
 
+    open_entries(&Vault::of(pool), entries)
+}
+
+/// Compute word counts left NULL by the migration that added them. Needs the
+/// content, so with encryption this waits until the journal is unlocked.
+async fn fill_missing_word_counts(pool: &SqlitePool) -> Result<(), AppError> {
+    let vault = Vault::of(pool);
+    if vault.is_locked() {
+        return Ok(());
+    }
+
+    let rows: Vec<(String, String)> =
+        sqlx::query_as("SELECT id, content_text FROM entries WHERE word_count IS NULL")
+            .fetch_all(pool)
+            .await?;
+    if rows.is_empty() {
+        return Ok(());
+    }
+
+    let mut tx = pool.begin().await?;
+    for (id, content_text) in rows {
+        sqlx::query("UPDATE entries SET word_count = ? WHERE id = ?")
+            .bind(count_text(&vault.open(content_text)?).words)
+            .bind(&id)
+            .execute(&mut *tx)
+            .await?;
+    }
+    tx.commit().await?;
+
+    Ok(())
+}
+
+/// Per-day totals between `start_date` and `end_date` (inclusive), one record
+/// for every day in the range. Reads no entry content, so it stays cheap for
+/// drawing calendars and year heatmaps.
+pub async fn get_calendar_days(
+    pool: &SqlitePool,
+    start_date: chrono::NaiveDate,
+    end_date: chrono::NaiveDate,
+) -> Result<Vec<CalendarDay>, AppError> {
+    fill_missing_word_counts(pool).await?;
+
+    let start = start_date.format("%Y-%m-%d").to_string();
+    let end = end_date.format("%Y-%m-%d").to_string();
+    let totals: Vec<(String, i64, i64, bool, i64)> = sqlx::query_as(
+        "SELECT e.entry_date, COUNT(*), COALESCE(SUM(e.word_count), 0),
+                COALESCE(SUM(a.clips), 0) > 0, COALESCE(SUM(o.operations), 0)
+         FROM entries e
+         LEFT JOIN (SELECT entry_id, COUNT(*) AS clips FROM audio_records GROUP BY entry_id) a
+                ON a.entry_id = e.id
+         LEFT JOIN (SELECT entry_id, COUNT(*) AS operations FROM ai_operations GROUP BY entry_id) o
+                ON o.entry_id = e.id
+         WHERE e.deleted_at IS NULL AND e.entry_date BETWEEN ? AND ?
+         GROUP BY e.entry_date",
+    )
+    .bind(&start)
+    .bind(&end)
+    .fetch_all(pool)
+    .await?;
+
+    // A day's mood is that of its first entry with one
+    let moods: Vec<(String, String, Option<String>)> = sqlx::query_as(&format!(
+        "SELECT entry_date, mood, mood_emoji FROM entries
+         WHERE deleted_at IS NULL AND mood IS NOT NULL AND entry_date BETWEEN ? AND ?
+         ORDER BY entry_date, {DAY_ORDER}"
+    ))
+    .bind(&start)
+    .bind(&end)
+    .fetch_all(pool)
+    .await?;
+
+    let mut by_date: HashMap<String, CalendarDay> = totals
+        .into_iter()
+        .map(|(date, entry_count, word_count, has_audio, ai_ops_count)| {
+            let day = CalendarDay {
+                date: date.clone(),
+                has_entry: true,
+                entry_count,
+                word_count,
+                mood: None,
+                mood_emoji: None,
+                has_audio,
+                ai_ops_count,
+            };
+            (date, day)
+        })
+        .collect();
+    for (date, mood, mood_emoji) in moods {
+        if let Some(day) = by_date.get_mut(&date) {
+            if day.mood.is_none() {
+                day.mood = Some(mood);
+                day.mood_emoji = mood_emoji;
+            }
+        }
+    }
+
+    Ok(start_date
+        .iter_days()
+        .take_while(|date| *date <= end_date)
+        .map(|date| {
+            let date = date.format("%Y-%m-%d").to_string();
+            by_date.remove(&date).unwrap_or(CalendarDay {
+                date,
+                has_entry: false,
+                entry_count: 0,
+                word_count: 0,
+                mood: None,
+                mood_emoji: None,
+                has_audio: false,
+                ai_ops_count: 0,
+            })
+        })
+        .collect())
+}
+
+/// Start of an entry's plain text on one line, cut at [`PREVIEW_CHARS`]
+pub fn preview_of(text: &str) -> String {
+    let collapsed = text.split_whitespace().collect::<Vec<_>>().join(" ");
+    match collapsed.char_indices().nth(PREVIEW_CHARS) {
+        Some((end, _)) => format!("{}…", &collapsed[..end]),
+        None => collapsed,
+    }
+}
+
+/// Month-days of earlier years that resurface on `date`. Feb 29 entries show
+/// up on Feb 28 in years without one, and on Feb 29 itself in leap years.
+fn on_this_day_month_days(date: chrono::NaiveDate) -> Vec<String> {
+    let month_day = date.format("%m-%d").to_string();
+    let leap_year = chrono::NaiveDate::from_ymd_opt(date.year(), 2, 29).is_some();
+    if month_day == "02-28" && !leap_year {
+        vec![month_day, "02-29".to_string()]
+    } else {
+        vec![month_day]
+    }
+}
+
+/// Entries written on the same day in earlier years, newest year first, and
+/// optionally those from a month and a week before `date`. Trashed entries
+/// are left out, and so is anything that can't be read while the journal is locked.
+pub async fn get_on_this_day(
+    pool: &SqlitePool,
+    date: chrono::NaiveDate,
+    include_lookbacks: bool,
+) -> Result<Vec<OnThisDayEntry>, AppError> {
+    type Row = (
+        String,
+        String,
+        Option<String>,
+        Option<String>,
+        String,
+        Option<String>,
+        Option<String>,
+    );
+    const COLUMNS: &str =
+        "SELECT id, entry_date, entry_time, title, content_text, mood, mood_emoji FROM entries";
+
+    let date_str = date.format("%Y-%m-%d").to_string();
+    let mut found: Vec<(&str, Row)> = Vec::new();
+
+    let mut qb = QueryBuilder::<Sqlite>::new(COLUMNS);
+    qb.push(" WHERE deleted_at IS NULL AND entry_date < ")
+        .push_bind(&date_str)
+        .push(" AND substr(entry_date, 6) IN (");
+    let mut separated = qb.separated(", ");
+    for month_day in on_this_day_month_days(date) {
+        separated.push_bind(month_day);
+    }
+    qb.push(format!(") ORDER BY entry_date DESC, {DAY_ORDER}"));
+    for row in qb.build_query_as::<Row>().fetch_all(pool).await? {
+        found.push(("year", row));
+    }
+
+    if include_lookbacks {
+        let lookbacks = [
+            ("month", date.checked_sub_months(chrono::Months::new(1))),
+            ("week", date.checked_sub_days(chrono::Days::new(7))),
+        ];
+        for (kind, day) in lookbacks {
+            let Some(day) = day else { continue };
+            let rows: Vec<Row> = sqlx::query_as(&format!(
+                "{COLUMNS} WHERE deleted_at IS NULL AND entry_date = ? ORDER BY {DAY_ORDER}"
+            ))
+            .bind(day.format("%Y-%m-%d").to_string())
+            .fetch_all(pool)
+            .await?;
+            found.extend(rows.into_iter().map(|row| (kind, row)));
+        }
+    }
+
+    let vault = Vault::of(pool);
+    let mut entries = Vec::with_capacity(found.len());
+    for (kind, (entry_id, entry_date, entry_time, title, content_text, mood, mood_emoji)) in found {
+        let opened = vault
+            .open(content_text)
+            .and_then(|text| Ok((text, title.map(|t| vault.open(t)).transpose()?)));
+        let (text, title) = match opened {
+            Ok(opened) => opened,
+            Err(_) if vault.is_locked() => continue,
+            Err(e) => return Err(e),
+        };
+        let years_ago = match chrono::NaiveDate::parse_from_str(&entry_date, "%Y-%m-%d") {
+            Ok(written) if kind == "year" => i64::from(date.year() - written.year()),
+            _ => 0,
+        };
+        entries.push(OnThisDayEntry {
+            entry_id,
+            entry_date,
+            entry_time,
+            title,
+            preview: preview_of(&text),
+            mood,
+            mood_emoji,
+            kind: kind.to_string(),
+            years_ago,
+        });
+    }
+
     Ok(entries)
 }
 
-pub async fn delete_entry(pool: &SqlitePool, entry_date: &str) -> Result<bool, AppError> {
-    let result = sqlx::query("DELETE FROM entries WHERE entry_date = ?")
-        .bind(entry_date)
-        .execute(pool)
-        .await?;
+/// Move an entry to the trash
+pub async fn delete_entry(pool: &SqlitePool, entry_id: &str) -> Result<bool, AppError> {
+    let now = chrono::Utc::now().timestamp_millis();
+
+    let result =
+        sqlx::query("UPDATE entries SET deleted_at = ? WHERE id = ? AND deleted_at IS NULL")
+            .bind(now)
+            .bind(entry_id)
+            .execute(pool)
+            .await?;
 
     Ok(result.rows_affected() > 0)
 }
 
-// AI Operations
+// ===== Trash =====
 
-pub async fn create_ai_operation(
+/// Default number of days an entry stays in the trash
+pub const DEFAULT_TRASH_RETENTION_DAYS: i64 = 30;
+
+/// Longest an entry can be kept in the trash (ten years)
+pub const MAX_TRASH_RETENTION_DAYS: i64 = 3_650;
+
+const TRASH_RETENTION_SETTING: &str = "trash_retention_days";
+
+/// List entries in the trash, most recently deleted first
+pub async fn list_trash(pool: &SqlitePool) -> Result<Vec<DiaryEntry>, AppError> {
+    let entries = sqlx::query_as::<_, DiaryEntry>(
+        "SELECT * FROM entries
+         WHERE deleted_at IS NOT NULL
+         ORDER BY deleted_at DESC",
+    )
+    .fetch_all(pool)
+    .await?;
+
+    open_entries(&Vault::of(pool), entries)
+}
+
+/// Move a trashed entry back out of the trash
+pub async fn restore_entry(
     pool: &SqlitePool,
     entry_id: &str,
-    op_type: &str,
-    original_text: &str,
-    result_text: &str,
-    provider: &str,
-    model: &str,
-) -> Result<AIOperation, AppError> {
-    let id = Uuid::new_v4().to_string();
-    let now = chrono::Utc::now().timestamp_millis();
+) -> Result<Option<DiaryEntry>, AppError> {
+    // Check before the update commits, so a locked journal keeps the entry in the trash
+    let vault = Vault::of(pool);
+    vault.ensure_unlocked()?;
 
-    let operation = AIOperation {
-        id: id.clone(),
-        entry_id: entry_id.to_string(),
-        op_type: op_type.to_string(),
-        original_text: original_text.to_string(),
-        result_text: result_text.to_string(),
-        provider: provider.to_string(),
-        model: model.to_string(),
-        created_at: now,
-    };
+    let entry = sqlx::query_as::<_, DiaryEntry>(
+        "UPDATE entries SET deleted_at = NULL
+         WHERE id = ? AND deleted_at IS NOT NULL
+         RETURNING *",
+    )
+    .bind(entry_id)
+    .fetch_optional(pool)
+    .await?;
 
-    sqlx::query(
-        "INSERT INTO ai_operations (id, entry_id, op_type, original_text, result_text, provider, model, created_at)
-         VALUES (?, ?, ?, ?, ?, ?, ?, ?)"
+    entry.map(|entry| open_entry(&vault, entry)).transpose()
+}
+
+/// Permanently delete every entry in the trash.
+/// Returns the ids of purged entries so their files can be cleaned up.
+pub async fn empty_trash(pool: &SqlitePool) -> Result<Vec<String>, AppError> {
+    purge_trashed_before(pool, i64::MAX).await
+}
+
+/// Permanently delete entries that have been in the trash longer than the
+/// configured retention period. Returns the ids of purged entries.
+pub async fn purge_expired_trash(pool: &SqlitePool) -> Result<Vec<String>, AppError> {
+    let settings = get_trash_settings(pool).await?;
+    let cutoff = chrono::Utc::now()
+        .timestamp_millis()
+        .saturating_sub(settings.retention_days.saturating_mul(86_400_000));
+    purge_trashed_before(pool, cutoff).await
+}
+
+async fn purge_trashed_before(pool: &SqlitePool, cutoff: i64) -> Result<Vec<String>, AppError> {
+    let ids: Vec<String> = sqlx::query_scalar(
+        "SELECT id FROM entries WHERE deleted_at IS NOT NULL AND deleted_at < ?",
     )
-    .bind(&operation.id)
-    .bind(&operation.entry_id)
-    .bind(&operation.op_type)
-    .bind(&operation.original_text)
-    .bind(&operation.result_text)
-    .bind(&operation.provider)
-    .bind(&operation.model)
-    .bind(operation.created_at)
-    .execute(pool)
+    .bind(cutoff)
+    .fetch_all(pool)
     .await?;
 
-    Ok(operation)
+    // While locked the index is not loaded, so there is nothing to remove from it
+    let vault = Vault::of(pool);
+    let mut purged = Vec::with_capacity(ids.len());
+    for id in ids {
+        // One transaction per entry, so a failure never leaves a restorable
+        // entry that has already lost its children
+        let mut tx = pool.begin().await?;
+        delete_ai_operations_for_entry(&mut *tx, &id).await?;
+        sqlx::query("DELETE FROM audio_records WHERE entry_id = ?")
+            .bind(&id)
+            .execute(&mut *tx)
+            .await?;
+        sqlx::query("DELETE FROM attachments WHERE entry_id = ?")
+            .bind(&id)
+            .execute(&mut *tx)
+            .await?;
+        sqlx::query("DELETE FROM entry_prompts WHERE entry_id = ?")
+            .bind(&id)
+            .execute(&mut *tx)
+            .await?;
+        if !vault.is_locked() {
+            sqlx::query(&format!(
+                "DELETE FROM {} WHERE entry_id = ?",
+                vault.fts_table()
+            ))
+            .bind(&id)
+            .execute(&mut *tx)
+            .await?;
+        }
+        let result = sqlx::query("DELETE FROM entries WHERE id = ?")
+            .bind(&id)
+            .execute(&mut *tx)
+            .await?;
+        tx.commit().await?;
+        if result.rows_affected() > 0 {
+            purged.push(id);
+        }
+    }
+
+    Ok(purged)
+}
+
+/// Get trash settings, falling back to defaults
+pub async fn get_trash_settings(pool: &SqlitePool) -> Result<TrashSettings, AppError> {
+    let retention_days = get_setting(pool, TRASH_RETENTION_SETTING)
+        .await?
+        .and_then(|value| value.parse::<i64>().ok())
+        .unwrap_or(DEFAULT_TRASH_RETENTION_DAYS);
+
+    Ok(TrashSettings { retention_days })
+}
+
+/// Save trash settings
+pub async fn save_trash_settings(
+    pool: &SqlitePool,
+    settings: &TrashSettings,
+) -> Result<(), AppError> {
+    if !(1..=MAX_TRASH_RETENTION_DAYS).contains(&settings.retention_days) {
+        return Err(AppError::InvalidSettings(format!(
+            "Trash retention must be between 1 and {MAX_TRASH_RETENTION_DAYS} days, got {}",
+            settings.retention_days
+        )));
+    }
+    save_setting(
+        pool,
+        TRASH_RETENTION_SETTING,
+        &settings.retention_days.to_string(),
+    )
+    .await
+}
+
+// ===== Tags =====
+
+/// Longest tag name accepted
+const MAX_TAG_NAME_LENGTH: usize = 50;
+
+/// Normalize a user-supplied tag name: trim, drop a leading `#`, collapse whitespace
+pub fn normalize_tag_name(name: &str) -> Result<String, AppError> {
+    let name = name.trim().trim_start_matches('#');
+    let name = name.split_whitespace().collect::<Vec<_>>().join(" ");
+
+    if name.is_empty() {
+        return Err(AppError::InvalidTag("Tag name cannot be empty".to_string()));
+    }
+    if name.chars().count() > MAX_TAG_NAME_LENGTH {
+        return Err(AppError::InvalidTag(format!(
+            "Tag name cannot be longer than {} characters",
+            MAX_TAG_NAME_LENGTH
+        )));
+    }
+
+    Ok(name)
+}
+
+/// Find a tag by name (case-insensitive)
+pub async fn get_tag_by_name(pool: &SqlitePool, name: &str) -> Result<Option<Tag>, AppError> {
+    let tag = sqlx::query_as::<_, Tag>("SELECT * FROM tags WHERE name = ?")
+        .bind(name)
+        .fetch_optional(pool)
+        .await?;
+
+    Ok(tag)
+}
+
+/// Get a tag by name, creating it if it doesn't exist
+async fn get_or_create_tag(pool: &SqlitePool, name: &str) -> Result<Tag, AppError> {
+    let name = normalize_tag_name(name)?;
+    if let Some(tag) = get_tag_by_name(pool, &name).await? {
+        return Ok(tag);
+    }
+
+    let tag = Tag {
+        id: Uuid::new_v4().to_string(),
+        name,
+        created_at: chrono::Utc::now().timestamp_millis(),
+    };
+
+    sqlx::query("INSERT INTO tags (id, name, created_at) VALUES (?, ?, ?)")
+        .bind(&tag.id)
+        .bind(&tag.name)
+        .bind(tag.created_at)
+        .execute(pool)
+        .await?;
+
+    Ok(tag)
+}
+
+/// List all tags with their usage counts, most used first
+pub async fn list_tags(pool: &SqlitePool) -> Result<Vec<TagUsage>, AppError> {
+    let tags = sqlx::query_as::<_, TagUsage>(
+        "SELECT t.id, t.name, t.created_at, COUNT(e.id) AS entry_count
+         FROM tags t
+         LEFT JOIN entry_tags et ON et.tag_id = t.id
+         LEFT JOIN entries e ON e.id = et.entry_id AND e.deleted_at IS NULL
+         GROUP BY t.id
+         ORDER BY entry_count DESC, t.name COLLATE NOCASE ASC",
+    )
+    .fetch_all(pool)
+    .await?;
+
+    Ok(tags)
+}
+
+/// List tags attached to an entry
+pub async fn list_entry_tags(pool: &SqlitePool, entry_id: &str) -> Result<Vec<Tag>, AppError> {
+    let tags = sqlx::query_as::<_, Tag>(
+        "SELECT t.* FROM tags t
+         INNER JOIN entry_tags et ON et.tag_id = t.id
+         WHERE et.entry_id = ?
+         ORDER BY t.name COLLATE NOCASE ASC",
+    )
+    .bind(entry_id)
+    .fetch_all(pool)
+    .await?;
+
+    Ok(tags)
+}
+
+/// Attach a tag (by name, created on demand) to an entry
+pub async fn add_entry_tag(pool: &SqlitePool, entry_id: &str, name: &str) -> Result<Tag, AppError> {
+    let tag = get_or_create_tag(pool, name).await?;
+
+    sqlx::query("INSERT OR IGNORE INTO entry_tags (entry_id, tag_id, created_at) VALUES (?, ?, ?)")
+        .bind(entry_id)
+        .bind(&tag.id)
+        .bind(chrono::Utc::now().timestamp_millis())
+        .execute(pool)
+        .await?;
+    reindex_entries(pool, &[entry_id.to_string()]).await?;
+
+    Ok(tag)
+}
+
+/// Detach a tag from an entry
+pub async fn remove_entry_tag(
+    pool: &SqlitePool,
+    entry_id: &str,
+    tag_id: &str,
+) -> Result<bool, AppError> {
+    let result = sqlx::query("DELETE FROM entry_tags WHERE entry_id = ? AND tag_id = ?")
+        .bind(entry_id)
+        .bind(tag_id)
+        .execute(pool)
+        .await?;
+    reindex_entries(pool, &[entry_id.to_string()]).await?;
+
+    Ok(result.rows_affected() > 0)
+}
+
+/// Rename a tag. Fails if another tag already has the new name; merge them instead.
+pub async fn rename_tag(pool: &SqlitePool, tag_id: &str, name: &str) -> Result<Tag, AppError> {
+    let name = normalize_tag_name(name)?;
+
+    if let Some(other) = get_tag_by_name(pool, &name).await? {
+        if other.id != tag_id {
+            return Err(AppError::InvalidTag(format!(
+                "A tag named \"{}\" already exists",
+                other.name
+            )));
+        }
+    }
+
+    let tag = sqlx::query_as::<_, Tag>("UPDATE tags SET name = ? WHERE id = ? RETURNING *")
+        .bind(&name)
+        .bind(tag_id)
+        .fetch_optional(pool)
+        .await?
+        .ok_or_else(|| AppError::InvalidTag(format!("Tag {tag_id} does not exist")))?;
+    reindex_entries(pool, &tagged_entry_ids(pool, tag_id).await?).await?;
+
+    Ok(tag)
+}
+
+/// Ids of entries carrying a tag
+async fn tagged_entry_ids(pool: &SqlitePool, tag_id: &str) -> Result<Vec<String>, AppError> {
+    let ids = sqlx::query_scalar("SELECT entry_id FROM entry_tags WHERE tag_id = ?")
+        .bind(tag_id)
+        .fetch_all(pool)
+        .await?;
+
+    Ok(ids)
+}
+
+/// Merge `source_ids` into `target_id`: entries keep a single link to the target
+/// and the source tags are deleted
+pub async fn merge_tags(
+    pool: &SqlitePool,
+    source_ids: &[String],
+    target_id: &str,
+) -> Result<Tag, AppError> {
+    let mut tx = pool.begin().await?;
+
+    let target = sqlx::query_as::<_, Tag>("SELECT * FROM tags WHERE id = ?")
+        .bind(target_id)
+        .fetch_optional(&mut *tx)
+        .await?
+        .ok_or_else(|| AppError::InvalidTag(format!("Tag {target_id} does not exist")))?;
+
+    for source_id in source_ids.iter().filter(|id| id.as_str() != target_id) {
+        sqlx::query(
+            "INSERT OR IGNORE INTO entry_tags (entry_id, tag_id, created_at)
+             SELECT entry_id, ?, created_at FROM entry_tags WHERE tag_id = ?",
+        )
+        .bind(target_id)
+        .bind(source_id)
+        .execute(&mut *tx)
+        .await?;
+
+        sqlx::query("DELETE FROM entry_tags WHERE tag_id = ?")
+            .bind(source_id)
+            .execute(&mut *tx)
+            .await?;
+
+        sqlx::query("DELETE FROM tags WHERE id = ?")
+            .bind(source_id)
+            .execute(&mut *tx)
+            .await?;
+    }
+
+    tx.commit().await?;
+    reindex_entries(pool, &tagged_entry_ids(pool, target_id).await?).await?;
+
+    Ok(target)
+}
+
+/// Delete a tag and all its entry links
+pub async fn delete_tag(pool: &SqlitePool, tag_id: &str) -> Result<bool, AppError> {
+    let affected = tagged_entry_ids(pool, tag_id).await?;
+    let mut tx = pool.begin().await?;
+
+    sqlx::query("DELETE FROM entry_tags WHERE tag_id = ?")
+        .bind(tag_id)
+        .execute(&mut *tx)
+        .await?;
+
+    let result = sqlx::query("DELETE FROM tags WHERE id = ?")
+        .bind(tag_id)
+        .execute(&mut *tx)
+        .await?;
+
+    tx.commit().await?;
+    reindex_entries(pool, &affected).await?;
+
+    Ok(result.rows_affected() > 0)
+}
+
+/// List entries carrying a tag, newest first
+pub async fn list_entries_by_tag(
+    pool: &SqlitePool,
+    tag_id: &str,
+) -> Result<Vec<DiaryEntry>, AppError> {
+    let entries = sqlx::query_as::<_, DiaryEntry>(
+        "SELECT e.* FROM entries e
+         INNER JOIN entry_tags et ON et.entry_id = e.id
+         WHERE et.tag_id = ? AND e.deleted_at IS NULL
+         ORDER BY e.entry_date DESC, e.entry_time IS NOT NULL, e.entry_time, e.created_at",
+    )
+    .bind(tag_id)
+    .fetch_all(pool)
+    .await?;
+
+    open_entries(&Vault::of(pool), entries)
+}
+
+// ===== Entry Revisions =====
+
+/// List revisions of an entry, newest first
+pub async fn list_entry_revisions(
+    pool: &SqlitePool,
+    entry_id: &str,
+) -> Result<Vec<EntryRevisionSummary>, AppError> {
+    fill_missing_revision_word_counts(pool, entry_id).await?;
+
+    let revisions = sqlx::query_as::<_, EntryRevisionSummary>(
+        "SELECT id, entry_id, entry_date, COALESCE(word_count, 0) AS word_count,
+                saved_at, created_at
+         FROM entry_revisions
+         WHERE entry_id = ?
+         ORDER BY created_at DESC",
+    )
+    .bind(entry_id)
+    .fetch_all(pool)
+    .await?;
+
+    Ok(revisions)
+}
+
+/// Compute revision word counts left NULL by the migration that added them,
+/// once the journal is unlocked (see [`fill_missing_word_counts`])
+async fn fill_missing_revision_word_counts(
+    pool: &SqlitePool,
+    entry_id: &str,
+) -> Result<(), AppError> {
+    let vault = Vault::of(pool);
+    if vault.is_locked() {
+        return Ok(());
+    }
+
+    let rows: Vec<(String, String)> = sqlx::query_as(
+        "SELECT id, content_json FROM entry_revisions
+         WHERE entry_id = ? AND word_count IS NULL",
+    )
+    .bind(entry_id)
+    .fetch_all(pool)
+    .await?;
+    if rows.is_empty() {
+        return Ok(());
+    }
+
+    let mut tx = pool.begin().await?;
+    for (id, content_json) in rows {
+        let plain_text = extract_plain_text(&vault.open(content_json)?);
+        sqlx::query("UPDATE entry_revisions SET word_count = ? WHERE id = ?")
+            .bind(count_text(&plain_text).words)
+            .bind(&id)
+            .execute(&mut *tx)
+            .await?;
+    }
+    tx.commit().await?;
+
+    Ok(())
+}
+
+/// Get a single revision including its content
+pub async fn get_entry_revision(
+    pool: &SqlitePool,
+    revision_id: &str,
+) -> Result<Option<EntryRevision>, AppError> {
+    let revision = sqlx::query_as::<_, EntryRevision>("SELECT * FROM entry_revisions WHERE id = ?")
+        .bind(revision_id)
+        .fetch_optional(pool)
+        .await?;
+
+    let vault = Vault::of(pool);
+    revision
+        .map(|mut revision| {
+            revision.content_json = vault.open(revision.content_json)?;
+            Ok(revision)
+        })
+        .transpose()
+}
+
+/// Make a revision the current content of its entry.
+/// The content being replaced is always snapshotted, so a restore can be undone.
+pub async fn restore_entry_revision(
+    pool: &SqlitePool,
+    revision_id: &str,
+) -> Result<DiaryEntry, AppError> {
+    let vault = Vault::of(pool);
+    let mut tx = pool.begin().await?;
+
+    let revision = sqlx::query_as::<_, EntryRevision>("SELECT * FROM entry_revisions WHERE id = ?")
+        .bind(revision_id)
+        .fetch_optional(&mut *tx)
+        .await?
+        .ok_or_else(|| AppError::EntryNotFound(format!("Revision {revision_id}")))?;
+    let content_json = vault.open(revision.content_json)?;
+    let existing = sqlx::query_as::<_, DiaryEntry>("SELECT * FROM entries WHERE id = ?")
+        .bind(&revision.entry_id)
+        .fetch_optional(&mut *tx)
+        .await?;
+
+    let entry = write_entry_in(
+        &mut tx,
+        &vault,
+        existing,
+        &revision.entry_date,
+        &content_json,
+        true,
+    )
+    .await?;
+    tx.commit().await?;
+
+    Ok(entry)
+}
+
+// AI Operations
+
+pub async fn create_ai_operation(
+    pool: &SqlitePool,
+    entry_id: &str,
+    op_type: &str,
+    original_text: &str,
+    result_text: &str,
+    provider: &str,
+    model: &str,
+) -> Result<AIOperation, AppError> {
+    let vault = Vault::of(pool);
+    let id = Uuid::new_v4().to_string();
+    let now = chrono::Utc::now().timestamp_millis();
+
+    let operation = AIOperation {
+        id: id.clone(),
+        entry_id: entry_id.to_string(),
+        op_type: op_type.to_string(),
+        original_text: original_text.to_string(),
+        result_text: result_text.to_string(),
+        provider: provider.to_string(),
+        model: model.to_string(),
+        created_at: now,
+    };
+
+    sqlx::query(
+        "INSERT INTO ai_operations (id, entry_id, op_type, original_text, result_text, provider, model, created_at)
+         VALUES (?, ?, ?, ?, ?, ?, ?, ?)"
+    )
+    .bind(&operation.id)
+    .bind(&operation.entry_id)
+    .bind(&operation.op_type)
+    .bind(vault.seal(&operation.original_text)?)
+    .bind(vault.seal(&operation.result_text)?)
+    .bind(&operation.provider)
+    .bind(&operation.model)
+    .bind(operation.created_at)
+    .execute(pool)
+    .await?;
+
+    Ok(operation)
+}
+
+pub async fn list_ai_operations(
+    pool: &SqlitePool,
+    entry_id: &str,
+) -> Result<Vec<AIOperation>, AppError> {
+    let operations = sqlx::query_as::<_, AIOperation>(
+        "SELECT * FROM ai_operations
+         WHERE entry_id = ?
+         ORDER BY created_at DESC",
+    )
+    .bind(entry_id)
+    .fetch_all(pool)
+    .await?;
+
+    let vault = Vault::of(pool);
+    operations
+        .into_iter()
+        .map(|operation| open_ai_operation(&vault, operation))
+        .collect()
+}
+
+fn open_ai_operation(vault: &Vault, mut operation: AIOperation) -> Result<AIOperation, AppError> {
+    operation.original_text = vault.open(operation.original_text)?;
+    operation.result_text = vault.open(operation.result_text)?;
+    Ok(operation)
+}
+
+pub async fn delete_ai_operations_for_entry(
+    executor: impl SqliteExecutor<'_>,
+    entry_id: &str,
+) -> Result<u64, AppError> {
+    let result = sqlx::query("DELETE FROM ai_operations WHERE entry_id = ?")
+        .bind(entry_id)
+        .execute(executor)
+        .await?;
+
+    Ok(result.rows_affected())
+}
+
+// ===== Audio Records =====
+
+pub async fn create_audio_record(pool: &SqlitePool, record: &AudioRecord) -> Result<(), AppError> {
+    sqlx::query(
+        "INSERT INTO audio_records (id, entry_id, text, audio_relpath, format, provider, model,
+                                    voice, speed, duration_ms, size_bytes, created_at, cache_key)
+         VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?)",
+    )
+    .bind(&record.id)
+    .bind(&record.entry_id)
+    .bind(Vault::of(pool).seal(&record.text)?)
+    .bind(&record.audio_relpath)
+    .bind(&record.format)
+    .bind(&record.provider)
+    .bind(&record.model)
+    .bind(&record.voice)
+    .bind(record.speed)
+    .bind(record.duration_ms)
+    .bind(record.size_bytes)
+    .bind(record.created_at)
+    .bind(&record.cache_key)
+    .execute(pool)
+    .await?;
+
+    Ok(())
+}
+
+fn open_audio_record(vault: &Vault, mut record: AudioRecord) -> Result<AudioRecord, AppError> {
+    record.text = vault.open(record.text)?;
+    Ok(record)
+}
+
+pub async fn list_audio_records(
+    pool: &SqlitePool,
+    entry_id: &str,
+) -> Result<Vec<AudioRecord>, AppError> {
+    let records = sqlx::query_as::<_, AudioRecord>(
+        "SELECT * FROM audio_records
+         WHERE entry_id = ?
+         ORDER BY created_at DESC",
+    )
+    .bind(entry_id)
+    .fetch_all(pool)
+    .await?;
+
+    let vault = Vault::of(pool);
+    records
+        .into_iter()
+        .map(|record| open_audio_record(&vault, record))
+        .collect()
+}
+
+/// Audio records of every entry outside the trash, oldest first
+pub async fn list_all_audio_records(pool: &SqlitePool) -> Result<Vec<AudioRecord>, AppError> {
+    let records = sqlx::query_as::<_, AudioRecord>(
+        "SELECT a.* FROM audio_records a
+         INNER JOIN entries e ON e.id = a.entry_id
+         WHERE e.deleted_at IS NULL
+         ORDER BY a.created_at ASC",
+    )
+    .fetch_all(pool)
+    .await?;
+
+    let vault = Vault::of(pool);
+    records
+        .into_iter()
+        .map(|record| open_audio_record(&vault, record))
+        .collect()
+}
+
+pub async fn get_audio_record(
+    pool: &SqlitePool,
+    record_id: &str,
+) -> Result<Option<AudioRecord>, AppError> {
+    let record = sqlx::query_as::<_, AudioRecord>("SELECT * FROM audio_records WHERE id = ?")
+        .bind(record_id)
+        .fetch_optional(pool)
+        .await?;
+
+    record
+        .map(|record| open_audio_record(&Vault::of(pool), record))
+        .transpose()
+}
+
+/// Find an audio record of an entry synthesized under a TTS cache key
+pub async fn find_audio_record_by_cache_key(
+    pool: &SqlitePool,
+    entry_id: &str,
+    cache_key: &str,
+) -> Result<Option<AudioRecord>, AppError> {
+    let record = sqlx::query_as::<_, AudioRecord>(
+        "SELECT * FROM audio_records
+         WHERE entry_id = ? AND cache_key = ?
+         ORDER BY created_at DESC
+         LIMIT 1",
+    )
+    .bind(entry_id)
+    .bind(cache_key)
+    .fetch_optional(pool)
+    .await?;
+
+    record
+        .map(|record| open_audio_record(&Vault::of(pool), record))
+        .transpose()
+}
+
+pub async fn delete_audio_record(pool: &SqlitePool, record_id: &str) -> Result<bool, AppError> {
+    let result = sqlx::query("DELETE FROM audio_records WHERE id = ?")
+        .bind(record_id)
+        .execute(pool)
+        .await?;
+
+    Ok(result.rows_affected() > 0)
+}
+
+// ===== Attachments =====
+
+pub async fn create_attachment(pool: &SqlitePool, attachment: &Attachment) -> Result<(), AppError> {
+    insert_attachment(pool, "INSERT", attachment).await
+}
+
+async fn insert_attachment(
+    pool: &SqlitePool,
+    verb: &str,
+    attachment: &Attachment,
+) -> Result<(), AppError> {
+    sqlx::query(&format!(
+        "{verb} INTO attachments (id, entry_id, sha256, file_name, mime_type, size_bytes,
+                                  width, height, has_thumbnail, created_at)
+         VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?)"
+    ))
+    .bind(&attachment.id)
+    .bind(&attachment.entry_id)
+    .bind(&attachment.sha256)
+    .bind(Vault::of(pool).seal(&attachment.file_name)?)
+    .bind(&attachment.mime_type)
+    .bind(attachment.size_bytes)
+    .bind(attachment.width)
+    .bind(attachment.height)
+    .bind(attachment.has_thumbnail)
+    .bind(attachment.created_at)
+    .execute(pool)
+    .await?;
+
+    Ok(())
+}
+
+fn open_attachment(vault: &Vault, mut attachment: Attachment) -> Result<Attachment, AppError> {
+    attachment.file_name = vault.open(attachment.file_name)?;
+    Ok(attachment)
+}
+
+pub async fn list_attachments(
+    pool: &SqlitePool,
+    entry_id: &str,
+) -> Result<Vec<Attachment>, AppError> {
+    let attachments = sqlx::query_as::<_, Attachment>(
+        "SELECT * FROM attachments
+         WHERE entry_id = ?
+         ORDER BY created_at ASC",
+    )
+    .bind(entry_id)
+    .fetch_all(pool)
+    .await?;
+
+    let vault = Vault::of(pool);
+    attachments
+        .into_iter()
+        .map(|attachment| open_attachment(&vault, attachment))
+        .collect()
+}
+
+pub async fn get_attachment(
+    pool: &SqlitePool,
+    attachment_id: &str,
+) -> Result<Option<Attachment>, AppError> {
+    let attachment = sqlx::query_as::<_, Attachment>("SELECT * FROM attachments WHERE id = ?")
+        .bind(attachment_id)
+        .fetch_optional(pool)
+        .await?;
+
+    attachment
+        .map(|attachment| open_attachment(&Vault::of(pool), attachment))
+        .transpose()
+}
+
+pub async fn delete_attachment(pool: &SqlitePool, attachment_id: &str) -> Result<bool, AppError> {
+    let result = sqlx::query("DELETE FROM attachments WHERE id = ?")
+        .bind(attachment_id)
+        .execute(pool)
+        .await?;
+
+    Ok(result.rows_affected() > 0)
+}
+
+/// Hashes of every file still attached somewhere. Attachments of trashed
+/// entries count, so restoring an entry brings its files back.
+pub async fn referenced_attachment_hashes(pool: &SqlitePool) -> Result<HashSet<String>, AppError> {
+    let hashes: Vec<String> = sqlx::query_scalar("SELECT DISTINCT sha256 FROM attachments")
+        .fetch_all(pool)
+        .await?;
+    Ok(hashes.into_iter().collect())
+}
+
+/// Point every attachment of a stored file at its new name
+pub async fn rename_attachment_hash(
+    pool: &SqlitePool,
+    old: &str,
+    new: &str,
+) -> Result<(), AppError> {
+    sqlx::query("UPDATE attachments SET sha256 = ? WHERE sha256 = ?")
+        .bind(new)
+        .bind(old)
+        .execute(pool)
+        .await?;
+    Ok(())
+}
+
+// ===== TTS Cache =====
+
+pub async fn get_tts_cache_entry(
+    pool: &SqlitePool,
+    cache_key: &str,
+) -> Result<Option<TTSCacheEntry>, AppError> {
+    let entry = sqlx::query_as::<_, TTSCacheEntry>("SELECT * FROM tts_cache WHERE cache_key = ?")
+        .bind(cache_key)
+        .fetch_optional(pool)
+        .await?;
+
+    Ok(entry)
+}
+
+pub async fn upsert_tts_cache_entry(
+    pool: &SqlitePool,
+    entry: &TTSCacheEntry,
+) -> Result<(), AppError> {
+    sqlx::query(
+        "INSERT INTO tts_cache (cache_key, relpath, format, provider, model, voice,
+                                duration_ms, size_bytes, created_at, last_used_at)
+         VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?)
+         ON CONFLICT(cache_key) DO UPDATE SET
+             relpath = excluded.relpath,
+             size_bytes = excluded.size_bytes,
+             last_used_at = excluded.last_used_at",
+    )
+    .bind(&entry.cache_key)
+    .bind(&entry.relpath)
+    .bind(&entry.format)
+    .bind(&entry.provider)
+    .bind(&entry.model)
+    .bind(&entry.voice)
+    .bind(entry.duration_ms)
+    .bind(entry.size_bytes)
+    .bind(entry.created_at)
+    .bind(entry.last_used_at)
+    .execute(pool)
+    .await?;
+
+    Ok(())
+}
+
+/// Mark a cached clip as just used
+pub async fn touch_tts_cache_entry(
+    pool: &SqlitePool,
+    cache_key: &str,
+    now: i64,
+) -> Result<(), AppError> {
+    sqlx::query("UPDATE tts_cache SET last_used_at = ? WHERE cache_key = ?")
+        .bind(now)
+        .bind(cache_key)
+        .execute(pool)
+        .await?;
+
+    Ok(())
+}
+
+pub async fn delete_tts_cache_entry(pool: &SqlitePool, cache_key: &str) -> Result<(), AppError> {
+    sqlx::query("DELETE FROM tts_cache WHERE cache_key = ?")
+        .bind(cache_key)
+        .execute(pool)
+        .await?;
+
+    Ok(())
+}
+
+/// Forget every TTS cache key, which were hashed without the data key (used
+/// when encryption is turned on). Saved audio records are kept.
+pub async fn forget_tts_cache_keys(tx: &mut Transaction<'_, Sqlite>) -> Result<(), AppError> {
+    sqlx::query("UPDATE audio_records SET cache_key = NULL")
+        .execute(&mut **tx)
+        .await?;
+    sqlx::query("DELETE FROM tts_cache")
+        .execute(&mut **tx)
+        .await?;
+
+    Ok(())
+}
+
+/// List cached clips, least recently used first
+pub async fn list_tts_cache_entries_lru(pool: &SqlitePool) -> Result<Vec<TTSCacheEntry>, AppError> {
+    let entries =
+        sqlx::query_as::<_, TTSCacheEntry>("SELECT * FROM tts_cache ORDER BY last_used_at ASC")
+            .fetch_all(pool)
+            .await?;
+
+    Ok(entries)
+}
+
+pub async fn tts_cache_total_bytes(pool: &SqlitePool) -> Result<i64, AppError> {
+    let total: i64 = sqlx::query_scalar("SELECT COALESCE(SUM(size_bytes), 0) FROM tts_cache")
+        .fetch_one(pool)
+        .await?;
+
+    Ok(total)
+}
+
+pub async fn tts_cache_entry_count(pool: &SqlitePool) -> Result<i64, AppError> {
+    let count: i64 = sqlx::query_scalar("SELECT COUNT(*) FROM tts_cache")
+        .fetch_one(pool)
+        .await?;
+
+    Ok(count)
+}
+
+// ===== Templates =====
+
+const DEFAULT_TEMPLATE_SETTING: &str = "default_template_id";
+
+/// Trim a template name and check the content is a JSON document
+fn validate_template(name: &str, content_json: &str) -> Result<String, AppError> {
+    let name = name.trim();
+    if name.is_empty() {
+        return Err(AppError::InvalidTemplate(
+            "Name cannot be empty".to_string(),
+        ));
+    }
+    if !serde_json::from_str::<serde_json::Value>(content_json).is_ok_and(|doc| doc.is_object()) {
+        return Err(AppError::InvalidTemplate(format!(
+            "\"{name}\" is not a document"
+        )));
+    }
+    Ok(name.to_string())
+}
+
+/// List all templates by name
+pub async fn list_templates(pool: &SqlitePool) -> Result<Vec<Template>, AppError> {
+    let templates =
+        sqlx::query_as::<_, Template>("SELECT * FROM templates ORDER BY name COLLATE NOCASE")
+            .fetch_all(pool)
+            .await?;
+
+    Ok(templates)
+}
+
+/// Get a template by id
+pub async fn get_template(pool: &SqlitePool, template_id: &str) -> Result<Template, AppError> {
+    sqlx::query_as::<_, Template>("SELECT * FROM templates WHERE id = ?")
+        .bind(template_id)
+        .fetch_optional(pool)
+        .await?
+        .ok_or_else(|| AppError::InvalidTemplate(format!("Template {template_id} does not exist")))
+}
+
+/// Create a template
+pub async fn create_template(
+    pool: &SqlitePool,
+    name: &str,
+    content_json: &str,
+) -> Result<Template, AppError> {
+    let name = validate_template(name, content_json)?;
+    let now = chrono::Utc::now().timestamp_millis();
+    let template = sqlx::query_as::<_, Template>(
+        "INSERT INTO templates (id, name, content_json, created_at, updated_at)
+         VALUES (?, ?, ?, ?, ?)
+         RETURNING *",
+    )
+    .bind(Uuid::new_v4().to_string())
+    .bind(&name)
+    .bind(content_json)
+    .bind(now)
+    .bind(now)
+    .fetch_one(pool)
+    .await?;
+
+    Ok(template)
+}
+
+/// Rename a template and replace its content
+pub async fn update_template(
+    pool: &SqlitePool,
+    template_id: &str,
+    name: &str,
+    content_json: &str,
+) -> Result<Template, AppError> {
+    let name = validate_template(name, content_json)?;
+    sqlx::query_as::<_, Template>(
+        "UPDATE templates SET name = ?, content_json = ?, updated_at = ?
+         WHERE id = ?
+         RETURNING *",
+    )
+    .bind(&name)
+    .bind(content_json)
+    .bind(chrono::Utc::now().timestamp_millis())
+    .bind(template_id)
+    .fetch_optional(pool)
+    .await?
+    .ok_or_else(|| AppError::InvalidTemplate(format!("Template {template_id} does not exist")))
+}
+
+/// Delete a template, unsetting it as the default if it was
+pub async fn delete_template(pool: &SqlitePool, template_id: &str) -> Result<bool, AppError> {
+    let mut tx = pool.begin().await?;
+    sqlx::query("DELETE FROM app_settings WHERE key = ? AND value = ?")
+        .bind(DEFAULT_TEMPLATE_SETTING)
+        .bind(template_id)
+        .execute(&mut *tx)
+        .await?;
+    let result = sqlx::query("DELETE FROM templates WHERE id = ?")
+        .bind(template_id)
+        .execute(&mut *tx)
+        .await?;
+    tx.commit().await?;
+
+    Ok(result.rows_affected() > 0)
+}
+
+/// Get the template settings; by default no template is used
+pub async fn get_template_settings(pool: &SqlitePool) -> Result<TemplateSettings, AppError> {
+    let default_template_id = get_setting(pool, DEFAULT_TEMPLATE_SETTING)
+        .await?
+        .filter(|id| !id.is_empty());
+
+    Ok(TemplateSettings {
+        default_template_id,
+    })
+}
+
+/// Save the template settings
+pub async fn save_template_settings(
+    pool: &SqlitePool,
+    settings: &TemplateSettings,
+) -> Result<(), AppError> {
+    if let Some(template_id) = &settings.default_template_id {
+        get_template(pool, template_id).await?;
+    }
+    save_setting(
+        pool,
+        DEFAULT_TEMPLATE_SETTING,
+        settings.default_template_id.as_deref().unwrap_or(""),
+    )
+    .await
+}
+
+/// Mood of the first entry with one on the day before `entry_date`, as
+/// "emoji mood", or an empty string
+async fn yesterday_mood(
+    pool: &SqlitePool,
+    entry_date: chrono::NaiveDate,
+) -> Result<String, AppError> {
+    let Some(yesterday) = entry_date.pred_opt() else {
+        return Ok(String::new());
+    };
+    let mood: Option<(Option<String>, Option<String>)> = sqlx::query_as(&format!(
+        "SELECT mood_emoji, mood FROM entries
+         WHERE entry_date = ? AND deleted_at IS NULL AND mood IS NOT NULL
+         ORDER BY {DAY_ORDER}
+         LIMIT 1"
+    ))
+    .bind(yesterday.format("%Y-%m-%d").to_string())
+    .fetch_optional(pool)
+    .await?;
+
+    Ok(mood
+        .map(|(emoji, mood)| {
+            [emoji, mood]
+                .into_iter()
+                .flatten()
+                .collect::<Vec<_>>()
+                .join(" ")
+        })
+        .unwrap_or_default())
+}
+
+/// Start a day from a template (the default one if `template_id` is None),
+/// filling in `{{date}}`, `{{weekday}}`, `{{yesterday_mood}}` and `{{prompt}}`.
+/// The prompt is `prompt_id` or else the day's prompt, and is recorded as
+/// answered when the template uses it. The day's first entry is written like
+/// [`upsert_entry`] does, so a day that already has text is refused rather
+/// than overwritten.
+pub async fn create_entry_from_template(
+    pool: &SqlitePool,
+    entry_date: &str,
+    template_id: Option<&str>,
+    prompt_id: Option<&str>,
+) -> Result<DiaryEntry, AppError> {
+    let template_id = match template_id {
+        Some(id) => id.to_string(),
+        None => get_template_settings(pool)
+            .await?
+            .default_template_id
+            .ok_or_else(|| AppError::InvalidTemplate("No default template is set".to_string()))?,
+    };
+    let template = get_template(pool, &template_id).await?;
+
+    let date = chrono::NaiveDate::parse_from_str(entry_date, "%Y-%m-%d")
+        .map_err(|_| AppError::InvalidEntryDate(entry_date.to_string()))?;
+    let prompt = if placeholder_names(&template.content_json).contains("prompt") {
+        Some(match prompt_id {
+            Some(prompt_id) => get_prompt(pool, prompt_id).await?,
+            None => get_daily_prompt(pool, date, None).await?,
+        })
+    } else {
+        None
+    };
+    let values = HashMap::from([
+        ("date", date.format("%B %-d, %Y").to_string()),
+        ("weekday", date.format("%A").to_string()),
+        ("yesterday_mood", yesterday_mood(pool, date).await?),
+        (
+            "prompt",
+            prompt.as_ref().map(|p| p.text.clone()).unwrap_or_default(),
+        ),
+    ]);
+    let content_json = expand_placeholders(&template.content_json, &values)?;
+
+    // Check and write in one transaction, so an autosave landing in between
+    // is never overwritten by the template
+    let vault = Vault::of(pool);
+    let mut tx = pool.begin().await?;
+    let existing = first_entry_of_day(&mut *tx, entry_date).await?;
+    if let Some(existing) = &existing {
+        if !extract_plain_text(&vault.open(existing.content_json.clone())?).is_empty() {
+            return Err(AppError::InvalidTemplate(format!(
+                "{entry_date} already has an entry"
+            )));
+        }
+    }
+    let entry = write_entry_in(&mut tx, &vault, existing, entry_date, &content_json, false).await?;
+    tx.commit().await?;
+
+    if let Some(prompt) = prompt {
+        set_entry_prompt(pool, &entry.id, Some(&prompt.id)).await?;
+    }
+
+    Ok(entry)
+}
+
+// ===== Writing Prompts =====
+
+/// An answered prompt is not picked again for this many days
+pub const PROMPT_REPEAT_DAYS: i64 = 90;
+
+const PROMPT_LEVEL_SETTING: &str = "prompt_level";
+
+/// Check a CEFR level filter or label
+fn validate_prompt_level(level: &str) -> Result<(), AppError> {
+    if !prompts::LEVELS.contains(&level) {
+        return Err(AppError::InvalidPrompt(format!(
+            "Unknown level \"{level}\", expected one of {}",
+            prompts::LEVELS.join(", ")
+        )));
+    }
+    Ok(())
+}
+
+/// Get the prompt settings; by default prompts of every level are used
+pub async fn get_prompt_settings(pool: &SqlitePool) -> Result<PromptSettings, AppError> {
+    let level = get_setting(pool, PROMPT_LEVEL_SETTING)
+        .await?
+        .filter(|level| prompts::LEVELS.contains(&level.as_str()));
+
+    Ok(PromptSettings { level })
+}
+
+/// Save the prompt settings
+pub async fn save_prompt_settings(
+    pool: &SqlitePool,
+    settings: &PromptSettings,
+) -> Result<(), AppError> {
+    if let Some(level) = &settings.level {
+        validate_prompt_level(level).map_err(|e| AppError::InvalidSettings(e.to_string()))?;
+    }
+    save_setting(
+        pool,
+        PROMPT_LEVEL_SETTING,
+        settings.level.as_deref().unwrap_or(""),
+    )
+    .await
+}
+
+/// Every prompt, bundled ones first, with the user's favorites and answers
+async fn all_prompts(pool: &SqlitePool) -> Result<Vec<WritingPrompt>, AppError> {
+    let favorites: HashSet<String> =
+        sqlx::query_scalar("SELECT prompt_id FROM prompt_marks WHERE is_favorite = 1")
+            .fetch_all(pool)
+            .await?
+            .into_iter()
+            .collect();
+    let answered: HashMap<String, String> = sqlx::query_as::<_, (String, String)>(
+        "SELECT ep.prompt_id, MAX(ep.answered_on) FROM entry_prompts ep
+         INNER JOIN entries e ON e.id = ep.entry_id AND e.deleted_at IS NULL
+         GROUP BY ep.prompt_id",
+    )
+    .fetch_all(pool)
+    .await?
+    .into_iter()
+    .collect();
+    let custom: Vec<(String, String, Option<String>, Option<String>)> =
+        sqlx::query_as("SELECT id, text, level, topic FROM custom_prompts ORDER BY created_at")
+            .fetch_all(pool)
+            .await?;
+
+    let builtin = prompts::BUILTIN_PROMPTS.iter().map(|p| {
+        (
+            p.id.to_string(),
+            p.text.to_string(),
+            Some(p.level.to_string()),
+            Some(p.topic.to_string()),
+            false,
+        )
+    });
+    let custom = custom
+        .into_iter()
+        .map(|(id, text, level, topic)| (id, text, level, topic, true));
+
+    Ok(builtin
+        .chain(custom)
+        .map(|(id, text, level, topic, is_custom)| WritingPrompt {
+            is_favorite: favorites.contains(&id),
+            last_answered_on: answered.get(&id).cloned(),
+            id,
+            text,
+            level,
+            topic,
+            is_custom,
+        })
+        .collect())
+}
+
+/// List prompts, optionally only those of one level, one topic, or favorites
+pub async fn list_prompts(
+    pool: &SqlitePool,
+    level: Option<&str>,
+    topic: Option<&str>,
+    favorites_only: bool,
+) -> Result<Vec<WritingPrompt>, AppError> {
+    if let Some(level) = level {
+        validate_prompt_level(level)?;
+    }
+    let prompts = all_prompts(pool)
+        .await?
+        .into_iter()
+        .filter(|p| level.is_none() || p.level.as_deref() == level)
+        .filter(|p| topic.is_none() || p.topic.as_deref() == topic)
+        .filter(|p| !favorites_only || p.is_favorite)
+        .collect();
+
+    Ok(prompts)
+}
+
+/// Get a prompt by id
+pub async fn get_prompt(pool: &SqlitePool, prompt_id: &str) -> Result<WritingPrompt, AppError> {
+    all_prompts(pool)
+        .await?
+        .into_iter()
+        .find(|p| p.id == prompt_id)
+        .ok_or_else(|| AppError::InvalidPrompt(format!("Prompt {prompt_id} does not exist")))
+}
+
+/// The prompt for a diary day, of `level` or else the level in the prompt
+/// settings. A day whose entry answered a prompt keeps that prompt. Otherwise
+/// one is picked by date from the prompts not skipped that day and not
+/// answered in the [`PROMPT_REPEAT_DAYS`] before it, so asking again gives
+/// the same prompt.
+pub async fn get_daily_prompt(
+    pool: &SqlitePool,
+    date: chrono::NaiveDate,
+    level: Option<&str>,
+) -> Result<WritingPrompt, AppError> {
+    let day = date.format("%Y-%m-%d").to_string();
+    let answered_today: Option<String> = sqlx::query_scalar(
+        "SELECT ep.prompt_id FROM entry_prompts ep
+         INNER JOIN entries e ON e.id = ep.entry_id AND e.deleted_at IS NULL
+         WHERE ep.answered_on = ?
+         ORDER BY ep.created_at
+         LIMIT 1",
+    )
+    .bind(&day)
+    .fetch_optional(pool)
+    .await?;
+    if let Some(prompt_id) = answered_today {
+        return get_prompt(pool, &prompt_id).await;
+    }
+
+    let level = match level {
+        Some(level) => Some(level.to_string()),
+        None => get_prompt_settings(pool).await?.level,
+    };
+    let candidates = list_prompts(pool, level.as_deref(), None, false).await?;
+    let skipped: HashSet<String> =
+        sqlx::query_scalar("SELECT prompt_id FROM prompt_marks WHERE skipped_on = ?")
+            .bind(&day)
+            .fetch_all(pool)
+            .await?
+            .into_iter()
+            .collect();
+    let repeat_after = (date - chrono::Duration::days(PROMPT_REPEAT_DAYS))
+        .format("%Y-%m-%d")
+        .to_string();
+    let fresh = |p: &WritingPrompt| {
+        p.last_answered_on
+            .as_deref()
+            .is_none_or(|answered| answered < repeat_after.as_str() || answered > day.as_str())
+    };
+
+    // Relax the rules rather than come up empty once everything was used
+    let pick = |allowed: &dyn Fn(&WritingPrompt) -> bool| {
+        candidates
+            .iter()
+            .filter(|p| allowed(p))
+            .min_by_key(|p| prompts::daily_rank(date, &p.id))
+    };
+    let prompt = pick(&|p| !skipped.contains(&p.id) && fresh(p))
+        .or_else(|| pick(&|p| !skipped.contains(&p.id)))
+        .or_else(|| pick(&|_| true))
+        .ok_or_else(|| {
+            AppError::InvalidPrompt(format!("No prompts at level {}", level.unwrap_or_default()))
+        })?;
+
+    Ok(prompt.clone())
+}
+
+/// Skip a prompt for a diary day and return the day's next prompt
+pub async fn skip_prompt(
+    pool: &SqlitePool,
+    prompt_id: &str,
+    date: chrono::NaiveDate,
+    level: Option<&str>,
+) -> Result<WritingPrompt, AppError> {
+    get_prompt(pool, prompt_id).await?;
+    sqlx::query(
+        "INSERT INTO prompt_marks (prompt_id, skipped_on, updated_at) VALUES (?, ?, ?)
+         ON CONFLICT(prompt_id) DO UPDATE SET skipped_on = excluded.skipped_on,
+             updated_at = excluded.updated_at",
+    )
+    .bind(prompt_id)
+    .bind(date.format("%Y-%m-%d").to_string())
+    .bind(chrono::Utc::now().timestamp_millis())
+    .execute(pool)
+    .await?;
+
+    get_daily_prompt(pool, date, level).await
+}
+
+/// Mark or unmark a prompt as a favorite
+pub async fn set_prompt_favorite(
+    pool: &SqlitePool,
+    prompt_id: &str,
+    favorite: bool,
+) -> Result<WritingPrompt, AppError> {
+    get_prompt(pool, prompt_id).await?;
+    sqlx::query(
+        "INSERT INTO prompt_marks (prompt_id, is_favorite, updated_at) VALUES (?, ?, ?)
+         ON CONFLICT(prompt_id) DO UPDATE SET is_favorite = excluded.is_favorite,
+             updated_at = excluded.updated_at",
+    )
+    .bind(prompt_id)
+    .bind(favorite)
+    .bind(chrono::Utc::now().timestamp_millis())
+    .execute(pool)
+    .await?;
+
+    get_prompt(pool, prompt_id).await
+}
+
+/// Add a prompt of the user's own
+pub async fn add_custom_prompt(
+    pool: &SqlitePool,
+    text: &str,
+    level: Option<&str>,
+    topic: Option<&str>,
+) -> Result<WritingPrompt, AppError> {
+    let text = text.trim();
+    if text.is_empty() {
+        return Err(AppError::InvalidPrompt(
+            "Prompt cannot be empty".to_string(),
+        ));
+    }
+    if let Some(level) = level {
+        validate_prompt_level(level)?;
+    }
+    let topic = topic.map(str::trim).filter(|t| !t.is_empty());
+
+    let id = Uuid::new_v4().to_string();
+    sqlx::query(
+        "INSERT INTO custom_prompts (id, text, level, topic, created_at) VALUES (?, ?, ?, ?, ?)",
+    )
+    .bind(&id)
+    .bind(text)
+    .bind(level)
+    .bind(topic)
+    .bind(chrono::Utc::now().timestamp_millis())
+    .execute(pool)
+    .await?;
+
+    get_prompt(pool, &id).await
+}
+
+/// Delete a prompt of the user's own. Entries keep their text; only the
+/// record of having answered it goes.
+pub async fn delete_custom_prompt(pool: &SqlitePool, prompt_id: &str) -> Result<bool, AppError> {
+    let mut tx = pool.begin().await?;
+    for table in ["prompt_marks", "entry_prompts"] {
+        sqlx::query(&format!("DELETE FROM {table} WHERE prompt_id = ?"))
+            .bind(prompt_id)
+            .execute(&mut *tx)
+            .await?;
+    }
+    let result = sqlx::query("DELETE FROM custom_prompts WHERE id = ?")
+        .bind(prompt_id)
+        .execute(&mut *tx)
+        .await?;
+    tx.commit().await?;
+
+    Ok(result.rows_affected() > 0)
 }
 
-pub async fn list_ai_operations(
+/// Record the prompt an entry answers, or clear it with None
+pub async fn set_entry_prompt(
     pool: &SqlitePool,
     entry_id: &str,
-) -> Result<Vec<AIOperation>, AppError> {
-    let operations = sqlx::query_as::<_, AIOperation>(
-        "SELECT * FROM ai_operations
-         WHERE entry_id = ?
-         ORDER BY created_at DESC",
+    prompt_id: Option<&str>,
+) -> Result<(), AppError> {
+    let Some(prompt_id) = prompt_id else {
+        sqlx::query("DELETE FROM entry_prompts WHERE entry_id = ?")
+            .bind(entry_id)
+            .execute(pool)
+            .await?;
+        return Ok(());
+    };
+    get_prompt(pool, prompt_id).await?;
+    let entry_date: String =
+        sqlx::query_scalar("SELECT entry_date FROM entries WHERE id = ? AND deleted_at IS NULL")
+            .bind(entry_id)
+            .fetch_optional(pool)
+            .await?
+            .ok_or_else(|| AppError::EntryNotFound(entry_id.to_string()))?;
+
+    sqlx::query(
+        "INSERT INTO entry_prompts (entry_id, prompt_id, answered_on, created_at) VALUES (?, ?, ?, ?)
+         ON CONFLICT(entry_id) DO UPDATE SET prompt_id = excluded.prompt_id,
+             answered_on = excluded.answered_on",
     )
     .bind(entry_id)
-    .fetch_all(pool)
+    .bind(prompt_id)
+    .bind(entry_date)
+    .bind(chrono::Utc::now().timestamp_millis())
+    .execute(pool)
     .await?;
 
-    Ok(operations)
+    Ok(())
 }
 
-pub async fn delete_ai_operations_for_entry(
+/// The prompt an entry answers, if any
+pub async fn get_entry_prompt(
     pool: &SqlitePool,
     entry_id: &str,
-) -> Result<u64, AppError> {
-    let result = sqlx::query("DELETE FROM ai_operations WHERE entry_id = ?")
-        .bind(entry_id)
-        .execute(pool)
-        .await?;
-
-    Ok(result.rows_affected())
+) -> Result<Option<WritingPrompt>, AppError> {
+    let prompt_id: Option<String> =
+        sqlx::query_scalar("SELECT prompt_id FROM entry_prompts WHERE entry_id = ?")
+            .bind(entry_id)
+            .fetch_optional(pool)
+            .await?;
+    match prompt_id {
+        Some(prompt_id) => Ok(Some(get_prompt(pool, &prompt_id).await?)),
+        None => Ok(None),
+    }
 }
 
 // ===== App Settings =====
 
 /// Save an app setting (key-value store)
-pub async fn save_setting(pool: &SqlitePool, key: &str, value: &str) -> Result<(), AppError> {
+pub async fn save_setting(
+    executor: impl SqliteExecutor<'_>,
+    key: &str,
+    value: &str,
+) -> Result<(), AppError> {
     let now = chrono::Utc::now().timestamp_millis();
 
     sqlx::query(
@@ -180,7 +2097,7 @@ pub async fn save_setting(pool: &SqlitePool, key: &str, value: &str) -> Result<(
     .bind(now)
     .bind(value)
     .bind(now)
-    .execute(pool)
+    .execute(executor)
     .await?;
 
     Ok(())
@@ -198,60 +2115,64 @@ pub async fn get_setting(pool: &SqlitePool, key: &str) -> Result<Option<String>,
 
 // ===== Mood Tracking =====
 
-/// Update or create an entry with mood information
+/// Set the mood of the first entry of a day, creating an empty entry if the day has none
 pub async fn upsert_entry_mood(
     pool: &SqlitePool,
     entry_date: &str,
     mood: Option<&str>,
     mood_emoji: Option<&str>,
 ) -> Result<DiaryEntry, AppError> {
+    let vault = Vault::of(pool);
+    vault.ensure_unlocked()?;
+
+    let entry = match first_entry_of_day(pool, entry_date).await? {
+        Some(entry) => entry,
+        None => {
+            let mut tx = pool.begin().await?;
+            let entry = write_entry_in(
+                &mut tx,
+                &vault,
+                None,
+                entry_date,
+                &json!({}).to_string(),
+                false,
+            )
+            .await?;
+            tx.commit().await?;
+            entry
+        }
+    };
+
+    set_entry_mood(pool, &entry.id, mood, mood_emoji).await
+}
+
+/// Set the mood of a specific entry
+pub async fn set_entry_mood(
+    pool: &SqlitePool,
+    entry_id: &str,
+    mood: Option<&str>,
+    mood_emoji: Option<&str>,
+) -> Result<DiaryEntry, AppError> {
+    let vault = Vault::of(pool);
+    vault.ensure_unlocked()?;
     let now = chrono::Utc::now().timestamp_millis();
 
-    // First try to update existing entry
-    let result = sqlx::query_as::<_, DiaryEntry>(
+    let entry = sqlx::query_as::<_, DiaryEntry>(
         "UPDATE entries
          SET mood = ?, mood_emoji = ?, updated_at = ?
-         WHERE entry_date = ?
+         WHERE id = ? AND deleted_at IS NULL
          RETURNING *",
     )
     .bind(mood)
     .bind(mood_emoji)
     .bind(now)
-    .bind(entry_date)
+    .bind(entry_id)
     .fetch_optional(pool)
-    .await?;
-
-    if let Some(entry) = result {
-        Ok(entry)
-    } else {
-        // Entry doesn't exist, create it with mood
-        let id = Uuid::new_v4().to_string();
-        let entry = DiaryEntry {
-            id: id.clone(),
-            entry_date: entry_date.to_string(),
-            content_json: serde_json::to_string(&json!({})).unwrap(), // Empty content
-            mood: mood.map(|s| s.to_string()),
-            mood_emoji: mood_emoji.map(|s| s.to_string()),
-            created_at: now,
-            updated_at: now,
-        };
-
-        sqlx::query(
-            "INSERT INTO entries (id, entry_date, content_json, mood, mood_emoji, created_at, updated_at)
-             VALUES (?, ?, ?, ?, ?, ?, ?)"
-        )
-        .bind(&entry.id)
-        .bind(&entry.entry_date)
-        .bind(&entry.content_json)
-        .bind(&entry.mood)
-        .bind(&entry.mood_emoji)
-        .bind(entry.created_at)
-        .bind(entry.updated_at)
-        .execute(pool)
-        .await?;
+    .await?
+    .ok_or_else(|| AppError::EntryNotFound(entry_id.to_string()))?;
+    reindex_entries(pool, std::slice::from_ref(&entry.id)).await?;
 
-        Ok(entry)
-    }
+    open_entry(&vault, entry)
 }
 
 /// List entries by mood for a given month
@@ -260,75 +2181,442 @@ pub async fn list_entries_by_mood(
     month: &str, // YYYY-MM
     mood: &str,
 ) -> Result<Vec<DiaryEntry>, AppError> {
-    let entries = sqlx::query_as::<_, DiaryEntry>(
+    let entries = sqlx::query_as::<_, DiaryEntry>(&format!(
         "SELECT * FROM entries
-         WHERE entry_date LIKE ? AND mood = ?
-         ORDER BY entry_date DESC",
-    )
+         WHERE entry_date LIKE ? AND mood = ? AND deleted_at IS NULL
+         ORDER BY entry_date DESC, {DAY_ORDER}"
+    ))
     .bind(format!("{}%", month))
     .bind(mood)
     .fetch_all(pool)
     .await?;
 
-    Ok(entries)
+    open_entries(&Vault::of(pool), entries)
 }
 
-// ===== Full-Text Search =====
+// ===== Search Index =====
 
-/// Search entries by full-text query
-/// Returns entries matching the search query, ordered by relevance
-pub async fn search_entries(pool: &SqlitePool, query: &str) -> Result<Vec<DiaryEntry>, AppError> {
-    // Use FTS5 to search, then join with entries table to get full entry data
-    let entries = sqlx::query_as::<_, DiaryEntry>(
-        "SELECT e.* FROM entries e
-         INNER JOIN entries_fts fts ON e.id = fts.entry_id
-         WHERE entries_fts MATCH ?
-         ORDER BY bm25(entries_fts) DESC, e.entry_date DESC",
-    )
-    .bind(query)
-    .fetch_all(pool)
+const INDEX_ROW_SELECT: &str = "
+    SELECT e.id, COALESCE(e.title, ''), COALESCE(e.summary, ''), e.content_text,
+           COALESCE(e.mood, ''),
+           COALESCE((SELECT group_concat(t.name, ' ') FROM entry_tags et
+                     JOIN tags t ON t.id = et.tag_id WHERE et.entry_id = e.id), '')
+    FROM entries e";
+
+type IndexRow = (String, String, String, String, String, String);
+
+/// The in-memory index used while encryption is on, with the same layout as
+/// `entries_fts` (see migration 014)
+const SECURE_INDEX_SQL: &str = concat!(
+    "CREATE VIRTUAL TABLE IF NOT EXISTS secure.entries_fts USING fts5(
+        entry_id UNINDEXED, title, summary, content, mood, tags,
+        tokenize = \"unicode61 separators '",
+    "\u{E002}",
+    "'\")"
+);
+
+/// Insert index rows, segmenting CJK text so Chinese substrings are searchable
+async fn insert_index_rows(
+    conn: &mut SqliteConnection,
+    vault: &Vault,
+    rows: Vec<IndexRow>,
+) -> Result<(), AppError> {
+    let sql = format!(
+        "INSERT INTO {}(entry_id, title, summary, content, mood, tags) VALUES (?, ?, ?, ?, ?, ?)",
+        vault.fts_table()
+    );
+    for (entry_id, title, summary, content, mood, tags) in rows {
+        sqlx::query(&sql)
+            .bind(entry_id)
+            .bind(segment(&vault.open(title)?))
+            .bind(segment(&vault.open(summary)?))
+            .bind(segment(&vault.open(content)?))
+            .bind(mood)
+            .bind(segment(&tags))
+            .execute(&mut *conn)
+            .await?;
+    }
+
+    Ok(())
+}
+
+/// Refresh the full-text index row of one entry from its plain text, mood and tags.
+/// Does nothing while locked; the index is rebuilt on unlock.
+pub async fn reindex_entry(
+    conn: &mut SqliteConnection,
+    vault: &Vault,
+    entry_id: &str,
+) -> Result<(), AppError> {
+    if vault.is_locked() {
+        return Ok(());
+    }
+
+    sqlx::query(&format!(
+        "DELETE FROM {} WHERE entry_id = ?",
+        vault.fts_table()
+    ))
+    .bind(entry_id)
+    .execute(&mut *conn)
     .await?;
+    let rows = sqlx::query_as(&format!("{INDEX_ROW_SELECT} WHERE e.id = ?"))
+        .bind(entry_id)
+        .fetch_all(&mut *conn)
+        .await?;
+    insert_index_rows(conn, vault, rows).await
+}
 
-    Ok(entries)
+async fn reindex_entries(pool: &SqlitePool, entry_ids: &[String]) -> Result<(), AppError> {
+    let vault = Vault::of(pool);
+    let mut conn = pool.acquire().await?;
+    for entry_id in entry_ids {
+        reindex_entry(&mut conn, &vault, entry_id).await?;
+    }
+
+    Ok(())
+}
+
+/// Rebuild the whole full-text index. With encryption on, the on-disk index
+/// is emptied and the in-memory one rebuilt (or left unbuilt while locked).
+pub async fn rebuild_search_index(
+    conn: &mut SqliteConnection,
+    vault: &Vault,
+) -> Result<(), AppError> {
+    sqlx::query("DELETE FROM entries_fts")
+        .execute(&mut *conn)
+        .await?;
+
+    match vault {
+        Vault::Plain => {}
+        Vault::Locked => return Ok(()),
+        Vault::Unlocked(_) => {
+            sqlx::query(SECURE_INDEX_SQL).execute(&mut *conn).await?;
+            sqlx::query(&format!("DELETE FROM {}", vault.fts_table()))
+                .execute(&mut *conn)
+                .await?;
+        }
+    }
+
+    let rows = sqlx::query_as(INDEX_ROW_SELECT)
+        .fetch_all(&mut *conn)
+        .await?;
+    insert_index_rows(conn, vault, rows).await
+}
+
+/// Recompute `content_text` from `content_json` for every entry
+pub async fn refresh_content_text(
+    conn: &mut SqliteConnection,
+    vault: &Vault,
+) -> Result<(), AppError> {
+    let rows: Vec<(String, String)> = sqlx::query_as("SELECT id, content_json FROM entries")
+        .fetch_all(&mut *conn)
+        .await?;
+
+    for (id, content_json) in rows {
+        let content_text = extract_plain_text(&vault.open(content_json)?);
+        sqlx::query("UPDATE entries SET content_text = ? WHERE id = ?")
+            .bind(vault.seal(&content_text)?)
+            .bind(&id)
+            .execute(&mut *conn)
+            .await?;
+    }
+
+    Ok(())
+}
+
+/// Encrypt all diary text in place (used when encryption is turned on)
+pub async fn seal_all_content(
+    tx: &mut Transaction<'_, Sqlite>,
+    vault: &Vault,
+) -> Result<(), AppError> {
+    for (table, columns) in [
+        (
+            "entries",
+            &["content_json", "content_text", "title", "summary"][..],
+        ),
+        ("entry_revisions", &["content_json"][..]),
+        ("ai_operations", &["original_text", "result_text"][..]),
+        ("audio_records", &["text"][..]),
+        ("attachments", &["file_name"][..]),
+    ] {
+        for column in columns {
+            let rows: Vec<(String, String)> = sqlx::query_as(&format!(
+                "SELECT id, {column} FROM {table} WHERE {column} IS NOT NULL"
+            ))
+            .fetch_all(&mut **tx)
+            .await?;
+            let update = format!("UPDATE {table} SET {column} = ? WHERE id = ?");
+            for (id, value) in rows {
+                // Already-encrypted values pass through open() unchanged in meaning
+                let sealed = vault.seal(&vault.open(value)?)?;
+                sqlx::query(&update)
+                    .bind(sealed)
+                    .bind(&id)
+                    .execute(&mut **tx)
+                    .await?;
+            }
+        }
+    }
+
+    Ok(())
+}
+
+// ===== Full-Text Search =====
+
+/// Default and maximum page sizes for search results
+pub const DEFAULT_SEARCH_LIMIT: i64 = 20;
+pub const MAX_SEARCH_LIMIT: i64 = 100;
+
+/// Private-use characters marking highlighted terms in FTS5 snippets
+const HIGHLIGHT_START: char = '\u{E000}';
+const HIGHLIGHT_END: char = '\u{E001}';
+
+#[derive(FromRow)]
+struct SearchHitRow {
+    entry_id: String,
+    entry_date: String,
+    title: Option<String>,
+    mood: Option<String>,
+    mood_emoji: Option<String>,
+    snippet: String,
+    score: f64,
+}
+
+/// Length of the plain-text preview shown for filter-only searches
+const PREVIEW_CHARS: usize = 160;
+
+/// Search entries with the query language in [`crate::search::query`].
+/// Returns one page of hits ordered best match first, plus the total hit count.
+/// Queries without any full-text term (only filters or negations) are ordered
+/// newest first and show the start of the entry as their snippet.
+pub async fn search_entries(
+    pool: &SqlitePool,
+    query: &str,
+    limit: i64,
+    offset: i64,
+) -> Result<SearchResults, AppError> {
+    let limit = limit.clamp(1, MAX_SEARCH_LIMIT);
+    let offset = offset.max(0);
+    let vault = Vault::of(pool);
+    vault.ensure_unlocked()?;
+
+    let parsed = parse_query(query)?;
+    if parsed.is_empty() {
+        return Ok(SearchResults {
+            hits: Vec::new(),
+            total: 0,
+            limit,
+            offset,
+        });
+    }
+    let fts = parsed.fts_expression();
+
+    let mut count = QueryBuilder::<Sqlite>::new("SELECT COUNT(*) FROM entries e ");
+    let mut select = QueryBuilder::<Sqlite>::new(
+        "SELECT e.id AS entry_id, e.entry_date, e.title, e.mood, e.mood_emoji, ",
+    );
+    match &fts {
+        Some(_) => {
+            // bm25() is lower for better matches; flip the sign so a higher score is better
+            select.push(
+                "snippet(entries_fts, -1, char(57344), char(57345), '…', 16) AS snippet,
+                 -bm25(entries_fts) AS score
+                 FROM entries e ",
+            );
+        }
+        None => {
+            select.push("e.content_text AS snippet, 0.0 AS score FROM entries e ");
+        }
+    }
+
+    for qb in [&mut count, &mut select] {
+        if let Some(expr) = &fts {
+            qb.push(format!(
+                "INNER JOIN {} ON entries_fts.entry_id = e.id WHERE entries_fts MATCH ",
+                vault.fts_table()
+            ));
+            qb.push_bind(expr.clone());
+            qb.push(" AND ");
+        } else {
+            qb.push("WHERE ");
+        }
+        qb.push("e.deleted_at IS NULL");
+        parsed.push_predicates(qb, vault.fts_table());
+    }
+
+    let total: i64 = count.build_query_scalar().fetch_one(pool).await?;
+
+    if fts.is_some() {
+        select.push(" ORDER BY bm25(entries_fts) ASC, e.entry_date DESC");
+    } else {
+        select.push(" ORDER BY e.entry_date DESC");
+    }
+    select.push(" LIMIT ");
+    select.push_bind(limit);
+    select.push(" OFFSET ");
+    select.push_bind(offset);
+
+    let rows = select
+        .build_query_as::<SearchHitRow>()
+        .fetch_all(pool)
+        .await?;
+
+    let hits = rows
+        .into_iter()
+        .map(|row| {
+            let snippet = if fts.is_some() {
+                parse_snippet(&row.snippet)
+            } else {
+                vec![SnippetSegment {
+                    text: preview_of(&vault.open(row.snippet)?),
+                    highlight: false,
+                }]
+            };
+            Ok(SearchHit {
+                entry_id: row.entry_id,
+                entry_date: row.entry_date,
+                title: row.title.map(|title| vault.open(title)).transpose()?,
+                mood: row.mood,
+                mood_emoji: row.mood_emoji,
+                snippet,
+                score: row.score,
+            })
+        })
+        .collect::<Result<_, AppError>>()?;
+
+    Ok(SearchResults {
+        hits,
+        total,
+        limit,
+        offset,
+    })
+}
+
+/// Split an FTS5 snippet into plain and highlighted segments.
+/// Segmented CJK characters are highlighted one by one, so adjacent
+/// highlighted characters are merged back into a single segment.
+fn parse_snippet(snippet: &str) -> Vec<SnippetSegment> {
+    let mut segments: Vec<SnippetSegment> = Vec::new();
+    let mut current = String::new();
+    let mut highlight = false;
+
+    let mut flush = |text: String, highlight: bool| {
+        if text.is_empty() {
+            return;
+        }
+        match segments.last_mut() {
+            Some(last) if last.highlight == highlight => last.text.push_str(&text),
+            _ => segments.push(SnippetSegment { text, highlight }),
+        }
+    };
+
+    for ch in desegment(snippet).chars() {
+        if ch == HIGHLIGHT_START || ch == HIGHLIGHT_END {
+            flush(std::mem::take(&mut current), highlight);
+            highlight = ch == HIGHLIGHT_START;
+        } else {
+            current.push(ch);
+        }
+    }
+    flush(current, highlight);
+
+    segments
 }
 
 // ===== Statistics =====
 
+const DAY_START_HOUR_SETTING: &str = "day_start_hour";
+
+/// Get the day boundary settings; by default days start at midnight
+pub async fn get_day_settings(pool: &SqlitePool) -> Result<DaySettings, AppError> {
+    let day_start_hour = get_setting(pool, DAY_START_HOUR_SETTING)
+        .await?
+        .and_then(|value| value.parse::<u32>().ok())
+        .filter(|hour| *hour < 24)
+        .unwrap_or(0);
+
+    Ok(DaySettings { day_start_hour })
+}
+
+/// Save the day boundary settings
+pub async fn save_day_settings(pool: &SqlitePool, settings: &DaySettings) -> Result<(), AppError> {
+    if settings.day_start_hour > 23 {
+        return Err(AppError::InvalidSettings(format!(
+            "Day start hour must be between 0 and 23, got {}",
+            settings.day_start_hour
+        )));
+    }
+    save_setting(
+        pool,
+        DAY_START_HOUR_SETTING,
+        &settings.day_start_hour.to_string(),
+    )
+    .await
+}
+
+/// The diary date at `now`: before `day_start_hour` on the local wall clock it
+/// is still the previous day. Wall-clock time rather than elapsed time keeps
+/// the boundary at the same hour across DST changes.
+pub fn journal_date<Tz: chrono::TimeZone>(
+    now: &chrono::DateTime<Tz>,
+    day_start_hour: u32,
+) -> chrono::NaiveDate {
+    let local = now.naive_local();
+    if local.hour() < day_start_hour {
+        local.date() - chrono::Duration::days(1)
+    } else {
+        local.date()
+    }
+}
+
+/// Today's diary date in the local timezone
+pub async fn journal_today(pool: &SqlitePool) -> Result<chrono::NaiveDate, AppError> {
+    let settings = get_day_settings(pool).await?;
+    Ok(journal_date(&chrono::Local::now(), settings.day_start_hour))
+}
+
 /// Get writing statistics
 pub async fn get_writing_stats(pool: &SqlitePool) -> Result<WritingStats, AppError> {
     // Get total entry count
-    let total_count: i64 = sqlx::query_scalar("SELECT COUNT(*) FROM entries")
-        .fetch_one(pool)
-        .await?;
-
-    // Get all entry dates sorted
-    let dates: Vec<String> =
-        sqlx::query_scalar("SELECT entry_date FROM entries ORDER BY entry_date ASC")
-            .fetch_all(pool)
+    let total_count: i64 =
+        sqlx::query_scalar("SELECT COUNT(*) FROM entries WHERE deleted_at IS NULL")
+            .fetch_one(pool)
             .await?;
 
+    // Get all distinct entry dates sorted; a day with several entries counts once
+    let dates: Vec<String> = sqlx::query_scalar(
+        "SELECT DISTINCT entry_date FROM entries WHERE deleted_at IS NULL ORDER BY entry_date ASC",
+    )
+    .fetch_all(pool)
+    .await?;
+
     // Calculate current streak (consecutive days ending today or before)
-    let current_streak = calculate_current_streak(&dates);
+    let current_streak = calculate_current_streak(&dates, journal_today(pool).await?);
 
     // Calculate longest streak
     let longest_streak = calculate_longest_streak(&dates);
 
     Ok(WritingStats {
         total_entries: total_count,
+        total_days: dates.len() as i64,
         current_streak,
         longest_streak,
     })
 }
 
-/// Calculate the current streak of consecutive days
-fn calculate_current_streak(dates: &[String]) -> i64 {
+/// Calculate the current streak of consecutive days ending at diary date `today`.
+/// Today can still be written, so without an entry yet the streak ends yesterday.
+pub fn calculate_current_streak(dates: &[String], today: chrono::NaiveDate) -> i64 {
     if dates.is_empty() {
         return 0;
     }
 
-    let today = chrono::Utc::now().date_naive();
+    let today_str = today.format("%Y-%m-%d").to_string();
     let mut streak_count: i64 = 0;
-    let mut check_date = today;
+    let mut check_date = if dates.contains(&today_str) {
+        today
+    } else {
+        today - chrono::Duration::days(1)
+    };
 
     for date_str in dates.iter().rev() {
         if let Ok(entry_date) = chrono::NaiveDate::parse_from_str(date_str, "%Y-%m-%d") {
@@ -378,77 +2666,314 @@ fn calculate_longest_streak(dates: &[String]) -> i64 {
     longest_streak.max(current_streak)
 }
 
+/// Totals for one period while [`get_writing_analytics`] accumulates them
+#[derive(Default)]
+struct PeriodTotals {
+    entries: i64,
+    days: BTreeSet<String>,
+    words: i64,
+    characters: i64,
+}
+
+impl PeriodTotals {
+    fn add(&mut self, entry_date: &str, counts: TextCounts) {
+        self.entries += 1;
+        self.days.insert(entry_date.to_string());
+        self.words += counts.words;
+        self.characters += counts.characters;
+    }
+}
+
+fn average(total: i64, count: i64) -> f64 {
+    if count == 0 {
+        0.0
+    } else {
+        total as f64 / count as f64
+    }
+}
+
+fn period_stats(periods: BTreeMap<String, PeriodTotals>) -> Vec<PeriodStats> {
+    periods
+        .into_iter()
+        .map(|(period, totals)| PeriodStats {
+            period,
+            entries: totals.entries,
+            days: totals.days.len() as i64,
+            words: totals.words,
+            characters: totals.characters,
+            average_words: average(totals.words, totals.entries),
+        })
+        .collect()
+}
+
+/// Get detailed writing statistics. Counts come from the extracted plain
+/// text, so formatting in the stored JSON never counts as words.
+pub async fn get_writing_analytics(pool: &SqlitePool) -> Result<WritingAnalytics, AppError> {
+    let rows: Vec<(String, String, Option<String>, String)> = sqlx::query_as(&format!(
+        "SELECT id, entry_date, title, content_text FROM entries
+         WHERE deleted_at IS NULL
+         ORDER BY entry_date ASC, {DAY_ORDER}"
+    ))
+    .fetch_all(pool)
+    .await?;
+
+    let vault = Vault::of(pool);
+    let mut entries = Vec::with_capacity(rows.len());
+    let mut weeks: BTreeMap<String, PeriodTotals> = BTreeMap::new();
+    let mut months: BTreeMap<String, PeriodTotals> = BTreeMap::new();
+    let mut years: BTreeMap<String, PeriodTotals> = BTreeMap::new();
+    let mut weekdays: Vec<WeekdayStats> = (0..7)
+        .map(|weekday| WeekdayStats {
+            weekday,
+            entries: 0,
+            words: 0,
+        })
+        .collect();
+    let mut total = TextCounts::default();
+
+    for (entry_id, entry_date, title, content_text) in rows {
+        let counts = count_text(&vault.open(content_text)?);
+        total.words += counts.words;
+        total.characters += counts.characters;
+
+        if let Ok(date) = chrono::NaiveDate::parse_from_str(&entry_date, "%Y-%m-%d") {
+            let week = date.iso_week();
+            weeks
+                .entry(format!("{}-W{:02}", week.year(), week.week()))
+                .or_default()
+                .add(&entry_date, counts);
+            months
+                .entry(entry_date[..7].to_string())
+                .or_default()
+                .add(&entry_date, counts);
+            years
+                .entry(entry_date[..4].to_string())
+                .or_default()
+                .add(&entry_date, counts);
+            let weekday = &mut weekdays[date.weekday().num_days_from_monday() as usize];
+            weekday.entries += 1;
+            weekday.words += counts.words;
+        }
+
+        entries.push(EntryTextStats {
+            entry_id,
+            entry_date,
+            title: title.map(|title| vault.open(title)).transpose()?,
+            words: counts.words,
+            characters: counts.characters,
+        });
+    }
+
+    // AI operations of trashed entries are left out, like the entries themselves
+    let ai_usage = sqlx::query_as::<_, AIUsage>(
+        "SELECT a.op_type, COUNT(*) AS count FROM ai_operations a
+         INNER JOIN entries e ON e.id = a.entry_id
+         WHERE e.deleted_at IS NULL
+         GROUP BY a.op_type
+         ORDER BY count DESC, a.op_type ASC",
+    )
+    .fetch_all(pool)
+    .await?;
+
+    Ok(WritingAnalytics {
+        total_words: total.words,
+        total_characters: total.characters,
+        average_words: average(total.words, entries.len() as i64),
+        entries,
+        weeks: period_stats(weeks),
+        months: period_stats(months),
+        years: period_stats(years),
+        weekdays,
+        ai_usage,
+    })
+}
+
 // ===== Export/Import =====
 
+/// Export format version. Before 1.2 a date held at most one entry, so older
+/// exports are matched to local entries by date rather than by id.
+const EXPORT_VERSION: &str = "1.2";
+
 /// Export all user data (entries and AI operations)
 pub async fn export_all_data(pool: &SqlitePool) -> Result<ExportData, AppError> {
     // Get all entries
-    let entries = sqlx::query_as::<_, DiaryEntry>("SELECT * FROM entries ORDER BY entry_date ASC")
+    let entries = sqlx::query_as::<_, DiaryEntry>(&format!(
+        "SELECT * FROM entries WHERE deleted_at IS NULL ORDER BY entry_date ASC, {DAY_ORDER}"
+    ))
+    .fetch_all(pool)
+    .await?;
+
+    // Get the AI operations of those entries
+    let ai_operations = sqlx::query_as::<_, AIOperation>(
+        "SELECT o.* FROM ai_operations o
+         INNER JOIN entries e ON e.id = o.entry_id
+         WHERE e.deleted_at IS NULL
+         ORDER BY o.created_at ASC",
+    )
+    .fetch_all(pool)
+    .await?;
+
+    // Get all tags and their entry links
+    let tags = sqlx::query_as::<_, Tag>("SELECT * FROM tags ORDER BY created_at ASC")
         .fetch_all(pool)
         .await?;
+    let entry_tags = sqlx::query_as::<_, EntryTag>(
+        "SELECT et.* FROM entry_tags et
+         INNER JOIN entries e ON e.id = et.entry_id
+         WHERE e.deleted_at IS NULL
+         ORDER BY et.created_at ASC",
+    )
+    .fetch_all(pool)
+    .await?;
 
-    // Get all AI operations
-    let ai_operations =
-        sqlx::query_as::<_, AIOperation>("SELECT * FROM ai_operations ORDER BY created_at ASC")
-            .fetch_all(pool)
-            .await?;
+    // Attachment files are added by the caller, which knows where they are stored
+    let attachments = sqlx::query_as::<_, Attachment>(
+        "SELECT a.* FROM attachments a
+         INNER JOIN entries e ON e.id = a.entry_id
+         WHERE e.deleted_at IS NULL
+         ORDER BY a.created_at ASC",
+    )
+    .fetch_all(pool)
+    .await?;
+
+    // Exports are always plaintext
+    let vault = Vault::of(pool);
+    let entries = open_entries(&vault, entries)?;
+    let attachments = attachments
+        .into_iter()
+        .map(|attachment| open_attachment(&vault, attachment))
+        .collect::<Result<_, AppError>>()?;
+    let ai_operations = ai_operations
+        .into_iter()
+        .map(|operation| open_ai_operation(&vault, operation))
+        .collect::<Result<_, AppError>>()?;
 
     Ok(ExportData {
-        version: "1.0".to_string(),
+        version: EXPORT_VERSION.to_string(),
         exported_at: chrono::Utc::now().timestamp_millis(),
         entries,
         ai_operations,
+        tags,
+        entry_tags,
+        attachments,
+        attachment_files: vec![],
     })
 }
 
+/// Whether dotted version `version` is older than `other` (unparsable parts count as 0)
+fn version_before(version: &str, other: &str) -> bool {
+    let parse = |v: &str| -> Vec<u32> { v.split('.').map(|p| p.parse().unwrap_or(0)).collect() };
+    parse(version) < parse(other)
+}
+
 /// Import user data from export JSON
 pub async fn import_data(
     pool: &SqlitePool,
     data: ExportData,
     options: ImportOptions,
 ) -> Result<usize, AppError> {
+    let vault = Vault::of(pool);
+    vault.ensure_unlocked()?;
     let mut imported_count = 0;
+    // Exported entry id -> local entry id, for entries that were written
+    let mut imported_entry_ids: HashMap<String, String> = HashMap::new();
+
+    let match_by_date = version_before(&data.version, EXPORT_VERSION);
+
+    // Reject the whole file before writing anything if a date is malformed;
+    // exports build file paths from it
+    for entry in &data.entries {
+        chrono::NaiveDate::parse_from_str(&entry.entry_date, "%Y-%m-%d")
+            .map_err(|_| AppError::InvalidEntryDate(entry.entry_date.clone()))?;
+    }
 
     // Import entries
     for entry in data.entries {
         // Check if entry exists
-        let existing =
-            sqlx::query_scalar::<_, String>("SELECT id FROM entries WHERE entry_date = ?")
-                .bind(&entry.entry_date)
+        let existing = if match_by_date {
+            sqlx::query_scalar::<_, String>(&format!(
+                "SELECT id FROM entries WHERE entry_date = ?
+                 ORDER BY deleted_at IS NOT NULL, {DAY_ORDER}
+                 LIMIT 1"
+            ))
+            .bind(&entry.entry_date)
+            .fetch_optional(pool)
+            .await?
+        } else {
+            sqlx::query_scalar::<_, String>("SELECT id FROM entries WHERE id = ?")
+                .bind(&entry.id)
                 .fetch_optional(pool)
-                .await?;
+                .await?
+        };
+
+        let title = entry.title.as_deref().map(|t| vault.seal(t)).transpose()?;
+        let summary = entry
+            .summary
+            .as_deref()
+            .map(|s| vault.seal(s))
+            .transpose()?;
+        let plain_text = extract_plain_text(&entry.content_json);
+        let word_count = count_text(&plain_text).words;
+        let content_text = vault.seal(&plain_text)?;
 
         match (existing, options.overwrite) {
             (None, _) => {
                 // Insert new entry
                 sqlx::query(
-                    "INSERT INTO entries (id, entry_date, content_json, mood, mood_emoji, created_at, updated_at)
-                     VALUES (?, ?, ?, ?, ?, ?, ?)"
+                    "INSERT INTO entries (id, entry_date, entry_time, title, summary, content_json, content_text, word_count, mood, mood_emoji, created_at, updated_at)
+                     VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?)"
                 )
                 .bind(&entry.id)
                 .bind(&entry.entry_date)
-                .bind(&entry.content_json)
+                .bind(&entry.entry_time)
+                .bind(&title)
+                .bind(&summary)
+                .bind(vault.seal(&entry.content_json)?)
+                .bind(&content_text)
+                .bind(word_count)
                 .bind(&entry.mood)
                 .bind(&entry.mood_emoji)
                 .bind(entry.created_at)
                 .bind(entry.updated_at)
                 .execute(pool)
                 .await?;
+                imported_entry_ids.insert(entry.id.clone(), entry.id.clone());
                 imported_count += 1;
             }
-            (Some(_), true) => {
-                // Update existing entry
+            (Some(local_id), true) => {
+                // Update existing entry, keeping what it replaces in its history
+                let mut tx = pool.begin().await?;
+                let previous =
+                    sqlx::query_as::<_, DiaryEntry>("SELECT * FROM entries WHERE id = ?")
+                        .bind(&local_id)
+                        .fetch_one(&mut *tx)
+                        .await?;
+                let previous_json = vault.open(previous.content_json.clone())?;
+                if previous_json != entry.content_json {
+                    let previous_words = count_text(&extract_plain_text(&previous_json)).words;
+                    let now = chrono::Utc::now().timestamp_millis();
+                    snapshot_revision(&mut tx, &previous, previous_words, now, true).await?;
+                }
                 sqlx::query(
-                    "UPDATE entries SET content_json = ?, mood = ?, mood_emoji = ?, updated_at = ?
-                     WHERE entry_date = ?",
+                    "UPDATE entries
+                     SET entry_time = ?, title = ?, summary = ?, content_json = ?, content_text = ?,
+                         word_count = ?, mood = ?, mood_emoji = ?, updated_at = ?, deleted_at = NULL
+                     WHERE id = ?",
                 )
-                .bind(&entry.content_json)
+                .bind(&entry.entry_time)
+                .bind(&title)
+                .bind(&summary)
+                .bind(vault.seal(&entry.content_json)?)
+                .bind(&content_text)
+                .bind(word_count)
                 .bind(&entry.mood)
                 .bind(&entry.mood_emoji)
                 .bind(entry.updated_at)
-                .bind(&entry.entry_date)
-                .execute(pool)
+                .bind(&local_id)
+                .execute(&mut *tx)
                 .await?;
+                tx.commit().await?;
+                imported_entry_ids.insert(entry.id.clone(), local_id);
                 imported_count += 1;
             }
             (Some(_), false) => {
@@ -458,9 +2983,54 @@ pub async fn import_data(
         }
     }
 
-    // Import AI operations if requested
+    // Import tags, merging by name with existing ones, and re-link imported entries
+    let mut tag_ids: HashMap<String, String> = HashMap::new();
+    for tag in &data.tags {
+        match get_or_create_tag(pool, &tag.name).await {
+            Ok(local) => {
+                tag_ids.insert(tag.id.clone(), local.id);
+            }
+            // Skip tags whose names are no longer valid rather than failing the import
+            Err(AppError::InvalidTag(_)) => continue,
+            Err(e) => return Err(e),
+        }
+    }
+    for link in &data.entry_tags {
+        if let (Some(entry_id), Some(tag_id)) = (
+            imported_entry_ids.get(&link.entry_id),
+            tag_ids.get(&link.tag_id),
+        ) {
+            sqlx::query(
+                "INSERT OR IGNORE INTO entry_tags (entry_id, tag_id, created_at) VALUES (?, ?, ?)",
+            )
+            .bind(entry_id)
+            .bind(tag_id)
+            .bind(link.created_at)
+            .execute(pool)
+            .await?;
+        }
+    }
+
+    // Attach files to imported entries; the caller has already stored their contents
+    for attachment in &data.attachments {
+        if let Some(entry_id) = imported_entry_ids.get(&attachment.entry_id) {
+            let attachment = Attachment {
+                entry_id: entry_id.clone(),
+                ..attachment.clone()
+            };
+            insert_attachment(pool, "INSERT OR IGNORE", &attachment).await?;
+        }
+    }
+
+    // Refresh the search index for everything written above
+    rebuild_search_index(&mut *pool.acquire().await?, &vault).await?;
+
+    // Import AI operations of imported entries if requested
     if options.include_ai_operations {
         for op in data.ai_operations {
+            let Some(entry_id) = imported_entry_ids.get(&op.entry_id) else {
+                continue;
+            };
             // Check if AI operation exists
             let existing =
                 sqlx::query_scalar::<_, String>("SELECT id FROM ai_operations WHERE id = ?")
@@ -475,10 +3045,10 @@ pub async fn import_data(
                      VALUES (?, ?, ?, ?, ?, ?, ?, ?)"
                 )
                 .bind(&op.id)
-                .bind(&op.entry_id)
+                .bind(entry_id)
                 .bind(&op.op_type)
-                .bind(&op.original_text)
-                .bind(&op.result_text)
+                .bind(vault.seal(&op.original_text)?)
+                .bind(vault.seal(&op.result_text)?)
                 .bind(&op.provider)
                 .bind(&op.model)
                 .bind(op.created_at)
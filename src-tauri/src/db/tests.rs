@@ -1,4 +1,5 @@
 use super::{migrations, queries};
+use crate::crypto;
 
 #[tokio::test]
 async fn migrations_are_idempotent() {
@@ -10,6 +11,110 @@ async fn migrations_are_idempotent() {
     migrations::run(&pool).await.expect("second migration");
 }
 
+#[tokio::test]
+async fn migrations_upgrade_journals_with_entries() {
+    let pool = sqlx::SqlitePool::connect("sqlite::memory:")
+        .await
+        .expect("connect");
+
+    // A journal from before plain text was extracted (migration 11)
+    migrations::run_up_to(&pool, 10)
+        .await
+        .expect("migrate to v10");
+    let doc = |text: &str| {
+        serde_json::json!({
+            "type": "doc",
+            "content": [{"type": "paragraph", "content": [{"type": "text", "text": text}]}]
+        })
+        .to_string()
+    };
+    for (id, date, text) in [
+        ("e1", "2026-02-01", "a quiet morning"),
+        ("e2", "2026-02-02", "rain"),
+    ] {
+        sqlx::query(
+            "INSERT INTO entries (id, entry_date, content_json, created_at, updated_at)
+             VALUES (?, ?, ?, 1, 1)",
+        )
+        .bind(id)
+        .bind(date)
+        .bind(doc(text))
+        .execute(&pool)
+        .await
+        .expect("insert");
+    }
+
+    migrations::run(&pool).await.expect("migrate");
+    let info = migrations::schema_info(&pool).await.expect("schema info");
+    assert_eq!(info.current_version, migrations::latest_version());
+
+    // Word counts are left for the first read that needs them
+    let start = chrono::NaiveDate::from_ymd_opt(2026, 2, 1).expect("date");
+    let end = chrono::NaiveDate::from_ymd_opt(2026, 2, 2).expect("date");
+    queries::get_calendar_days(&pool, start, end)
+        .await
+        .expect("calendar");
+    let rows: Vec<(String, Option<i64>)> =
+        sqlx::query_as("SELECT content_text, word_count FROM entries ORDER BY entry_date")
+            .fetch_all(&pool)
+            .await
+            .expect("rows");
+    assert_eq!(
+        rows,
+        [
+            ("a quiet morning".to_string(), Some(3)),
+            ("rain".to_string(), Some(1)),
+        ]
+    );
+    let results = queries::search_entries(&pool, "quiet", 20, 0)
+        .await
+        .expect("search");
+    assert_eq!(results.total, 1);
+}
+
+#[tokio::test]
+async fn migrations_reject_edited_or_newer_schemas() {
+    let pool = sqlx::SqlitePool::connect("sqlite::memory:")
+        .await
+        .expect("connect");
+    migrations::run(&pool).await.expect("migrate");
+
+    let info = migrations::schema_info(&pool).await.expect("schema info");
+    assert_eq!(info.current_version, migrations::latest_version());
+    assert_eq!(info.applied.len() as i64, migrations::latest_version());
+    assert!(info.applied.iter().all(|m| m.checksum.is_some()));
+
+    // Databases from before checksums were recorded get them backfilled
+    sqlx::query("UPDATE schema_migrations SET checksum = NULL")
+        .execute(&pool)
+        .await
+        .expect("clear checksums");
+    migrations::run(&pool).await.expect("backfill");
+    let info = migrations::schema_info(&pool).await.expect("schema info");
+    assert!(info.applied.iter().all(|m| m.checksum.is_some()));
+
+    sqlx::query("UPDATE schema_migrations SET checksum = 'edited' WHERE version = 3")
+        .execute(&pool)
+        .await
+        .expect("tamper");
+    assert!(matches!(
+        migrations::run(&pool).await,
+        Err(crate::error::AppError::Migration(_))
+    ));
+    sqlx::query("UPDATE schema_migrations SET checksum = NULL WHERE version = 3")
+        .execute(&pool)
+        .await
+        .expect("untamper");
+
+    sqlx::query("INSERT INTO schema_migrations (version, applied_at) VALUES (?, 0)")
+        .bind(migrations::latest_version() + 1)
+        .execute(&pool)
+        .await
+        .expect("future version");
+    let err = migrations::run(&pool).await.expect_err("newer schema");
+    assert!(err.to_string().contains("newer than this app supports"));
+}
+
 #[tokio::test]
 async fn upsert_overwrites_same_date() {
     let pool = sqlx::SqlitePool::connect("sqlite::memory:")
@@ -33,7 +138,7 @@ async fn upsert_overwrites_same_date() {
     assert_eq!(first.entry_date, second.entry_date);
     assert_eq!(second.content_json, content2);
 
-    let fetched = queries::get_entry(&pool, date)
+    let fetched = queries::get_first_entry(&pool, date)
         .await
         .expect("get")
         .expect("some entry");
@@ -43,3 +148,1940 @@ async fn upsert_overwrites_same_date() {
     assert_eq!(entries.len(), 1);
     assert_eq!(entries[0].entry_date, date);
 }
+
+#[tokio::test]
+async fn revisions_are_coalesced_and_restorable() {
+    let pool = sqlx::SqlitePool::connect("sqlite::memory:")
+        .await
+        .expect("connect");
+    migrations::run(&pool).await.expect("migrate");
+
+    let date = "2026-01-02";
+    let entry = queries::upsert_entry(&pool, date, "a").await.expect("a");
+    queries::upsert_entry(&pool, date, "b").await.expect("b");
+    queries::upsert_entry(&pool, date, "c").await.expect("c");
+
+    // Rapid autosaves collapse into a single snapshot of the original content.
+    let revisions = queries::list_entry_revisions(&pool, &entry.id)
+        .await
+        .expect("list revisions");
+    assert_eq!(revisions.len(), 1);
+
+    let revision = queries::get_entry_revision(&pool, &revisions[0].id)
+        .await
+        .expect("get revision")
+        .expect("some revision");
+    assert_eq!(revision.content_json, "a");
+
+    let restored = queries::restore_entry_revision(&pool, &revision.id)
+        .await
+        .expect("restore");
+    assert_eq!(restored.content_json, "a");
+
+    // Restoring always snapshots the content it replaces.
+    let revisions = queries::list_entry_revisions(&pool, &entry.id)
+        .await
+        .expect("list revisions");
+    assert_eq!(revisions.len(), 2);
+
+    // So does an import that overwrites the day
+    let mut data = queries::export_all_data(&pool).await.expect("export");
+    data.entries[0].content_json = "imported".to_string();
+    let options = crate::models::ImportOptions {
+        overwrite: true,
+        include_ai_operations: false,
+    };
+    queries::import_data(&pool, data, options)
+        .await
+        .expect("import");
+    let revisions = queries::list_entry_revisions(&pool, &entry.id)
+        .await
+        .expect("list revisions");
+    assert_eq!(revisions.len(), 3);
+    let mut replaced = Vec::new();
+    for summary in &revisions {
+        let revision = queries::get_entry_revision(&pool, &summary.id)
+            .await
+            .expect("get revision")
+            .expect("some revision");
+        if revision.content_json == "a" {
+            replaced.push(revision);
+        }
+    }
+    // The first snapshot of "a", plus the one taken before the import
+    assert_eq!(replaced.len(), 2);
+    let restored = queries::restore_entry_revision(&pool, &replaced[0].id)
+        .await
+        .expect("restore");
+    assert_eq!(restored.content_json, "a");
+
+    // Restoring a revision of a trashed entry takes it out of the trash as is
+    queries::set_entry_mood(&pool, &entry.id, Some("happy"), Some("😊"))
+        .await
+        .expect("set mood");
+    assert!(queries::delete_entry(&pool, &entry.id)
+        .await
+        .expect("delete"));
+    let restored = queries::restore_entry_revision(&pool, &replaced[0].id)
+        .await
+        .expect("restore trashed");
+    assert!(restored.deleted_at.is_none());
+    assert_eq!(restored.mood.as_deref(), Some("happy"));
+    assert_eq!(restored.mood_emoji.as_deref(), Some("😊"));
+    assert_eq!(restored.created_at, entry.created_at);
+    assert!(queries::list_trash(&pool).await.expect("trash").is_empty());
+}
+
+#[tokio::test]
+async fn revisions_coalesce_on_when_they_were_taken() {
+    let pool = sqlx::SqlitePool::connect("sqlite::memory:")
+        .await
+        .expect("connect");
+    migrations::run(&pool).await.expect("migrate");
+
+    let date = "2026-01-02";
+    let entry = queries::upsert_entry(&pool, date, "yesterday's two words")
+        .await
+        .expect("first session");
+    let saved_at = entry.updated_at - 24 * 60 * 60 * 1000;
+    sqlx::query("UPDATE entries SET updated_at = ? WHERE id = ?")
+        .bind(saved_at)
+        .bind(&entry.id)
+        .execute(&pool)
+        .await
+        .expect("age entry");
+
+    // A new editing session snapshots the old content once, not once per save
+    queries::upsert_entry(&pool, date, "today")
+        .await
+        .expect("b");
+    queries::upsert_entry(&pool, date, "today again")
+        .await
+        .expect("c");
+
+    let revisions = queries::list_entry_revisions(&pool, &entry.id)
+        .await
+        .expect("list revisions");
+    assert_eq!(revisions.len(), 1);
+    assert_eq!(revisions[0].saved_at, saved_at);
+    assert!(revisions[0].created_at > saved_at);
+    assert_eq!(revisions[0].word_count, 3);
+}
+
+#[tokio::test]
+async fn deleted_entries_go_to_trash() {
+    let pool = sqlx::SqlitePool::connect("sqlite::memory:")
+        .await
+        .expect("connect");
+    migrations::run(&pool).await.expect("migrate");
+
+    let date = "2026-01-03";
+    let entry = queries::upsert_entry(&pool, date, "a")
+        .await
+        .expect("upsert");
+    assert!(queries::delete_entry(&pool, &entry.id)
+        .await
+        .expect("delete"));
+
+    assert!(queries::get_first_entry(&pool, date)
+        .await
+        .expect("get")
+        .is_none());
+    assert!(queries::list_entries(&pool, "2026-01")
+        .await
+        .expect("list")
+        .is_empty());
+    assert_eq!(queries::list_trash(&pool).await.expect("trash").len(), 1);
+
+    let restored = queries::restore_entry(&pool, &entry.id)
+        .await
+        .expect("restore")
+        .expect("some entry");
+    assert_eq!(restored.content_json, "a");
+    assert!(queries::list_trash(&pool).await.expect("trash").is_empty());
+
+    queries::delete_entry(&pool, &entry.id)
+        .await
+        .expect("delete");
+    assert_eq!(queries::empty_trash(&pool).await.expect("empty").len(), 1);
+    assert!(queries::list_trash(&pool).await.expect("trash").is_empty());
+
+    // Retention is bounded on save, and a huge stored value purges nothing
+    for retention_days in [0, queries::MAX_TRASH_RETENTION_DAYS + 1] {
+        assert!(queries::save_trash_settings(
+            &pool,
+            &crate::models::TrashSettings { retention_days }
+        )
+        .await
+        .is_err());
+    }
+    queries::save_setting(&pool, "trash_retention_days", &i64::MAX.to_string())
+        .await
+        .expect("save setting");
+    let kept = queries::upsert_entry(&pool, date, "b")
+        .await
+        .expect("upsert");
+    assert!(queries::delete_entry(&pool, &kept.id)
+        .await
+        .expect("delete"));
+    assert!(queries::purge_expired_trash(&pool)
+        .await
+        .expect("purge")
+        .is_empty());
+}
+
+#[tokio::test]
+async fn days_hold_several_entries() {
+    let pool = sqlx::SqlitePool::connect("sqlite::memory:")
+        .await
+        .expect("connect");
+    migrations::run(&pool).await.expect("migrate");
+
+    let date = "2026-01-05";
+    let morning = queries::upsert_entry(&pool, date, "coffee")
+        .await
+        .expect("upsert");
+    let evening = queries::create_entry(&pool, date, Some(" Evening "), Some("21:30"), "stars")
+        .await
+        .expect("create");
+    let noon = queries::create_entry(&pool, date, None, Some("12:00"), "lunch")
+        .await
+        .expect("create");
+    assert_eq!(evening.title.as_deref(), Some("Evening"));
+
+    // Untimed entries come first, then timed ones by time
+    let day = queries::get_day_entries(&pool, date).await.expect("day");
+    let ids: Vec<_> = day.iter().map(|e| e.id.as_str()).collect();
+    assert_eq!(ids, [&*morning.id, &*noon.id, &*evening.id]);
+
+    // Date-only writes keep going to the first entry; ids address the others
+    queries::upsert_entry(&pool, date, "more coffee")
+        .await
+        .expect("upsert");
+    queries::save_entry(&pool, &evening.id, date, "many stars")
+        .await
+        .expect("save");
+    // An editor still showing another day can't write to this one
+    assert!(matches!(
+        queries::save_entry(&pool, &evening.id, "2026-01-06", "stale").await,
+        Err(crate::error::AppError::InvalidEntryDate(_))
+    ));
+    let day = queries::get_day_entries(&pool, date).await.expect("day");
+    assert_eq!(day[0].content_json, "more coffee");
+    assert_eq!(day[2].content_json, "many stars");
+    assert_eq!(
+        queries::list_entry_revisions(&pool, &evening.id)
+            .await
+            .expect("revisions")[0]
+            .entry_id,
+        evening.id
+    );
+
+    queries::set_entry_mood(&pool, &noon.id, Some("happy"), Some("😊"))
+        .await
+        .expect("mood");
+    let details = queries::EntryDetails {
+        title: Some("Lunch"),
+        summary: None,
+        entry_time: None,
+    };
+    let noon = queries::update_entry_details(&pool, &noon.id, details)
+        .await
+        .expect("details");
+    assert_eq!(noon.entry_time, None);
+    assert_eq!(noon.mood.as_deref(), Some("happy"));
+
+    // Trashing one entry leaves the rest of the day alone
+    queries::delete_entry(&pool, &morning.id)
+        .await
+        .expect("delete");
+    assert_eq!(
+        queries::get_day_entries(&pool, date)
+            .await
+            .expect("day")
+            .len(),
+        2
+    );
+
+    // A day counts once towards totals and streaks
+    queries::upsert_entry(&pool, "2026-01-06", "next day")
+        .await
+        .expect("upsert");
+    let stats = queries::get_writing_stats(&pool).await.expect("stats");
+    assert_eq!(stats.total_entries, 3);
+    assert_eq!(stats.total_days, 2);
+    assert_eq!(stats.longest_streak, 2);
+
+    // Entries round-trip through export by id
+    let export = queries::export_all_data(&pool).await.expect("export");
+    assert_eq!(export.entries.len(), 3);
+    let other = sqlx::SqlitePool::connect("sqlite::memory:")
+        .await
+        .expect("connect");
+    migrations::run(&other).await.expect("migrate");
+    let options = crate::models::ImportOptions {
+        overwrite: false,
+        include_ai_operations: false,
+    };
+    queries::import_data(&other, export, options)
+        .await
+        .expect("import");
+    let day = queries::get_day_entries(&other, date).await.expect("day");
+    assert_eq!(day.len(), 2);
+    assert_eq!(day[0].title.as_deref(), Some("Lunch"));
+}
+
+#[tokio::test]
+async fn single_entry_days_migrate_without_losing_children() {
+    let pool = sqlx::SqlitePool::connect("sqlite::memory:")
+        .await
+        .expect("connect");
+    migrations::run(&pool).await.expect("migrate");
+
+    // Put the entries table back in its one-entry-per-day shape
+    for sql in [
+        "DELETE FROM schema_migrations WHERE version >= 13",
+        "DROP TABLE entries",
+        "CREATE TABLE entries (
+            id TEXT PRIMARY KEY,
+            entry_date TEXT NOT NULL UNIQUE,
+            content_json TEXT NOT NULL,
+            mood TEXT,
+            mood_emoji TEXT,
+            created_at INTEGER NOT NULL,
+            updated_at INTEGER NOT NULL,
+            deleted_at INTEGER,
+            content_text TEXT NOT NULL DEFAULT ''
+        )",
+        "INSERT INTO entries (id, entry_date, content_json, content_text, created_at, updated_at)
+         VALUES ('e1', '2026-01-07', 'old', 'old', 1, 1)",
+        "INSERT INTO tags (id, name, created_at) VALUES ('t1', 'kept', 1)",
+        "INSERT INTO entry_tags (entry_id, tag_id, created_at) VALUES ('e1', 't1', 1)",
+    ] {
+        sqlx::query(sql).execute(&pool).await.expect(sql);
+    }
+
+    migrations::run(&pool).await.expect("migrate");
+
+    let entry = queries::get_first_entry(&pool, "2026-01-07")
+        .await
+        .expect("get")
+        .expect("entry");
+    assert_eq!(entry.id, "e1");
+    assert_eq!(entry.content_json, "old");
+    assert_eq!(
+        queries::list_entry_tags(&pool, "e1")
+            .await
+            .expect("tags")
+            .len(),
+        1
+    );
+    queries::create_entry(&pool, "2026-01-07", None, None, "new")
+        .await
+        .expect("second entry");
+}
+
+#[tokio::test]
+async fn titles_and_summaries_are_searchable() {
+    let pool = sqlx::SqlitePool::connect("sqlite::memory:")
+        .await
+        .expect("connect");
+    migrations::run(&pool).await.expect("migrate");
+
+    let entry = queries::upsert_entry(&pool, "2026-01-08", "we walked for hours")
+        .await
+        .expect("upsert");
+    let details = queries::EntryDetails {
+        title: Some("Coastal hike"),
+        summary: Some("A long walk along the cliffs."),
+        entry_time: None,
+    };
+    queries::update_entry_details(&pool, &entry.id, details)
+        .await
+        .expect("details");
+
+    // Later content saves keep the title and summary, in the row and the index
+    let saved = queries::upsert_entry(&pool, "2026-01-08", "we walked for hours and hours")
+        .await
+        .expect("upsert");
+    assert_eq!(saved.title.as_deref(), Some("Coastal hike"));
+    assert_eq!(
+        saved.summary.as_deref(),
+        Some("A long walk along the cliffs.")
+    );
+
+    let hits = queries::search_entries(&pool, "cliffs", 20, 0)
+        .await
+        .expect("search")
+        .hits;
+    assert_eq!(hits.len(), 1);
+    assert_eq!(hits[0].title.as_deref(), Some("Coastal hike"));
+    assert_eq!(
+        queries::search_entries(&pool, "coastal", 20, 0)
+            .await
+            .expect("search")
+            .total,
+        1
+    );
+
+    let export = queries::export_all_data(&pool).await.expect("export");
+    assert_eq!(
+        export.entries[0].summary.as_deref(),
+        Some("A long walk along the cliffs.")
+    );
+}
+
+#[tokio::test]
+async fn tags_are_searchable_and_mergeable() {
+    let pool = sqlx::SqlitePool::connect("sqlite::memory:")
+        .await
+        .expect("connect");
+    migrations::run(&pool).await.expect("migrate");
+
+    let entry = queries::upsert_entry(&pool, "2026-01-04", "flight to lisbon")
+        .await
+        .expect("upsert");
+    let travel = queries::add_entry_tag(&pool, &entry.id, " #Travel ")
+        .await
+        .expect("add tag");
+    assert_eq!(travel.name, "Travel");
+    let trips = queries::add_entry_tag(&pool, &entry.id, "trips")
+        .await
+        .expect("add tag");
+
+    let hits = queries::search_entries(&pool, "travel", 20, 0)
+        .await
+        .expect("search")
+        .hits;
+    assert_eq!(hits.len(), 1);
+
+    queries::rename_tag(&pool, &travel.id, "journeys")
+        .await
+        .expect("rename");
+    assert!(queries::search_entries(&pool, "travel", 20, 0)
+        .await
+        .expect("search")
+        .hits
+        .is_empty());
+    assert_eq!(
+        queries::search_entries(&pool, "journeys", 20, 0)
+            .await
+            .expect("search")
+            .hits
+            .len(),
+        1
+    );
+
+    queries::merge_tags(&pool, std::slice::from_ref(&trips.id), &travel.id)
+        .await
+        .expect("merge");
+    let tags = queries::list_tags(&pool).await.expect("list tags");
+    assert_eq!(tags.len(), 1);
+    assert_eq!(tags[0].entry_count, 1);
+    assert_eq!(
+        queries::list_entries_by_tag(&pool, &travel.id)
+            .await
+            .expect("by tag")
+            .len(),
+        1
+    );
+}
+
+#[tokio::test]
+async fn tts_cache_evicts_least_recently_used() {
+    use crate::tts::cache::{cache_key, CacheKeyParts, TTSCache};
+    use crate::tts::TTSResponse;
+
+    let pool = sqlx::SqlitePool::connect("sqlite::memory:")
+        .await
+        .expect("connect");
+    migrations::run(&pool).await.expect("migrate");
+
+    let dir = std::env::temp_dir().join(format!("echo-daily-tts-cache-{}", uuid::Uuid::new_v4()));
+    let cache = TTSCache::new(&pool, dir.clone());
+    cache.set_max_bytes(10).await.expect("set limit");
+
+    let key = |text: &str| {
+        cache_key(
+            &CacheKeyParts {
+                provider: "qwen",
+                model: "qwen3-tts-flash",
+                voice: "cherry",
+                speed: 1.0,
+                language: None,
+                format: "mp3",
+                text,
+            },
+            &crypto::Vault::Plain,
+        )
+        .expect("cache key")
+    };
+    assert_eq!(key("hello   world"), key(" hello world "));
+
+    let response = |bytes: Vec<u8>| TTSResponse {
+        audio_bytes: Some(bytes),
+        audio_file: None,
+        audio_base64: None,
+        format: "mp3".to_string(),
+        duration_ms: None,
+        provider: "qwen".to_string(),
+        model: "qwen3-tts-flash".to_string(),
+        voice: "Cherry".to_string(),
+        record_id: None,
+        cached: false,
+    };
+
+    cache
+        .put(&key("one"), &response(vec![1; 6]))
+        .await
+        .expect("put one");
+    tokio::time::sleep(std::time::Duration::from_millis(5)).await;
+    cache
+        .put(&key("two"), &response(vec![2; 6]))
+        .await
+        .expect("put two");
+
+    // Both clips don't fit, so the older one is evicted.
+    assert!(cache.get(&key("one")).await.expect("get one").is_none());
+    let hit = cache.get(&key("two")).await.expect("get two").expect("hit");
+    assert!(hit.cached);
+    assert_eq!(hit.audio_bytes.as_deref(), Some(&[2u8; 6][..]));
+
+    assert_eq!(cache.clear().await.expect("clear"), 6);
+    let _ = std::fs::remove_dir_all(dir);
+}
+
+#[tokio::test]
+async fn search_indexes_plain_text_not_json() {
+    let pool = sqlx::SqlitePool::connect("sqlite::memory:")
+        .await
+        .expect("connect");
+    migrations::run(&pool).await.expect("migrate");
+
+    let content = r#"{"type":"doc","content":[{"type":"heading","content":[{"type":"text","text":"Morning"}]},{"type":"paragraph","content":[{"type":"text","text":"coffee "},{"type":"text","marks":[{"type":"bold"}],"text":"first"}]}]}"#;
+    assert_eq!(
+        crate::content::extract_plain_text(content),
+        "Morning\ncoffee first"
+    );
+
+    queries::upsert_entry(&pool, "2026-01-05", content)
+        .await
+        .expect("upsert");
+
+    assert_eq!(
+        queries::search_entries(&pool, "coffee", 20, 0)
+            .await
+            .expect("search")
+            .hits
+            .len(),
+        1
+    );
+    for noise in ["paragraph", "type", "text", "bold"] {
+        assert!(
+            queries::search_entries(&pool, noise, 20, 0)
+                .await
+                .expect("search")
+                .hits
+                .is_empty(),
+            "{noise} should not match"
+        );
+    }
+}
+
+#[tokio::test]
+async fn search_ranks_best_first_and_paginates() {
+    let pool = sqlx::SqlitePool::connect("sqlite::memory:")
+        .await
+        .expect("connect");
+    migrations::run(&pool).await.expect("migrate");
+
+    queries::upsert_entry(
+        &pool,
+        "2026-02-01",
+        "rain once, then a long walk home through the park",
+    )
+    .await
+    .expect("upsert");
+    queries::upsert_entry(&pool, "2026-02-02", "rain rain rain")
+        .await
+        .expect("upsert");
+    queries::upsert_entry(&pool, "2026-02-03", "sunny all day")
+        .await
+        .expect("upsert");
+
+    let page = queries::search_entries(&pool, "rain", 1, 0)
+        .await
+        .expect("search");
+    assert_eq!(page.total, 2);
+    assert_eq!(page.hits.len(), 1);
+    assert_eq!(page.hits[0].entry_date, "2026-02-02");
+    assert!(page.hits[0]
+        .snippet
+        .iter()
+        .any(|segment| segment.highlight && segment.text == "rain"));
+
+    let next = queries::search_entries(&pool, "rain", 1, 1)
+        .await
+        .expect("search");
+    assert_eq!(next.hits.len(), 1);
+    assert_eq!(next.hits[0].entry_date, "2026-02-01");
+    assert!(page.hits[0].score > next.hits[0].score);
+}
+
+#[tokio::test]
+async fn search_query_language_filters_and_escapes() {
+    let pool = sqlx::SqlitePool::connect("sqlite::memory:")
+        .await
+        .expect("connect");
+    migrations::run(&pool).await.expect("migrate");
+
+    let beach = queries::upsert_entry(&pool, "2026-03-01", "don't forget: walking by the sea")
+        .await
+        .expect("upsert");
+    queries::upsert_entry(&pool, "2026-03-05", "learning C++ while it rains")
+        .await
+        .expect("upsert");
+    queries::upsert_entry_mood(&pool, "2026-03-05", Some("sad"), Some("😢"))
+        .await
+        .expect("mood");
+    queries::add_entry_tag(&pool, &beach.id, "travel")
+        .await
+        .expect("tag");
+
+    let dates = |results: crate::models::SearchResults| {
+        results
+            .hits
+            .into_iter()
+            .map(|hit| hit.entry_date)
+            .collect::<Vec<_>>()
+    };
+    let search = |q: &'static str| queries::search_entries(&pool, q, 20, 0);
+
+    // Punctuation that used to be FTS5 syntax errors
+    assert_eq!(
+        dates(search("don't").await.expect("search")),
+        ["2026-03-01"]
+    );
+    assert_eq!(dates(search("C++").await.expect("search")), ["2026-03-05"]);
+    assert_eq!(
+        dates(search("\"by the sea\"").await.expect("search")),
+        ["2026-03-01"]
+    );
+    assert_eq!(
+        dates(search("walk*").await.expect("search")),
+        ["2026-03-01"]
+    );
+    assert!(search("walk").await.expect("search").hits.is_empty());
+
+    // Negation with and without a positive term
+    assert!(search("sea -walking")
+        .await
+        .expect("search")
+        .hits
+        .is_empty());
+    assert_eq!(dates(search("-sea").await.expect("search")), ["2026-03-05"]);
+
+    // Filters, alone and combined with text
+    assert_eq!(
+        dates(search("tag:travel").await.expect("search")),
+        ["2026-03-01"]
+    );
+    assert_eq!(
+        dates(search("mood:sad rains").await.expect("search")),
+        ["2026-03-05"]
+    );
+    assert_eq!(
+        dates(search("-mood:sad").await.expect("search")),
+        ["2026-03-01"]
+    );
+    assert_eq!(
+        dates(search("after:2026-03-01").await.expect("search")),
+        ["2026-03-05"]
+    );
+    assert_eq!(
+        dates(search("before:2026-03-05 has:tags").await.expect("search")),
+        ["2026-03-01"]
+    );
+    assert!(search("has:audio").await.expect("search").hits.is_empty());
+
+    // Mistakes are reported as query errors, not database errors
+    for bad in ["\"unclosed", "before:yesterday", "has:pizza", "tag:"] {
+        assert!(matches!(
+            search(bad).await,
+            Err(crate::error::AppError::InvalidQuery(_))
+        ));
+    }
+
+    // Filter-only hits get the same one-line preview as everywhere else
+    let long = format!("first line\nsecond line {}", "word ".repeat(60));
+    let diary = queries::upsert_entry(&pool, "2026-04-01", &long)
+        .await
+        .expect("upsert");
+    queries::add_entry_tag(&pool, &diary.id, "long")
+        .await
+        .expect("tag");
+    let hits = search("tag:long").await.expect("search").hits;
+    assert_eq!(hits[0].snippet.len(), 1);
+    let preview = &hits[0].snippet[0].text;
+    assert_eq!(preview, &queries::preview_of(&long));
+    assert!(preview.starts_with("first line second line"));
+    assert!(preview.ends_with('…'));
+}
+
+#[tokio::test]
+async fn search_matches_chinese_substrings() {
+    let pool = sqlx::SqlitePool::connect("sqlite::memory:")
+        .await
+        .expect("connect");
+    migrations::run(&pool).await.expect("migrate");
+
+    let entry = queries::upsert_entry(
+        &pool,
+        "2026-04-01",
+        "今天天气很好，我们去公园散步。Then coffee.",
+    )
+    .await
+    .expect("upsert");
+    queries::upsert_entry(&pool, "2026-04-02", "明天要下雨")
+        .await
+        .expect("upsert");
+    queries::add_entry_tag(&pool, &entry.id, "旅行日记")
+        .await
+        .expect("tag");
+
+    let search = |q: &'static str| queries::search_entries(&pool, q, 20, 0);
+    let dates = |results: crate::models::SearchResults| {
+        results
+            .hits
+            .into_iter()
+            .map(|hit| hit.entry_date)
+            .collect::<Vec<_>>()
+    };
+
+    // Two-character words in the middle of a sentence
+    assert_eq!(dates(search("天气").await.expect("search")), ["2026-04-01"]);
+    assert_eq!(dates(search("公园").await.expect("search")), ["2026-04-01"]);
+    assert_eq!(search("天").await.expect("search").total, 2);
+    // Characters must be adjacent
+    assert!(search("天公").await.expect("search").hits.is_empty());
+    // Mixed-script queries and tags
+    assert_eq!(
+        dates(search("散步 coffee").await.expect("search")),
+        ["2026-04-01"]
+    );
+    assert_eq!(
+        dates(search("tag:旅行日记 天气").await.expect("search")),
+        ["2026-04-01"]
+    );
+    assert_eq!(dates(search("旅行").await.expect("search")), ["2026-04-01"]);
+    assert_eq!(
+        dates(search("下雨 -天气").await.expect("search")),
+        ["2026-04-02"]
+    );
+
+    // Snippets come back without the segmentation markers, highlighted as one word
+    let hit = &search("天气").await.expect("search").hits[0];
+    let text: String = hit.snippet.iter().map(|s| s.text.as_str()).collect();
+    assert!(text.contains("今天天气很好"));
+    assert!(hit
+        .snippet
+        .iter()
+        .any(|segment| segment.highlight && segment.text == "天气"));
+}
+
+#[tokio::test]
+async fn backups_rotate_and_restore() {
+    use super::backup::{self, KIND_PRE_RESTORE};
+    use crate::models::{BackupInfo, BackupSettings};
+
+    // VACUUM INTO needs an on-disk source database
+    let dir = std::env::temp_dir().join(format!("echo-daily-backups-{}", uuid::Uuid::new_v4()));
+    std::fs::create_dir_all(&dir).expect("create dir");
+    let pool = sqlx::SqlitePool::connect(&format!(
+        "sqlite:{}?mode=rwc",
+        dir.join("source.db").display()
+    ))
+    .await
+    .expect("connect");
+    migrations::run(&pool).await.expect("migrate");
+    queries::upsert_entry(&pool, "2026-05-01", "backed up")
+        .await
+        .expect("upsert");
+
+    let snapshot = backup::create_backup(&pool, &dir).await.expect("backup");
+    assert_eq!(backup::list_backups(&dir).expect("list").len(), 1);
+    assert!(!backup::snapshot_due(&dir, snapshot.created_at + 1000).expect("due"));
+    assert!(
+        backup::snapshot_due(&dir, snapshot.created_at + backup::SNAPSHOT_INTERVAL_MS)
+            .expect("due")
+    );
+
+    // The snapshot is a complete database
+    let copy = sqlx::SqlitePool::connect(&format!(
+        "sqlite:{}",
+        dir.join(&snapshot.file_name).display()
+    ))
+    .await
+    .expect("open snapshot");
+    let count: i64 = sqlx::query_scalar("SELECT COUNT(*) FROM entries")
+        .fetch_one(&copy)
+        .await
+        .expect("count");
+    assert_eq!(count, 1);
+    copy.close().await;
+
+    // Retention: newest per day for 2 days, newest per ISO week for 2 weeks
+    let noon = |date: &str| {
+        chrono::NaiveDate::parse_from_str(date, "%Y-%m-%d")
+            .unwrap()
+            .and_hms_opt(12, 0, 0)
+            .unwrap()
+            .and_local_timezone(chrono::Local)
+            .unwrap()
+            .timestamp_millis()
+    };
+    let fake = |name: &str, date: &str, hour_offset: i64, kind: &str| BackupInfo {
+        file_name: name.to_string(),
+        created_at: noon(date) + hour_offset * 3_600_000,
+        size_bytes: 0,
+        kind: kind.to_string(),
+    };
+    let backups = [
+        fake("wed-late", "2026-03-18", 2, "snapshot"),
+        fake("wed-early", "2026-03-18", 0, "snapshot"),
+        fake("tue", "2026-03-17", 0, "snapshot"),
+        fake("mon", "2026-03-16", 0, "snapshot"),
+        fake("last-week", "2026-03-11", 0, "snapshot"),
+        fake("two-weeks-ago", "2026-03-04", 0, "snapshot"),
+        fake("set-aside", "2026-01-01", 0, KIND_PRE_RESTORE),
+        fake("older-set-aside", "2025-12-01", 0, KIND_PRE_RESTORE),
+        fake("oldest-set-aside", "2025-11-01", 0, KIND_PRE_RESTORE),
+    ];
+    let mut keep: Vec<String> = backup::backups_to_keep(
+        &backups,
+        &BackupSettings {
+            keep_daily: 2,
+            keep_weekly: 2,
+            keep_pre_restore: 2,
+        },
+    )
+    .into_iter()
+    .collect();
+    keep.sort();
+    assert_eq!(
+        keep,
+        [
+            "last-week",
+            "older-set-aside",
+            "set-aside",
+            "tue",
+            "wed-late"
+        ]
+    );
+
+    // Restoring sets the current database aside and swaps the snapshot in
+    let db_path = dir.join("live.db");
+    std::fs::write(&db_path, b"current database").expect("write live db");
+    backup::stage_restore(&dir, &snapshot.file_name).expect("stage");
+    assert!(backup::stage_restore(&dir, "../live.db").is_err());
+    assert!(backup::apply_pending_restore(&db_path, &dir).expect("apply"));
+    assert!(!backup::apply_pending_restore(&db_path, &dir).expect("nothing pending"));
+
+    let restored = sqlx::SqlitePool::connect(&format!("sqlite:{}", db_path.display()))
+        .await
+        .expect("open restored");
+    let count: i64 = sqlx::query_scalar("SELECT COUNT(*) FROM entries")
+        .fetch_one(&restored)
+        .await
+        .expect("count");
+    assert_eq!(count, 1);
+    restored.close().await;
+
+    let set_aside: Vec<BackupInfo> = backup::list_backups(&dir)
+        .expect("list")
+        .into_iter()
+        .filter(|b| b.kind == KIND_PRE_RESTORE)
+        .collect();
+    assert_eq!(set_aside.len(), 1);
+    assert_eq!(
+        std::fs::read(dir.join(&set_aside[0].file_name)).expect("read set-aside"),
+        b"current database"
+    );
+
+    pool.close().await;
+    std::fs::remove_dir_all(&dir).ok();
+}
+
+#[tokio::test]
+async fn failed_migration_rolls_back_to_snapshot() {
+    let dir = std::env::temp_dir().join(format!("echo-daily-migrate-{}", uuid::Uuid::new_v4()));
+    std::fs::create_dir_all(&dir).expect("create dir");
+    let db_path = dir.join("echo-daily.db");
+    let backups_dir = dir.join("backups");
+
+    // Fresh databases migrate without a snapshot
+    let pool = super::open_and_migrate(&db_path, &backups_dir)
+        .await
+        .expect("open");
+    queries::upsert_entry(&pool, "2026-06-01", "precious")
+        .await
+        .expect("upsert");
+
+    // Pretend we are on v9 with a leftover column that makes migration 11 fail
+    // after migration 10 has been committed
+    for sql in [
+        "DELETE FROM schema_migrations WHERE version >= 10",
+        "DROP INDEX idx_audio_records_cache_key",
+        "ALTER TABLE audio_records DROP COLUMN cache_key",
+        "DROP TABLE tts_cache",
+    ] {
+        sqlx::query(sql).execute(&pool).await.expect(sql);
+    }
+    pool.close().await;
+
+    let err = super::open_and_migrate(&db_path, &backups_dir)
+        .await
+        .expect_err("migration 11 fails");
+    assert_eq!(err.kind, crate::models::STARTUP_MIGRATION_FAILED);
+    assert_eq!(err.schema_version, Some(9));
+    assert!(err.rolled_back);
+    assert!(std::path::Path::new(err.snapshot_path.as_deref().expect("snapshot")).is_file());
+
+    // Back on v9, data intact, migration 10 undone
+    let pool = sqlx::SqlitePool::connect(&format!("sqlite:{}", db_path.display()))
+        .await
+        .expect("reopen");
+    assert_eq!(
+        migrations::current_version(&pool).await.expect("version"),
+        9
+    );
+    let tts_cache: i64 =
+        sqlx::query_scalar("SELECT COUNT(*) FROM sqlite_master WHERE name = 'tts_cache'")
+            .fetch_one(&pool)
+            .await
+            .expect("tts_cache");
+    assert_eq!(tts_cache, 0);
+    assert!(queries::get_first_entry(&pool, "2026-06-01")
+        .await
+        .expect("get")
+        .is_some());
+    pool.close().await;
+
+    std::fs::remove_dir_all(&dir).ok();
+}
+
+#[tokio::test]
+async fn encryption_seals_content_and_keeps_search_working() {
+    let dir = std::env::temp_dir().join(format!("echo-daily-crypto-{}", uuid::Uuid::new_v4()));
+    std::fs::create_dir_all(&dir).expect("create dir");
+    let db_path = dir.join("echo-daily.db");
+    let pool = super::open_and_migrate(&db_path, &dir.join("backups"))
+        .await
+        .expect("open");
+
+    let entry = queries::upsert_entry(&pool, "2026-07-01", "a secret picnic by the lake")
+        .await
+        .expect("upsert");
+    queries::create_ai_operation(&pool, &entry.id, "polish", "picnic", "a picnic", "p", "m")
+        .await
+        .expect("ai op");
+
+    assert!(crypto::enable(&pool, "short").await.is_err());
+    crypto::enable(&pool, "correct horse")
+        .await
+        .expect("enable");
+
+    // Existing and new rows are stored encrypted
+    queries::upsert_entry(&pool, "2026-07-02", "lakeside notes")
+        .await
+        .expect("upsert");
+    let raw: Vec<(String, String)> =
+        sqlx::query_as("SELECT content_json, content_text FROM entries")
+            .fetch_all(&pool)
+            .await
+            .expect("raw");
+    assert!(raw
+        .iter()
+        .all(|(json, text)| json.starts_with("enc:v1:") && text.starts_with("enc:v1:")));
+    let raw_op: String = sqlx::query_scalar("SELECT result_text FROM ai_operations")
+        .fetch_one(&pool)
+        .await
+        .expect("raw op");
+    assert!(raw_op.starts_with("enc:v1:"));
+    let on_disk: i64 = sqlx::query_scalar("SELECT COUNT(*) FROM entries_fts")
+        .fetch_one(&pool)
+        .await
+        .expect("disk index");
+    assert_eq!(on_disk, 0);
+
+    // Reads decrypt and search uses the in-memory index
+    let read = queries::get_first_entry(&pool, "2026-07-01")
+        .await
+        .expect("get")
+        .expect("entry");
+    assert_eq!(read.content_json, "a secret picnic by the lake");
+    let ops = queries::list_ai_operations(&pool, &entry.id)
+        .await
+        .expect("ops");
+    assert_eq!(ops[0].result_text, "a picnic");
+    let results = queries::search_entries(&pool, "picnic", 20, 0)
+        .await
+        .expect("search");
+    assert_eq!(results.total, 1);
+
+    // Content fingerprints (TTS cache keys) are keyed, so they can't confirm a guess
+    let keyed = crypto::Vault::of(&pool)
+        .fingerprint(b"a picnic")
+        .expect("fingerprint");
+    let unkeyed = crypto::Vault::Plain
+        .fingerprint(b"a picnic")
+        .expect("fingerprint");
+    assert_ne!(keyed, unkeyed);
+
+    // Locked: nothing readable, nothing searchable, nothing leaves the trash
+    let trashed = queries::upsert_entry(&pool, "2026-07-03", "thrown away")
+        .await
+        .expect("upsert");
+    queries::delete_entry(&pool, &trashed.id)
+        .await
+        .expect("delete");
+    crypto::lock(&pool).await.expect("lock");
+    assert!(queries::get_first_entry(&pool, "2026-07-01").await.is_err());
+    assert!(queries::search_entries(&pool, "picnic", 20, 0)
+        .await
+        .is_err());
+    assert!(queries::restore_entry(&pool, &trashed.id).await.is_err());
+    let still_trashed: bool =
+        sqlx::query_scalar("SELECT deleted_at IS NOT NULL FROM entries WHERE id = ?")
+            .bind(&trashed.id)
+            .fetch_one(&pool)
+            .await
+            .expect("trashed");
+    assert!(still_trashed);
+    assert!(crypto::unlock(&pool, "wrong horse").await.is_err());
+
+    crypto::change_passphrase(&pool, "correct horse", "battery staple")
+        .await
+        .expect("change passphrase");
+    crypto::lock(&pool).await.expect("lock");
+    assert!(crypto::unlock(&pool, "correct horse").await.is_err());
+    crypto::unlock(&pool, "battery staple")
+        .await
+        .expect("unlock");
+    let results = queries::search_entries(&pool, "lake*", 20, 0)
+        .await
+        .expect("search");
+    assert_eq!(results.total, 2);
+
+    // Audio files are sealed in place and read back transparently
+    let audio = dir.join("audio");
+    std::fs::create_dir_all(audio.join("entry")).expect("audio dir");
+    std::fs::write(audio.join("entry/clip.mp3"), b"ID3 audio").expect("write");
+    std::fs::write(audio.join("entry/clip.partial"), b"half sealed").expect("write");
+    let vault = crypto::Vault::of(&pool);
+    assert_eq!(crypto::seal_files(&audio, &vault).expect("seal"), 1);
+    assert_eq!(crypto::seal_files(&audio, &vault).expect("seal"), 0);
+    assert!(!audio.join("entry/clip.partial").exists());
+    let sealed = std::fs::read(audio.join("entry/clip.mp3")).expect("read");
+    assert!(sealed.starts_with(crypto::BLOB_MAGIC));
+    assert_eq!(vault.open_bytes(sealed).expect("open"), b"ID3 audio");
+
+    // Plaintext backups, snapshots included, can all be removed
+    let backups = dir.join("backups");
+    super::backup::create_backup(&pool, &backups)
+        .await
+        .expect("backup");
+    super::backup::create_pre_migration_snapshot(&pool, &backups, 1)
+        .await
+        .expect("snapshot");
+    assert_eq!(
+        super::backup::delete_all_backups(&backups).expect("delete"),
+        2
+    );
+    assert!(super::backup::list_backups(&backups)
+        .expect("list")
+        .is_empty());
+    assert!(!backups.join("pre-migration").exists());
+
+    pool.close().await;
+    std::fs::remove_dir_all(&dir).ok();
+}
+
+#[tokio::test]
+async fn enabling_encryption_seals_files_until_it_completes() {
+    let dir = std::env::temp_dir().join(format!("echo-daily-crypto-{}", uuid::Uuid::new_v4()));
+    std::fs::create_dir_all(&dir).expect("create dir");
+    let backups = dir.join("backups");
+    let pool = super::open_and_migrate(&dir.join("echo-daily.db"), &backups)
+        .await
+        .expect("open");
+
+    let audio = dir.join("audio");
+    std::fs::create_dir_all(&audio).expect("audio dir");
+    std::fs::write(audio.join("clip.mp3"), b"ID3 audio").expect("write");
+    let tts_cache = dir.join("tts_cache");
+    std::fs::create_dir_all(&tts_cache).expect("cache dir");
+    std::fs::write(tts_cache.join("0123abcd.mp3"), b"ID3 cached").expect("write");
+    super::backup::create_backup(&pool, &backups)
+        .await
+        .expect("backup");
+    let plaintext = super::backup::list_backups(&backups).expect("list");
+    let entry = queries::upsert_entry(&pool, "2026-08-01", "beach day")
+        .await
+        .expect("upsert");
+    let stored = crate::attachments::store(&dir, &crypto::Vault::Plain, "notes.txt", b"notes")
+        .expect("store");
+    let attachment = crate::models::Attachment {
+        id: "notes".to_string(),
+        entry_id: entry.id,
+        sha256: stored.sha256.clone(),
+        file_name: "notes.txt".to_string(),
+        mime_type: stored.mime_type,
+        size_bytes: stored.size_bytes,
+        width: None,
+        height: None,
+        has_thumbnail: false,
+        created_at: 1,
+    };
+    queries::create_attachment(&pool, &attachment)
+        .await
+        .expect("attach");
+
+    // Nothing is pending before encryption is turned on
+    let dirs = [audio.clone()];
+    let discard = [tts_cache.clone()];
+    assert!(
+        !crypto::seal_pending_files(&pool, &dir, &dirs, &discard, &backups)
+            .await
+            .expect("nothing pending")
+    );
+
+    crypto::enable(&pool, "correct horse")
+        .await
+        .expect("enable");
+
+    // Interrupted before the files were sealed: the next unlock finishes
+    crypto::lock(&pool).await.expect("lock");
+    assert!(
+        crypto::seal_pending_files(&pool, &dir, &dirs, &discard, &backups)
+            .await
+            .is_err()
+    );
+    crypto::unlock(&pool, "correct horse")
+        .await
+        .expect("unlock");
+    assert!(
+        crypto::seal_pending_files(&pool, &dir, &dirs, &discard, &backups)
+            .await
+            .expect("seal")
+    );
+
+    let sealed = std::fs::read(audio.join("clip.mp3")).expect("read");
+    assert!(sealed.starts_with(crypto::BLOB_MAGIC));
+    assert!(!tts_cache.exists());
+
+    // Attachments stored before are renamed from their plain SHA-256
+    let vault = crypto::Vault::of(&pool);
+    let renamed = queries::get_attachment(&pool, "notes")
+        .await
+        .expect("get")
+        .expect("attachment");
+    assert_ne!(renamed.sha256, stored.sha256);
+    assert_eq!(
+        renamed.sha256,
+        vault.fingerprint(b"notes").expect("fingerprint")
+    );
+    assert!(!crate::attachments::locate(&dir, &stored.sha256, false)
+        .expect("locate")
+        .exists());
+    assert_eq!(
+        crate::attachments::read(&dir, &vault, &renamed.sha256, false).expect("read"),
+        b"notes"
+    );
+
+    let encrypted = super::backup::list_backups(&backups).expect("list");
+    assert_eq!(encrypted.len(), 1);
+    assert!(plaintext
+        .iter()
+        .all(|backup| backup.file_name != encrypted[0].file_name));
+
+    // Done once, later unlocks leave files and backups alone
+    assert!(
+        !crypto::seal_pending_files(&pool, &dir, &dirs, &discard, &backups)
+            .await
+            .expect("done")
+    );
+
+    pool.close().await;
+    std::fs::remove_dir_all(&dir).ok();
+}
+
+#[tokio::test]
+async fn attachments_follow_their_entry() {
+    use crate::models::Attachment;
+
+    let pool = sqlx::SqlitePool::connect("sqlite::memory:")
+        .await
+        .expect("connect");
+    migrations::run(&pool).await.expect("migrate");
+
+    let kept = queries::upsert_entry(&pool, "2026-08-01", "beach day")
+        .await
+        .expect("upsert");
+    let trashed = queries::upsert_entry(&pool, "2026-08-02", "rainy day")
+        .await
+        .expect("upsert");
+    let attachment = |id: &str, entry_id: &str, sha256: &str| Attachment {
+        id: id.to_string(),
+        entry_id: entry_id.to_string(),
+        sha256: sha256.repeat(64),
+        file_name: format!("{id}.jpg"),
+        mime_type: "image/jpeg".to_string(),
+        size_bytes: 10,
+        width: Some(4),
+        height: Some(3),
+        has_thumbnail: true,
+        created_at: 1,
+    };
+    for (id, entry_id, sha256) in [
+        ("sand", &kept.id, "a"),
+        ("waves", &kept.id, "b"),
+        ("puddle", &trashed.id, "b"),
+    ] {
+        queries::create_attachment(&pool, &attachment(id, entry_id, sha256))
+            .await
+            .expect("attach");
+    }
+    assert_eq!(
+        queries::list_attachments(&pool, &kept.id)
+            .await
+            .expect("list")
+            .len(),
+        2
+    );
+
+    // Trashed entries keep their files until purged; shared files survive the purge
+    queries::delete_entry(&pool, &trashed.id)
+        .await
+        .expect("delete");
+    let hashes = queries::referenced_attachment_hashes(&pool)
+        .await
+        .expect("hashes");
+    assert_eq!(hashes.len(), 2);
+    queries::empty_trash(&pool).await.expect("empty");
+    assert!(queries::get_attachment(&pool, "puddle")
+        .await
+        .expect("get")
+        .is_none());
+    assert_eq!(
+        queries::referenced_attachment_hashes(&pool)
+            .await
+            .expect("hashes"),
+        hashes
+    );
+
+    assert!(queries::delete_attachment(&pool, "sand")
+        .await
+        .expect("remove"));
+    assert!(!queries::referenced_attachment_hashes(&pool)
+        .await
+        .expect("hashes")
+        .contains(&"a".repeat(64)));
+
+    // Attachment rows travel with their entries through export
+    let export = queries::export_all_data(&pool).await.expect("export");
+    assert_eq!(export.attachments.len(), 1);
+    let other = sqlx::SqlitePool::connect("sqlite::memory:")
+        .await
+        .expect("connect");
+    migrations::run(&other).await.expect("migrate");
+    let options = crate::models::ImportOptions {
+        overwrite: false,
+        include_ai_operations: false,
+    };
+    queries::import_data(&other, export, options)
+        .await
+        .expect("import");
+    let imported = queries::list_attachments(&other, &kept.id)
+        .await
+        .expect("list");
+    assert_eq!(imported.len(), 1);
+    assert_eq!(imported[0].file_name, "waves.jpg");
+}
+
+#[test]
+fn diary_days_follow_the_local_wall_clock() {
+    use chrono::{NaiveDate, TimeZone};
+    use chrono_tz::{America::New_York, Asia::Shanghai};
+
+    let date = |y, m, d| NaiveDate::from_ymd_opt(y, m, d).expect("date");
+
+    // East of UTC the local date is already the next day
+    let morning = Shanghai.with_ymd_and_hms(2026, 5, 2, 7, 0, 0).unwrap();
+    assert_eq!(queries::journal_date(&morning, 0), date(2026, 5, 2));
+    assert_eq!(morning.naive_utc().date(), date(2026, 5, 1));
+
+    // Night owls: 1am still belongs to the previous day when days start at 4
+    let late = Shanghai.with_ymd_and_hms(2026, 5, 2, 1, 30, 0).unwrap();
+    assert_eq!(queries::journal_date(&late, 4), date(2026, 5, 1));
+    assert_eq!(queries::journal_date(&late, 0), date(2026, 5, 2));
+
+    // Spring forward (2:00 -> 3:00): the day still turns at 3:00 wall-clock
+    let before = New_York.with_ymd_and_hms(2026, 3, 8, 1, 59, 0).unwrap();
+    let after = New_York.with_ymd_and_hms(2026, 3, 8, 3, 0, 0).unwrap();
+    assert_eq!((after - before).num_minutes(), 1);
+    assert_eq!(queries::journal_date(&before, 3), date(2026, 3, 7));
+    assert_eq!(queries::journal_date(&after, 3), date(2026, 3, 8));
+
+    // Fall back (2:00 -> 1:00): both 1:30s are before a 2:00 day start
+    let repeated = New_York.with_ymd_and_hms(2026, 11, 1, 1, 30, 0);
+    let (first, second) = (repeated.earliest().unwrap(), repeated.latest().unwrap());
+    assert_eq!(queries::journal_date(&first, 2), date(2026, 10, 31));
+    assert_eq!(queries::journal_date(&second, 2), date(2026, 10, 31));
+    let turned = New_York.with_ymd_and_hms(2026, 11, 1, 2, 0, 0).unwrap();
+    assert_eq!(queries::journal_date(&turned, 2), date(2026, 11, 1));
+}
+
+#[tokio::test]
+async fn streaks_count_diary_days_and_the_day_start_is_a_setting() {
+    let pool = sqlx::SqlitePool::connect("sqlite::memory:")
+        .await
+        .expect("connect");
+    migrations::run(&pool).await.expect("migrate");
+
+    let settings = queries::get_day_settings(&pool).await.expect("settings");
+    assert_eq!(settings.day_start_hour, 0);
+    let invalid = crate::models::DaySettings { day_start_hour: 24 };
+    assert!(queries::save_day_settings(&pool, &invalid).await.is_err());
+    let settings = crate::models::DaySettings { day_start_hour: 4 };
+    queries::save_day_settings(&pool, &settings)
+        .await
+        .expect("save");
+    assert_eq!(
+        queries::get_day_settings(&pool)
+            .await
+            .expect("settings")
+            .day_start_hour,
+        4
+    );
+
+    let dates: Vec<String> = ["2026-03-06", "2026-03-07", "2026-03-08", "2026-03-10"]
+        .iter()
+        .map(|d| d.to_string())
+        .collect();
+    let day = |d: &str| chrono::NaiveDate::parse_from_str(d, "%Y-%m-%d").expect("date");
+    assert_eq!(
+        queries::calculate_current_streak(&dates, day("2026-03-08")),
+        3
+    );
+    assert_eq!(
+        queries::calculate_current_streak(&dates, day("2026-03-10")),
+        1
+    );
+    // Nothing written today yet: the streak up to yesterday still counts
+    assert_eq!(
+        queries::calculate_current_streak(&dates, day("2026-03-09")),
+        3
+    );
+    assert_eq!(
+        queries::calculate_current_streak(&dates, day("2026-03-11")),
+        1
+    );
+    assert_eq!(
+        queries::calculate_current_streak(&dates, day("2026-03-12")),
+        0
+    );
+}
+
+#[tokio::test]
+async fn analytics_count_words_of_the_plain_text() {
+    use crate::content::{count_text, TextCounts};
+
+    assert_eq!(
+        count_text("Don't panic, it's   well-known.\n今天天气很好！"),
+        TextCounts {
+            words: 10,
+            characters: 33
+        }
+    );
+    assert_eq!(
+        count_text(" — … "),
+        TextCounts {
+            words: 0,
+            characters: 2
+        }
+    );
+
+    let pool = sqlx::SqlitePool::connect("sqlite::memory:")
+        .await
+        .expect("connect");
+    migrations::run(&pool).await.expect("migrate");
+
+    // Node types and attributes in the JSON are not words
+    let doc = |text: &str| {
+        serde_json::json!({
+            "type": "doc",
+            "content": [{
+                "type": "heading",
+                "attrs": { "level": 1 },
+                "content": [{ "type": "text", "text": text }]
+            }]
+        })
+        .to_string()
+    };
+    // Monday and Tuesday of ISO week 2026-W10, then a Monday in the next year
+    let monday = queries::upsert_entry(&pool, "2026-03-02", &doc("one two three"))
+        .await
+        .expect("upsert");
+    queries::create_entry(&pool, "2026-03-02", None, None, &doc("four"))
+        .await
+        .expect("create");
+    queries::upsert_entry(&pool, "2026-03-03", &doc("five six"))
+        .await
+        .expect("upsert");
+    queries::upsert_entry(&pool, "2027-01-04", &doc("写日记"))
+        .await
+        .expect("upsert");
+    for op_type in ["polish", "polish", "expand"] {
+        queries::create_ai_operation(&pool, &monday.id, op_type, "a", "b", "p", "m")
+            .await
+            .expect("ai op");
+    }
+
+    let analytics = queries::get_writing_analytics(&pool)
+        .await
+        .expect("analytics");
+    assert_eq!(analytics.total_words, 9);
+    assert_eq!(analytics.entries.len(), 4);
+    assert_eq!(analytics.entries[0].words, 3);
+    assert_eq!(analytics.entries[0].characters, 11);
+
+    let week = &analytics.weeks[0];
+    assert_eq!(week.period, "2026-W10");
+    assert_eq!((week.entries, week.days, week.words), (3, 2, 6));
+    assert_eq!(week.average_words, 2.0);
+    let months: Vec<_> = analytics
+        .months
+        .iter()
+        .map(|m| (m.period.as_str(), m.entries))
+        .collect();
+    assert_eq!(months, [("2026-03", 3), ("2027-01", 1)]);
+    assert_eq!(analytics.years.len(), 2);
+
+    assert_eq!(analytics.weekdays.len(), 7);
+    assert_eq!(
+        (analytics.weekdays[0].entries, analytics.weekdays[0].words),
+        (3, 7)
+    );
+    assert_eq!(analytics.weekdays[1].words, 2);
+
+    let usage: Vec<_> = analytics
+        .ai_usage
+        .iter()
+        .map(|u| (u.op_type.as_str(), u.count))
+        .collect();
+    assert_eq!(usage, [("polish", 2), ("expand", 1)]);
+}
+
+#[tokio::test]
+async fn calendar_days_cover_the_whole_range_without_content() {
+    let pool = sqlx::SqlitePool::connect("sqlite::memory:")
+        .await
+        .expect("connect");
+    migrations::run(&pool).await.expect("migrate");
+
+    let first = queries::upsert_entry(&pool, "2026-02-27", "one two")
+        .await
+        .expect("upsert");
+    let second = queries::create_entry(&pool, "2026-02-27", None, Some("21:00"), "three")
+        .await
+        .expect("create");
+    queries::set_entry_mood(&pool, &second.id, Some("happy"), Some("😊"))
+        .await
+        .expect("mood");
+    queries::create_ai_operation(&pool, &first.id, "polish", "a", "b", "p", "m")
+        .await
+        .expect("ai op");
+    queries::create_audio_record(
+        &pool,
+        &crate::models::AudioRecord {
+            id: "clip".to_string(),
+            entry_id: second.id.clone(),
+            text: "three".to_string(),
+            audio_relpath: "audio/clip.mp3".to_string(),
+            format: "mp3".to_string(),
+            provider: "p".to_string(),
+            model: "m".to_string(),
+            voice: None,
+            speed: None,
+            duration_ms: None,
+            size_bytes: 1,
+            created_at: 1,
+            cache_key: None,
+        },
+    )
+    .await
+    .expect("audio");
+    let trashed = queries::upsert_entry(&pool, "2026-03-01", "gone")
+        .await
+        .expect("upsert");
+    queries::delete_entry(&pool, &trashed.id)
+        .await
+        .expect("delete");
+
+    // Counts the migration left unset are filled in on first use
+    sqlx::query("UPDATE entries SET word_count = NULL")
+        .execute(&pool)
+        .await
+        .expect("clear counts");
+
+    let date = |d: &str| chrono::NaiveDate::parse_from_str(d, "%Y-%m-%d").expect("date");
+    let days = queries::get_calendar_days(&pool, date("2026-02-26"), date("2026-03-01"))
+        .await
+        .expect("calendar");
+    let dates: Vec<_> = days.iter().map(|d| d.date.as_str()).collect();
+    assert_eq!(
+        dates,
+        ["2026-02-26", "2026-02-27", "2026-02-28", "2026-03-01"]
+    );
+
+    let busy = &days[1];
+    assert!(busy.has_entry);
+    assert_eq!((busy.entry_count, busy.word_count), (2, 3));
+    assert_eq!(busy.mood.as_deref(), Some("happy"));
+    assert!(busy.has_audio);
+    assert_eq!(busy.ai_ops_count, 1);
+
+    assert!(!days[0].has_entry);
+    // Trashed entries don't count
+    assert!(!days[3].has_entry);
+    assert_eq!(days[3].word_count, 0);
+}
+
+#[tokio::test]
+async fn on_this_day_resurfaces_earlier_years_and_lookbacks() {
+    // On disk, so the journal can be encrypted and locked at the end
+    let dir = std::env::temp_dir().join(format!("echo-daily-memories-{}", uuid::Uuid::new_v4()));
+    std::fs::create_dir_all(&dir).expect("create dir");
+    let pool = super::open_and_migrate(&dir.join("echo-daily.db"), &dir.join("backups"))
+        .await
+        .expect("open");
+
+    for (date, text) in [
+        ("2023-03-14", "pi day   in\nthe park"),
+        ("2025-03-14", "pi day again"),
+        ("2026-02-14", "a month back"),
+        ("2026-03-07", "a week back"),
+        ("2026-03-14", "today itself"),
+        ("2024-02-29", "leap day"),
+        ("2023-02-28", "last of february"),
+    ] {
+        queries::upsert_entry(&pool, date, text)
+            .await
+            .expect("upsert");
+    }
+    let trashed = queries::upsert_entry(&pool, "2024-03-14", "thrown away")
+        .await
+        .expect("upsert");
+    queries::delete_entry(&pool, &trashed.id)
+        .await
+        .expect("delete");
+
+    let date = |d: &str| chrono::NaiveDate::parse_from_str(d, "%Y-%m-%d").expect("date");
+    let found = queries::get_on_this_day(&pool, date("2026-03-14"), true)
+        .await
+        .expect("on this day");
+    let summary: Vec<_> = found
+        .iter()
+        .map(|e| (e.entry_date.as_str(), e.kind.as_str(), e.years_ago))
+        .collect();
+    assert_eq!(
+        summary,
+        [
+            ("2025-03-14", "year", 1),
+            ("2023-03-14", "year", 3),
+            ("2026-02-14", "month", 0),
+            ("2026-03-07", "week", 0),
+        ]
+    );
+    assert_eq!(found[1].preview, "pi day in the park");
+
+    let without_lookbacks = queries::get_on_this_day(&pool, date("2026-03-14"), false)
+        .await
+        .expect("on this day");
+    assert_eq!(without_lookbacks.len(), 2);
+
+    // Feb 29 shows up on Feb 28 of years without one, and only there
+    let dates = |entries: Vec<crate::models::OnThisDayEntry>| {
+        entries
+            .into_iter()
+            .map(|e| e.entry_date)
+            .collect::<Vec<_>>()
+    };
+    let feb_28 = queries::get_on_this_day(&pool, date("2025-02-28"), false)
+        .await
+        .expect("feb 28");
+    // A full year has passed since the leap day, not zero
+    let years: Vec<_> = feb_28.iter().map(|e| e.years_ago).collect();
+    assert_eq!(years, [1, 2]);
+    assert_eq!(dates(feb_28), ["2024-02-29", "2023-02-28"]);
+    let leap_feb_28 = queries::get_on_this_day(&pool, date("2028-02-28"), false)
+        .await
+        .expect("leap feb 28");
+    assert_eq!(dates(leap_feb_28), ["2023-02-28"]);
+    let feb_29 = queries::get_on_this_day(&pool, date("2028-02-29"), false)
+        .await
+        .expect("feb 29");
+    assert_eq!(dates(feb_29), ["2024-02-29"]);
+
+    // Nothing comes back while the journal is locked
+    crypto::enable(&pool, "correct horse")
+        .await
+        .expect("enable");
+    crypto::lock(&pool).await.expect("lock");
+    let locked = queries::get_on_this_day(&pool, date("2026-03-14"), true)
+        .await
+        .expect("locked");
+    assert!(locked.is_empty());
+    pool.close().await;
+
+    std::fs::remove_dir_all(&dir).ok();
+}
+
+#[tokio::test]
+async fn templates_fill_placeholders_when_starting_a_day() {
+    let pool = sqlx::SqlitePool::connect("sqlite::memory:")
+        .await
+        .expect("connect");
+    migrations::run(&pool).await.expect("migrate");
+
+    let doc = |text: &str| {
+        serde_json::json!({
+            "type": "doc",
+            "content": [{"type": "paragraph", "content": [{"type": "text", "text": text}]}],
+        })
+        .to_string()
+    };
+    assert!(queries::create_template(&pool, "  ", &doc("x"))
+        .await
+        .is_err());
+    assert!(queries::create_template(&pool, "Broken", "not json")
+        .await
+        .is_err());
+    let template = queries::create_template(
+        &pool,
+        " Morning ",
+        &doc("{{weekday}}, {{ date }}. Yesterday: {{yesterday_mood}}. {{prompt}} {{unknown}}"),
+    )
+    .await
+    .expect("create");
+    assert_eq!(template.name, "Morning");
+
+    // Without a default template there is nothing to start from
+    assert!(
+        queries::create_entry_from_template(&pool, "2026-03-14", None, None)
+            .await
+            .is_err()
+    );
+    queries::save_template_settings(
+        &pool,
+        &crate::models::TemplateSettings {
+            default_template_id: Some(template.id.clone()),
+        },
+    )
+    .await
+    .expect("save settings");
+
+    let yesterday = queries::upsert_entry(&pool, "2026-03-13", "fine")
+        .await
+        .expect("upsert");
+    queries::set_entry_mood(&pool, &yesterday.id, Some("happy"), Some("😊"))
+        .await
+        .expect("mood");
+
+    let prompt = queries::add_custom_prompt(&pool, "What surprised you?", None, None)
+        .await
+        .expect("prompt");
+    let entry = queries::create_entry_from_template(&pool, "2026-03-14", None, Some(&prompt.id))
+        .await
+        .expect("from template");
+    assert_eq!(
+        crate::content::extract_plain_text(&entry.content_json),
+        "Saturday, March 14, 2026. Yesterday: 😊 happy. What surprised you? {{unknown}}"
+    );
+    // The prompt counts as answered
+    let answered = queries::get_entry_prompt(&pool, &entry.id)
+        .await
+        .expect("entry prompt")
+        .expect("answered");
+    assert_eq!(answered.id, prompt.id);
+
+    // A day with text is never overwritten
+    assert!(
+        queries::create_entry_from_template(&pool, "2026-03-14", Some(&template.id), None)
+            .await
+            .is_err()
+    );
+
+    // Deleting the default template unsets it
+    assert!(queries::delete_template(&pool, &template.id)
+        .await
+        .expect("delete"));
+    let settings = queries::get_template_settings(&pool)
+        .await
+        .expect("settings");
+    assert!(settings.default_template_id.is_none());
+    assert!(queries::list_templates(&pool)
+        .await
+        .expect("list")
+        .is_empty());
+}
+
+#[tokio::test]
+async fn daily_prompts_are_stable_and_not_repeated_soon() {
+    let pool = sqlx::SqlitePool::connect("sqlite::memory:")
+        .await
+        .expect("connect");
+    migrations::run(&pool).await.expect("migrate");
+
+    let date = |d: &str| chrono::NaiveDate::parse_from_str(d, "%Y-%m-%d").expect("date");
+    let day = date("2026-05-04");
+
+    // The same day always gets the same prompt, of the requested level
+    let first = queries::get_daily_prompt(&pool, day, Some("B1"))
+        .await
+        .expect("prompt");
+    let again = queries::get_daily_prompt(&pool, day, Some("B1"))
+        .await
+        .expect("prompt");
+    assert_eq!(first.id, again.id);
+    assert_eq!(first.level.as_deref(), Some("B1"));
+    // Answering another prompt the day before doesn't change it either
+    let b1 = queries::list_prompts(&pool, Some("B1"), None, false)
+        .await
+        .expect("prompts");
+    let other = b1
+        .iter()
+        .find(|p| p.id != first.id)
+        .expect("another prompt");
+    let yesterday = queries::upsert_entry(&pool, "2026-05-03", "yesterday")
+        .await
+        .expect("upsert");
+    queries::set_entry_prompt(&pool, &yesterday.id, Some(&other.id))
+        .await
+        .expect("answer");
+    assert_eq!(
+        queries::get_daily_prompt(&pool, day, Some("B1"))
+            .await
+            .expect("prompt")
+            .id,
+        first.id
+    );
+    assert!(queries::get_daily_prompt(&pool, day, Some("D1"))
+        .await
+        .is_err());
+    // Without a level the one in the settings is used
+    queries::save_prompt_settings(
+        &pool,
+        &crate::models::PromptSettings {
+            level: Some("B1".to_string()),
+        },
+    )
+    .await
+    .expect("settings");
+    assert_eq!(
+        queries::get_daily_prompt(&pool, day, None)
+            .await
+            .expect("prompt")
+            .id,
+        first.id
+    );
+
+    // Skipping moves on to another prompt for that day only
+    let next = queries::skip_prompt(&pool, &first.id, day, Some("B1"))
+        .await
+        .expect("skip");
+    assert_ne!(next.id, first.id);
+
+    // Answering keeps the prompt for its day and rests it for the next months
+    let entry = queries::upsert_entry(&pool, "2026-05-04", "answer")
+        .await
+        .expect("upsert");
+    queries::set_entry_prompt(&pool, &entry.id, Some(&next.id))
+        .await
+        .expect("answer");
+    assert_eq!(
+        queries::get_daily_prompt(&pool, day, Some("A1"))
+            .await
+            .expect("prompt")
+            .id,
+        next.id
+    );
+    let mut later = day;
+    for _ in 0..60 {
+        later = later.succ_opt().expect("date");
+        let prompt = queries::get_daily_prompt(&pool, later, Some("B1"))
+            .await
+            .expect("prompt");
+        assert_ne!(prompt.id, next.id);
+    }
+
+    // Favorites and the user's own prompts
+    let custom = queries::add_custom_prompt(
+        &pool,
+        "  Describe your commute.  ",
+        Some("A2"),
+        Some("travel"),
+    )
+    .await
+    .expect("custom");
+    assert_eq!(custom.text, "Describe your commute.");
+    assert!(custom.is_custom);
+    assert!(queries::add_custom_prompt(&pool, " ", None, None)
+        .await
+        .is_err());
+    queries::set_prompt_favorite(&pool, &custom.id, true)
+        .await
+        .expect("favorite");
+    let favorites = queries::list_prompts(&pool, None, None, true)
+        .await
+        .expect("favorites");
+    assert_eq!(favorites.len(), 1);
+    assert_eq!(favorites[0].id, custom.id);
+    let travel = queries::list_prompts(&pool, Some("A2"), Some("travel"), false)
+        .await
+        .expect("travel");
+    assert!(travel.iter().any(|p| p.id == custom.id) && travel.len() > 1);
+
+    assert!(queries::delete_custom_prompt(&pool, &custom.id)
+        .await
+        .expect("delete"));
+    assert!(queries::list_prompts(&pool, None, None, true)
+        .await
+        .expect("favorites")
+        .is_empty());
+}
+
+#[tokio::test]
+async fn import_rejects_malformed_entry_dates() {
+    let pool = sqlx::SqlitePool::connect("sqlite::memory:")
+        .await
+        .expect("connect");
+    migrations::run(&pool).await.expect("migrate");
+    queries::upsert_entry(&pool, "2026-01-05", "coffee")
+        .await
+        .expect("upsert");
+    queries::upsert_entry(&pool, "2026-01-06", "tea")
+        .await
+        .expect("upsert");
+
+    let other = sqlx::SqlitePool::connect("sqlite::memory:")
+        .await
+        .expect("connect");
+    migrations::run(&other).await.expect("migrate");
+
+    // One malformed date rejects the whole file
+    let mut tampered = queries::export_all_data(&pool).await.expect("export");
+    tampered.entries[1].entry_date = "../../escape".to_string();
+    let options = crate::models::ImportOptions {
+        overwrite: false,
+        include_ai_operations: false,
+    };
+    assert!(matches!(
+        queries::import_data(&other, tampered, options).await,
+        Err(crate::error::AppError::InvalidEntryDate(_))
+    ));
+    assert!(queries::export_all_data(&other)
+        .await
+        .expect("export")
+        .entries
+        .is_empty());
+}
+
+#[tokio::test]
+async fn import_links_ai_operations_to_local_entries() {
+    let pool = sqlx::SqlitePool::connect("sqlite::memory:")
+        .await
+        .expect("connect");
+    migrations::run(&pool).await.expect("migrate");
+    let exported = queries::upsert_entry(&pool, "2026-03-01", "from the export")
+        .await
+        .expect("upsert");
+    queries::create_ai_operation(&pool, &exported.id, "polish", "from", "From", "p", "m")
+        .await
+        .expect("ai op");
+    queries::upsert_entry(&pool, "2026-03-02", "only in the export")
+        .await
+        .expect("upsert");
+    // Exports from before entry ids were kept are matched by date
+    let mut data = queries::export_all_data(&pool).await.expect("export");
+    data.version = "1.0".to_string();
+
+    let other = sqlx::SqlitePool::connect("sqlite::memory:")
+        .await
+        .expect("connect");
+    migrations::run(&other).await.expect("migrate");
+    let local = queries::upsert_entry(&other, "2026-03-01", "already here")
+        .await
+        .expect("upsert");
+
+    // A skipped day drops its operations instead of failing the import
+    let skip = crate::models::ImportOptions {
+        overwrite: false,
+        include_ai_operations: true,
+    };
+    assert_eq!(
+        queries::import_data(&other, data, skip)
+            .await
+            .expect("import"),
+        1
+    );
+    assert!(queries::list_ai_operations(&other, &local.id)
+        .await
+        .expect("ops")
+        .is_empty());
+
+    // An overwritten day takes the operations over under its local id
+    let mut data = queries::export_all_data(&pool).await.expect("export");
+    data.version = "1.0".to_string();
+    let overwrite = crate::models::ImportOptions {
+        overwrite: true,
+        include_ai_operations: true,
+    };
+    queries::import_data(&other, data, overwrite)
+        .await
+        .expect("import");
+    let ops = queries::list_ai_operations(&other, &local.id)
+        .await
+        .expect("ops");
+    assert_eq!(ops.len(), 1);
+    assert_eq!(ops[0].result_text, "From");
+}
+
+#[tokio::test]
+async fn export_leaves_out_ai_operations_of_trashed_entries() {
+    let pool = sqlx::SqlitePool::connect("sqlite::memory:")
+        .await
+        .expect("connect");
+    migrations::run(&pool).await.expect("migrate");
+
+    let kept = queries::upsert_entry(&pool, "2026-04-01", "kept")
+        .await
+        .expect("upsert");
+    let trashed = queries::upsert_entry(&pool, "2026-04-02", "secret")
+        .await
+        .expect("upsert");
+    for entry in [&kept, &trashed] {
+        queries::create_ai_operation(&pool, &entry.id, "polish", "text", "Text", "p", "m")
+            .await
+            .expect("ai op");
+    }
+    queries::delete_entry(&pool, &trashed.id)
+        .await
+        .expect("delete");
+
+    let export = queries::export_all_data(&pool).await.expect("export");
+    assert_eq!(export.entries.len(), 1);
+    assert_eq!(export.ai_operations.len(), 1);
+    assert_eq!(export.ai_operations[0].entry_id, kept.id);
+}
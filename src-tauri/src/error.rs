@@ -5,18 +5,30 @@ pub enum AppError {
     #[error("Database error: {0}")]
     Database(#[from] sqlx::Error),
 
+    #[error("Migration error: {0}")]
+    Migration(String),
+
     #[error("Entry not found: {0}")]
     EntryNotFound(String),
 
     #[error("Invalid entry date: {0}")]
     InvalidEntryDate(String),
 
+    #[error("Invalid entry time: {0}")]
+    InvalidEntryTime(String),
+
     #[error("Serialization error: {0}")]
     Serialization(#[from] serde_json::Error),
 
     #[error("IO error: {0}")]
     Io(#[from] std::io::Error),
 
+    #[error("Invalid tag: {0}")]
+    InvalidTag(String),
+
+    #[error("Invalid search: {0}")]
+    InvalidQuery(String),
+
     #[error("AI error: {0}")]
     AI(String),
 
@@ -24,6 +36,27 @@ pub enum AppError {
     #[allow(clippy::upper_case_acronyms)]
     TTS(String),
 
+    #[error("Encryption error: {0}")]
+    Encryption(String),
+
+    #[error("Incorrect passphrase")]
+    IncorrectPassphrase,
+
+    #[error("App is locked")]
+    Locked,
+
+    #[error("Invalid template: {0}")]
+    InvalidTemplate(String),
+
+    #[error("Invalid prompt: {0}")]
+    InvalidPrompt(String),
+
+    #[error("Attachment error: {0}")]
+    Attachment(String),
+
+    #[error("Export error: {0}")]
+    Export(String),
+
     #[error("Keychain error: {0}")]
     Keychain(String),
 
@@ -42,6 +75,12 @@ impl serde::Serialize for AppError {
     }
 }
 
+impl From<zip::result::ZipError> for AppError {
+    fn from(err: zip::result::ZipError) -> Self {
+        AppError::Export(err.to_string())
+    }
+}
+
 impl From<keyring::Error> for AppError {
     fn from(err: keyring::Error) -> Self {
         AppError::Keychain(err.to_string())
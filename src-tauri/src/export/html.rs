@@ -0,0 +1,516 @@
+//! Static HTML site export.
+//!
+//! The site opens straight from disk in any browser: an index grouped by year
+//! and month, one page per entry with its attachments and audio players, and a
+//! search box backed by an index written as a script, since pages loaded from
+//! `file://` can't fetch JSON.
+
+use std::collections::BTreeMap;
+
+use chrono::Datelike;
+use serde_json::{json, Value};
+
+use super::{
+    attachment_path, audio_path, collect_entries, copy_media, Archive, ExportedEntry, MediaSource,
+};
+use crate::content::extract_plain_text;
+use crate::db::queries::preview_of;
+use crate::error::AppError;
+use crate::models::{AudioRecord, ExportData, ExportSummary};
+
+const STYLE: &str = r#"* { box-sizing: border-box; }
+body { margin: 0; background: #faf9f7; color: #292524; font: 16px/1.65 Georgia, "Times New Roman", serif; }
+main { max-width: 720px; margin: 0 auto; padding: 32px 20px 64px; }
+a { color: #2563eb; text-decoration: none; }
+a:hover { text-decoration: underline; }
+h1 { font-size: 1.8em; line-height: 1.3; margin: 0 0 4px; }
+h2.year { margin: 40px 0 0; font-size: 1.5em; }
+h3.month { margin: 20px 0 8px; font-size: 1em; color: #78716c; text-transform: uppercase; letter-spacing: .05em; }
+ul.entries { list-style: none; margin: 0; padding: 0; }
+ul.entries li { padding: 8px 0; border-bottom: 1px solid #e7e5e4; }
+.date, .meta, .preview { color: #78716c; font-size: .9em; }
+.mood { margin-left: 4px; }
+.preview { display: block; }
+.tags span { display: inline-block; margin: 0 4px 4px 0; padding: 0 8px; border-radius: 999px; background: #e7e5e4; font-size: .8em; }
+nav { display: flex; justify-content: space-between; gap: 12px; margin: 0 0 24px; font-size: .9em; }
+article { margin-top: 24px; }
+blockquote { margin: 16px 0; padding-left: 16px; border-left: 3px solid #d6d3d1; color: #57534e; }
+pre { overflow-x: auto; padding: 12px; border-radius: 6px; background: #f5f5f4; }
+code { font-family: ui-monospace, Menlo, monospace; font-size: .9em; }
+img { max-width: 100%; height: auto; border-radius: 6px; }
+table { border-collapse: collapse; }
+td, th { padding: 4px 8px; border: 1px solid #d6d3d1; }
+ul.tasks { list-style: none; padding-left: 4px; }
+section.media { margin-top: 32px; padding-top: 16px; border-top: 1px solid #e7e5e4; }
+section.media audio { display: block; width: 100%; margin: 4px 0 12px; }
+#search { width: 100%; margin: 16px 0 8px; padding: 8px 12px; border: 1px solid #d6d3d1; border-radius: 8px; font: inherit; }
+"#;
+
+const SEARCH_SCRIPT: &str = r#"(function () {
+  var input = document.getElementById('search');
+  var results = document.getElementById('results');
+  var archive = document.getElementById('archive');
+  function text(tag, value, className) {
+    var node = document.createElement(tag);
+    node.textContent = value;
+    if (className) node.className = className;
+    return node;
+  }
+  input.addEventListener('input', function () {
+    var terms = input.value.toLowerCase().split(/\s+/).filter(Boolean);
+    results.innerHTML = '';
+    archive.hidden = terms.length > 0;
+    if (!terms.length) return;
+    var hits = window.SEARCH_INDEX.filter(function (entry) {
+      var haystack = [entry.title, entry.date, entry.tags.join(' '), entry.text].join(' ').toLowerCase();
+      return terms.every(function (term) { return haystack.indexOf(term) !== -1; });
+    });
+    if (!hits.length) results.appendChild(text('li', 'No matching entries', 'preview'));
+    hits.forEach(function (entry) {
+      var item = document.createElement('li');
+      var link = text('a', entry.title, '');
+      link.href = entry.url;
+      item.appendChild(text('span', entry.date + ' ', 'date'));
+      item.appendChild(link);
+      if (entry.mood) item.appendChild(text('span', entry.mood, 'mood'));
+      item.appendChild(text('span', entry.preview, 'preview'));
+      results.appendChild(item);
+    });
+  });
+})();
+"#;
+
+/// Write the site: `index.html`, `entries/{date}.html`, the stylesheet and
+/// search scripts, and the entries' attachments and audio.
+pub fn export(
+    mut archive: Archive,
+    data: &ExportData,
+    audio: &[AudioRecord],
+    media: &MediaSource,
+) -> Result<ExportSummary, AppError> {
+    let entries = collect_entries(data, audio);
+    let copied = copy_media(&mut archive, &entries, media)?;
+
+    for (i, exported) in entries.iter().enumerate() {
+        // Newer and older neighbours, for the links at the top of the page
+        let newer = entries.get(i + 1);
+        let older = i.checked_sub(1).and_then(|i| entries.get(i));
+
+        let mut body = String::from("<nav><a href=\"../index.html\">All entries</a><span>");
+        if let Some(older) = older {
+            body.push_str(&format!(
+                "<a href=\"{}.html\">← {}</a> ",
+                escape(&older.stem),
+                older.date.format("%Y-%m-%d")
+            ));
+        }
+        if let Some(newer) = newer {
+            body.push_str(&format!(
+                "<a href=\"{}.html\">{} →</a>",
+                escape(&newer.stem),
+                newer.date.format("%Y-%m-%d")
+            ));
+        }
+        body.push_str("</span></nav>\n");
+
+        body.push_str(&format!("<h1>{}", escape(&entry_title(exported))));
+        if let Some(emoji) = &exported.entry.mood_emoji {
+            body.push_str(&format!("<span class=\"mood\">{}</span>", escape(emoji)));
+        }
+        body.push_str("</h1>\n");
+        body.push_str(&format!(
+            "<div class=\"meta\">{}</div>\n",
+            escape(&long_date(exported))
+        ));
+        if !exported.tags.is_empty() {
+            body.push_str("<div class=\"tags\">");
+            for tag in &exported.tags {
+                body.push_str(&format!("<span>{}</span>", escape(tag)));
+            }
+            body.push_str("</div>\n");
+        }
+        body.push_str(&format!(
+            "<article>\n{}</article>\n",
+            to_html(&exported.entry.content_json)
+        ));
+
+        let attachments: Vec<_> = exported
+            .attachments
+            .iter()
+            .map(|a| (a, attachment_path(a)))
+            .filter(|(_, path)| copied.contains(path))
+            .collect();
+        let clips: Vec<_> = exported
+            .audio
+            .iter()
+            .map(|r| (r, audio_path(r)))
+            .filter(|(_, path)| copied.contains(path))
+            .collect();
+        if !attachments.is_empty() || !clips.is_empty() {
+            body.push_str("<section class=\"media\">\n");
+            for (attachment, path) in attachments {
+                let src = format!("../{}", url_path(&path));
+                if attachment.mime_type.starts_with("image/") {
+                    body.push_str(&format!(
+                        "<p><img src=\"{}\" alt=\"{}\"></p>\n",
+                        escape(&src),
+                        escape(&attachment.file_name)
+                    ));
+                } else {
+                    body.push_str(&format!(
+                        "<p><a href=\"{}\">{}</a></p>\n",
+                        escape(&src),
+                        escape(&attachment.file_name)
+                    ));
+                }
+            }
+            for (record, path) in clips {
+                body.push_str(&format!(
+                    "<div class=\"preview\">{}</div>\n<audio controls preload=\"none\" src=\"../{}\"></audio>\n",
+                    escape(&preview_of(&record.text)),
+                    escape(&url_path(&path))
+                ));
+            }
+            body.push_str("</section>\n");
+        }
+
+        let page = page(&entry_title(exported), "../", &body, "");
+        archive.add(&entry_page(exported), page.as_bytes())?;
+    }
+
+    archive.add("style.css", STYLE.as_bytes())?;
+    archive.add("search.js", SEARCH_SCRIPT.as_bytes())?;
+    archive.add("search-index.js", search_index(&entries).as_bytes())?;
+    archive.add("index.html", index(&entries).as_bytes())?;
+
+    let path = archive.path().display().to_string();
+    archive.finish()?;
+    Ok(ExportSummary {
+        path,
+        entries: entries.len(),
+        media_files: copied.files,
+        skipped_media: copied.skipped,
+    })
+}
+
+/// Path of an entry's page inside the site
+pub fn entry_page(exported: &ExportedEntry) -> String {
+    format!("entries/{}.html", exported.stem)
+}
+
+fn entry_title(exported: &ExportedEntry) -> String {
+    match exported.entry.title.as_deref().map(str::trim) {
+        Some(title) if !title.is_empty() => title.to_string(),
+        _ => long_date(exported),
+    }
+}
+
+/// "Tuesday, March 5, 2024 · 21:00"
+fn long_date(exported: &ExportedEntry) -> String {
+    let mut date = exported.date.format("%A, %B %-d, %Y").to_string();
+    if let Some(time) = &exported.entry.entry_time {
+        date.push_str(&format!(" · {time}"));
+    }
+    date
+}
+
+fn page(title: &str, root: &str, body: &str, scripts: &str) -> String {
+    format!(
+        "<!DOCTYPE html>\n<html>\n<head>\n<meta charset=\"utf-8\">\n\
+         <meta name=\"viewport\" content=\"width=device-width, initial-scale=1\">\n\
+         <title>{}</title>\n<link rel=\"stylesheet\" href=\"{root}style.css\">\n</head>\n\
+         <body>\n<main>\n{body}</main>\n{scripts}</body>\n</html>\n",
+        escape(title)
+    )
+}
+
+/// The index: a search box over the entries grouped by year and month, newest first
+fn index(entries: &[ExportedEntry]) -> String {
+    let mut months: BTreeMap<(i32, u32), Vec<&ExportedEntry>> = BTreeMap::new();
+    for exported in entries {
+        months
+            .entry((exported.date.year(), exported.date.month()))
+            .or_default()
+            .push(exported);
+    }
+
+    let mut body = String::from(
+        "<h1>Journal</h1>\n\
+         <input id=\"search\" type=\"search\" placeholder=\"Search entries\" autocomplete=\"off\">\n\
+         <ul id=\"results\" class=\"entries\"></ul>\n<div id=\"archive\">\n",
+    );
+    if entries.is_empty() {
+        body.push_str("<p class=\"preview\">No entries yet.</p>\n");
+    }
+    let mut year = None;
+    for (&(month_year, _), entries) in months.iter().rev() {
+        if year != Some(month_year) {
+            year = Some(month_year);
+            body.push_str(&format!("<h2 class=\"year\">{month_year}</h2>\n"));
+        }
+        body.push_str(&format!(
+            "<h3 class=\"month\">{}</h3>\n<ul class=\"entries\">\n",
+            entries[0].date.format("%B")
+        ));
+        for exported in entries.iter().rev() {
+            body.push_str(&format!(
+                "<li><span class=\"date\">{} </span><a href=\"{}\">{}</a>",
+                exported.date.format("%Y-%m-%d"),
+                escape(&url_path(&entry_page(exported))),
+                escape(&entry_title(exported))
+            ));
+            if let Some(emoji) = &exported.entry.mood_emoji {
+                body.push_str(&format!("<span class=\"mood\">{}</span>", escape(emoji)));
+            }
+            let preview = preview_of(&extract_plain_text(&exported.entry.content_json));
+            if !preview.is_empty() {
+                body.push_str(&format!(
+                    "<span class=\"preview\">{}</span>",
+                    escape(&preview)
+                ));
+            }
+            body.push_str("</li>\n");
+        }
+        body.push_str("</ul>\n");
+    }
+    body.push_str("</div>\n");
+
+    page(
+        "Journal",
+        "",
+        &body,
+        "<script src=\"search-index.js\"></script>\n<script src=\"search.js\"></script>\n",
+    )
+}
+
+/// Plain text of every entry for the search box, as a script defining
+/// `window.SEARCH_INDEX`
+fn search_index(entries: &[ExportedEntry]) -> String {
+    let index: Vec<Value> = entries
+        .iter()
+        .map(|exported| {
+            let text = extract_plain_text(&exported.entry.content_json);
+            json!({
+                "url": url_path(&entry_page(exported)),
+                "date": exported.date.format("%Y-%m-%d").to_string(),
+                "title": entry_title(exported),
+                "mood": exported.entry.mood_emoji,
+                "tags": exported.tags,
+                "preview": preview_of(&text),
+                "text": text,
+            })
+        })
+        .collect();
+    // "</" would end the script early if the index were ever inlined
+    let json = Value::from(index).to_string().replace("</", "<\\/");
+    format!("window.SEARCH_INDEX = {json};\n")
+}
+
+/// Percent-encode what a relative URL can't hold as written
+fn url_path(path: &str) -> String {
+    let mut out = String::with_capacity(path.len());
+    for byte in path.bytes() {
+        match byte {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'_' | b'.' | b'~' | b'/' => {
+                out.push(byte as char)
+            }
+            _ => out.push_str(&format!("%{byte:02X}")),
+        }
+    }
+    out
+}
+
+/// Escape text for HTML content and quoted attributes
+fn escape(text: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '&' => out.push_str("&amp;"),
+            '<' => out.push_str("&lt;"),
+            '>' => out.push_str("&gt;"),
+            '"' => out.push_str("&quot;"),
+            '\'' => out.push_str("&#39;"),
+            c => out.push(c),
+        }
+    }
+    out
+}
+
+/// Only links that can't run script in the exported page are kept
+fn safe_url(url: &str) -> Option<&str> {
+    let lower = url.trim().to_ascii_lowercase();
+    let scheme = lower.split_once(':').map(|(scheme, _)| scheme);
+    match scheme {
+        None => Some(url),
+        Some("http" | "https" | "mailto") => Some(url),
+        Some("data") if lower.starts_with("data:image/") => Some(url),
+        Some(scheme) if scheme.contains(['/', '?', '#']) => Some(url),
+        _ => None,
+    }
+}
+
+/// Convert a ProseMirror document to HTML.
+///
+/// Every string from the document is escaped and links or images with a
+/// script-capable URL are dropped. Content that is not valid JSON is shown as
+/// paragraphs of plain text.
+pub fn to_html(content_json: &str) -> String {
+    let mut out = String::new();
+    match serde_json::from_str::<Value>(content_json) {
+        Ok(doc) => render_children(&doc, &mut out),
+        Err(_) => {
+            for line in content_json.lines().filter(|l| !l.trim().is_empty()) {
+                out.push_str(&format!("<p>{}</p>\n", escape(line.trim())));
+            }
+        }
+    }
+    out
+}
+
+fn attr<'a>(node: &'a Value, name: &str) -> Option<&'a Value> {
+    node.get("attrs").and_then(|attrs| attrs.get(name))
+}
+
+fn render_children(node: &Value, out: &mut String) {
+    for child in node
+        .get("content")
+        .and_then(Value::as_array)
+        .into_iter()
+        .flatten()
+    {
+        render(child, out);
+    }
+}
+
+fn wrap(tag: &str, node: &Value, out: &mut String) {
+    out.push_str(&format!("<{tag}>"));
+    render_children(node, out);
+    out.push_str(&format!("</{tag}>"));
+}
+
+fn render(node: &Value, out: &mut String) {
+    match node.get("type").and_then(Value::as_str).unwrap_or("") {
+        "text" => render_text(node, out),
+        "paragraph" => {
+            wrap("p", node, out);
+            out.push('\n');
+        }
+        "heading" => {
+            // The page title is the h1, so entry headings start one level lower
+            let level = attr(node, "level").and_then(Value::as_u64).unwrap_or(1);
+            wrap(&format!("h{}", (level + 1).clamp(2, 6)), node, out);
+            out.push('\n');
+        }
+        "blockquote" => {
+            out.push_str("<blockquote>\n");
+            render_children(node, out);
+            out.push_str("</blockquote>\n");
+        }
+        "codeBlock" => {
+            let code: String = node
+                .get("content")
+                .and_then(Value::as_array)
+                .into_iter()
+                .flatten()
+                .filter_map(|n| n.get("text").and_then(Value::as_str))
+                .collect();
+            out.push_str(&format!("<pre><code>{}</code></pre>\n", escape(&code)));
+        }
+        "bulletList" => {
+            out.push_str("<ul>\n");
+            render_children(node, out);
+            out.push_str("</ul>\n");
+        }
+        "orderedList" => {
+            match attr(node, "start").and_then(Value::as_u64) {
+                Some(start) if start != 1 => out.push_str(&format!("<ol start=\"{start}\">\n")),
+                _ => out.push_str("<ol>\n"),
+            }
+            render_children(node, out);
+            out.push_str("</ol>\n");
+        }
+        "taskList" => {
+            out.push_str("<ul class=\"tasks\">\n");
+            render_children(node, out);
+            out.push_str("</ul>\n");
+        }
+        "listItem" => {
+            wrap("li", node, out);
+            out.push('\n');
+        }
+        "taskItem" => {
+            let checked = attr(node, "checked").and_then(Value::as_bool) == Some(true);
+            out.push_str(if checked {
+                "<li><input type=\"checkbox\" checked disabled> "
+            } else {
+                "<li><input type=\"checkbox\" disabled> "
+            });
+            render_children(node, out);
+            out.push_str("</li>\n");
+        }
+        "horizontalRule" => out.push_str("<hr>\n"),
+        "hardBreak" => out.push_str("<br>"),
+        "image" => {
+            let src = attr(node, "src").and_then(Value::as_str).and_then(safe_url);
+            if let Some(src) = src {
+                let alt = attr(node, "alt").and_then(Value::as_str).unwrap_or("");
+                out.push_str(&format!(
+                    "<img src=\"{}\" alt=\"{}\">",
+                    escape(src),
+                    escape(alt)
+                ));
+            }
+        }
+        "table" => {
+            out.push_str("<table>\n");
+            render_children(node, out);
+            out.push_str("</table>\n");
+        }
+        "tableRow" => {
+            wrap("tr", node, out);
+            out.push('\n');
+        }
+        "tableHeader" => wrap("th", node, out),
+        "tableCell" => wrap("td", node, out),
+        _ => render_children(node, out),
+    }
+}
+
+fn render_text(node: &Value, out: &mut String) {
+    let text = node.get("text").and_then(Value::as_str).unwrap_or("");
+    let mut open = String::new();
+    let mut close = Vec::new();
+    for mark in node
+        .get("marks")
+        .and_then(Value::as_array)
+        .into_iter()
+        .flatten()
+    {
+        let tag = match mark.get("type").and_then(Value::as_str) {
+            Some("bold") => "strong",
+            Some("italic") => "em",
+            Some("strike") => "s",
+            Some("underline") => "u",
+            Some("code") => "code",
+            Some("highlight") => "mark",
+            Some("link") => {
+                let href = attr(mark, "href")
+                    .and_then(Value::as_str)
+                    .and_then(safe_url);
+                if let Some(href) = href {
+                    open.push_str(&format!("<a href=\"{}\" rel=\"noopener\">", escape(href)));
+                    close.push("</a>".to_string());
+                }
+                continue;
+            }
+            _ => continue,
+        };
+        open.push_str(&format!("<{tag}>"));
+        close.push(format!("</{tag}>"));
+    }
+    out.push_str(&open);
+    out.push_str(&escape(text));
+    for tag in close.iter().rev() {
+        out.push_str(tag);
+    }
+}
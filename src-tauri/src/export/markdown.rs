@@ -0,0 +1,485 @@
+//! Markdown export: one file per entry, with YAML front matter.
+
+use serde_json::Value;
+
+use super::{
+    attachment_path, audio_path, collect_entries, copy_media, Archive, ExportedEntry, MediaSource,
+};
+use crate::error::AppError;
+use crate::models::{AudioRecord, ExportData, ExportSummary};
+
+/// Write every entry as `YYYY/{date}.md`. With `media`, attachments and saved
+/// audio are copied into `media/` and `audio/` and linked from their entries.
+pub fn export(
+    mut archive: Archive,
+    data: &ExportData,
+    audio: &[AudioRecord],
+    media: Option<&MediaSource>,
+) -> Result<ExportSummary, AppError> {
+    let entries = collect_entries(data, audio);
+    let copied = match media {
+        Some(media) => copy_media(&mut archive, &entries, media)?,
+        None => Default::default(),
+    };
+
+    for exported in &entries {
+        let mut file = front_matter(exported);
+        let body = to_markdown(&exported.entry.content_json);
+        if !body.is_empty() {
+            file.push('\n');
+            file.push_str(&body);
+            file.push('\n');
+        }
+
+        let attachments: Vec<_> = exported
+            .attachments
+            .iter()
+            .map(|a| (a, attachment_path(a)))
+            .filter(|(_, path)| copied.contains(path))
+            .collect();
+        if !attachments.is_empty() {
+            file.push_str("\n## Attachments\n\n");
+            for (attachment, path) in attachments {
+                let bang = if attachment.mime_type.starts_with("image/") {
+                    "!"
+                } else {
+                    ""
+                };
+                file.push_str(&format!(
+                    "- {bang}[{}](../{})\n",
+                    escape(&attachment.file_name),
+                    link_target(&path)
+                ));
+            }
+        }
+
+        let clips: Vec<_> = exported
+            .audio
+            .iter()
+            .map(|r| (r, audio_path(r)))
+            .filter(|(_, path)| copied.contains(path))
+            .collect();
+        if !clips.is_empty() {
+            file.push_str("\n## Audio\n\n");
+            for (record, path) in clips {
+                file.push_str(&format!(
+                    "- [{}](../{})\n",
+                    escape(&clip_label(&record.text)),
+                    link_target(&path)
+                ));
+            }
+        }
+
+        archive.add(&entry_path(exported), file.as_bytes())?;
+    }
+
+    let path = archive.path().display().to_string();
+    archive.finish()?;
+    Ok(ExportSummary {
+        path,
+        entries: entries.len(),
+        media_files: copied.files,
+        skipped_media: copied.skipped,
+    })
+}
+
+/// Path of an entry's file inside the export
+pub fn entry_path(exported: &ExportedEntry) -> String {
+    format!("{}/{}.md", exported.date.format("%Y"), exported.stem)
+}
+
+/// YAML front matter. Strings are written JSON-quoted, which is valid YAML
+/// whatever they contain.
+fn front_matter(exported: &ExportedEntry) -> String {
+    let entry = exported.entry;
+    let quote = |s: &str| Value::from(s).to_string();
+
+    let mut out = format!("---\ndate: {}\n", entry.entry_date);
+    if let Some(time) = &entry.entry_time {
+        out.push_str(&format!("time: {}\n", quote(time)));
+    }
+    if let Some(title) = &entry.title {
+        out.push_str(&format!("title: {}\n", quote(title)));
+    }
+    if let Some(summary) = &entry.summary {
+        out.push_str(&format!("summary: {}\n", quote(summary)));
+    }
+    if let Some(mood) = &entry.mood {
+        out.push_str(&format!("mood: {}\n", quote(mood)));
+    }
+    if let Some(emoji) = &entry.mood_emoji {
+        out.push_str(&format!("mood_emoji: {}\n", quote(emoji)));
+    }
+    if !exported.tags.is_empty() {
+        let tags: Vec<String> = exported.tags.iter().map(|t| quote(t)).collect();
+        out.push_str(&format!("tags: [{}]\n", tags.join(", ")));
+    }
+    out.push_str("---\n");
+    out
+}
+
+/// First words of a clip's text, to label its link
+fn clip_label(text: &str) -> String {
+    let text = text.split_whitespace().collect::<Vec<_>>().join(" ");
+    match text.char_indices().nth(60) {
+        Some((end, _)) => format!("{}…", &text[..end]),
+        None if text.is_empty() => "Audio".to_string(),
+        None => text,
+    }
+}
+
+/// Percent-encode the characters that would end or break a link target
+fn link_target(path: &str) -> String {
+    path.chars()
+        .map(|c| match c {
+            ' ' => "%20".to_string(),
+            '(' => "%28".to_string(),
+            ')' => "%29".to_string(),
+            '<' => "%3C".to_string(),
+            '>' => "%3E".to_string(),
+            c => c.to_string(),
+        })
+        .collect()
+}
+
+/// Convert a ProseMirror document to Markdown.
+///
+/// Covers the nodes and marks the editor produces: headings, paragraphs,
+/// lists (including task lists), blockquotes, code, rules, images, tables,
+/// bold, italic, strikethrough, inline code and links. Unknown nodes keep
+/// their text. Content that is not valid JSON is returned trimmed as-is.
+pub fn to_markdown(content_json: &str) -> String {
+    let Ok(doc) = serde_json::from_str::<Value>(content_json) else {
+        return content_json.trim().to_string();
+    };
+    blocks(&doc).join("\n\n")
+}
+
+fn children(node: &Value) -> &[Value] {
+    node.get("content")
+        .and_then(Value::as_array)
+        .map(Vec::as_slice)
+        .unwrap_or_default()
+}
+
+fn attr<'a>(node: &'a Value, name: &str) -> Option<&'a Value> {
+    node.get("attrs").and_then(|attrs| attrs.get(name))
+}
+
+/// The Markdown blocks of a node's children
+fn blocks(node: &Value) -> Vec<String> {
+    children(node).iter().filter_map(block).collect()
+}
+
+fn block(node: &Value) -> Option<String> {
+    let text = match node.get("type").and_then(Value::as_str)? {
+        "paragraph" => escape_line_start(&inline(node)),
+        "heading" => {
+            let level = attr(node, "level").and_then(Value::as_u64).unwrap_or(1);
+            let text = inline(node);
+            if text.is_empty() {
+                return None;
+            }
+            format!("{} {text}", "#".repeat(level.clamp(1, 6) as usize))
+        }
+        "blockquote" => prefix_lines(&blocks(node).join("\n\n"), "> ", ">"),
+        "codeBlock" => {
+            let code: String = children(node)
+                .iter()
+                .filter_map(|n| n.get("text").and_then(Value::as_str))
+                .collect();
+            let language = attr(node, "language").and_then(Value::as_str).unwrap_or("");
+            let fence = if code.contains("```") { "~~~" } else { "```" };
+            format!(
+                "{fence}{language}\n{}\n{fence}",
+                code.trim_end_matches('\n')
+            )
+        }
+        "bulletList" => list(node, |_, _| "- ".to_string()),
+        "orderedList" => {
+            let start = attr(node, "start").and_then(Value::as_u64).unwrap_or(1);
+            list(node, |i, _| format!("{}. ", start + i as u64))
+        }
+        "taskList" => list(node, |_, item| {
+            let checked = attr(item, "checked").and_then(Value::as_bool) == Some(true);
+            if checked { "- [x] " } else { "- [ ] " }.to_string()
+        }),
+        "horizontalRule" => "---".to_string(),
+        "image" => image(node),
+        "table" => table(node),
+        _ => blocks(node).join("\n\n"),
+    };
+    (!text.is_empty()).then_some(text)
+}
+
+/// A list whose items start with `marker(index, item)`. Item content after the
+/// first line is indented under the marker.
+fn list(node: &Value, marker: impl Fn(usize, &Value) -> String) -> String {
+    children(node)
+        .iter()
+        .enumerate()
+        .map(|(i, item)| {
+            let marker = marker(i, item);
+            let indent = if marker.starts_with("- [") {
+                2
+            } else {
+                marker.len()
+            };
+            let body = item_body(item);
+            let mut lines = body.lines();
+            let mut out = format!("{marker}{}", lines.next().unwrap_or(""));
+            for line in lines {
+                out.push('\n');
+                if !line.is_empty() {
+                    out.push_str(&" ".repeat(indent));
+                    out.push_str(line);
+                }
+            }
+            out
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+/// A list item's blocks. A nested list follows its text directly, which keeps
+/// the list tight.
+fn item_body(item: &Value) -> String {
+    let mut out = String::new();
+    for child in children(item) {
+        let Some(text) = block(child) else { continue };
+        if !out.is_empty() {
+            let kind = child.get("type").and_then(Value::as_str).unwrap_or("");
+            out.push_str(if kind.ends_with("List") { "\n" } else { "\n\n" });
+        }
+        out.push_str(&text);
+    }
+    out
+}
+
+fn image(node: &Value) -> String {
+    let src = attr(node, "src").and_then(Value::as_str).unwrap_or("");
+    if src.is_empty() {
+        return String::new();
+    }
+    let alt = attr(node, "alt").and_then(Value::as_str).unwrap_or("");
+    format!("![{}]({})", escape(alt), link_target(src))
+}
+
+/// A pipe table. The first row is the header, as in the editor.
+fn table(node: &Value) -> String {
+    let rows: Vec<Vec<String>> = children(node)
+        .iter()
+        .map(|row| {
+            children(row)
+                .iter()
+                .map(|cell| {
+                    children(cell)
+                        .iter()
+                        .map(inline)
+                        .filter(|text| !text.is_empty())
+                        .collect::<Vec<_>>()
+                        .join(" ")
+                        .replace('\n', " ")
+                        .replace('|', "\\|")
+                })
+                .collect()
+        })
+        .filter(|cells: &Vec<String>| !cells.is_empty())
+        .collect();
+    let Some(columns) = rows.iter().map(Vec::len).max() else {
+        return String::new();
+    };
+
+    let line = |cells: &[String]| {
+        let mut out = String::from("|");
+        for i in 0..columns {
+            out.push_str(&format!(
+                " {} |",
+                cells.get(i).map(String::as_str).unwrap_or("")
+            ));
+        }
+        out
+    };
+    let mut out = vec![line(&rows[0]), format!("|{}", " --- |".repeat(columns))];
+    out.extend(rows[1..].iter().map(|row| line(row)));
+    out.join("\n")
+}
+
+fn prefix_lines(text: &str, prefix: &str, empty_prefix: &str) -> String {
+    text.lines()
+        .map(|line| {
+            if line.is_empty() {
+                empty_prefix.to_string()
+            } else {
+                format!("{prefix}{line}")
+            }
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+/// A mark that wraps text in delimiters
+#[derive(PartialEq)]
+enum Mark<'a> {
+    Bold,
+    Italic,
+    Strike,
+    Link(&'a str),
+}
+
+impl Mark<'_> {
+    fn open(&self) -> &'static str {
+        match self {
+            Mark::Bold => "**",
+            Mark::Italic => "*",
+            Mark::Strike => "~~",
+            Mark::Link(_) => "[",
+        }
+    }
+
+    fn close(&self) -> String {
+        match self {
+            Mark::Bold => "**".to_string(),
+            Mark::Italic => "*".to_string(),
+            Mark::Strike => "~~".to_string(),
+            Mark::Link(href) => format!("]({})", link_target(href)),
+        }
+    }
+}
+
+/// Inline content of a text block.
+///
+/// Marks shared by neighbouring text nodes stay open across them, so "**a b**"
+/// doesn't become "**a** **b**". Spaces at the edges of marked text are moved
+/// outside the delimiters, where Markdown expects them.
+fn inline(node: &Value) -> String {
+    let mut out = String::new();
+    let mut open: Vec<Mark> = Vec::new();
+    // Trailing whitespace of the last text, written once its marks are closed
+    let mut pending = String::new();
+
+    for child in children(node) {
+        let (text, marks, code) = match child.get("type").and_then(Value::as_str) {
+            Some("text") => {
+                let text = child.get("text").and_then(Value::as_str).unwrap_or("");
+                let (marks, code) = text_marks(child);
+                (text.to_string(), marks, code)
+            }
+            Some("hardBreak") => ("\n".to_string(), Vec::new(), false),
+            Some("image") => (image(child), Vec::new(), false),
+            _ => continue,
+        };
+        let is_break = text == "\n";
+
+        let keep = open.iter().zip(&marks).take_while(|(a, b)| a == b).count();
+        while open.len() > keep {
+            let mark = open.pop().expect("open mark");
+            out.push_str(&mark.close());
+        }
+        out.push_str(&std::mem::take(&mut pending));
+
+        if is_break {
+            // Delimiters can't span lines, so close everything at a break
+            while let Some(mark) = open.pop() {
+                out.push_str(&mark.close());
+            }
+            out.push_str("\\\n");
+            continue;
+        }
+
+        let trimmed = text.trim_start();
+        out.push_str(&text[..text.len() - trimmed.len()]);
+        let core = trimmed.trim_end();
+        pending = trimmed[core.len()..].to_string();
+        if core.is_empty() {
+            continue;
+        }
+
+        for mark in marks.into_iter().skip(keep) {
+            out.push_str(mark.open());
+            open.push(mark);
+        }
+        if code {
+            let ticks = "`".repeat(longest_run(core, '`') + 1);
+            let pad = if core.starts_with('`') || core.ends_with('`') {
+                " "
+            } else {
+                ""
+            };
+            out.push_str(&format!("{ticks}{pad}{core}{pad}{ticks}"));
+        } else if child.get("type").and_then(Value::as_str) == Some("image") {
+            out.push_str(core);
+        } else {
+            out.push_str(&escape(core));
+        }
+    }
+
+    while let Some(mark) = open.pop() {
+        out.push_str(&mark.close());
+    }
+    out.push_str(&pending);
+    out.trim_end().to_string()
+}
+
+/// The delimiting marks of a text node (links outermost) and whether it is code
+fn text_marks(node: &Value) -> (Vec<Mark<'_>>, bool) {
+    let mut marks = Vec::new();
+    let mut code = false;
+    for mark in node
+        .get("marks")
+        .and_then(Value::as_array)
+        .into_iter()
+        .flatten()
+    {
+        match mark.get("type").and_then(Value::as_str) {
+            Some("bold") => marks.push(Mark::Bold),
+            Some("italic") => marks.push(Mark::Italic),
+            Some("strike") => marks.push(Mark::Strike),
+            Some("code") => code = true,
+            Some("link") => {
+                if let Some(href) = attr(mark, "href").and_then(Value::as_str) {
+                    marks.insert(0, Mark::Link(href));
+                }
+            }
+            _ => {}
+        }
+    }
+    (marks, code)
+}
+
+fn longest_run(text: &str, c: char) -> usize {
+    text.split(|ch| ch != c).map(str::len).max().unwrap_or(0)
+}
+
+/// Backslash-escape characters that Markdown would read as formatting
+fn escape(text: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+    for c in text.chars() {
+        if matches!(c, '\\' | '*' | '_' | '`' | '[' | ']' | '<' | '~') {
+            out.push('\\');
+        }
+        out.push(c);
+    }
+    out
+}
+
+/// Escape a paragraph that would otherwise start a heading, quote, list or rule
+fn escape_line_start(text: &str) -> String {
+    let digits = text.chars().take_while(char::is_ascii_digit).count();
+    let rest = &text[digits..];
+    let needs_escape = if digits > 0 {
+        (rest.starts_with(". ") || rest.starts_with(") ")) && digits <= 9
+    } else {
+        text.starts_with('#')
+            || text.starts_with('>')
+            || text.starts_with("- ")
+            || text.starts_with("+ ")
+            || text == "-"
+            || text.starts_with("---")
+    };
+    match needs_escape {
+        false => text.to_string(),
+        true if digits > 0 => format!("{}\\{}", &text[..digits], rest),
+        true => format!("\\{text}"),
+    }
+}
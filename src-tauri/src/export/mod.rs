@@ -0,0 +1,283 @@
+//! Readable exports of the journal.
+//!
+//! Unlike `export_data`, which round-trips everything as JSON for import,
+//! these exports are meant to be read outside the app. They work on the same
+//! [`ExportData`] (always plaintext) and write into an [`Archive`]: a new
+//! directory or a single zip file.
+
+pub mod html;
+pub mod markdown;
+
+use std::collections::{HashMap, HashSet};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+
+use chrono::NaiveDate;
+use zip::write::SimpleFileOptions;
+
+use crate::attachments;
+use crate::crypto::Vault;
+use crate::error::AppError;
+use crate::models::{ArchiveFormat, Attachment, AudioRecord, DiaryEntry, ExportData};
+use crate::tts;
+
+/// Where exported files go
+pub enum Archive {
+    Directory(PathBuf),
+    Zip {
+        writer: Box<zip::ZipWriter<std::fs::File>>,
+        partial: PartialFile,
+        path: PathBuf,
+    },
+}
+
+/// A zip being written next to its destination, removed when dropped so a
+/// failed export leaves nothing behind. Once moved into place there is
+/// nothing left to remove.
+pub struct PartialFile(PathBuf);
+
+impl Drop for PartialFile {
+    fn drop(&mut self) {
+        let _ = std::fs::remove_file(&self.0);
+    }
+}
+
+impl Archive {
+    /// Start an export at `path`. A directory must be new or empty and a zip
+    /// must not exist yet, so nothing of the user's is overwritten; a zip is
+    /// written next to `path` and only moved there once complete.
+    pub fn create(path: &Path, format: ArchiveFormat) -> Result<Self, AppError> {
+        match format {
+            ArchiveFormat::Directory => {
+                if path.is_dir() && std::fs::read_dir(path)?.next().is_some() {
+                    return Err(AppError::Export(format!("{} is not empty", path.display())));
+                }
+                std::fs::create_dir_all(path)?;
+                Ok(Archive::Directory(path.to_path_buf()))
+            }
+            ArchiveFormat::Zip => {
+                if path.exists() {
+                    return Err(AppError::Export(format!(
+                        "{} already exists",
+                        path.display()
+                    )));
+                }
+                let partial = PartialFile(path.with_extension("zip.partial"));
+                let writer = Box::new(zip::ZipWriter::new(std::fs::File::create(&partial.0)?));
+                Ok(Archive::Zip {
+                    writer,
+                    partial,
+                    path: path.to_path_buf(),
+                })
+            }
+        }
+    }
+
+    /// Where the export ends up
+    pub fn path(&self) -> &Path {
+        match self {
+            Archive::Directory(root) => root,
+            Archive::Zip { path, .. } => path,
+        }
+    }
+
+    /// Add a file at a `/`-separated path relative to the export root
+    pub fn add(&mut self, relpath: &str, bytes: &[u8]) -> Result<(), AppError> {
+        match self {
+            Archive::Directory(root) => {
+                let path = root.join(relpath);
+                if let Some(parent) = path.parent() {
+                    std::fs::create_dir_all(parent)?;
+                }
+                std::fs::write(path, bytes)?;
+            }
+            Archive::Zip { writer, .. } => {
+                let options = SimpleFileOptions::default()
+                    .compression_method(zip::CompressionMethod::Deflated);
+                writer.start_file(relpath, options)?;
+                writer.write_all(bytes)?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Complete the export
+    pub fn finish(self) -> Result<(), AppError> {
+        if let Archive::Zip {
+            writer,
+            partial,
+            path,
+        } = self
+        {
+            writer.finish()?;
+            std::fs::rename(&partial.0, path)?;
+        }
+        Ok(())
+    }
+}
+
+/// Reads attachment and audio files for an export, decrypted
+pub struct MediaSource<'a> {
+    pub app_data_dir: &'a Path,
+    pub vault: &'a Vault,
+}
+
+impl MediaSource<'_> {
+    pub fn attachment(&self, attachment: &Attachment) -> Result<Vec<u8>, AppError> {
+        attachments::read(self.app_data_dir, self.vault, &attachment.sha256, false)
+    }
+
+    pub fn audio(&self, record: &AudioRecord) -> Result<Vec<u8>, AppError> {
+        let path = tts::storage::resolve_audio_path(self.app_data_dir, &record.audio_relpath)?;
+        self.vault.open_bytes(std::fs::read(path)?)
+    }
+}
+
+/// An entry with everything that belongs to it
+pub struct ExportedEntry<'a> {
+    pub entry: &'a DiaryEntry,
+    pub date: NaiveDate,
+    /// File name without extension, unique in the export: the date, with
+    /// `-2`, `-3`, ... for a day's later entries
+    pub stem: String,
+    pub tags: Vec<&'a str>,
+    pub attachments: Vec<&'a Attachment>,
+    pub audio: Vec<&'a AudioRecord>,
+}
+
+/// Group tags, attachments and audio under their entries, in journal order.
+/// Entries whose date can't be parsed are left out, since file paths are
+/// built from it.
+pub fn collect_entries<'a>(
+    data: &'a ExportData,
+    audio: &'a [AudioRecord],
+) -> Vec<ExportedEntry<'a>> {
+    let tag_names: HashMap<&str, &str> = data
+        .tags
+        .iter()
+        .map(|tag| (tag.id.as_str(), tag.name.as_str()))
+        .collect();
+
+    let mut per_day: HashMap<NaiveDate, usize> = HashMap::new();
+    data.entries
+        .iter()
+        .filter_map(|entry| {
+            let Ok(date) = NaiveDate::parse_from_str(&entry.entry_date, "%Y-%m-%d") else {
+                println!(
+                    "Export: Skipping entry {} with invalid date {:?}",
+                    entry.id, entry.entry_date
+                );
+                return None;
+            };
+            let nth = per_day.entry(date).or_default();
+            *nth += 1;
+            let stem = match *nth {
+                1 => date.format("%Y-%m-%d").to_string(),
+                n => format!("{}-{n}", date.format("%Y-%m-%d")),
+            };
+
+            let mut tags: Vec<&str> = data
+                .entry_tags
+                .iter()
+                .filter(|link| link.entry_id == entry.id)
+                .filter_map(|link| tag_names.get(link.tag_id.as_str()).copied())
+                .collect();
+            tags.sort_unstable_by_key(|name| name.to_lowercase());
+
+            Some(ExportedEntry {
+                entry,
+                date,
+                stem,
+                tags,
+                attachments: data
+                    .attachments
+                    .iter()
+                    .filter(|a| a.entry_id == entry.id)
+                    .collect(),
+                audio: audio.iter().filter(|r| r.entry_id == entry.id).collect(),
+            })
+        })
+        .collect()
+}
+
+/// Media files copied into an export
+#[derive(Default)]
+pub struct CopiedMedia {
+    paths: HashSet<String>,
+    pub files: usize,
+    pub skipped: usize,
+}
+
+impl CopiedMedia {
+    /// Whether the file at this export path was copied and can be linked
+    pub fn contains(&self, path: &str) -> bool {
+        self.paths.contains(path)
+    }
+}
+
+/// Copy the attachments and audio of `entries` into the archive. Files that
+/// can't be read are counted and left out rather than failing the export.
+pub fn copy_media(
+    archive: &mut Archive,
+    entries: &[ExportedEntry],
+    media: &MediaSource,
+) -> Result<CopiedMedia, AppError> {
+    let mut copied = CopiedMedia::default();
+    for exported in entries {
+        for attachment in &exported.attachments {
+            let path = attachment_path(attachment);
+            if copied.contains(&path) {
+                continue;
+            }
+            match media.attachment(attachment) {
+                Ok(bytes) => {
+                    archive.add(&path, &bytes)?;
+                    copied.paths.insert(path);
+                    copied.files += 1;
+                }
+                Err(err) => {
+                    println!("Export: Skipping attachment {}: {err}", attachment.id);
+                    copied.skipped += 1;
+                }
+            }
+        }
+        for record in &exported.audio {
+            let path = audio_path(record);
+            match media.audio(record) {
+                Ok(bytes) => {
+                    archive.add(&path, &bytes)?;
+                    copied.paths.insert(path);
+                    copied.files += 1;
+                }
+                Err(err) => {
+                    println!("Export: Skipping audio {}: {err}", record.id);
+                    copied.skipped += 1;
+                }
+            }
+        }
+    }
+    Ok(copied)
+}
+
+/// Path of an attachment inside an export: `media/{short hash}-{file name}`.
+/// The hash keeps different files with the same name apart.
+pub fn attachment_path(attachment: &Attachment) -> String {
+    let name: String = attachment
+        .file_name
+        .chars()
+        .map(|c| match c {
+            '/' | '\\' | ':' | '*' | '?' | '"' | '<' | '>' | '|' => '_',
+            c if c.is_control() => '_',
+            c => c,
+        })
+        .collect();
+    format!("media/{}-{}", &attachment.sha256[..12], name.trim())
+}
+
+/// Path of a saved audio clip inside an export
+pub fn audio_path(record: &AudioRecord) -> String {
+    format!("audio/{}.{}", record.id, record.format)
+}
+
+#[cfg(test)]
+mod tests;
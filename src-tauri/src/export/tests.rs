@@ -0,0 +1,364 @@
+use std::io::Read;
+
+use serde_json::json;
+
+use super::html::to_html;
+use super::markdown::to_markdown;
+use super::{html, markdown, Archive, MediaSource};
+use crate::attachments;
+use crate::crypto::Vault;
+use crate::models::{
+    ArchiveFormat, Attachment, AudioRecord, DiaryEntry, EntryTag, ExportData, Tag,
+};
+use crate::tts;
+
+fn doc(content: serde_json::Value) -> String {
+    json!({ "type": "doc", "content": content }).to_string()
+}
+
+fn text(text: &str, marks: &[&str]) -> serde_json::Value {
+    let marks: Vec<_> = marks.iter().map(|m| json!({ "type": m })).collect();
+    json!({ "type": "text", "text": text, "marks": marks })
+}
+
+fn paragraph(content: serde_json::Value) -> serde_json::Value {
+    json!({ "type": "paragraph", "content": content })
+}
+
+fn entry(id: &str, date: &str, content_json: String) -> DiaryEntry {
+    DiaryEntry {
+        id: id.to_string(),
+        entry_date: date.to_string(),
+        entry_time: None,
+        title: None,
+        summary: None,
+        content_json,
+        mood: None,
+        mood_emoji: None,
+        created_at: 0,
+        updated_at: 0,
+        deleted_at: None,
+    }
+}
+
+#[test]
+fn prosemirror_documents_convert_to_markdown() {
+    let content = doc(json!([
+        { "type": "heading", "attrs": { "level": 2 }, "content": [text("A good day", &[])] },
+        paragraph(json!([
+            text("Went ", &[]),
+            text("hiking ", &["bold"]),
+            text("with", &["bold", "italic"]),
+            text(" Sam, ", &[]),
+            text("see map", &[]),
+        ])),
+        paragraph(json!([
+            { "type": "text", "text": "the trail", "marks": [
+                { "type": "link", "attrs": { "href": "https://example.com/a b" } }
+            ] },
+            text(" and ", &[]),
+            text("ls -la", &["code"]),
+            { "type": "hardBreak" },
+            text("2 * 3 = 6", &["strike"]),
+        ])),
+        { "type": "bulletList", "content": [
+            { "type": "listItem", "content": [
+                paragraph(json!([text("water", &[])])),
+                { "type": "orderedList", "attrs": { "start": 3 }, "content": [
+                    { "type": "listItem", "content": [paragraph(json!([text("cold", &[])]))] },
+                    { "type": "listItem", "content": [paragraph(json!([text("hot", &[])]))] },
+                ] },
+            ] },
+        ] },
+        { "type": "taskList", "content": [
+            { "type": "taskItem", "attrs": { "checked": true }, "content": [paragraph(json!([text("pack", &[])]))] },
+            { "type": "taskItem", "attrs": { "checked": false }, "content": [paragraph(json!([text("rest", &[])]))] },
+        ] },
+        { "type": "blockquote", "content": [
+            paragraph(json!([text("Nature is not a place to visit.", &[])])),
+            paragraph(json!([text("It is home.", &[])])),
+        ] },
+        { "type": "codeBlock", "attrs": { "language": "rust" }, "content": [text("fn main() {}", &[])] },
+        { "type": "horizontalRule" },
+        paragraph(json!([text("# not a heading", &[])])),
+        paragraph(json!([])),
+    ]));
+
+    assert_eq!(
+        to_markdown(&content),
+        "## A good day\n\n\
+         Went **hiking *with*** Sam, see map\n\n\
+         [the trail](https://example.com/a%20b) and `ls -la`\\\n~~2 \\* 3 = 6~~\n\n\
+         - water\n  3. cold\n  4. hot\n\n\
+         - [x] pack\n- [ ] rest\n\n\
+         > Nature is not a place to visit.\n>\n> It is home.\n\n\
+         ```rust\nfn main() {}\n```\n\n\
+         ---\n\n\
+         \\# not a heading"
+    );
+
+    // Entries from before the editor stored JSON
+    assert_eq!(to_markdown("  plain old text \n"), "plain old text");
+}
+
+#[test]
+fn markdown_export_writes_front_matter_and_media() {
+    let dir = std::env::temp_dir().join(format!("echo-daily-export-{}", uuid::Uuid::new_v4()));
+    let vault = Vault::Plain;
+    let stored = attachments::store(&dir.join("data"), &vault, "notes.txt", b"remember the milk")
+        .expect("store attachment");
+
+    let mut first = entry(
+        "e1",
+        "2024-03-05",
+        doc(json!([paragraph(json!([text("Morning", &[])]))])),
+    );
+    first.title = Some("A \"quoted\" title".to_string());
+    first.mood = Some("happy".to_string());
+    first.mood_emoji = Some("😊".to_string());
+    let mut second = entry("e2", "2024-03-05", "Evening".to_string());
+    second.entry_time = Some("21:00".to_string());
+    let data = ExportData {
+        version: "1.2".to_string(),
+        exported_at: 0,
+        entries: vec![first, second],
+        ai_operations: vec![],
+        tags: vec![
+            Tag {
+                id: "t1".to_string(),
+                name: "walks".to_string(),
+                created_at: 0,
+            },
+            Tag {
+                id: "t2".to_string(),
+                name: "Family".to_string(),
+                created_at: 0,
+            },
+        ],
+        entry_tags: vec![
+            EntryTag {
+                entry_id: "e1".to_string(),
+                tag_id: "t1".to_string(),
+                created_at: 0,
+            },
+            EntryTag {
+                entry_id: "e1".to_string(),
+                tag_id: "t2".to_string(),
+                created_at: 0,
+            },
+        ],
+        attachments: vec![
+            Attachment {
+                id: "a1".to_string(),
+                entry_id: "e1".to_string(),
+                sha256: stored.sha256.clone(),
+                file_name: "notes.txt".to_string(),
+                mime_type: stored.mime_type.clone(),
+                size_bytes: 17,
+                width: None,
+                height: None,
+                has_thumbnail: false,
+                created_at: 0,
+            },
+            Attachment {
+                id: "a2".to_string(),
+                entry_id: "e2".to_string(),
+                sha256: "0".repeat(64),
+                file_name: "gone.png".to_string(),
+                mime_type: "image/png".to_string(),
+                size_bytes: 1,
+                width: None,
+                height: None,
+                has_thumbnail: false,
+                created_at: 0,
+            },
+        ],
+        attachment_files: vec![],
+    };
+
+    // A folder export, with media
+    let out = dir.join("markdown");
+    let media = MediaSource {
+        app_data_dir: &dir.join("data"),
+        vault: &vault,
+    };
+    let archive = Archive::create(&out, ArchiveFormat::Directory).expect("create folder");
+    let summary = markdown::export(archive, &data, &[], Some(&media)).expect("export folder");
+    assert_eq!(
+        (summary.entries, summary.media_files, summary.skipped_media),
+        (2, 1, 1)
+    );
+
+    let media_path = format!("media/{}-notes.txt", &stored.sha256[..12]);
+    let first_file = std::fs::read_to_string(out.join("2024/2024-03-05.md")).expect("first file");
+    assert_eq!(
+        first_file,
+        format!(
+            "---\ndate: 2024-03-05\ntitle: \"A \\\"quoted\\\" title\"\nmood: \"happy\"\n\
+             mood_emoji: \"😊\"\ntags: [\"Family\", \"walks\"]\n---\n\nMorning\n\n\
+             ## Attachments\n\n- [notes.txt](../{media_path})\n"
+        )
+    );
+    let second_file =
+        std::fs::read_to_string(out.join("2024/2024-03-05-2.md")).expect("second file");
+    assert_eq!(
+        second_file,
+        "---\ndate: 2024-03-05\ntime: \"21:00\"\n---\n\nEvening\n"
+    );
+    assert_eq!(
+        std::fs::read(out.join(&media_path)).expect("media"),
+        b"remember the milk"
+    );
+
+    // A folder that already has files is refused
+    assert!(Archive::create(&out, ArchiveFormat::Directory).is_err());
+
+    // A zip export, without media
+    let zip_path = dir.join("journal.zip");
+    let archive = Archive::create(&zip_path, ArchiveFormat::Zip).expect("create zip");
+    let mut data = data;
+    data.entries
+        .push(entry("e3", "../../2024", "Outside".to_string()));
+    let summary = markdown::export(archive, &data, &[], None).expect("export zip");
+    assert_eq!((summary.entries, summary.media_files), (2, 0));
+    assert!(!dir.join("journal.zip.partial").exists());
+
+    let mut zip =
+        zip::ZipArchive::new(std::fs::File::open(&zip_path).expect("open zip")).expect("read zip");
+    assert_eq!(zip.len(), 2);
+    let mut zipped = String::new();
+    zip.by_name("2024/2024-03-05-2.md")
+        .expect("zipped entry")
+        .read_to_string(&mut zipped)
+        .expect("read zipped entry");
+    assert_eq!(zipped, second_file);
+
+    // An existing zip is refused, and an unfinished one leaves nothing behind
+    assert!(Archive::create(&zip_path, ArchiveFormat::Zip).is_err());
+    let unfinished = dir.join("unfinished.zip");
+    let mut archive = Archive::create(&unfinished, ArchiveFormat::Zip).expect("create zip");
+    archive.add("a.md", b"a").expect("add");
+    drop(archive);
+    assert!(!unfinished.exists());
+    assert!(!dir.join("unfinished.zip.partial").exists());
+
+    std::fs::remove_dir_all(&dir).ok();
+}
+
+#[test]
+fn html_is_escaped_and_unsafe_links_dropped() {
+    let content = doc(json!([
+        { "type": "heading", "attrs": { "level": 1 }, "content": [text("Fish & <chips>", &[])] },
+        paragraph(json!([
+            text("so ", &[]),
+            text("good", &["bold", "italic"]),
+            { "type": "text", "text": "menu", "marks": [
+                { "type": "link", "attrs": { "href": "https://example.com/?a=1&b=\"2\"" } }
+            ] },
+            { "type": "text", "text": "trap", "marks": [
+                { "type": "link", "attrs": { "href": "javascript:alert(1)" } }
+            ] },
+        ])),
+        { "type": "taskList", "content": [
+            { "type": "taskItem", "attrs": { "checked": true }, "content": [paragraph(json!([text("eat", &[])]))] },
+        ] },
+    ]));
+
+    assert_eq!(
+        to_html(&content),
+        "<h2>Fish &amp; &lt;chips&gt;</h2>\n\
+         <p>so <strong><em>good</em></strong>\
+         <a href=\"https://example.com/?a=1&amp;b=&quot;2&quot;\" rel=\"noopener\">menu</a>trap</p>\n\
+         <ul class=\"tasks\">\n<li><input type=\"checkbox\" checked disabled> <p>eat</p>\n</li>\n</ul>\n"
+    );
+    assert_eq!(to_html("one\n\n<two>"), "<p>one</p>\n<p>&lt;two&gt;</p>\n");
+}
+
+#[test]
+fn html_site_has_index_pages_audio_and_search() {
+    let dir = std::env::temp_dir().join(format!("echo-daily-site-{}", uuid::Uuid::new_v4()));
+    let data_dir = dir.join("data");
+    let vault = Vault::Plain;
+
+    let mut older = entry(
+        "e1",
+        "2023-12-31",
+        doc(json!([paragraph(json!([text(
+            "Fireworks over the river",
+            &[]
+        )]))])),
+    );
+    older.mood_emoji = Some("😄".to_string());
+    let mut newer = entry("e2", "2024-01-02", "Back to <work>".to_string());
+    newer.title = Some("Monday again".to_string());
+    let data = ExportData {
+        version: "1.2".to_string(),
+        exported_at: 0,
+        // Too short to group by month; left out rather than breaking the site
+        entries: vec![older, newer, entry("e3", "2024", "No day".to_string())],
+        ai_operations: vec![],
+        tags: vec![],
+        entry_tags: vec![],
+        attachments: vec![],
+        attachment_files: vec![],
+    };
+
+    let relpath = tts::storage::audio_relpath("e1", "clip1", "mp3");
+    tts::storage::write_audio(&data_dir, &relpath, b"ID3 not really").expect("write audio");
+    let clip = AudioRecord {
+        id: "clip1".to_string(),
+        entry_id: "e1".to_string(),
+        text: "Fireworks over the river".to_string(),
+        audio_relpath: relpath,
+        format: "mp3".to_string(),
+        provider: "murf".to_string(),
+        model: "falcon".to_string(),
+        voice: None,
+        speed: None,
+        duration_ms: None,
+        size_bytes: 14,
+        created_at: 0,
+        cache_key: None,
+    };
+
+    let out = dir.join("site");
+    let media = MediaSource {
+        app_data_dir: &data_dir,
+        vault: &vault,
+    };
+    let archive = Archive::create(&out, ArchiveFormat::Directory).expect("create site folder");
+    let summary = html::export(archive, &data, &[clip], &media).expect("export site");
+    assert_eq!((summary.entries, summary.media_files), (2, 1));
+
+    // Newest year and month first, with mood and preview
+    let index = std::fs::read_to_string(out.join("index.html")).expect("index");
+    let (year_2024, year_2023) = (index.find(">2024<").unwrap(), index.find(">2023<").unwrap());
+    assert!(year_2024 < year_2023);
+    assert!(index.contains("<h3 class=\"month\">December</h3>"));
+    assert!(index.contains("<a href=\"entries/2023-12-31.html\">Sunday, December 31, 2023</a>"));
+    assert!(index.contains("<span class=\"mood\">😄</span>"));
+    assert!(index.contains("Back to &lt;work&gt;"));
+
+    let page = std::fs::read_to_string(out.join("entries/2023-12-31.html")).expect("entry page");
+    assert!(page.contains("<p>Fireworks over the river</p>"));
+    assert!(page.contains("<audio controls preload=\"none\" src=\"../audio/clip1.mp3\"></audio>"));
+    assert!(page.contains("<a href=\"2024-01-02.html\">2024-01-02 →</a>"));
+    assert_eq!(
+        std::fs::read(out.join("audio/clip1.mp3")).expect("audio"),
+        b"ID3 not really"
+    );
+
+    let search = std::fs::read_to_string(out.join("search-index.js")).expect("search index");
+    let json = search
+        .strip_prefix("window.SEARCH_INDEX = ")
+        .and_then(|s| s.strip_suffix(";\n"))
+        .expect("search index script");
+    let index: serde_json::Value = serde_json::from_str(json).expect("search index json");
+    assert_eq!(index[1]["title"], "Monday again");
+    assert_eq!(index[1]["url"], "entries/2024-01-02.html");
+    assert_eq!(index[1]["text"], "Back to <work>");
+    assert!(!search.contains("</"));
+    assert!(out.join("search.js").exists() && out.join("style.css").exists());
+
+    std::fs::remove_dir_all(&dir).ok();
+}
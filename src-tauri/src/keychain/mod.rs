@@ -5,6 +5,8 @@ const SERVICE_NAME: &str = "echo-daily";
 const API_KEY_ENTRY: &str = "ai-api-key";
 const TTS_API_KEY_ENTRY: &str = "tts-api-key";
 const MURF_API_KEY_ENTRY: &str = "murf-api-key";
+const LOCK_HASH_ENTRY: &str = "app-lock-hash";
+const WRAPPED_KEY_ENTRY: &str = "journal-wrapped-key";
 
 /// Get the AI API key from secure storage
 pub fn get_api_key() -> Result<Option<String>, AppError> {
@@ -75,3 +77,61 @@ pub fn set_murf_api_key(api_key: &str) -> Result<(), AppError> {
     entry.set_password(api_key)?;
     Ok(())
 }
+
+/// ===== App Lock Passphrase Hash =====
+/// Get the app lock passphrase hash from secure storage
+pub fn get_lock_hash() -> Result<Option<String>, AppError> {
+    let entry = Entry::new(SERVICE_NAME, LOCK_HASH_ENTRY)?;
+    let password = entry.get_password();
+
+    match password {
+        Ok(hash) if !hash.is_empty() => Ok(Some(hash)),
+        Ok(_) => Ok(None),
+        Err(keyring::Error::NoEntry) => Ok(None),
+        Err(e) => Err(AppError::from(e)),
+    }
+}
+
+/// Store the app lock passphrase hash in secure storage
+pub fn set_lock_hash(hash: &str) -> Result<(), AppError> {
+    let entry = Entry::new(SERVICE_NAME, LOCK_HASH_ENTRY)?;
+    entry.set_password(hash)?;
+    Ok(())
+}
+
+/// Delete the app lock passphrase hash, turning the lock off
+pub fn delete_lock_hash() -> Result<(), AppError> {
+    let entry = Entry::new(SERVICE_NAME, LOCK_HASH_ENTRY)?;
+    // Try to delete, ignore if it doesn't exist
+    let _ = entry.delete_password();
+    Ok(())
+}
+
+/// ===== Journal Wrapped Key =====
+/// Get the journal data key, wrapped with the passphrase, from secure storage
+pub fn get_wrapped_key() -> Result<Option<String>, AppError> {
+    let entry = Entry::new(SERVICE_NAME, WRAPPED_KEY_ENTRY)?;
+    let password = entry.get_password();
+
+    match password {
+        Ok(key) if !key.is_empty() => Ok(Some(key)),
+        Ok(_) => Ok(None),
+        Err(keyring::Error::NoEntry) => Ok(None),
+        Err(e) => Err(AppError::from(e)),
+    }
+}
+
+/// Store the wrapped journal data key in secure storage
+pub fn set_wrapped_key(key: &str) -> Result<(), AppError> {
+    let entry = Entry::new(SERVICE_NAME, WRAPPED_KEY_ENTRY)?;
+    entry.set_password(key)?;
+    Ok(())
+}
+
+/// Delete the wrapped journal data key from secure storage
+pub fn delete_wrapped_key() -> Result<(), AppError> {
+    let entry = Entry::new(SERVICE_NAME, WRAPPED_KEY_ENTRY)?;
+    // Try to delete, ignore if it doesn't exist
+    let _ = entry.delete_password();
+    Ok(())
+}
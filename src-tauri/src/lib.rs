@@ -1,13 +1,29 @@
 mod ai;
+mod app_lock;
+mod attachments;
+mod content;
+mod crypto;
 mod db;
 mod error;
+mod export;
 mod keychain;
 mod models;
+mod prompts;
+mod search;
 mod tts;
 
+use app_lock::AppLock;
 use base64::prelude::*;
+use crypto::Vault;
 use error::AppError;
-use models::{AIOperation, DiaryEntry, ExportData, ImportOptions, WritingStats};
+use models::{
+    AIOperation, ArchiveFormat, Attachment, AttachmentContent, AttachmentFile, AudioPlayback,
+    AudioRecord, BackupInfo, BackupSettings, CalendarDay, DaySettings, DiaryEntry,
+    EncryptionStatus, EntryRevision, EntryRevisionSummary, EntrySuggestion, ExportData,
+    ExportSummary, ImportOptions, LockSettings, LockStatus, OnThisDayEntry, PromptSettings,
+    SchemaInfo, SearchResults, StartupError, TTSCacheInfo, Tag, TagUsage, Template,
+    TemplateSettings, TrashSettings, WritingAnalytics, WritingPrompt, WritingStats,
+};
 use sqlx::SqlitePool;
 use tauri::Manager;
 
@@ -32,65 +48,1004 @@ async fn init_db() -> Result<(), AppError> {
     Ok(())
 }
 
-fn validate_entry_date(value: &str) -> Result<(), AppError> {
+/// Outcome of opening the database in `setup`
+struct StartupStatus(Option<StartupError>);
+
+/// Why the database failed to open at startup, if it did
+#[tauri::command]
+async fn get_startup_error(
+    status: tauri::State<'_, StartupStatus>,
+) -> Result<Option<StartupError>, AppError> {
+    Ok(status.0.clone())
+}
+
+#[tauri::command]
+async fn get_schema_version(pool: tauri::State<'_, SqlitePool>) -> Result<SchemaInfo, AppError> {
+    db::migrations::schema_info(&pool).await
+}
+
+fn parse_entry_date(value: &str) -> Result<chrono::NaiveDate, AppError> {
     chrono::NaiveDate::parse_from_str(value, "%Y-%m-%d")
+        .map_err(|_| AppError::InvalidEntryDate(value.to_string()))
+}
+
+fn validate_entry_date(value: &str) -> Result<(), AppError> {
+    parse_entry_date(value).map(|_| ())
+}
+
+/// Entry times are wall-clock "HH:MM"
+fn validate_entry_time(value: &str) -> Result<(), AppError> {
+    if value.len() != 5 {
+        return Err(AppError::InvalidEntryTime(value.to_string()));
+    }
+    chrono::NaiveTime::parse_from_str(value, "%H:%M")
+        .map(|_| ())
+        .map_err(|_| AppError::InvalidEntryTime(value.to_string()))
+}
+
+fn validate_month(value: &str) -> Result<(), AppError> {
+    if value.len() != 7 {
+        return Err(AppError::InvalidEntryDate(value.to_string()));
+    }
+    let candidate = format!("{value}-01");
+    chrono::NaiveDate::parse_from_str(&candidate, "%Y-%m-%d")
         .map(|_| ())
         .map_err(|_| AppError::InvalidEntryDate(value.to_string()))
 }
 
-fn validate_month(value: &str) -> Result<(), AppError> {
-    if value.len() != 7 {
-        return Err(AppError::InvalidEntryDate(value.to_string()));
-    }
-    let candidate = format!("{value}-01");
-    chrono::NaiveDate::parse_from_str(&candidate, "%Y-%m-%d")
-        .map(|_| ())
-        .map_err(|_| AppError::InvalidEntryDate(value.to_string()))
+/// Audio smaller than this is also returned inline as base64
+const INLINE_AUDIO_MAX_BYTES: usize = 500_000;
+
+/// Base64 of a saved clip when it should be inlined for playback.
+/// Encrypted clips are always inlined since the player can't read them from disk.
+fn inline_audio(
+    vault: &Vault,
+    path: &std::path::Path,
+    size_bytes: i64,
+) -> Result<Option<String>, AppError> {
+    if matches!(vault, Vault::Plain) && size_bytes as usize >= INLINE_AUDIO_MAX_BYTES {
+        return Ok(None);
+    }
+    let bytes = vault.open_bytes(std::fs::read(path)?)?;
+    Ok(Some(BASE64_STANDARD.encode(bytes)))
+}
+
+/// Remove files owned by permanently deleted entries
+async fn remove_purged_entry_files(
+    app: &tauri::AppHandle,
+    pool: &SqlitePool,
+    entry_ids: &[String],
+) {
+    let Ok(app_data_dir) = db::app_data_dir(app) else {
+        return;
+    };
+    for entry_id in entry_ids {
+        if let Err(e) = tts::storage::remove_entry_audio(&app_data_dir, entry_id) {
+            println!("Trash: Failed to remove audio for {}: {}", entry_id, e);
+        }
+    }
+    if !entry_ids.is_empty() {
+        if let Err(e) = collect_attachment_files(&app_data_dir, pool).await {
+            println!("Trash: Failed to remove attachment files: {}", e);
+        }
+    }
+}
+
+/// Remove attachment files no attachment refers to any more
+async fn collect_attachment_files(
+    app_data_dir: &std::path::Path,
+    pool: &SqlitePool,
+) -> Result<usize, AppError> {
+    let _store = attachments::STORE_LOCK.lock().await;
+    let referenced = db::queries::referenced_attachment_hashes(pool).await?;
+    attachments::collect_garbage(app_data_dir, &referenced)
+}
+
+/// Save an entry's content. Without `entry_id` the first entry of the day is
+/// written, and created if the day has none.
+#[tauri::command]
+async fn upsert_entry(
+    entry_date: String,
+    content_json: String,
+    entry_id: Option<String>,
+    pool: tauri::State<'_, SqlitePool>,
+    lock: tauri::State<'_, AppLock>,
+) -> Result<DiaryEntry, AppError> {
+    lock.check()?;
+    validate_entry_date(&entry_date)?;
+    let entry = match entry_id {
+        Some(entry_id) => {
+            db::queries::save_entry(&pool, &entry_id, &entry_date, &content_json).await?
+        }
+        None => db::queries::upsert_entry(&pool, &entry_date, &content_json).await?,
+    };
+    Ok(entry)
+}
+
+/// Get the first entry of a day. Kept for callers from before days held
+/// several entries; prefer [`get_day_entries`].
+#[tauri::command]
+async fn get_entry(
+    entry_date: String,
+    pool: tauri::State<'_, SqlitePool>,
+    lock: tauri::State<'_, AppLock>,
+) -> Result<Option<DiaryEntry>, AppError> {
+    lock.check()?;
+    validate_entry_date(&entry_date)?;
+    db::queries::get_first_entry(&pool, &entry_date).await
+}
+
+/// Get every entry of a day, in day order
+#[tauri::command]
+async fn get_day_entries(
+    entry_date: String,
+    pool: tauri::State<'_, SqlitePool>,
+    lock: tauri::State<'_, AppLock>,
+) -> Result<Vec<DiaryEntry>, AppError> {
+    lock.check()?;
+    validate_entry_date(&entry_date)?;
+    let entries = db::queries::get_day_entries(&pool, &entry_date).await?;
+    Ok(entries)
+}
+
+/// Add another entry to a day
+#[tauri::command]
+async fn create_entry(
+    entry_date: String,
+    title: Option<String>,
+    entry_time: Option<String>,
+    content_json: Option<String>,
+    pool: tauri::State<'_, SqlitePool>,
+    lock: tauri::State<'_, AppLock>,
+) -> Result<DiaryEntry, AppError> {
+    lock.check()?;
+    validate_entry_date(&entry_date)?;
+    if let Some(entry_time) = &entry_time {
+        validate_entry_time(entry_time)?;
+    }
+    let content_json = content_json.unwrap_or_else(|| serde_json::json!({}).to_string());
+    db::queries::create_entry(
+        &pool,
+        &entry_date,
+        title.as_deref(),
+        entry_time.as_deref(),
+        &content_json,
+    )
+    .await
+}
+
+/// Change the title, summary and time of an entry
+#[tauri::command]
+async fn update_entry_details(
+    entry_id: String,
+    title: Option<String>,
+    summary: Option<String>,
+    entry_time: Option<String>,
+    pool: tauri::State<'_, SqlitePool>,
+    lock: tauri::State<'_, AppLock>,
+) -> Result<DiaryEntry, AppError> {
+    lock.check()?;
+    if let Some(entry_time) = &entry_time {
+        validate_entry_time(entry_time)?;
+    }
+    let details = db::queries::EntryDetails {
+        title: title.as_deref(),
+        summary: summary.as_deref(),
+        entry_time: entry_time.as_deref(),
+    };
+    db::queries::update_entry_details(&pool, &entry_id, details).await
+}
+
+#[tauri::command]
+async fn list_entries(
+    month: String,
+    pool: tauri::State<'_, SqlitePool>,
+    lock: tauri::State<'_, AppLock>,
+) -> Result<Vec<DiaryEntry>, AppError> {
+    lock.check()?;
+    validate_month(&month)?;
+    let entries = db::queries::list_entries(&pool, &month).await?;
+    Ok(entries)
+}
+
+/// Longest range `get_calendar_days` answers in one call (ten years and change)
+const MAX_CALENDAR_DAYS: i64 = 3_700;
+
+/// Per-day totals (entries, words, mood, audio, AI use) for every day from
+/// `start_date` to `end_date` inclusive, without entry content
+#[tauri::command]
+async fn get_calendar_days(
+    start_date: String,
+    end_date: String,
+    pool: tauri::State<'_, SqlitePool>,
+    lock: tauri::State<'_, AppLock>,
+) -> Result<Vec<CalendarDay>, AppError> {
+    lock.check()?;
+    let (start, end) = (parse_entry_date(&start_date)?, parse_entry_date(&end_date)?);
+    let span = (end - start).num_days();
+    if !(0..MAX_CALENDAR_DAYS).contains(&span) {
+        return Err(AppError::InvalidEntryDate(format!(
+            "{start_date} to {end_date} is not a range of at most {MAX_CALENDAR_DAYS} days"
+        )));
+    }
+    db::queries::get_calendar_days(&pool, start, end).await
+}
+
+/// Entries from the same day in earlier years and, with `include_lookbacks`,
+/// from a month and a week before `date`
+#[tauri::command]
+async fn get_on_this_day(
+    date: String,
+    include_lookbacks: Option<bool>,
+    pool: tauri::State<'_, SqlitePool>,
+    lock: tauri::State<'_, AppLock>,
+) -> Result<Vec<OnThisDayEntry>, AppError> {
+    lock.check()?;
+    db::queries::get_on_this_day(
+        &pool,
+        parse_entry_date(&date)?,
+        include_lookbacks.unwrap_or(true),
+    )
+    .await
+}
+
+#[tauri::command]
+async fn delete_entry(
+    entry_id: String,
+    pool: tauri::State<'_, SqlitePool>,
+    lock: tauri::State<'_, AppLock>,
+) -> Result<bool, AppError> {
+    lock.check()?;
+    // Moves the entry to the trash; AI operations are removed when it is purged
+    let deleted = db::queries::delete_entry(&pool, &entry_id).await?;
+    Ok(deleted)
+}
+
+// ===== Trash =====
+
+/// List entries in the trash, purging expired ones first
+#[tauri::command]
+async fn list_trash(
+    app: tauri::AppHandle,
+    pool: tauri::State<'_, SqlitePool>,
+    lock: tauri::State<'_, AppLock>,
+) -> Result<Vec<DiaryEntry>, AppError> {
+    lock.check()?;
+    let purged = db::queries::purge_expired_trash(&pool).await?;
+    remove_purged_entry_files(&app, &pool, &purged).await;
+    let entries = db::queries::list_trash(&pool).await?;
+    Ok(entries)
+}
+
+/// Restore a trashed entry
+#[tauri::command]
+async fn restore_entry(
+    entry_id: String,
+    pool: tauri::State<'_, SqlitePool>,
+    lock: tauri::State<'_, AppLock>,
+) -> Result<DiaryEntry, AppError> {
+    lock.check()?;
+    db::queries::restore_entry(&pool, &entry_id)
+        .await?
+        .ok_or(AppError::EntryNotFound(format!(
+            "No trashed entry {}",
+            entry_id
+        )))
+}
+
+/// Permanently delete everything in the trash
+#[tauri::command]
+async fn empty_trash(
+    app: tauri::AppHandle,
+    pool: tauri::State<'_, SqlitePool>,
+    lock: tauri::State<'_, AppLock>,
+) -> Result<u64, AppError> {
+    lock.check()?;
+    let purged = db::queries::empty_trash(&pool).await?;
+    remove_purged_entry_files(&app, &pool, &purged).await;
+    Ok(purged.len() as u64)
+}
+
+/// Get trash settings
+#[tauri::command]
+async fn get_trash_settings(pool: tauri::State<'_, SqlitePool>) -> Result<TrashSettings, AppError> {
+    db::queries::get_trash_settings(&pool).await
+}
+
+/// Save trash settings
+#[tauri::command]
+async fn save_trash_settings(
+    settings: TrashSettings,
+    pool: tauri::State<'_, SqlitePool>,
+    lock: tauri::State<'_, AppLock>,
+) -> Result<(), AppError> {
+    lock.check()?;
+    db::queries::save_trash_settings(&pool, &settings).await
+}
+
+// ===== Day Boundary =====
+
+/// Get when a diary day starts
+#[tauri::command]
+async fn get_day_settings(pool: tauri::State<'_, SqlitePool>) -> Result<DaySettings, AppError> {
+    db::queries::get_day_settings(&pool).await
+}
+
+/// Save when a diary day starts
+#[tauri::command]
+async fn save_day_settings(
+    settings: DaySettings,
+    pool: tauri::State<'_, SqlitePool>,
+    lock: tauri::State<'_, AppLock>,
+) -> Result<(), AppError> {
+    lock.check()?;
+    db::queries::save_day_settings(&pool, &settings).await
+}
+
+/// Today's diary date (YYYY-MM-DD) in local time, honouring the day start hour
+#[tauri::command]
+async fn get_journal_today(pool: tauri::State<'_, SqlitePool>) -> Result<String, AppError> {
+    let today = db::queries::journal_today(&pool).await?;
+    Ok(today.format("%Y-%m-%d").to_string())
+}
+
+// ===== Backups =====
+
+/// Take a snapshot now and again whenever the newest one is a day old,
+/// rotating old snapshots after each one
+async fn run_backup_schedule(app: tauri::AppHandle, pool: SqlitePool) {
+    let mut startup = true;
+    loop {
+        if let Err(e) = take_scheduled_backup(&app, &pool, startup).await {
+            println!("Backup: Scheduled snapshot failed: {}", e);
+        }
+        startup = false;
+        tokio::time::sleep(std::time::Duration::from_secs(60 * 60)).await;
+    }
+}
+
+async fn take_scheduled_backup(
+    app: &tauri::AppHandle,
+    pool: &SqlitePool,
+    force: bool,
+) -> Result<(), AppError> {
+    let dir = db::backup::backups_dir(&db::app_data_dir(app)?);
+    if !force && !db::backup::snapshot_due(&dir, chrono::Utc::now().timestamp_millis())? {
+        return Ok(());
+    }
+
+    let backup = db::backup::create_backup(pool, &dir).await?;
+    println!("Backup: Created {}", backup.file_name);
+
+    let settings = db::backup::get_backup_settings(pool).await?;
+    for file_name in db::backup::prune_backups(&dir, &settings)? {
+        println!("Backup: Rotated out {}", file_name);
+    }
+
+    Ok(())
+}
+
+/// List database backups, newest first
+#[tauri::command]
+async fn list_backups<R: tauri::Runtime>(
+    app: tauri::AppHandle<R>,
+    lock: tauri::State<'_, AppLock>,
+) -> Result<Vec<BackupInfo>, AppError> {
+    lock.check()?;
+    db::backup::list_backups(&db::backup::backups_dir(&db::app_data_dir(&app)?))
+}
+
+/// Take a backup now, outside the daily schedule
+#[tauri::command]
+async fn create_backup<R: tauri::Runtime>(
+    app: tauri::AppHandle<R>,
+    pool: tauri::State<'_, SqlitePool>,
+    lock: tauri::State<'_, AppLock>,
+) -> Result<BackupInfo, AppError> {
+    lock.check()?;
+    let dir = db::backup::backups_dir(&db::app_data_dir(&app)?);
+    let backup = db::backup::create_backup(&pool, &dir).await?;
+    db::backup::prune_backups(&dir, &db::backup::get_backup_settings(&pool).await?)?;
+    Ok(backup)
+}
+
+/// Restore a backup. The current database is set aside in the backups
+/// directory and the app restarts to reopen the restored one.
+#[tauri::command]
+async fn restore_backup(
+    file_name: String,
+    app: tauri::AppHandle,
+    pool: tauri::State<'_, SqlitePool>,
+    lock: tauri::State<'_, AppLock>,
+) -> Result<(), AppError> {
+    lock.check()?;
+    let dir = db::backup::backups_dir(&db::app_data_dir(&app)?);
+    db::backup::stage_restore(&dir, &file_name)?;
+    println!("Backup: Restoring {} after restart", file_name);
+
+    pool.close().await;
+    app.restart();
+}
+
+/// Get backup retention settings
+#[tauri::command]
+async fn get_backup_settings(
+    pool: tauri::State<'_, SqlitePool>,
+) -> Result<BackupSettings, AppError> {
+    db::backup::get_backup_settings(&pool).await
+}
+
+/// Save backup retention settings
+#[tauri::command]
+async fn save_backup_settings(
+    settings: BackupSettings,
+    pool: tauri::State<'_, SqlitePool>,
+    lock: tauri::State<'_, AppLock>,
+) -> Result<(), AppError> {
+    lock.check()?;
+    db::backup::save_backup_settings(&pool, &settings).await
+}
+
+// ===== Encryption =====
+
+/// Get whether encryption is on and the journal is unlocked
+#[tauri::command]
+async fn get_encryption_status(
+    pool: tauri::State<'_, SqlitePool>,
+) -> Result<EncryptionStatus, AppError> {
+    let enabled = crypto::is_enabled(&pool).await?;
+    Ok(EncryptionStatus {
+        enabled,
+        unlocked: enabled && !Vault::of(&pool).is_locked(),
+    })
+}
+
+/// Seal files and backups left over from turning encryption on. Failures are
+/// only logged: the database is already encrypted and the next unlock retries.
+async fn finish_enabling_encryption<R: tauri::Runtime>(
+    app: &tauri::AppHandle<R>,
+    pool: &SqlitePool,
+) {
+    let result = async {
+        let app_data_dir = db::app_data_dir(app)?;
+        let dirs = [
+            app_data_dir.join(tts::storage::AUDIO_DIR),
+            app_data_dir.join(attachments::ATTACHMENTS_DIR),
+        ];
+        let discard_dirs = [app_data_dir.join(tts::cache::CACHE_DIR)];
+        let backups_dir = db::backup::backups_dir(&app_data_dir);
+        crypto::seal_pending_files(pool, &app_data_dir, &dirs, &discard_dirs, &backups_dir).await
+    }
+    .await;
+    if let Err(e) = result {
+        println!(
+            "Encryption: Failed to encrypt files, retrying on unlock: {}",
+            e
+        );
+    }
+}
+
+/// Turn on encryption, encrypting existing entries, audio and attachment files
+#[tauri::command]
+async fn enable_encryption<R: tauri::Runtime>(
+    passphrase: String,
+    app: tauri::AppHandle<R>,
+    pool: tauri::State<'_, SqlitePool>,
+    lock: tauri::State<'_, AppLock>,
+) -> Result<(), AppError> {
+    lock.check()?;
+    crypto::enable(&pool, &passphrase).await?;
+    finish_enabling_encryption(&app, &pool).await;
+    Ok(())
+}
+
+/// Unlock the journal with its passphrase
+#[tauri::command]
+async fn unlock_journal<R: tauri::Runtime>(
+    passphrase: String,
+    app: tauri::AppHandle<R>,
+    pool: tauri::State<'_, SqlitePool>,
+    lock: tauri::State<'_, AppLock>,
+) -> Result<(), AppError> {
+    lock.check()?;
+    crypto::unlock(&pool, &passphrase).await?;
+    finish_enabling_encryption(&app, &pool).await;
+    Ok(())
+}
+
+/// Lock the journal
+#[tauri::command]
+async fn lock_journal(
+    pool: tauri::State<'_, SqlitePool>,
+    lock: tauri::State<'_, AppLock>,
+) -> Result<(), AppError> {
+    lock.check()?;
+    crypto::lock(&pool).await
+}
+
+/// Change the encryption passphrase
+#[tauri::command]
+async fn change_passphrase(
+    current: String,
+    new: String,
+    pool: tauri::State<'_, SqlitePool>,
+    lock: tauri::State<'_, AppLock>,
+) -> Result<(), AppError> {
+    lock.check()?;
+    crypto::change_passphrase(&pool, &current, &new).await
+}
+
+// ===== App Lock =====
+
+/// Lock the app once it has been idle for the configured timeout
+async fn run_idle_lock(app: tauri::AppHandle) {
+    loop {
+        tokio::time::sleep(app_lock::IDLE_CHECK_INTERVAL).await;
+        if app
+            .state::<AppLock>()
+            .lock_if_idle(std::time::Instant::now())
+        {
+            println!("App lock: Locked after inactivity");
+        }
+    }
+}
+
+/// Get whether the app lock is on and engaged
+#[tauri::command]
+async fn get_lock_status(lock: tauri::State<'_, AppLock>) -> Result<LockStatus, AppError> {
+    lock.lock_if_idle(std::time::Instant::now());
+    Ok(lock.status())
+}
+
+/// Report user activity so the idle timeout starts over
+#[tauri::command]
+async fn record_activity(lock: tauri::State<'_, AppLock>) -> Result<(), AppError> {
+    lock.check()
+}
+
+/// Unlock the app with its passphrase
+#[tauri::command]
+async fn unlock_app(passphrase: String, lock: tauri::State<'_, AppLock>) -> Result<(), AppError> {
+    app_lock::unlock(&lock, &passphrase)
+}
+
+/// Lock the app now
+#[tauri::command]
+async fn lock_app(lock: tauri::State<'_, AppLock>) -> Result<(), AppError> {
+    lock.lock();
+    Ok(())
+}
+
+/// Turn the app lock on, or change its passphrase (requires the current one)
+#[tauri::command]
+async fn set_lock_passphrase(
+    current: Option<String>,
+    passphrase: String,
+    lock: tauri::State<'_, AppLock>,
+) -> Result<(), AppError> {
+    lock.check()?;
+    app_lock::set_passphrase(&lock, current.as_deref(), &passphrase)
+}
+
+/// Turn the app lock off
+#[tauri::command]
+async fn disable_app_lock(
+    passphrase: String,
+    lock: tauri::State<'_, AppLock>,
+) -> Result<(), AppError> {
+    lock.check()?;
+    app_lock::disable(&lock, &passphrase)
+}
+
+/// Get app lock settings
+#[tauri::command]
+async fn get_lock_settings(pool: tauri::State<'_, SqlitePool>) -> Result<LockSettings, AppError> {
+    app_lock::get_lock_settings(&pool).await
+}
+
+/// Save app lock settings
+#[tauri::command]
+async fn save_lock_settings(
+    settings: LockSettings,
+    pool: tauri::State<'_, SqlitePool>,
+    lock: tauri::State<'_, AppLock>,
+) -> Result<(), AppError> {
+    lock.check()?;
+    app_lock::save_lock_settings(&pool, &lock, &settings).await
+}
+
+// ===== Tags =====
+
+/// Look up an entry by id, or the first entry of a date when no id is given
+async fn find_entry(
+    pool: &SqlitePool,
+    entry_date: &str,
+    entry_id: Option<&str>,
+) -> Result<Option<DiaryEntry>, AppError> {
+    validate_entry_date(entry_date)?;
+    match entry_id {
+        Some(entry_id) => db::queries::get_entry(pool, entry_id).await,
+        None => db::queries::get_first_entry(pool, entry_date).await,
+    }
+}
+
+/// Like [`find_entry`], failing if nothing has been saved yet
+async fn require_entry(
+    pool: &SqlitePool,
+    entry_date: &str,
+    entry_id: Option<&str>,
+) -> Result<DiaryEntry, AppError> {
+    find_entry(pool, entry_date, entry_id)
+        .await?
+        .ok_or(AppError::EntryNotFound(format!(
+            "Entry for {} does not exist. Please write and save some content first.",
+            entry_date
+        )))
+}
+
+/// List all tags with usage counts
+#[tauri::command]
+async fn list_tags(
+    pool: tauri::State<'_, SqlitePool>,
+    lock: tauri::State<'_, AppLock>,
+) -> Result<Vec<TagUsage>, AppError> {
+    lock.check()?;
+    db::queries::list_tags(&pool).await
+}
+
+/// List tags attached to an entry (the first of the date unless `entry_id` is given)
+#[tauri::command]
+async fn list_entry_tags(
+    entry_date: String,
+    entry_id: Option<String>,
+    pool: tauri::State<'_, SqlitePool>,
+    lock: tauri::State<'_, AppLock>,
+) -> Result<Vec<Tag>, AppError> {
+    lock.check()?;
+    match find_entry(&pool, &entry_date, entry_id.as_deref()).await? {
+        Some(entry) => db::queries::list_entry_tags(&pool, &entry.id).await,
+        None => Ok(vec![]),
+    }
+}
+
+/// Add a tag (created if needed) to an entry
+#[tauri::command]
+async fn add_entry_tag(
+    entry_date: String,
+    name: String,
+    entry_id: Option<String>,
+    pool: tauri::State<'_, SqlitePool>,
+    lock: tauri::State<'_, AppLock>,
+) -> Result<Tag, AppError> {
+    lock.check()?;
+    let entry = require_entry(&pool, &entry_date, entry_id.as_deref()).await?;
+    db::queries::add_entry_tag(&pool, &entry.id, &name).await
+}
+
+/// Remove a tag from an entry
+#[tauri::command]
+async fn remove_entry_tag(
+    entry_date: String,
+    tag_id: String,
+    entry_id: Option<String>,
+    pool: tauri::State<'_, SqlitePool>,
+    lock: tauri::State<'_, AppLock>,
+) -> Result<bool, AppError> {
+    lock.check()?;
+    let entry = require_entry(&pool, &entry_date, entry_id.as_deref()).await?;
+    db::queries::remove_entry_tag(&pool, &entry.id, &tag_id).await
+}
+
+/// Rename a tag
+#[tauri::command]
+async fn rename_tag(
+    tag_id: String,
+    name: String,
+    pool: tauri::State<'_, SqlitePool>,
+    lock: tauri::State<'_, AppLock>,
+) -> Result<Tag, AppError> {
+    lock.check()?;
+    db::queries::rename_tag(&pool, &tag_id, &name).await
+}
+
+/// Merge several tags into one
+#[tauri::command]
+async fn merge_tags(
+    source_tag_ids: Vec<String>,
+    target_tag_id: String,
+    pool: tauri::State<'_, SqlitePool>,
+    lock: tauri::State<'_, AppLock>,
+) -> Result<Tag, AppError> {
+    lock.check()?;
+    db::queries::merge_tags(&pool, &source_tag_ids, &target_tag_id).await
+}
+
+/// Delete a tag from every entry
+#[tauri::command]
+async fn delete_tag(
+    tag_id: String,
+    pool: tauri::State<'_, SqlitePool>,
+    lock: tauri::State<'_, AppLock>,
+) -> Result<bool, AppError> {
+    lock.check()?;
+    db::queries::delete_tag(&pool, &tag_id).await
+}
+
+/// List entries carrying a tag
+#[tauri::command]
+async fn list_entries_by_tag(
+    tag_id: String,
+    pool: tauri::State<'_, SqlitePool>,
+    lock: tauri::State<'_, AppLock>,
+) -> Result<Vec<DiaryEntry>, AppError> {
+    lock.check()?;
+    db::queries::list_entries_by_tag(&pool, &tag_id).await
+}
+
+// ===== Templates =====
+
+/// List all templates
+#[tauri::command]
+async fn list_templates(
+    pool: tauri::State<'_, SqlitePool>,
+    lock: tauri::State<'_, AppLock>,
+) -> Result<Vec<Template>, AppError> {
+    lock.check()?;
+    db::queries::list_templates(&pool).await
+}
+
+/// Create a template from a ProseMirror document
+#[tauri::command]
+async fn create_template(
+    name: String,
+    content_json: String,
+    pool: tauri::State<'_, SqlitePool>,
+    lock: tauri::State<'_, AppLock>,
+) -> Result<Template, AppError> {
+    lock.check()?;
+    db::queries::create_template(&pool, &name, &content_json).await
+}
+
+/// Rename a template and replace its content
+#[tauri::command]
+async fn update_template(
+    template_id: String,
+    name: String,
+    content_json: String,
+    pool: tauri::State<'_, SqlitePool>,
+    lock: tauri::State<'_, AppLock>,
+) -> Result<Template, AppError> {
+    lock.check()?;
+    db::queries::update_template(&pool, &template_id, &name, &content_json).await
+}
+
+/// Delete a template
+#[tauri::command]
+async fn delete_template(
+    template_id: String,
+    pool: tauri::State<'_, SqlitePool>,
+    lock: tauri::State<'_, AppLock>,
+) -> Result<bool, AppError> {
+    lock.check()?;
+    db::queries::delete_template(&pool, &template_id).await
+}
+
+/// Get which template new days start from
+#[tauri::command]
+async fn get_template_settings(
+    pool: tauri::State<'_, SqlitePool>,
+) -> Result<TemplateSettings, AppError> {
+    db::queries::get_template_settings(&pool).await
+}
+
+/// Save which template new days start from
+#[tauri::command]
+async fn save_template_settings(
+    settings: TemplateSettings,
+    pool: tauri::State<'_, SqlitePool>,
+    lock: tauri::State<'_, AppLock>,
+) -> Result<(), AppError> {
+    lock.check()?;
+    db::queries::save_template_settings(&pool, &settings).await
+}
+
+/// Start a day from a template (the default one if `template_id` is None),
+/// with its placeholders filled in
+#[tauri::command]
+async fn create_entry_from_template(
+    entry_date: String,
+    template_id: Option<String>,
+    prompt_id: Option<String>,
+    pool: tauri::State<'_, SqlitePool>,
+    lock: tauri::State<'_, AppLock>,
+) -> Result<DiaryEntry, AppError> {
+    lock.check()?;
+    validate_entry_date(&entry_date)?;
+    db::queries::create_entry_from_template(
+        &pool,
+        &entry_date,
+        template_id.as_deref(),
+        prompt_id.as_deref(),
+    )
+    .await
+}
+
+// ===== Writing Prompts =====
+
+/// List prompts, optionally only those of one CEFR level, one topic, or favorites
+#[tauri::command]
+async fn list_prompts(
+    level: Option<String>,
+    topic: Option<String>,
+    favorites_only: Option<bool>,
+    pool: tauri::State<'_, SqlitePool>,
+    lock: tauri::State<'_, AppLock>,
+) -> Result<Vec<WritingPrompt>, AppError> {
+    lock.check()?;
+    db::queries::list_prompts(
+        &pool,
+        level.as_deref(),
+        topic.as_deref(),
+        favorites_only.unwrap_or(false),
+    )
+    .await
+}
+
+/// Get which level the daily prompt is picked from
+#[tauri::command]
+async fn get_prompt_settings(
+    pool: tauri::State<'_, SqlitePool>,
+) -> Result<PromptSettings, AppError> {
+    db::queries::get_prompt_settings(&pool).await
+}
+
+/// Save which level the daily prompt is picked from
+#[tauri::command]
+async fn save_prompt_settings(
+    settings: PromptSettings,
+    pool: tauri::State<'_, SqlitePool>,
+    lock: tauri::State<'_, AppLock>,
+) -> Result<(), AppError> {
+    lock.check()?;
+    db::queries::save_prompt_settings(&pool, &settings).await
 }
 
+/// The prompt for a diary day; the same day gives the same prompt
 #[tauri::command]
-async fn upsert_entry(
-    entry_date: String,
-    content_json: String,
+async fn get_daily_prompt(
+    date: String,
+    level: Option<String>,
     pool: tauri::State<'_, SqlitePool>,
-) -> Result<DiaryEntry, AppError> {
-    validate_entry_date(&entry_date)?;
-    let entry = db::queries::upsert_entry(&pool, &entry_date, &content_json).await?;
-    Ok(entry)
+    lock: tauri::State<'_, AppLock>,
+) -> Result<WritingPrompt, AppError> {
+    lock.check()?;
+    db::queries::get_daily_prompt(&pool, parse_entry_date(&date)?, level.as_deref()).await
 }
 
+/// Skip a day's prompt and get the next one
 #[tauri::command]
-async fn get_entry(
-    entry_date: String,
+async fn skip_prompt(
+    prompt_id: String,
+    date: String,
+    level: Option<String>,
     pool: tauri::State<'_, SqlitePool>,
-) -> Result<Option<DiaryEntry>, AppError> {
-    validate_entry_date(&entry_date)?;
-    let entry = db::queries::get_entry(&pool, &entry_date).await?;
-    Ok(entry)
+    lock: tauri::State<'_, AppLock>,
+) -> Result<WritingPrompt, AppError> {
+    lock.check()?;
+    db::queries::skip_prompt(
+        &pool,
+        &prompt_id,
+        parse_entry_date(&date)?,
+        level.as_deref(),
+    )
+    .await
 }
 
+/// Mark or unmark a prompt as a favorite
 #[tauri::command]
-async fn list_entries(
-    month: String,
+async fn set_prompt_favorite(
+    prompt_id: String,
+    favorite: bool,
     pool: tauri::State<'_, SqlitePool>,
-) -> Result<Vec<DiaryEntry>, AppError> {
-    validate_month(&month)?;
-    let entries = db::queries::list_entries(&pool, &month).await?;
-    Ok(entries)
+    lock: tauri::State<'_, AppLock>,
+) -> Result<WritingPrompt, AppError> {
+    lock.check()?;
+    db::queries::set_prompt_favorite(&pool, &prompt_id, favorite).await
 }
 
+/// Add a prompt of the user's own
 #[tauri::command]
-async fn delete_entry(
-    entry_date: String,
+async fn add_custom_prompt(
+    text: String,
+    level: Option<String>,
+    topic: Option<String>,
+    pool: tauri::State<'_, SqlitePool>,
+    lock: tauri::State<'_, AppLock>,
+) -> Result<WritingPrompt, AppError> {
+    lock.check()?;
+    db::queries::add_custom_prompt(&pool, &text, level.as_deref(), topic.as_deref()).await
+}
+
+/// Delete a prompt of the user's own
+#[tauri::command]
+async fn delete_custom_prompt(
+    prompt_id: String,
     pool: tauri::State<'_, SqlitePool>,
+    lock: tauri::State<'_, AppLock>,
 ) -> Result<bool, AppError> {
+    lock.check()?;
+    db::queries::delete_custom_prompt(&pool, &prompt_id).await
+}
+
+/// Record the prompt an entry answers, or clear it
+#[tauri::command]
+async fn set_entry_prompt(
+    entry_id: String,
+    prompt_id: Option<String>,
+    pool: tauri::State<'_, SqlitePool>,
+    lock: tauri::State<'_, AppLock>,
+) -> Result<(), AppError> {
+    lock.check()?;
+    db::queries::set_entry_prompt(&pool, &entry_id, prompt_id.as_deref()).await
+}
+
+/// The prompt an entry answers, if any
+#[tauri::command]
+async fn get_entry_prompt(
+    entry_id: String,
+    pool: tauri::State<'_, SqlitePool>,
+    lock: tauri::State<'_, AppLock>,
+) -> Result<Option<WritingPrompt>, AppError> {
+    lock.check()?;
+    db::queries::get_entry_prompt(&pool, &entry_id).await
+}
+
+// ===== Entry Revisions =====
+
+/// List saved revisions of an entry, newest first. `entry_id` may name a
+/// trashed entry; without it the first entry of the date is used.
+#[tauri::command]
+async fn list_entry_revisions(
+    entry_date: String,
+    entry_id: Option<String>,
+    pool: tauri::State<'_, SqlitePool>,
+    lock: tauri::State<'_, AppLock>,
+) -> Result<Vec<EntryRevisionSummary>, AppError> {
+    lock.check()?;
     validate_entry_date(&entry_date)?;
-    let deleted = db::queries::delete_entry(&pool, &entry_date).await?;
-    if deleted {
-        // Cascade delete AI operations for this entry
-        let _ = db::queries::delete_ai_operations_for_entry(&pool, &entry_date).await?;
-    }
-    Ok(deleted)
+    let entry_id = match entry_id {
+        Some(entry_id) => entry_id,
+        None => match db::queries::get_first_entry(&pool, &entry_date).await? {
+            Some(entry) => entry.id,
+            None => return Ok(vec![]),
+        },
+    };
+    let revisions = db::queries::list_entry_revisions(&pool, &entry_id).await?;
+    Ok(revisions)
+}
+
+/// Get a single revision with its content
+#[tauri::command]
+async fn get_entry_revision(
+    revision_id: String,
+    pool: tauri::State<'_, SqlitePool>,
+    lock: tauri::State<'_, AppLock>,
+) -> Result<Option<EntryRevision>, AppError> {
+    lock.check()?;
+    let revision = db::queries::get_entry_revision(&pool, &revision_id).await?;
+    Ok(revision)
+}
+
+/// Restore a revision as the current content of its entry
+#[tauri::command]
+async fn restore_entry_revision(
+    revision_id: String,
+    pool: tauri::State<'_, SqlitePool>,
+    lock: tauri::State<'_, AppLock>,
+) -> Result<DiaryEntry, AppError> {
+    lock.check()?;
+    let entry = db::queries::restore_entry_revision(&pool, &revision_id).await?;
+    Ok(entry)
 }
 
 // AI Operations
@@ -101,17 +1056,13 @@ async fn ai_polish(
     text: String,
     pool: tauri::State<'_, SqlitePool>,
     #[allow(unused_variables)] op_type: Option<String>,
+    entry_id: Option<String>,
+    lock: tauri::State<'_, AppLock>,
 ) -> Result<AIOperation, AppError> {
-    validate_entry_date(&entry_date)?;
+    lock.check()?;
 
     // Get the entry first to have its ID
-    let entry =
-        db::queries::get_entry(&pool, &entry_date)
-            .await?
-            .ok_or(AppError::EntryNotFound(format!(
-                "Entry for {} does not exist. Please write and save some content first.",
-                entry_date
-            )))?;
+    let entry = require_entry(&pool, &entry_date, entry_id.as_deref()).await?;
 
     let api_key = keychain::get_api_key()?
         .ok_or(AppError::AI("API key not configured. Please click the wand icon in the header to configure your Zhipu AI API key.".to_string()))?;
@@ -143,8 +1094,66 @@ async fn ai_polish(
     Ok(operation)
 }
 
+/// Longest entry text sent to the AI when proposing a title and summary
+const SUGGESTION_INPUT_CHARS: usize = 4000;
+
+/// Ask the AI for a title and one-sentence summary of an entry. The proposal is
+/// recorded as an AI operation and only applied when the user saves it.
+#[tauri::command]
+async fn ai_suggest_title(
+    entry_date: String,
+    entry_id: Option<String>,
+    pool: tauri::State<'_, SqlitePool>,
+    lock: tauri::State<'_, AppLock>,
+) -> Result<EntrySuggestion, AppError> {
+    lock.check()?;
+    let entry = require_entry(&pool, &entry_date, entry_id.as_deref()).await?;
+    let text: String = content::extract_plain_text(&entry.content_json)
+        .chars()
+        .take(SUGGESTION_INPUT_CHARS)
+        .collect();
+    if text.trim().is_empty() {
+        return Err(AppError::AI(
+            "Write something first, then ask for a title.".to_string(),
+        ));
+    }
+
+    let api_key = keychain::get_api_key()?
+        .ok_or(AppError::AI("API key not configured. Please click the wand icon in the header to configure your Zhipu AI API key.".to_string()))?;
+
+    let provider = ai::ZhipuProvider::new(Some(api_key));
+    let request = ai::AIRequest {
+        op_type: "title_summary".to_string(),
+        text: text.clone(),
+        context: None,
+    };
+    let response = provider.process(request).await?;
+    let suggestion = ai::TitleSummary::parse(&response.result)?;
+
+    let operation = db::queries::create_ai_operation(
+        &pool,
+        &entry.id,
+        "title_summary",
+        &text,
+        &serde_json::to_string(&suggestion)?,
+        &response.provider,
+        &response.model,
+    )
+    .await?;
+
+    Ok(EntrySuggestion {
+        title: suggestion.title,
+        summary: suggestion.summary,
+        operation,
+    })
+}
+
 #[tauri::command]
-async fn save_ai_settings(settings: ai::AISettings) -> Result<(), AppError> {
+async fn save_ai_settings(
+    settings: ai::AISettings,
+    lock: tauri::State<'_, AppLock>,
+) -> Result<(), AppError> {
+    lock.check()?;
     let api_key = settings.api_key.trim();
     if api_key.is_empty() {
         keychain::delete_api_key()?;
@@ -157,7 +1166,10 @@ async fn save_ai_settings(settings: ai::AISettings) -> Result<(), AppError> {
 }
 
 #[tauri::command]
-async fn get_ai_settings() -> Result<Option<ai::AISettings>, AppError> {
+async fn get_ai_settings(
+    lock: tauri::State<'_, AppLock>,
+) -> Result<Option<ai::AISettings>, AppError> {
+    lock.check()?;
     let api_key = keychain::get_api_key()?;
     let is_configured = api_key.is_some();
 
@@ -176,26 +1188,228 @@ async fn get_ai_settings() -> Result<Option<ai::AISettings>, AppError> {
 async fn list_ai_operations(
     entry_id: String,
     pool: tauri::State<'_, SqlitePool>,
+    lock: tauri::State<'_, AppLock>,
 ) -> Result<Vec<AIOperation>, AppError> {
+    lock.check()?;
     let operations = db::queries::list_ai_operations(&pool, &entry_id).await?;
     Ok(operations)
 }
 
+// ===== Attachments =====
+
+/// Attach a file to an entry
+async fn attach_file(
+    app: &tauri::AppHandle,
+    pool: &SqlitePool,
+    entry_date: &str,
+    entry_id: Option<&str>,
+    file_name: &str,
+    bytes: &[u8],
+) -> Result<Attachment, AppError> {
+    let entry = require_entry(pool, entry_date, entry_id).await?;
+    let vault = Vault::of(pool);
+    vault.ensure_unlocked()?;
+
+    let _store = attachments::STORE_LOCK.lock().await;
+    let stored = attachments::store(&db::app_data_dir(app)?, &vault, file_name, bytes)?;
+    let attachment = Attachment {
+        id: uuid::Uuid::new_v4().to_string(),
+        entry_id: entry.id,
+        sha256: stored.sha256,
+        file_name: file_name.to_string(),
+        mime_type: stored.mime_type,
+        size_bytes: stored.size_bytes,
+        width: stored.width,
+        height: stored.height,
+        has_thumbnail: stored.has_thumbnail,
+        created_at: chrono::Utc::now().timestamp_millis(),
+    };
+    db::queries::create_attachment(pool, &attachment).await?;
+    Ok(attachment)
+}
+
+/// Attach a file from disk to an entry
+#[tauri::command]
+async fn add_attachment(
+    entry_date: String,
+    source_path: String,
+    entry_id: Option<String>,
+    app: tauri::AppHandle,
+    pool: tauri::State<'_, SqlitePool>,
+    lock: tauri::State<'_, AppLock>,
+) -> Result<Attachment, AppError> {
+    lock.check()?;
+    let path = std::path::Path::new(&source_path);
+    let file_name = path
+        .file_name()
+        .and_then(|n| n.to_str())
+        .ok_or_else(|| AppError::Attachment(format!("Not a file: {}", source_path)))?;
+    let size = std::fs::metadata(path)?.len();
+    if size > attachments::MAX_ATTACHMENT_BYTES as u64 {
+        return Err(AppError::Attachment(format!(
+            "File is larger than {} MB",
+            attachments::MAX_ATTACHMENT_BYTES / (1024 * 1024)
+        )));
+    }
+    let bytes = std::fs::read(path)?;
+    attach_file(
+        &app,
+        &pool,
+        &entry_date,
+        entry_id.as_deref(),
+        file_name,
+        &bytes,
+    )
+    .await
+}
+
+/// Attach pasted or dropped data (base64) to an entry
+#[tauri::command]
+async fn paste_attachment(
+    entry_date: String,
+    file_name: String,
+    data_base64: String,
+    entry_id: Option<String>,
+    app: tauri::AppHandle,
+    pool: tauri::State<'_, SqlitePool>,
+    lock: tauri::State<'_, AppLock>,
+) -> Result<Attachment, AppError> {
+    lock.check()?;
+    let bytes = BASE64_STANDARD
+        .decode(data_base64.as_bytes())
+        .map_err(|e| AppError::Attachment(format!("Invalid file data: {}", e)))?;
+    attach_file(
+        &app,
+        &pool,
+        &entry_date,
+        entry_id.as_deref(),
+        &file_name,
+        &bytes,
+    )
+    .await
+}
+
+/// List the attachments of an entry
+#[tauri::command]
+async fn list_attachments(
+    entry_date: String,
+    entry_id: Option<String>,
+    pool: tauri::State<'_, SqlitePool>,
+    lock: tauri::State<'_, AppLock>,
+) -> Result<Vec<Attachment>, AppError> {
+    lock.check()?;
+    match find_entry(&pool, &entry_date, entry_id.as_deref()).await? {
+        Some(entry) => db::queries::list_attachments(&pool, &entry.id).await,
+        None => Ok(vec![]),
+    }
+}
+
+/// Attachment files (and thumbnails) smaller than this are returned inline as base64
+const INLINE_ATTACHMENT_MAX_BYTES: i64 = 2_000_000;
+
+/// Resolve an attachment, or its thumbnail, for display
+#[tauri::command]
+async fn resolve_attachment(
+    attachment_id: String,
+    thumbnail: bool,
+    app: tauri::AppHandle,
+    pool: tauri::State<'_, SqlitePool>,
+    lock: tauri::State<'_, AppLock>,
+) -> Result<AttachmentContent, AppError> {
+    lock.check()?;
+    let attachment = db::queries::get_attachment(&pool, &attachment_id)
+        .await?
+        .ok_or_else(|| AppError::Attachment(format!("Attachment {} not found", attachment_id)))?;
+    if thumbnail && !attachment.has_thumbnail {
+        return Err(AppError::Attachment(format!(
+            "Attachment {} has no thumbnail",
+            attachment_id
+        )));
+    }
+
+    let app_data_dir = db::app_data_dir(&app)?;
+    let path = attachments::locate(&app_data_dir, &attachment.sha256, thumbnail)?;
+    if !path.is_file() {
+        return Err(AppError::Attachment(format!(
+            "File for attachment {} is missing",
+            attachment_id
+        )));
+    }
+
+    // Encrypted files are always inlined since the webview can't read them from disk
+    let vault = Vault::of(&pool);
+    let inline = thumbnail
+        || !matches!(vault, Vault::Plain)
+        || attachment.size_bytes < INLINE_ATTACHMENT_MAX_BYTES;
+    let data_base64 = if inline {
+        let bytes = attachments::read(&app_data_dir, &vault, &attachment.sha256, thumbnail)?;
+        Some(BASE64_STANDARD.encode(bytes))
+    } else {
+        None
+    };
+
+    Ok(AttachmentContent {
+        attachment_id: attachment.id,
+        mime_type: if thumbnail {
+            "image/png".to_string()
+        } else {
+            attachment.mime_type
+        },
+        file_path: (!inline).then(|| path.to_string_lossy().to_string()),
+        data_base64,
+    })
+}
+
+/// Remove an attachment, deleting its file when nothing else uses it
+#[tauri::command]
+async fn remove_attachment(
+    attachment_id: String,
+    app: tauri::AppHandle,
+    pool: tauri::State<'_, SqlitePool>,
+    lock: tauri::State<'_, AppLock>,
+) -> Result<bool, AppError> {
+    lock.check()?;
+    let removed = db::queries::delete_attachment(&pool, &attachment_id).await?;
+    if removed {
+        collect_attachment_files(&db::app_data_dir(&app)?, &pool).await?;
+    }
+    Ok(removed)
+}
+
+/// Delete attachment files no attachment refers to. Returns the number removed.
+#[tauri::command]
+async fn collect_attachment_garbage(
+    app: tauri::AppHandle,
+    pool: tauri::State<'_, SqlitePool>,
+    lock: tauri::State<'_, AppLock>,
+) -> Result<usize, AppError> {
+    lock.check()?;
+    collect_attachment_files(&db::app_data_dir(&app)?, &pool).await
+}
+
 // ===== TTS Operations =====
 
 /// Text to speech synthesis
 #[tauri::command]
+#[allow(clippy::too_many_arguments)]
 async fn text_to_speech(
+    entry_date: String,
     text: String,
     #[allow(unused_variables)] voice: Option<String>,
     language: Option<String>,
     #[allow(unused_variables)] speed: Option<f32>,
     #[allow(unused_variables)] provider: Option<String>,
+    entry_id: Option<String>,
     app: tauri::AppHandle,
     pool: tauri::State<'_, SqlitePool>,
+    lock: tauri::State<'_, AppLock>,
 ) -> Result<tts::TTSResponse, AppError> {
+    lock.check()?;
     println!("TTS: Command invoked, text length: {}", text.len());
 
+    // Audio is always saved against an entry
+    let entry = require_entry(&pool, &entry_date, entry_id.as_deref()).await?;
+
     // Get TTS settings from database to read configured provider, voice and speed
     let (configured_provider, configured_voice, configured_speed) =
         if let Some(config_json) = db::queries::get_setting(&pool, "tts_config").await? {
@@ -228,83 +1442,127 @@ async fn text_to_speech(
 
     println!("TTS: Using provider: {:?}", provider_type);
 
-    // Get the provider with API key
-    let tts_provider = tts::get_provider(provider_type).await.map_err(|e| {
-        println!("TTS: Failed to get provider: {}", e);
-        AppError::TTS(e.to_string())
-    })?;
-
     // Use default voice based on provider if not configured
     let final_voice = configured_voice.or_else(|| match provider_type {
         tts::TTSProviderType::Qwen => Some("cherry".to_string()),
         tts::TTSProviderType::Murf => Some("en-US-natalie".to_string()),
     });
 
-    println!(
-        "TTS: Provider created, building request with voice: {:?}",
-        final_voice
-    );
-    let request = tts::TTSRequest {
-        text,
-        voice: final_voice,
-        language,
-        speed: Some(configured_speed),
-        output_format: tts::TTSOutputFormat::Mp3,
-    };
-
-    println!("TTS: Calling synthesize...");
-    let mut response = tts_provider.synthesize(request).await.map_err(|e| {
-        println!("TTS: Synthesize error: {}", e);
-        AppError::TTS(e.to_string())
-    })?;
-
-    // Save audio bytes to app data directory
-    if let Some(bytes) = &response.audio_bytes {
-        println!("TTS: Processing {} bytes of audio", bytes.len());
-
-        // For small files, keep as base64 in response
-        if bytes.len() < 500_000 {
-            println!("TTS: Keeping audio as base64 (size: {})", bytes.len());
-            response.audio_base64 = Some(BASE64_STANDARD.encode(bytes));
-        } else {
-            println!("TTS: Saving large audio file to disk");
+    println!("TTS: Building request with voice: {:?}", final_voice);
+    let output_format = tts::TTSOutputFormat::Mp3;
+    let app_data_dir = db::app_data_dir(&app)?;
+    let cache = tts::cache::TTSCache::new(&pool, app_data_dir.clone());
+    let cache_key = tts::cache::cache_key(
+        &tts::cache::CacheKeyParts {
+            provider: provider_type.as_str(),
+            // Saved and cached audio play without an API key, so the key is
+            // only needed below on a miss
+            model: tts::get_provider_no_auth(provider_type).default_model(),
+            voice: final_voice.as_deref().unwrap_or(""),
+            speed: configured_speed,
+            language: language.as_deref(),
+            format: output_format.as_str(),
+            text: &text,
+        },
+        &Vault::of(&pool),
+    )?;
+
+    // The same clip was already saved for this entry: play it back as-is
+    if let Some(record) =
+        db::queries::find_audio_record_by_cache_key(&pool, &entry.id, &cache_key).await?
+    {
+        let path = tts::storage::resolve_audio_path(&app_data_dir, &record.audio_relpath)?;
+        if path.is_file() {
+            println!("TTS: Reusing saved audio record {}", record.id);
+            let audio_base64 = inline_audio(&Vault::of(&pool), &path, record.size_bytes)?;
+            return Ok(tts::TTSResponse {
+                audio_bytes: None,
+                audio_file: path.to_str().map(|s| s.to_string()),
+                audio_base64,
+                format: record.format,
+                duration_ms: record.duration_ms.map(|ms| ms as u32),
+                provider: record.provider,
+                model: record.model,
+                voice: record.voice.unwrap_or_default(),
+                record_id: Some(record.id),
+                cached: true,
+            });
+        }
+    }
 
-            // Get app data directory
-            let app_data_dir = app.path().app_data_dir().map_err(|e| {
-                println!("TTS: Failed to get app data dir: {}", e);
-                AppError::TTS(format!("Failed to get app data dir: {}", e))
+    let request_text = text.clone();
+    let mut response = match cache.get(&cache_key).await? {
+        Some(response) => {
+            println!("TTS: Cache hit");
+            response
+        }
+        None => {
+            // Get the provider with API key
+            let tts_provider = tts::get_provider(provider_type).await.map_err(|e| {
+                println!("TTS: Failed to get provider: {}", e);
+                AppError::TTS(e.to_string())
             })?;
+            let request = tts::TTSRequest {
+                text,
+                voice: final_voice,
+                language,
+                speed: Some(configured_speed),
+                output_format,
+            };
 
-            // Create directory if it doesn't exist
-            std::fs::create_dir_all(&app_data_dir).map_err(|e| {
-                println!("TTS: Failed to create app data dir: {}", e);
-                AppError::TTS(format!("Failed to create app data dir: {}", e))
+            println!("TTS: Calling synthesize...");
+            let response = tts_provider.synthesize(request).await.map_err(|e| {
+                println!("TTS: Synthesize error: {}", e);
+                AppError::TTS(e.to_string())
             })?;
 
-            // Generate unique filename
-            let file_name = format!(
-                "tts_{}.{}.{}",
-                std::process::id(),
-                std::time::SystemTime::now()
-                    .duration_since(std::time::UNIX_EPOCH)
-                    .unwrap()
-                    .as_millis(),
-                response.format
-            );
-            let file_path = app_data_dir.join(&file_name);
+            // Caching is best effort; a failure must not lose the synthesized audio
+            if let Err(e) = cache.put(&cache_key, &response).await {
+                println!("TTS: Failed to cache audio: {}", e);
+            }
+            response
+        }
+    };
 
-            // Write audio bytes to file
-            std::fs::write(&file_path, bytes).map_err(|e| {
+    // Save audio bytes as an audio record of the entry
+    if let Some(bytes) = response.audio_bytes.take() {
+        println!("TTS: Processing {} bytes of audio", bytes.len());
+
+        let record_id = uuid::Uuid::new_v4().to_string();
+        let audio_relpath = tts::storage::audio_relpath(&entry.id, &record_id, &response.format);
+        let vault = Vault::of(&pool);
+        let sealed = vault.seal_bytes(bytes.clone())?;
+        let file_path =
+            tts::storage::write_audio(&app_data_dir, &audio_relpath, &sealed).map_err(|e| {
                 println!("TTS: Failed to write audio file: {}", e);
                 AppError::TTS(format!("Failed to write audio file: {}", e))
             })?;
+        println!("TTS: Audio saved to: {}", file_path.display());
+
+        let record = AudioRecord {
+            id: record_id,
+            entry_id: entry.id.clone(),
+            text: request_text,
+            audio_relpath,
+            format: response.format.clone(),
+            provider: response.provider.clone(),
+            model: response.model.clone(),
+            voice: Some(response.voice.clone()),
+            speed: Some(configured_speed as f64),
+            duration_ms: response.duration_ms.map(i64::from),
+            size_bytes: bytes.len() as i64,
+            created_at: chrono::Utc::now().timestamp_millis(),
+            cache_key: Some(cache_key),
+        };
+        db::queries::create_audio_record(&pool, &record).await?;
 
-            println!("TTS: Audio saved to: {}", file_path.display());
-            response.audio_file = file_path.to_str().map(|s| s.to_string());
+        // For small files, also inline as base64 so playback needs no file access.
+        // Encrypted files can't be played from disk, so they are always inlined.
+        if bytes.len() < INLINE_AUDIO_MAX_BYTES || !matches!(vault, Vault::Plain) {
+            response.audio_base64 = Some(BASE64_STANDARD.encode(&bytes));
         }
-
-        // Clear bytes to avoid IPC size limit
-        response.audio_bytes = None;
+        response.audio_file = file_path.to_str().map(|s| s.to_string());
+        response.record_id = Some(record.id);
     }
 
     println!(
@@ -317,11 +1575,116 @@ async fn text_to_speech(
     Ok(response)
 }
 
+/// List saved audio clips of an entry (the first of the date unless `entry_id` is given)
+#[tauri::command]
+async fn list_audio_records(
+    entry_date: String,
+    entry_id: Option<String>,
+    pool: tauri::State<'_, SqlitePool>,
+    lock: tauri::State<'_, AppLock>,
+) -> Result<Vec<AudioRecord>, AppError> {
+    lock.check()?;
+    match find_entry(&pool, &entry_date, entry_id.as_deref()).await? {
+        Some(entry) => db::queries::list_audio_records(&pool, &entry.id).await,
+        None => Ok(vec![]),
+    }
+}
+
+/// Resolve a saved audio clip for playback
+#[tauri::command]
+async fn load_audio_record(
+    record_id: String,
+    app: tauri::AppHandle,
+    pool: tauri::State<'_, SqlitePool>,
+    lock: tauri::State<'_, AppLock>,
+) -> Result<AudioPlayback, AppError> {
+    lock.check()?;
+    let record = db::queries::get_audio_record(&pool, &record_id)
+        .await?
+        .ok_or_else(|| AppError::TTS(format!("Audio record {} not found", record_id)))?;
+
+    let path = tts::storage::resolve_audio_path(&db::app_data_dir(&app)?, &record.audio_relpath)?;
+    if !path.is_file() {
+        return Err(AppError::TTS(format!(
+            "Audio file for record {} is missing",
+            record_id
+        )));
+    }
+
+    let audio_base64 = inline_audio(&Vault::of(&pool), &path, record.size_bytes)?;
+
+    Ok(AudioPlayback {
+        record_id: record.id,
+        audio_file: path.to_string_lossy().to_string(),
+        audio_base64,
+        format: record.format,
+    })
+}
+
+/// Delete a saved audio clip and its file
+#[tauri::command]
+async fn delete_audio_record(
+    record_id: String,
+    app: tauri::AppHandle,
+    pool: tauri::State<'_, SqlitePool>,
+    lock: tauri::State<'_, AppLock>,
+) -> Result<bool, AppError> {
+    lock.check()?;
+    let Some(record) = db::queries::get_audio_record(&pool, &record_id).await? else {
+        return Ok(false);
+    };
+
+    tts::storage::remove_audio(&db::app_data_dir(&app)?, &record.audio_relpath)?;
+    db::queries::delete_audio_record(&pool, &record.id).await
+}
+
+/// Get TTS cache usage
+#[tauri::command]
+async fn get_tts_cache_info(
+    app: tauri::AppHandle,
+    pool: tauri::State<'_, SqlitePool>,
+    lock: tauri::State<'_, AppLock>,
+) -> Result<TTSCacheInfo, AppError> {
+    lock.check()?;
+    tts::cache::TTSCache::new(&pool, db::app_data_dir(&app)?)
+        .info()
+        .await
+}
+
+/// Set the TTS cache size cap in bytes
+#[tauri::command]
+async fn set_tts_cache_limit(
+    max_bytes: i64,
+    app: tauri::AppHandle,
+    pool: tauri::State<'_, SqlitePool>,
+    lock: tauri::State<'_, AppLock>,
+) -> Result<(), AppError> {
+    lock.check()?;
+    tts::cache::TTSCache::new(&pool, db::app_data_dir(&app)?)
+        .set_max_bytes(max_bytes)
+        .await
+}
+
+/// Remove all cached TTS clips, returning the number of bytes freed
+#[tauri::command]
+async fn clear_tts_cache(
+    app: tauri::AppHandle,
+    pool: tauri::State<'_, SqlitePool>,
+    lock: tauri::State<'_, AppLock>,
+) -> Result<i64, AppError> {
+    lock.check()?;
+    tts::cache::TTSCache::new(&pool, db::app_data_dir(&app)?)
+        .clear()
+        .await
+}
+
 /// List available TTS voices for a specific provider
 #[tauri::command]
 async fn list_tts_voices(
     #[allow(unused_variables)] provider: Option<String>,
+    lock: tauri::State<'_, AppLock>,
 ) -> Result<Vec<tts::TTSVoice>, AppError> {
+    lock.check()?;
     // Get provider from parameter or use default
     let provider_str = provider.unwrap_or_else(|| "qwen".to_string());
     let provider_type =
@@ -338,7 +1701,9 @@ async fn list_tts_voices(
 async fn save_tts_settings(
     settings: tts::TTSSettings,
     pool: tauri::State<'_, SqlitePool>,
+    lock: tauri::State<'_, AppLock>,
 ) -> Result<(), AppError> {
+    lock.check()?;
     // Save API key to appropriate keychain entry based on provider
     if settings.api_key != "***" {
         match settings.provider.as_str() {
@@ -354,7 +1719,7 @@ async fn save_tts_settings(
         "speed": settings.speed
     });
 
-    db::queries::save_setting(&pool, "tts_config", &config_json.to_string()).await?;
+    db::queries::save_setting(&*pool, "tts_config", &config_json.to_string()).await?;
     Ok(())
 }
 
@@ -363,7 +1728,9 @@ async fn save_tts_settings(
 async fn get_tts_settings(
     provider: Option<String>,
     pool: tauri::State<'_, SqlitePool>,
+    lock: tauri::State<'_, AppLock>,
 ) -> Result<Option<tts::TTSSettings>, AppError> {
+    lock.check()?;
     let provider_str = provider.unwrap_or_else(|| "qwen".to_string());
 
     // Get API key based on provider
@@ -417,7 +1784,8 @@ async fn get_tts_settings(
 
 /// Get available TTS providers
 #[tauri::command]
-async fn list_tts_providers() -> Result<Vec<String>, AppError> {
+async fn list_tts_providers(lock: tauri::State<'_, AppLock>) -> Result<Vec<String>, AppError> {
+    lock.check()?;
     Ok(tts::TTSProviderType::all()
         .iter()
         .map(|p| p.as_str().to_string())
@@ -426,18 +1794,34 @@ async fn list_tts_providers() -> Result<Vec<String>, AppError> {
 
 // ===== Mood Tracking Operations =====
 
-/// Update or create an entry with mood information
+/// Set the mood of an entry. Without `entry_id` the first entry of the day is
+/// used, and created if the day has none.
 #[tauri::command]
 async fn upsert_entry_mood(
     entry_date: String,
     mood: Option<String>,
     mood_emoji: Option<String>,
+    entry_id: Option<String>,
     pool: tauri::State<'_, SqlitePool>,
+    lock: tauri::State<'_, AppLock>,
 ) -> Result<DiaryEntry, AppError> {
+    lock.check()?;
     validate_entry_date(&entry_date)?;
-    let entry =
-        db::queries::upsert_entry_mood(&pool, &entry_date, mood.as_deref(), mood_emoji.as_deref())
-            .await?;
+    let entry = match entry_id {
+        Some(entry_id) => {
+            db::queries::set_entry_mood(&pool, &entry_id, mood.as_deref(), mood_emoji.as_deref())
+                .await?
+        }
+        None => {
+            db::queries::upsert_entry_mood(
+                &pool,
+                &entry_date,
+                mood.as_deref(),
+                mood_emoji.as_deref(),
+            )
+            .await?
+        }
+    };
     Ok(entry)
 }
 
@@ -447,51 +1831,186 @@ async fn list_entries_by_mood(
     month: String,
     mood: String,
     pool: tauri::State<'_, SqlitePool>,
+    lock: tauri::State<'_, AppLock>,
 ) -> Result<Vec<DiaryEntry>, AppError> {
+    lock.check()?;
     validate_month(&month)?;
     let entries = db::queries::list_entries_by_mood(&pool, &month, &mood).await?;
     Ok(entries)
 }
 
-/// Search entries by full-text query
+/// Search entries by full-text query, one page at a time
 #[tauri::command]
 async fn search_entries(
     query: String,
+    limit: Option<i64>,
+    offset: Option<i64>,
     pool: tauri::State<'_, SqlitePool>,
-) -> Result<Vec<DiaryEntry>, AppError> {
+    lock: tauri::State<'_, AppLock>,
+) -> Result<SearchResults, AppError> {
+    lock.check()?;
+    let limit = limit.unwrap_or(db::queries::DEFAULT_SEARCH_LIMIT);
+    let offset = offset.unwrap_or(0);
+
     // Validate query is not empty
     let query = query.trim();
     if query.is_empty() {
-        return Ok(vec![]);
+        return Ok(SearchResults {
+            hits: vec![],
+            total: 0,
+            limit,
+            offset,
+        });
     }
-    let entries = db::queries::search_entries(&pool, query).await?;
-    Ok(entries)
+    db::queries::search_entries(&pool, query, limit, offset).await
 }
 
 /// Get writing statistics
 #[tauri::command]
-async fn get_writing_stats(pool: tauri::State<'_, SqlitePool>) -> Result<WritingStats, AppError> {
+async fn get_writing_stats(
+    pool: tauri::State<'_, SqlitePool>,
+    lock: tauri::State<'_, AppLock>,
+) -> Result<WritingStats, AppError> {
+    lock.check()?;
     db::queries::get_writing_stats(&pool).await
 }
 
+/// Get word counts, per-period totals, weekday and AI usage statistics
+#[tauri::command]
+async fn get_writing_analytics(
+    pool: tauri::State<'_, SqlitePool>,
+    lock: tauri::State<'_, AppLock>,
+) -> Result<WritingAnalytics, AppError> {
+    lock.check()?;
+    db::queries::get_writing_analytics(&pool).await
+}
+
 // ===== Export/Import Operations =====
 
 /// Export all user data as JSON
 #[tauri::command]
-async fn export_data(pool: tauri::State<'_, SqlitePool>) -> Result<String, AppError> {
-    let data = db::queries::export_all_data(&pool).await?;
+async fn export_data(
+    app: tauri::AppHandle,
+    pool: tauri::State<'_, SqlitePool>,
+    lock: tauri::State<'_, AppLock>,
+) -> Result<String, AppError> {
+    lock.check()?;
+    let mut data = db::queries::export_all_data(&pool).await?;
+
+    // Embed each attached file once, decrypted
+    let app_data_dir = db::app_data_dir(&app)?;
+    let vault = Vault::of(&pool);
+    let mut seen = std::collections::HashSet::new();
+    for attachment in &data.attachments {
+        if !seen.insert(attachment.sha256.clone()) {
+            continue;
+        }
+        match attachments::read(&app_data_dir, &vault, &attachment.sha256, false) {
+            Ok(bytes) => data.attachment_files.push(AttachmentFile {
+                sha256: attachment.sha256.clone(),
+                data_base64: BASE64_STANDARD.encode(bytes),
+            }),
+            // A missing file shouldn't make the rest of the journal unexportable
+            Err(e) => println!(
+                "Export: Skipping file of attachment {}: {}",
+                attachment.id, e
+            ),
+        }
+    }
+
     let json = serde_json::to_string_pretty(&data)?;
     Ok(json)
 }
 
+/// Export the journal as Markdown files, into a new folder or a zip file
+#[tauri::command]
+async fn export_markdown(
+    path: String,
+    format: ArchiveFormat,
+    include_media: bool,
+    app: tauri::AppHandle,
+    pool: tauri::State<'_, SqlitePool>,
+    lock: tauri::State<'_, AppLock>,
+) -> Result<ExportSummary, AppError> {
+    lock.check()?;
+    let data = db::queries::export_all_data(&pool).await?;
+    let audio = db::queries::list_all_audio_records(&pool).await?;
+
+    let app_data_dir = db::app_data_dir(&app)?;
+    let vault = Vault::of(&pool);
+    let media = export::MediaSource {
+        app_data_dir: &app_data_dir,
+        vault: &vault,
+    };
+
+    let archive = export::Archive::create(std::path::Path::new(&path), format)?;
+    export::markdown::export(archive, &data, &audio, include_media.then_some(&media))
+}
+
+/// Export the journal as a static HTML site into a new folder
+#[tauri::command]
+async fn export_html_site(
+    path: String,
+    app: tauri::AppHandle,
+    pool: tauri::State<'_, SqlitePool>,
+    lock: tauri::State<'_, AppLock>,
+) -> Result<ExportSummary, AppError> {
+    lock.check()?;
+    let data = db::queries::export_all_data(&pool).await?;
+    let audio = db::queries::list_all_audio_records(&pool).await?;
+
+    let app_data_dir = db::app_data_dir(&app)?;
+    let vault = Vault::of(&pool);
+    let media = export::MediaSource {
+        app_data_dir: &app_data_dir,
+        vault: &vault,
+    };
+
+    let archive = export::Archive::create(std::path::Path::new(&path), ArchiveFormat::Directory)?;
+    export::html::export(archive, &data, &audio, &media)
+}
+
 /// Import user data from JSON
 #[tauri::command]
 async fn import_data(
     json_data: String,
     options: ImportOptions,
+    app: tauri::AppHandle,
     pool: tauri::State<'_, SqlitePool>,
+    lock: tauri::State<'_, AppLock>,
 ) -> Result<usize, AppError> {
-    let data: ExportData = serde_json::from_str(&json_data)?;
+    lock.check()?;
+    let mut data: ExportData = serde_json::from_str(&json_data)?;
+
+    // Store attached files first, then only import attachments whose file is
+    // present. Files are renamed to this journal's fingerprints, which differ
+    // from the exporting journal's while either is encrypted.
+    let app_data_dir = db::app_data_dir(&app)?;
+    let vault = Vault::of(&pool);
+    vault.ensure_unlocked()?;
+    let _store = attachments::STORE_LOCK.lock().await;
+    let mut renamed = std::collections::HashMap::new();
+    for file in std::mem::take(&mut data.attachment_files) {
+        let bytes = BASE64_STANDARD
+            .decode(file.data_base64.as_bytes())
+            .map_err(|e| AppError::Attachment(format!("Invalid file data: {}", e)))?;
+        let file_name = data
+            .attachments
+            .iter()
+            .find(|a| a.sha256 == file.sha256)
+            .map_or("", |a| a.file_name.as_str());
+        let stored = attachments::store(&app_data_dir, &vault, file_name, &bytes)?;
+        renamed.insert(file.sha256, stored.sha256);
+    }
+    let available = db::queries::referenced_attachment_hashes(&pool).await?;
+    data.attachments
+        .retain_mut(|a| match renamed.get(&a.sha256) {
+            Some(sha256) => {
+                a.sha256 = sha256.clone();
+                true
+            }
+            None => available.contains(&a.sha256),
+        });
 
     let count = db::queries::import_data(&pool, data, options).await?;
 
@@ -506,21 +2025,116 @@ pub fn run() {
         .plugin(tauri_plugin_fs::init())
         .plugin(tauri_plugin_dialog::init())
         .setup(|app| {
-            let pool = tauri::async_runtime::block_on(db::get_pool(app.handle()))?;
+            let pool = match tauri::async_runtime::block_on(db::get_pool(app.handle())) {
+                Ok(pool) => pool,
+                Err(e) => {
+                    // Keep running so the frontend can explain what happened
+                    println!("Database: Failed to open: {}", e.message);
+                    app.manage(StartupStatus(Some(e)));
+                    return Ok(());
+                }
+            };
+            app.manage(StartupStatus(None));
+            // Drop entries that outlived the trash retention period
+            match tauri::async_runtime::block_on(db::queries::purge_expired_trash(&pool)) {
+                Ok(purged) => tauri::async_runtime::block_on(remove_purged_entry_files(
+                    app.handle(),
+                    &pool,
+                    &purged,
+                )),
+                Err(e) => println!("Trash: Failed to purge expired entries: {}", e),
+            }
+            tauri::async_runtime::spawn(run_backup_schedule(app.handle().clone(), pool.clone()));
+            app.manage(tauri::async_runtime::block_on(app_lock::load(&pool)));
+            tauri::async_runtime::spawn(run_idle_lock(app.handle().clone()));
             app.manage(pool);
             Ok(())
         })
         .invoke_handler(tauri::generate_handler![
             init_db,
+            get_startup_error,
+            get_schema_version,
             upsert_entry,
             get_entry,
+            get_day_entries,
+            create_entry,
+            update_entry_details,
             list_entries,
+            get_calendar_days,
+            get_on_this_day,
             delete_entry,
+            list_trash,
+            restore_entry,
+            empty_trash,
+            get_trash_settings,
+            save_trash_settings,
+            get_day_settings,
+            save_day_settings,
+            get_journal_today,
+            list_backups,
+            create_backup,
+            restore_backup,
+            get_backup_settings,
+            save_backup_settings,
+            get_encryption_status,
+            enable_encryption,
+            unlock_journal,
+            lock_journal,
+            change_passphrase,
+            get_lock_status,
+            record_activity,
+            unlock_app,
+            lock_app,
+            set_lock_passphrase,
+            disable_app_lock,
+            get_lock_settings,
+            save_lock_settings,
+            list_tags,
+            list_entry_tags,
+            add_entry_tag,
+            remove_entry_tag,
+            rename_tag,
+            merge_tags,
+            delete_tag,
+            list_templates,
+            create_template,
+            update_template,
+            delete_template,
+            get_template_settings,
+            save_template_settings,
+            create_entry_from_template,
+            list_prompts,
+            get_prompt_settings,
+            save_prompt_settings,
+            get_daily_prompt,
+            skip_prompt,
+            set_prompt_favorite,
+            add_custom_prompt,
+            delete_custom_prompt,
+            set_entry_prompt,
+            get_entry_prompt,
+            list_entries_by_tag,
+            list_entry_revisions,
+            get_entry_revision,
+            restore_entry_revision,
             ai_polish,
+            ai_suggest_title,
             save_ai_settings,
             get_ai_settings,
             list_ai_operations,
             text_to_speech,
+            list_audio_records,
+            load_audio_record,
+            delete_audio_record,
+            add_attachment,
+            paste_attachment,
+            list_attachments,
+            resolve_attachment,
+            remove_attachment,
+            collect_attachment_garbage,
+            get_tts_cache_info,
+            set_tts_cache_limit,
+            clear_tts_cache,
             list_tts_voices,
             list_tts_providers,
             save_tts_settings,
@@ -529,9 +2143,15 @@ pub fn run() {
             list_entries_by_mood,
             search_entries,
             get_writing_stats,
+            get_writing_analytics,
             export_data,
+            export_markdown,
+            export_html_site,
             import_data,
         ])
         .run(tauri::generate_context!())
         .expect("error while running tauri application");
 }
+
+#[cfg(test)]
+mod tests;
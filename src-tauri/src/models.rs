@@ -5,6 +5,12 @@ use sqlx::FromRow;
 pub struct DiaryEntry {
     pub id: String,
     pub entry_date: String, // YYYY-MM-DD
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub entry_time: Option<String>, // HH:MM, orders entries within a day
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub title: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub summary: Option<String>, // one-sentence summary shown in lists
     pub content_json: String,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub mood: Option<String>, // Mood category: amazing, happy, neutral, sad, awful
@@ -12,6 +18,8 @@ pub struct DiaryEntry {
     pub mood_emoji: Option<String>, // Emoji representation: 😄, 😊, 😐, 😢, 😭
     pub created_at: i64, // unix timestamp ms
     pub updated_at: i64,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub deleted_at: Option<i64>, // set while the entry is in the trash
 }
 
 #[derive(Debug, Serialize, Deserialize, FromRow)]
@@ -26,13 +34,397 @@ pub struct AIOperation {
     pub created_at: i64,
 }
 
+/// Title and summary proposed by the AI for an entry, with the operation that
+/// recorded the request
+#[derive(Debug, Serialize, Deserialize)]
+pub struct EntrySuggestion {
+    pub title: String,
+    pub summary: String,
+    pub operation: AIOperation,
+}
+
+/// A synthesized audio clip saved for an entry
+#[derive(Debug, Serialize, Deserialize, FromRow)]
+pub struct AudioRecord {
+    pub id: String,
+    pub entry_id: String,
+    pub text: String,
+    pub audio_relpath: String, // relative to the app data dir
+    pub format: String,        // "mp3", "wav", "ogg"
+    pub provider: String,
+    pub model: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub voice: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub speed: Option<f64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub duration_ms: Option<i64>,
+    pub size_bytes: i64,
+    pub created_at: i64,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub cache_key: Option<String>, // TTS cache key the clip was synthesized under
+}
+
+/// Audio ready for playback
+#[derive(Debug, Serialize, Deserialize)]
+pub struct AudioPlayback {
+    pub record_id: String,
+    pub audio_file: String, // absolute path
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub audio_base64: Option<String>, // only for small files
+    pub format: String,
+}
+
+/// A file attached to an entry
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow)]
+pub struct Attachment {
+    pub id: String,
+    pub entry_id: String,
+    pub sha256: String, // content fingerprint, names the file in the attachment store
+    pub file_name: String,
+    pub mime_type: String,
+    pub size_bytes: i64,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub width: Option<i64>, // images only
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub height: Option<i64>,
+    pub has_thumbnail: bool,
+    pub created_at: i64,
+}
+
+/// Attachment content ready for display
+#[derive(Debug, Serialize, Deserialize)]
+pub struct AttachmentContent {
+    pub attachment_id: String,
+    pub mime_type: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub file_path: Option<String>, // absolute path, only for large unencrypted files
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub data_base64: Option<String>,
+}
+
+/// Contents of an attached file in an export
+#[derive(Debug, Serialize, Deserialize)]
+pub struct AttachmentFile {
+    pub sha256: String,
+    pub data_base64: String,
+}
+
+/// Index row of a cached TTS clip
+#[derive(Debug, Serialize, Deserialize, FromRow)]
+pub struct TTSCacheEntry {
+    pub cache_key: String,
+    pub relpath: String, // relative to the app data dir
+    pub format: String,
+    pub provider: String,
+    pub model: String,
+    pub voice: String,
+    pub duration_ms: Option<i64>,
+    pub size_bytes: i64,
+    pub created_at: i64,
+    pub last_used_at: i64,
+}
+
+/// TTS cache usage summary
+#[derive(Debug, Serialize, Deserialize)]
+pub struct TTSCacheInfo {
+    pub entry_count: i64,
+    pub total_bytes: i64,
+    pub max_bytes: i64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow)]
+pub struct Tag {
+    pub id: String,
+    pub name: String,
+    pub created_at: i64,
+}
+
+/// Tag with the number of (non-trashed) entries using it
+#[derive(Debug, Serialize, Deserialize, FromRow)]
+pub struct TagUsage {
+    pub id: String,
+    pub name: String,
+    pub created_at: i64,
+    pub entry_count: i64,
+}
+
+/// Link between an entry and a tag
+#[derive(Debug, Serialize, Deserialize, FromRow)]
+pub struct EntryTag {
+    pub entry_id: String,
+    pub tag_id: String,
+    pub created_at: i64,
+}
+
+/// A snapshot of an entry's content taken before it was overwritten
+#[derive(Debug, Serialize, Deserialize, FromRow)]
+pub struct EntryRevision {
+    pub id: String,
+    pub entry_id: String,
+    pub entry_date: String,
+    pub content_json: String,
+    pub saved_at: i64,   // when this content was saved
+    pub created_at: i64, // when the snapshot was taken
+}
+
+/// Revision metadata for listing, without the content body
+#[derive(Debug, Serialize, Deserialize, FromRow)]
+pub struct EntryRevisionSummary {
+    pub id: String,
+    pub entry_id: String,
+    pub entry_date: String,
+    pub word_count: i64,
+    pub saved_at: i64,
+    pub created_at: i64,
+}
+
+/// A reusable starting point for entries
+#[derive(Debug, Serialize, Deserialize, FromRow)]
+pub struct Template {
+    pub id: String,
+    pub name: String,
+    /// ProseMirror JSON whose text may contain `{{placeholders}}`
+    pub content_json: String,
+    pub created_at: i64,
+    pub updated_at: i64,
+}
+
+/// Which template new days start from (stored in app_settings)
+#[derive(Debug, Serialize, Deserialize)]
+pub struct TemplateSettings {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub default_template_id: Option<String>,
+}
+
+/// A writing prompt, bundled or written by the user
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct WritingPrompt {
+    pub id: String,
+    pub text: String,
+    /// CEFR level (A1-C2)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub level: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub topic: Option<String>,
+    pub is_custom: bool,
+    pub is_favorite: bool,
+    /// Latest diary date an entry answered the prompt
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub last_answered_on: Option<String>,
+}
+
+/// Which prompts the daily prompt is picked from (stored in app_settings)
+#[derive(Debug, Serialize, Deserialize)]
+pub struct PromptSettings {
+    /// CEFR level (A1-C2); any level when unset
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub level: Option<String>,
+}
+
+/// When a diary day begins (stored in app_settings)
+#[derive(Debug, Serialize, Deserialize)]
+pub struct DaySettings {
+    /// Local hour (0-23) at which a new day starts; writing before it counts
+    /// towards the previous day
+    pub day_start_hour: u32,
+}
+
+/// Trash settings (stored in app_settings)
+#[derive(Debug, Serialize, Deserialize)]
+pub struct TrashSettings {
+    /// Days an entry stays in the trash before it is purged automatically
+    pub retention_days: i64,
+}
+
+// ===== Backup Types =====
+
+/// A database snapshot in the backups directory
+#[derive(Debug, Serialize, Deserialize)]
+pub struct BackupInfo {
+    pub file_name: String,
+    pub created_at: i64,
+    pub size_bytes: i64,
+    /// "snapshot" (rotated) or "pre_restore" (database set aside by a restore)
+    pub kind: String,
+}
+
+/// Backup retention settings (stored in app_settings)
+#[derive(Debug, Serialize, Deserialize)]
+pub struct BackupSettings {
+    /// Number of most recent days that keep their newest snapshot
+    pub keep_daily: i64,
+    /// Number of most recent ISO weeks that keep their newest snapshot
+    pub keep_weekly: i64,
+    /// Number of most recent pre-restore copies kept
+    pub keep_pre_restore: i64,
+}
+
+// ===== Encryption Types =====
+
+/// Whether diary content is encrypted at rest and currently readable
+#[derive(Debug, Serialize, Deserialize)]
+pub struct EncryptionStatus {
+    pub enabled: bool,
+    pub unlocked: bool,
+}
+
+// ===== App Lock Types =====
+
+/// Whether the app lock is on and currently engaged
+#[derive(Debug, Serialize, Deserialize)]
+pub struct LockStatus {
+    pub enabled: bool,
+    pub locked: bool,
+    /// Minutes of inactivity before the app locks itself, 0 = never
+    pub idle_minutes: i64,
+}
+
+/// App lock settings (stored in app_settings)
+#[derive(Debug, Serialize, Deserialize)]
+pub struct LockSettings {
+    /// Minutes of inactivity before the app locks itself, 0 = never
+    pub idle_minutes: i64,
+}
+
+// ===== Search Types =====
+
+/// A piece of a search snippet; `highlight` marks text matching the query
+#[derive(Debug, Serialize, Deserialize)]
+pub struct SnippetSegment {
+    pub text: String,
+    pub highlight: bool,
+}
+
+/// A single full-text search match
+#[derive(Debug, Serialize, Deserialize)]
+pub struct SearchHit {
+    pub entry_id: String,
+    pub entry_date: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub title: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub mood: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub mood_emoji: Option<String>,
+    pub snippet: Vec<SnippetSegment>,
+    pub score: f64, // higher is more relevant
+}
+
+/// One page of search hits
+#[derive(Debug, Serialize, Deserialize)]
+pub struct SearchResults {
+    pub hits: Vec<SearchHit>,
+    pub total: i64, // total number of hits across all pages
+    pub limit: i64,
+    pub offset: i64,
+}
+
 #[derive(Debug, Serialize, Deserialize)]
 pub struct WritingStats {
     pub total_entries: i64,
+    /// Days with at least one entry
+    pub total_days: i64,
     pub current_streak: i64,
     pub longest_streak: i64,
 }
 
+/// An earlier entry resurfaced for a date
+#[derive(Debug, Serialize, Deserialize)]
+pub struct OnThisDayEntry {
+    pub entry_id: String,
+    pub entry_date: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub entry_time: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub title: Option<String>,
+    /// Start of the entry's plain text
+    pub preview: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub mood: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub mood_emoji: Option<String>,
+    /// "year" for the same day in an earlier year, "month" or "week" for the lookbacks
+    pub kind: String,
+    /// Whole years before the requested date (0 for the lookbacks)
+    pub years_ago: i64,
+}
+
+/// Totals of one day for calendars and heatmaps, without entry content
+#[derive(Debug, Serialize, Deserialize)]
+pub struct CalendarDay {
+    pub date: String, // YYYY-MM-DD
+    pub has_entry: bool,
+    pub entry_count: i64,
+    pub word_count: i64,
+    /// Mood of the day's first entry with one
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub mood: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub mood_emoji: Option<String>,
+    pub has_audio: bool,
+    pub ai_ops_count: i64,
+}
+
+/// Word and character counts of one entry
+#[derive(Debug, Serialize, Deserialize)]
+pub struct EntryTextStats {
+    pub entry_id: String,
+    pub entry_date: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub title: Option<String>,
+    pub words: i64,
+    pub characters: i64,
+}
+
+/// Writing totals for a week ("2026-W10"), month ("2026-03") or year ("2026")
+#[derive(Debug, Serialize, Deserialize)]
+pub struct PeriodStats {
+    pub period: String,
+    pub entries: i64,
+    /// Days with at least one entry
+    pub days: i64,
+    pub words: i64,
+    pub characters: i64,
+    /// Average words per entry
+    pub average_words: f64,
+}
+
+/// Writing totals for a day of the week
+#[derive(Debug, Serialize, Deserialize)]
+pub struct WeekdayStats {
+    /// 0 = Monday ... 6 = Sunday
+    pub weekday: u32,
+    pub entries: i64,
+    pub words: i64,
+}
+
+/// How often an AI operation type was used
+#[derive(Debug, Serialize, Deserialize, FromRow)]
+pub struct AIUsage {
+    pub op_type: String,
+    pub count: i64,
+}
+
+/// Detailed writing statistics, counted from the entries' plain text
+#[derive(Debug, Serialize, Deserialize)]
+pub struct WritingAnalytics {
+    pub total_words: i64,
+    pub total_characters: i64,
+    /// Average words per entry
+    pub average_words: f64,
+    /// Every entry, oldest first
+    pub entries: Vec<EntryTextStats>,
+    /// Oldest first; periods without entries are left out
+    pub weeks: Vec<PeriodStats>,
+    pub months: Vec<PeriodStats>,
+    pub years: Vec<PeriodStats>,
+    /// All seven days, Monday first
+    pub weekdays: Vec<WeekdayStats>,
+    /// Most used first
+    pub ai_usage: Vec<AIUsage>,
+}
+
 // ===== Export/Import Types =====
 
 /// Export data structure containing all user data
@@ -42,6 +434,35 @@ pub struct ExportData {
     pub exported_at: i64,
     pub entries: Vec<DiaryEntry>,
     pub ai_operations: Vec<AIOperation>,
+    #[serde(default)]
+    pub tags: Vec<Tag>,
+    #[serde(default)]
+    pub entry_tags: Vec<EntryTag>,
+    #[serde(default)]
+    pub attachments: Vec<Attachment>,
+    #[serde(default)]
+    pub attachment_files: Vec<AttachmentFile>,
+}
+
+/// How a readable export is written
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ArchiveFormat {
+    /// A new (or empty) directory
+    Directory,
+    /// A single .zip file
+    Zip,
+}
+
+/// What a readable export wrote
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ExportSummary {
+    pub path: String,
+    pub entries: usize,
+    /// Attachment and audio files copied into the export
+    pub media_files: usize,
+    /// Media files that could not be read (e.g. missing on disk)
+    pub skipped_media: usize,
 }
 
 /// Import options
@@ -52,3 +473,42 @@ pub struct ImportOptions {
     /// Whether to import AI operations
     pub include_ai_operations: bool,
 }
+
+// ===== Diagnostics Types =====
+
+pub const STARTUP_DATABASE_UNAVAILABLE: &str = "database_unavailable";
+pub const STARTUP_MIGRATION_FAILED: &str = "migration_failed";
+
+/// Why the database could not be opened at startup, shown by the frontend
+/// in place of the app
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct StartupError {
+    /// "database_unavailable" or "migration_failed"
+    pub kind: String,
+    pub message: String,
+    /// Schema version the database was on before migrating
+    pub schema_version: Option<i64>,
+    /// Whether the database was restored from its pre-migration snapshot
+    pub rolled_back: bool,
+    /// Pre-migration snapshot kept in the backups directory
+    pub snapshot_path: Option<String>,
+}
+
+/// A row of `schema_migrations`
+#[derive(Debug, Serialize, Deserialize)]
+pub struct AppliedMigration {
+    pub version: i64,
+    /// None for migrations unknown to this build
+    pub description: Option<String>,
+    pub applied_at: i64,
+    pub checksum: Option<String>,
+}
+
+/// Database schema version, for diagnostics
+#[derive(Debug, Serialize, Deserialize)]
+pub struct SchemaInfo {
+    pub current_version: i64,
+    /// Version this build of the app migrates to
+    pub latest_version: i64,
+    pub applied: Vec<AppliedMigration>,
+}
@@ -0,0 +1,87 @@
+//! Writing prompts bundled with the app.
+//!
+//! Each prompt has a CEFR level and a topic so learners can pick prompts that
+//! fit their English. Prompts the user writes live in `custom_prompts`;
+//! favorites, skips and answers are stored per prompt id for both kinds.
+
+use chrono::NaiveDate;
+
+/// CEFR levels, easiest first
+pub const LEVELS: &[&str] = &["A1", "A2", "B1", "B2", "C1", "C2"];
+
+/// A prompt shipped with the app. Ids are stable: answers and favorites refer to them.
+pub struct BuiltinPrompt {
+    pub id: &'static str,
+    pub level: &'static str,
+    pub topic: &'static str,
+    pub text: &'static str,
+}
+
+const fn prompt(
+    id: &'static str,
+    level: &'static str,
+    topic: &'static str,
+    text: &'static str,
+) -> BuiltinPrompt {
+    BuiltinPrompt {
+        id,
+        level,
+        topic,
+        text,
+    }
+}
+
+pub const BUILTIN_PROMPTS: &[BuiltinPrompt] = &[
+    prompt("a1-daily-01", "A1", "daily life", "What did you eat for breakfast today?"),
+    prompt("a1-daily-02", "A1", "daily life", "Describe your bedroom in five sentences."),
+    prompt("a1-people-01", "A1", "people", "Write about a friend. What do they look like?"),
+    prompt("a1-home-01", "A1", "home", "What is your favorite room at home, and why?"),
+    prompt("a1-weather-01", "A1", "nature", "What is the weather like today? Do you like it?"),
+    prompt("a2-daily-01", "A2", "daily life", "Describe your morning routine from the moment you wake up."),
+    prompt("a2-food-01", "A2", "food", "Write about a meal you cooked or want to learn to cook."),
+    prompt("a2-travel-01", "A2", "travel", "Describe a place you visited last year."),
+    prompt("a2-hobbies-01", "A2", "hobbies", "What do you like to do on weekends?"),
+    prompt("a2-shopping-01", "A2", "daily life", "What was the last thing you bought? Why did you buy it?"),
+    prompt("a2-people-01", "A2", "people", "Write about someone in your family and something you did together."),
+    prompt("a2-memories-01", "A2", "memories", "What was your favorite toy or game as a child?"),
+    prompt("b1-work-01", "B1", "work", "Describe a problem you solved at work or school this week."),
+    prompt("b1-travel-01", "B1", "travel", "Plan your dream trip: where would you go, and what would you do each day?"),
+    prompt("b1-memories-01", "B1", "memories", "Tell the story of a day you will never forget."),
+    prompt("b1-goals-01", "B1", "future", "What is one skill you want to learn this year, and how will you practise it?"),
+    prompt("b1-culture-01", "B1", "culture", "Describe a festival or holiday from your country to someone who has never seen it."),
+    prompt("b1-media-01", "B1", "culture", "Review a film, series or book you finished recently."),
+    prompt("b1-health-01", "B1", "health", "What do you do to relax after a stressful day?"),
+    prompt("b1-feelings-01", "B1", "feelings", "Write about something that made you laugh recently."),
+    prompt("b1-city-01", "B1", "places", "What would you change about the town or city where you live?"),
+    prompt("b1-gratitude-01", "B1", "feelings", "List three things you are grateful for today and explain one of them."),
+    prompt("b2-opinion-01", "B2", "opinions", "Should people work four days a week? Argue for one side."),
+    prompt("b2-technology-01", "B2", "technology", "How has your phone changed the way you spend your free time?"),
+    prompt("b2-learning-01", "B2", "learning", "What has been the hardest part of learning English so far, and how are you dealing with it?"),
+    prompt("b2-decisions-01", "B2", "memories", "Describe a decision you regret, and what you would do differently now."),
+    prompt("b2-environment-01", "B2", "nature", "What small changes could you make to live more sustainably?"),
+    prompt("b2-people-01", "B2", "people", "Write about someone who changed the way you think."),
+    prompt("b2-hypothetical-01", "B2", "future", "If you could live in any period of history, which would you choose and why?"),
+    prompt("b2-work-01", "B2", "work", "Describe the perfect job. Is it realistic for you?"),
+    prompt("b2-conflict-01", "B2", "feelings", "Write about a disagreement you had and how it was resolved."),
+    prompt("c1-society-01", "C1", "opinions", "Is social media making public debate better or worse? Support your view with examples."),
+    prompt("c1-identity-01", "C1", "culture", "How does speaking another language change the way you express yourself?"),
+    prompt("c1-success-01", "C1", "opinions", "What does success mean to you, and how has that meaning changed over time?"),
+    prompt("c1-reflection-01", "C1", "feelings", "Describe a habit of thought you are trying to unlearn."),
+    prompt("c1-future-01", "C1", "future", "Write a letter to yourself ten years from now."),
+    prompt("c1-ethics-01", "C1", "opinions", "Is it ever right to break a promise? Discuss with a real or imagined example."),
+    prompt("c2-abstract-01", "C2", "opinions", "Can a person be truly objective? Explore the question from more than one angle."),
+    prompt("c2-language-01", "C2", "learning", "Choose a word that has no exact translation in your native language and explain its nuances."),
+    prompt("c2-memory-01", "C2", "memories", "How reliable are your memories? Write about one you have come to doubt."),
+];
+
+/// Rank of a prompt on `date`; the day's prompt is the candidate ranked lowest.
+/// A rank depends only on the date and the prompt, so a day keeps its prompt
+/// while others are added or ruled out, and consecutive dates are spread apart.
+pub fn daily_rank(date: NaiveDate, prompt_id: &str) -> u64 {
+    // FNV-1a, which unlike std's hasher is the same on every build and platform
+    format!("{}/{prompt_id}", date.format("%Y-%m-%d"))
+        .bytes()
+        .fold(0xcbf2_9ce4_8422_2325_u64, |hash, byte| {
+            (hash ^ u64::from(byte)).wrapping_mul(0x0000_0100_0000_01b3)
+        })
+}
@@ -0,0 +1,4 @@
+pub mod query;
+pub mod segment;
+
+pub use query::parse_query;
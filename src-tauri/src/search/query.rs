@@ -0,0 +1,286 @@
+//! Search query language.
+//!
+//! Supported syntax (terms are AND-ed together):
+//! - `word` / `"exact phrase"`: full-text match
+//! - `walk*`: prefix match
+//! - `-word`, `-"phrase"`, `-mood:sad`: negation
+//! - `mood:happy`, `tag:travel`, `tag:"road trip"`: metadata filters
+//! - `before:2026-03-01`, `after:2026-01-01`: entry date bounds (exclusive)
+//! - `has:audio`, `has:ai`, `has:tags`: entries with saved audio, AI operations or tags
+//!
+//! Everything the user types ends up inside quoted FTS5 strings or bound SQL
+//! parameters, so punctuation such as `don't` or `C++` can never cause a syntax error.
+
+use sqlx::{QueryBuilder, Sqlite};
+
+use super::segment::segment;
+use crate::error::AppError;
+
+/// A full-text term
+#[derive(Debug, Clone, PartialEq)]
+pub struct Term {
+    pub text: String,
+    pub prefix: bool,
+    pub negated: bool,
+}
+
+/// What a `has:` filter checks for
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum HasKind {
+    Audio,
+    Ai,
+    Tags,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum FilterKind {
+    Mood(String),
+    Tag(String),
+    Before(String),
+    After(String),
+    Has(HasKind),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Filter {
+    pub kind: FilterKind,
+    pub negated: bool,
+}
+
+/// A parsed search query
+#[derive(Debug, Default, PartialEq)]
+pub struct SearchQuery {
+    pub terms: Vec<Term>,
+    pub filters: Vec<Filter>,
+}
+
+impl SearchQuery {
+    pub fn is_empty(&self) -> bool {
+        self.terms.is_empty() && self.filters.is_empty()
+    }
+
+    fn positive_terms(&self) -> impl Iterator<Item = &Term> {
+        self.terms.iter().filter(|t| !t.negated)
+    }
+
+    fn negated_terms(&self) -> impl Iterator<Item = &Term> {
+        self.terms.iter().filter(|t| t.negated)
+    }
+
+    /// FTS5 expression for the positive terms (with negated terms attached via
+    /// `NOT`), or `None` when the query has no positive term to rank by
+    pub fn fts_expression(&self) -> Option<String> {
+        let positive: Vec<String> = self.positive_terms().map(fts_term).collect();
+        if positive.is_empty() {
+            return None;
+        }
+
+        let mut expr = positive.join(" AND ");
+        for term in self.negated_terms() {
+            expr = format!("({expr}) NOT {}", fts_term(term));
+        }
+        Some(expr)
+    }
+
+    /// Append SQL predicates (each preceded by ` AND `) for the filters, and for
+    /// negated terms when there is no FTS expression to attach them to.
+    /// Expects the entries table to be aliased as `e`; `fts_table` is the
+    /// (possibly schema-qualified) full-text table to match against.
+    pub fn push_predicates(&self, qb: &mut QueryBuilder<'_, Sqlite>, fts_table: &str) {
+        if self.fts_expression().is_none() {
+            for term in self.negated_terms() {
+                qb.push(format!(
+                    " AND e.id NOT IN (SELECT entry_id FROM {fts_table} WHERE entries_fts MATCH "
+                ));
+                qb.push_bind(fts_term(term));
+                qb.push(")");
+            }
+        }
+
+        for filter in &self.filters {
+            qb.push(if filter.negated { " AND NOT " } else { " AND " });
+            match &filter.kind {
+                FilterKind::Mood(mood) => {
+                    qb.push("COALESCE(e.mood = ");
+                    qb.push_bind(mood.clone());
+                    qb.push(" COLLATE NOCASE, 0)");
+                }
+                FilterKind::Tag(name) => {
+                    qb.push(
+                        "EXISTS (SELECT 1 FROM entry_tags et JOIN tags t ON t.id = et.tag_id
+                                 WHERE et.entry_id = e.id AND t.name = ",
+                    );
+                    qb.push_bind(name.clone());
+                    qb.push(")");
+                }
+                FilterKind::Before(date) => {
+                    qb.push("e.entry_date < ");
+                    qb.push_bind(date.clone());
+                }
+                FilterKind::After(date) => {
+                    qb.push("e.entry_date > ");
+                    qb.push_bind(date.clone());
+                }
+                FilterKind::Has(HasKind::Audio) => {
+                    qb.push("EXISTS (SELECT 1 FROM audio_records a WHERE a.entry_id = e.id)");
+                }
+                FilterKind::Has(HasKind::Ai) => {
+                    qb.push("EXISTS (SELECT 1 FROM ai_operations o WHERE o.entry_id = e.id)");
+                }
+                FilterKind::Has(HasKind::Tags) => {
+                    qb.push("EXISTS (SELECT 1 FROM entry_tags et WHERE et.entry_id = e.id)");
+                }
+            }
+        }
+    }
+}
+
+/// Quote a term as an FTS5 string so its content is never parsed as syntax.
+/// CJK text is segmented like the index, so a Chinese word becomes a phrase
+/// of adjacent characters.
+fn fts_term(term: &Term) -> String {
+    let quoted = format!("\"{}\"", segment(&term.text).replace('"', "\"\""));
+    if term.prefix {
+        format!("{quoted}*")
+    } else {
+        quoted
+    }
+}
+
+/// Parse a user-typed query
+pub fn parse_query(input: &str) -> Result<SearchQuery, AppError> {
+    let mut query = SearchQuery::default();
+    let mut chars = input.chars().peekable();
+
+    loop {
+        while chars.next_if(|c| c.is_whitespace()).is_some() {}
+        let Some(&first) = chars.peek() else {
+            break;
+        };
+
+        let negated = first == '-';
+        if negated {
+            chars.next();
+        }
+
+        // Quoted phrase
+        if chars.peek() == Some(&'"') {
+            chars.next();
+            let phrase = read_quoted(&mut chars)?;
+            let prefix = chars.next_if_eq(&'*').is_some();
+            push_term(&mut query, phrase, prefix, negated);
+            continue;
+        }
+
+        // Bare word, possibly `key:value`
+        let mut word = String::new();
+        while let Some(c) = chars.next_if(|c| !c.is_whitespace()) {
+            if c == ':' && is_filter_key(&word) {
+                let value = if chars.peek() == Some(&'"') {
+                    chars.next();
+                    read_quoted(&mut chars)?
+                } else {
+                    let mut value = String::new();
+                    while let Some(c) = chars.next_if(|c| !c.is_whitespace()) {
+                        value.push(c);
+                    }
+                    value
+                };
+                query.filters.push(Filter {
+                    kind: parse_filter(&word, value.trim())?,
+                    negated,
+                });
+                word.clear();
+                break;
+            }
+            word.push(c);
+        }
+
+        if !word.is_empty() {
+            let prefix = word.len() > 1 && word.ends_with('*');
+            let text = word.trim_end_matches('*').to_string();
+            push_term(&mut query, text, prefix, negated);
+        }
+    }
+
+    Ok(query)
+}
+
+fn read_quoted(chars: &mut std::iter::Peekable<std::str::Chars<'_>>) -> Result<String, AppError> {
+    let mut text = String::new();
+    for c in chars.by_ref() {
+        if c == '"' {
+            return Ok(text);
+        }
+        text.push(c);
+    }
+    Err(AppError::InvalidQuery(
+        "Missing closing quote (\") in search".to_string(),
+    ))
+}
+
+fn push_term(query: &mut SearchQuery, text: String, prefix: bool, negated: bool) {
+    // Terms without any letters or digits (e.g. "-", "++") match nothing in FTS5
+    if text.chars().any(char::is_alphanumeric) {
+        query.terms.push(Term {
+            text,
+            prefix,
+            negated,
+        });
+    }
+}
+
+fn is_filter_key(word: &str) -> bool {
+    matches!(
+        word.to_lowercase().as_str(),
+        "mood" | "tag" | "before" | "after" | "has"
+    )
+}
+
+fn parse_filter(key: &str, value: &str) -> Result<FilterKind, AppError> {
+    let key = key.to_lowercase();
+    if value.is_empty() {
+        return Err(AppError::InvalidQuery(format!(
+            "\"{key}:\" needs a value, e.g. {}",
+            filter_example(&key)
+        )));
+    }
+
+    match key.as_str() {
+        "mood" => Ok(FilterKind::Mood(value.to_string())),
+        "tag" => Ok(FilterKind::Tag(
+            value.trim_start_matches('#').trim().to_string(),
+        )),
+        "before" | "after" => {
+            chrono::NaiveDate::parse_from_str(value, "%Y-%m-%d").map_err(|_| {
+                AppError::InvalidQuery(format!(
+                    "\"{key}:{value}\" is not a valid date, use YYYY-MM-DD (e.g. {})",
+                    filter_example(&key)
+                ))
+            })?;
+            Ok(if key == "before" {
+                FilterKind::Before(value.to_string())
+            } else {
+                FilterKind::After(value.to_string())
+            })
+        }
+        _ => match value.to_lowercase().as_str() {
+            "audio" => Ok(FilterKind::Has(HasKind::Audio)),
+            "ai" => Ok(FilterKind::Has(HasKind::Ai)),
+            "tag" | "tags" => Ok(FilterKind::Has(HasKind::Tags)),
+            _ => Err(AppError::InvalidQuery(format!(
+                "Unknown \"has:{value}\", try has:audio, has:ai or has:tags"
+            ))),
+        },
+    }
+}
+
+fn filter_example(key: &str) -> &'static str {
+    match key {
+        "mood" => "mood:happy",
+        "tag" => "tag:travel",
+        "before" => "before:2026-03-01",
+        "after" => "after:2026-01-01",
+        _ => "has:audio",
+    }
+}
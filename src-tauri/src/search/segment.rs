@@ -0,0 +1,43 @@
+//! CJK segmentation for the full-text index.
+//!
+//! FTS5's unicode61 tokenizer keeps a run of Chinese characters together as a
+//! single token, so a word inside a sentence can never match. Before indexing
+//! (and before matching), every CJK character is wrapped in [`SEPARATOR`],
+//! which `entries_fts` is configured to treat as a token separator. Each CJK
+//! character then becomes its own token and a multi-character word becomes a
+//! phrase of adjacent tokens, which matches any substring of the text.
+
+/// Private-use character declared as a separator in the `entries_fts` tokenizer
+pub const SEPARATOR: char = '\u{E002}';
+
+/// Whether `c` is written without spaces between words (Han, kana, Hangul)
+pub fn is_cjk(c: char) -> bool {
+    matches!(c,
+        '\u{3040}'..='\u{30FF}'     // Hiragana, Katakana
+        | '\u{3400}'..='\u{4DBF}'   // CJK Extension A
+        | '\u{4E00}'..='\u{9FFF}'   // CJK Unified Ideographs
+        | '\u{AC00}'..='\u{D7AF}'   // Hangul Syllables
+        | '\u{F900}'..='\u{FAFF}'   // CJK Compatibility Ideographs
+        | '\u{20000}'..='\u{2FA1F}' // Extensions B-F, Compatibility Supplement
+    )
+}
+
+/// Surround every CJK character with [`SEPARATOR`] so it is indexed as its own token
+pub fn segment(text: &str) -> String {
+    let mut out = String::with_capacity(text.len() * 2);
+    for c in text.chars() {
+        if is_cjk(c) {
+            out.push(SEPARATOR);
+            out.push(c);
+            out.push(SEPARATOR);
+        } else {
+            out.push(c);
+        }
+    }
+    out
+}
+
+/// Undo [`segment`] on text read back from the index (e.g. snippets)
+pub fn desegment(text: &str) -> String {
+    text.chars().filter(|&c| c != SEPARATOR).collect()
+}
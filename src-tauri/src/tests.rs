@@ -0,0 +1,111 @@
+use tauri::Manager;
+
+use crate::app_lock::AppLock;
+use crate::db::{migrations, queries};
+use crate::error::AppError;
+use crate::models::{BackupSettings, DaySettings, PromptSettings, TemplateSettings, TrashSettings};
+
+fn assert_locked(result: Result<impl std::fmt::Debug, AppError>) {
+    assert!(
+        matches!(result, Err(AppError::Locked)),
+        "expected Locked, got {result:?}"
+    );
+}
+
+#[tokio::test]
+async fn locked_app_rejects_encryption_backup_and_settings_commands() {
+    let pool = sqlx::SqlitePool::connect("sqlite::memory:")
+        .await
+        .expect("connect");
+    migrations::run(&pool).await.expect("migrate");
+
+    let app = tauri::test::mock_app();
+    app.manage(pool.clone());
+    app.manage(AppLock::new(true, 5));
+    let passphrase = "correct horse battery".to_string();
+
+    assert_locked(
+        super::enable_encryption(
+            passphrase.clone(),
+            app.handle().clone(),
+            app.state(),
+            app.state(),
+        )
+        .await,
+    );
+    assert_locked(
+        super::unlock_journal(
+            passphrase.clone(),
+            app.handle().clone(),
+            app.state(),
+            app.state(),
+        )
+        .await,
+    );
+    assert_locked(super::lock_journal(app.state(), app.state()).await);
+    assert_locked(
+        super::change_passphrase(passphrase.clone(), passphrase, app.state(), app.state()).await,
+    );
+    assert_locked(super::list_backups(app.handle().clone(), app.state()).await);
+    assert_locked(super::create_backup(app.handle().clone(), app.state(), app.state()).await);
+    assert_locked(
+        super::save_backup_settings(
+            BackupSettings {
+                keep_daily: 1,
+                keep_weekly: 1,
+                keep_pre_restore: 1,
+            },
+            app.state(),
+            app.state(),
+        )
+        .await,
+    );
+    assert_locked(
+        super::save_trash_settings(
+            TrashSettings { retention_days: 1 },
+            app.state(),
+            app.state(),
+        )
+        .await,
+    );
+    assert_locked(
+        super::save_day_settings(DaySettings { day_start_hour: 4 }, app.state(), app.state()).await,
+    );
+    assert_locked(
+        super::save_template_settings(
+            TemplateSettings {
+                default_template_id: None,
+            },
+            app.state(),
+            app.state(),
+        )
+        .await,
+    );
+    assert_locked(
+        super::save_prompt_settings(
+            PromptSettings {
+                level: Some("B1".to_string()),
+            },
+            app.state(),
+            app.state(),
+        )
+        .await,
+    );
+
+    // Nothing was changed behind the lock
+    assert!(!crate::crypto::is_enabled(&pool).await.expect("status"));
+    assert_ne!(
+        queries::get_trash_settings(&pool)
+            .await
+            .expect("trash settings")
+            .retention_days,
+        1
+    );
+    assert_eq!(
+        queries::get_prompt_settings(&pool)
+            .await
+            .expect("prompt settings")
+            .level,
+        None
+    );
+}
@@ -0,0 +1,190 @@
+use std::path::PathBuf;
+
+use sqlx::SqlitePool;
+
+use super::provider::TTSResponse;
+use super::storage;
+use crate::crypto::Vault;
+use crate::db::queries;
+use crate::error::AppError;
+use crate::models::{TTSCacheEntry, TTSCacheInfo};
+
+/// Directory (relative to the app data dir) holding cached clips
+pub const CACHE_DIR: &str = "tts_cache";
+
+/// Default cache size cap
+pub const DEFAULT_MAX_CACHE_BYTES: i64 = 200 * 1024 * 1024;
+
+const MAX_CACHE_BYTES_SETTING: &str = "tts_cache_max_bytes";
+
+/// Everything that influences the synthesized audio
+pub struct CacheKeyParts<'a> {
+    pub provider: &'a str,
+    pub model: &'a str,
+    pub voice: &'a str,
+    pub speed: f32,
+    pub language: Option<&'a str>,
+    pub format: &'a str,
+    pub text: &'a str,
+}
+
+/// Collapse whitespace so trivially different selections hit the same clip
+pub fn normalize_text(text: &str) -> String {
+    text.split_whitespace().collect::<Vec<_>>().join(" ")
+}
+
+/// Stable content hash identifying a synthesized clip. Keyed with the data key
+/// while encryption is on (see [`Vault::fingerprint`]).
+pub fn cache_key(parts: &CacheKeyParts<'_>, vault: &Vault) -> Result<String, AppError> {
+    let mut data = Vec::new();
+    for field in [
+        parts.provider,
+        parts.model,
+        &parts.voice.to_lowercase(),
+        &format!("{:.2}", parts.speed),
+        &parts.language.unwrap_or("").to_lowercase(),
+        parts.format,
+        &normalize_text(parts.text),
+    ] {
+        data.extend_from_slice(field.as_bytes());
+        // Unit separator keeps ("ab", "c") and ("a", "bc") apart
+        data.push(0x1f);
+    }
+
+    vault.fingerprint(&data)
+}
+
+/// Content-addressed cache of synthesized audio with LRU eviction
+pub struct TTSCache<'a> {
+    pool: &'a SqlitePool,
+    app_data_dir: PathBuf,
+}
+
+impl<'a> TTSCache<'a> {
+    pub fn new(pool: &'a SqlitePool, app_data_dir: PathBuf) -> Self {
+        Self { pool, app_data_dir }
+    }
+
+    /// Look up a clip, returning a response carrying its bytes and marked as cached
+    pub async fn get(&self, key: &str) -> Result<Option<TTSResponse>, AppError> {
+        let Some(entry) = queries::get_tts_cache_entry(self.pool, key).await? else {
+            return Ok(None);
+        };
+
+        let path = storage::resolve_audio_path(&self.app_data_dir, &entry.relpath)?;
+        let bytes = match std::fs::read(&path) {
+            Ok(bytes) => bytes,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => {
+                // File was removed behind our back; forget the stale index row
+                queries::delete_tts_cache_entry(self.pool, key).await?;
+                return Ok(None);
+            }
+            Err(e) => return Err(e.into()),
+        };
+        let bytes = Vault::of(self.pool).open_bytes(bytes)?;
+
+        queries::touch_tts_cache_entry(self.pool, key, chrono::Utc::now().timestamp_millis())
+            .await?;
+
+        Ok(Some(TTSResponse {
+            audio_bytes: Some(bytes),
+            audio_file: None,
+            audio_base64: None,
+            format: entry.format,
+            duration_ms: entry.duration_ms.map(|ms| ms as u32),
+            provider: entry.provider,
+            model: entry.model,
+            voice: entry.voice,
+            record_id: None,
+            cached: true,
+        }))
+    }
+
+    /// Store a freshly synthesized clip, then evict old clips over the size cap
+    pub async fn put(&self, key: &str, response: &TTSResponse) -> Result<(), AppError> {
+        let Some(bytes) = response.audio_bytes.as_deref() else {
+            return Ok(());
+        };
+
+        let relpath = format!("{CACHE_DIR}/{key}.{}", response.format);
+        let sealed = Vault::of(self.pool).seal_bytes(bytes.to_vec())?;
+        storage::write_audio(&self.app_data_dir, &relpath, &sealed)?;
+
+        let now = chrono::Utc::now().timestamp_millis();
+        queries::upsert_tts_cache_entry(
+            self.pool,
+            &TTSCacheEntry {
+                cache_key: key.to_string(),
+                relpath,
+                format: response.format.clone(),
+                provider: response.provider.clone(),
+                model: response.model.clone(),
+                voice: response.voice.clone(),
+                duration_ms: response.duration_ms.map(i64::from),
+                size_bytes: bytes.len() as i64,
+                created_at: now,
+                last_used_at: now,
+            },
+        )
+        .await?;
+
+        self.enforce_limit().await
+    }
+
+    /// Evict least recently used clips until the cache fits its size cap
+    pub async fn enforce_limit(&self) -> Result<(), AppError> {
+        let max_bytes = self.max_bytes().await?;
+        let mut total = queries::tts_cache_total_bytes(self.pool).await?;
+        if total <= max_bytes {
+            return Ok(());
+        }
+
+        for entry in queries::list_tts_cache_entries_lru(self.pool).await? {
+            if total <= max_bytes {
+                break;
+            }
+            storage::remove_audio(&self.app_data_dir, &entry.relpath)?;
+            queries::delete_tts_cache_entry(self.pool, &entry.cache_key).await?;
+            total -= entry.size_bytes;
+        }
+
+        Ok(())
+    }
+
+    /// Remove every cached clip, returning the number of bytes freed
+    pub async fn clear(&self) -> Result<i64, AppError> {
+        let mut freed = 0;
+        for entry in queries::list_tts_cache_entries_lru(self.pool).await? {
+            storage::remove_audio(&self.app_data_dir, &entry.relpath)?;
+            queries::delete_tts_cache_entry(self.pool, &entry.cache_key).await?;
+            freed += entry.size_bytes;
+        }
+        Ok(freed)
+    }
+
+    pub async fn info(&self) -> Result<TTSCacheInfo, AppError> {
+        Ok(TTSCacheInfo {
+            entry_count: queries::tts_cache_entry_count(self.pool).await?,
+            total_bytes: queries::tts_cache_total_bytes(self.pool).await?,
+            max_bytes: self.max_bytes().await?,
+        })
+    }
+
+    pub async fn max_bytes(&self) -> Result<i64, AppError> {
+        Ok(queries::get_setting(self.pool, MAX_CACHE_BYTES_SETTING)
+            .await?
+            .and_then(|value| value.parse::<i64>().ok())
+            .unwrap_or(DEFAULT_MAX_CACHE_BYTES))
+    }
+
+    /// Change the size cap and evict immediately if the cache is now over it
+    pub async fn set_max_bytes(&self, max_bytes: i64) -> Result<(), AppError> {
+        if max_bytes < 0 {
+            return Err(AppError::InvalidSettings(format!(
+                "TTS cache size cannot be negative, got {max_bytes}"
+            )));
+        }
+        queries::save_setting(self.pool, MAX_CACHE_BYTES_SETTING, &max_bytes.to_string()).await?;
+        self.enforce_limit().await
+    }
+}
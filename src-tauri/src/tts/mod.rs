@@ -1,8 +1,10 @@
 use std::sync::Arc;
 
+pub mod cache;
 pub mod murf;
 pub mod provider;
 pub mod qwen;
+pub mod storage;
 
 pub use murf::MurfTTSProvider;
 pub use provider::{
@@ -72,3 +74,6 @@ pub async fn get_provider(
 pub fn get_provider_no_auth(provider_type: TTSProviderType) -> Arc<dyn TTSProvider> {
     create_provider(provider_type, None)
 }
+
+#[cfg(test)]
+mod tests;
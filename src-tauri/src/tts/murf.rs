@@ -252,6 +252,8 @@ impl TTSProvider for MurfTTSProvider {
                 provider: self.provider_name().to_string(),
                 model: self.default_model().to_string(),
                 voice: voice_id,
+                record_id: None, // Will be set by Tauri command
+                cached: false,
             })
         } else {
             eprintln!("TTS: ERROR - No audio data available from Murf!");
@@ -10,6 +10,16 @@ pub enum TTSOutputFormat {
     Ogg,
 }
 
+impl TTSOutputFormat {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Mp3 => "mp3",
+            Self::Wav => "wav",
+            Self::Ogg => "ogg",
+        }
+    }
+}
+
 /// TTS request
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct TTSRequest {
@@ -36,6 +46,10 @@ pub struct TTSResponse {
     pub provider: String,
     pub model: String,
     pub voice: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub record_id: Option<String>, // Saved audio record (set by Tauri command)
+    #[serde(default)]
+    pub cached: bool, // Served without calling the provider
 }
 
 /// Voice information
@@ -234,6 +234,8 @@ impl TTSProvider for QwenTTSProvider {
                 provider: self.provider_name().to_string(),
                 model: self.default_model().to_string(),
                 voice: voice.clone(),
+                record_id: None, // Will be set by Tauri command
+                cached: false,
             })
         } else {
             eprintln!("TTS: ERROR - No audio data available!");
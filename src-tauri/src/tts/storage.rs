@@ -0,0 +1,65 @@
+use std::path::{Path, PathBuf};
+
+use super::cache::CACHE_DIR;
+
+/// Directory (relative to the app data dir) holding all persisted audio
+pub const AUDIO_DIR: &str = "audio";
+
+/// Relative path of an audio clip: `audio/{entry_id}/{audio_id}.{format}`
+pub fn audio_relpath(entry_id: &str, audio_id: &str, format: &str) -> String {
+    format!("{AUDIO_DIR}/{entry_id}/{audio_id}.{format}")
+}
+
+/// Resolve a stored relative path against the app data dir.
+/// Rejects absolute paths, `..` components and anything not inside the audio
+/// or cache dir, so a tampered record can't reach other files.
+pub fn resolve_audio_path(app_data_dir: &Path, relpath: &str) -> std::io::Result<PathBuf> {
+    let relative = Path::new(relpath);
+    let mut components = relative.components();
+    let in_audio_dir = components
+        .next()
+        .is_some_and(|c| c.as_os_str() == AUDIO_DIR || c.as_os_str() == CACHE_DIR);
+    let is_safe = in_audio_dir
+        && components.next().is_some()
+        && relative
+            .components()
+            .all(|c| matches!(c, std::path::Component::Normal(_)));
+    if !is_safe {
+        return Err(std::io::Error::new(
+            std::io::ErrorKind::InvalidInput,
+            format!("Invalid audio path: {relpath}"),
+        ));
+    }
+    Ok(app_data_dir.join(relative))
+}
+
+/// Write audio bytes to their relative path, creating parent directories
+pub fn write_audio(app_data_dir: &Path, relpath: &str, bytes: &[u8]) -> std::io::Result<PathBuf> {
+    let path = resolve_audio_path(app_data_dir, relpath)?;
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent)?;
+    }
+    // Write then rename so a crash never leaves a truncated clip a record points at
+    let partial = path.with_extension("partial");
+    std::fs::write(&partial, bytes)?;
+    std::fs::rename(&partial, &path)?;
+    Ok(path)
+}
+
+/// Remove a single audio file; a missing file is not an error
+pub fn remove_audio(app_data_dir: &Path, relpath: &str) -> std::io::Result<()> {
+    let path = resolve_audio_path(app_data_dir, relpath)?;
+    match std::fs::remove_file(path) {
+        Err(e) if e.kind() != std::io::ErrorKind::NotFound => Err(e),
+        _ => Ok(()),
+    }
+}
+
+/// Remove every audio file belonging to an entry
+pub fn remove_entry_audio(app_data_dir: &Path, entry_id: &str) -> std::io::Result<()> {
+    let dir = resolve_audio_path(app_data_dir, &format!("{AUDIO_DIR}/{entry_id}"))?;
+    match std::fs::remove_dir_all(dir) {
+        Err(e) if e.kind() != std::io::ErrorKind::NotFound => Err(e),
+        _ => Ok(()),
+    }
+}
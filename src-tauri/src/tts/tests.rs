@@ -0,0 +1,95 @@
+use super::storage::{audio_relpath, remove_audio, resolve_audio_path, write_audio};
+use crate::db::{migrations, queries};
+use crate::models::AudioRecord;
+
+#[test]
+fn audio_paths_stay_inside_the_audio_dirs() {
+    let dir = std::path::Path::new("/data/echo-daily");
+
+    assert_eq!(
+        resolve_audio_path(dir, &audio_relpath("e1", "clip", "mp3")).expect("audio path"),
+        dir.join("audio/e1/clip.mp3")
+    );
+    assert_eq!(
+        resolve_audio_path(dir, "tts_cache/abc.mp3").expect("cache path"),
+        dir.join("tts_cache/abc.mp3")
+    );
+
+    for relpath in [
+        "../secret.mp3",
+        "audio/../../secret.mp3",
+        "audio/e1/../../echo-daily.db",
+        "./audio/clip.mp3",
+        "/etc/passwd",
+        "/data/echo-daily/audio/clip.mp3",
+        "echo-daily.db",
+        "attachments/ab/abcdef",
+        "audio",
+        "",
+    ] {
+        assert!(
+            resolve_audio_path(dir, relpath).is_err(),
+            "{relpath} should be rejected"
+        );
+    }
+}
+
+#[tokio::test]
+async fn audio_records_round_trip_with_their_files() {
+    let dir = std::env::temp_dir().join(format!("echo-daily-audio-{}", uuid::Uuid::new_v4()));
+    let pool = sqlx::SqlitePool::connect("sqlite::memory:")
+        .await
+        .expect("connect");
+    migrations::run(&pool).await.expect("migrate");
+    let entry = queries::upsert_entry(&pool, "2026-04-02", "read me aloud")
+        .await
+        .expect("upsert");
+
+    let relpath = audio_relpath(&entry.id, "clip", "mp3");
+    let path = write_audio(&dir, &relpath, b"ID3 clip").expect("write audio");
+    assert!(path.starts_with(dir.join("audio")));
+    assert!(!path.with_extension("partial").exists());
+    let record = AudioRecord {
+        id: "clip".to_string(),
+        entry_id: entry.id.clone(),
+        text: "read me aloud".to_string(),
+        audio_relpath: relpath,
+        format: "mp3".to_string(),
+        provider: "qwen".to_string(),
+        model: "qwen-tts".to_string(),
+        voice: Some("cherry".to_string()),
+        speed: Some(1.0),
+        duration_ms: Some(1200),
+        size_bytes: 8,
+        created_at: 1,
+        cache_key: None,
+    };
+    queries::create_audio_record(&pool, &record)
+        .await
+        .expect("create");
+
+    let listed = queries::list_audio_records(&pool, &entry.id)
+        .await
+        .expect("list");
+    assert_eq!(listed.len(), 1);
+    assert_eq!(listed[0].text, "read me aloud");
+    let stored = resolve_audio_path(&dir, &listed[0].audio_relpath).expect("resolve");
+    assert_eq!(std::fs::read(&stored).expect("read"), b"ID3 clip");
+
+    remove_audio(&dir, &listed[0].audio_relpath).expect("remove file");
+    assert!(queries::delete_audio_record(&pool, "clip")
+        .await
+        .expect("delete"));
+    assert!(!stored.exists());
+    assert!(queries::list_audio_records(&pool, &entry.id)
+        .await
+        .expect("list")
+        .is_empty());
+    // Deleting twice finds nothing, and a missing file is not an error
+    assert!(!queries::delete_audio_record(&pool, "clip")
+        .await
+        .expect("delete"));
+    remove_audio(&dir, &record.audio_relpath).expect("remove missing file");
+
+    std::fs::remove_dir_all(&dir).ok();
+}
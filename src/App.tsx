@@ -1,17 +1,117 @@
-import { useEffect } from 'react'
+import { useEffect, useRef, useState } from 'react'
 import { Header } from './components/Header'
 import { Sidebar } from './components/Sidebar'
 import { Editor } from './components/Editor'
 import { SaveIndicator } from './components/SaveIndicator'
-import { initDb } from './lib/api'
+import { StartupErrorScreen } from './components/StartupErrorScreen'
+import { UnlockScreen } from './components/UnlockScreen'
+import {
+  initDb,
+  getStartupError,
+  getEncryptionStatus,
+  unlockJournal,
+  getLockStatus,
+  recordActivity,
+  unlockApp,
+  getJournalToday,
+} from './lib/api'
+import { useAppStore } from './store/useAppStore'
+import type { EncryptionStatus, LockStatus, StartupError } from './types'
 import './App.css'
 
+// How often today's diary date is re-checked, so the app follows the day change
+const TODAY_POLL_MS = 60_000
+
+// How often the lock state is polled and user activity reported
+const LOCK_POLL_MS = 15_000
+const ACTIVITY_THROTTLE_MS = 30_000
+
 function App() {
+  const [startupError, setStartupError] = useState<StartupError | null>(null)
+  const [encryption, setEncryption] = useState<EncryptionStatus | null>(null)
+  const [appLocked, setAppLocked] = useState<boolean | null>(null)
+  const lastActivity = useRef(0)
+  const setToday = useAppStore((state) => state.setToday)
+
   // Initialize the database
   useEffect(() => {
     initDb().catch(console.error)
+    getStartupError().then(setStartupError).catch(console.error)
+    getEncryptionStatus()
+      .then(setEncryption)
+      .catch((error) => {
+        console.error(error)
+        setEncryption({ enabled: false, unlocked: true })
+      })
+  }, [])
+
+  // Track today's diary date in local time and the "day starts at" hour
+  useEffect(() => {
+    const poll = () => getJournalToday().then(setToday).catch(console.error)
+    poll()
+    const timer = setInterval(poll, TODAY_POLL_MS)
+    return () => clearInterval(timer)
+  }, [setToday])
+
+  // Follow the app lock, which the backend engages after inactivity
+  useEffect(() => {
+    const poll = () =>
+      getLockStatus()
+        .then((status: LockStatus) => setAppLocked(status.locked))
+        .catch((error) => {
+          console.error(error)
+          // Without a status, stay behind the lock screen
+          setAppLocked((locked) => locked ?? true)
+        })
+    poll()
+    const timer = setInterval(poll, LOCK_POLL_MS)
+
+    const onActivity = () => {
+      const now = Date.now()
+      if (now - lastActivity.current < ACTIVITY_THROTTLE_MS) return
+      lastActivity.current = now
+      recordActivity().catch(() => setAppLocked(true))
+    }
+    window.addEventListener('keydown', onActivity)
+    window.addEventListener('mousedown', onActivity)
+
+    return () => {
+      clearInterval(timer)
+      window.removeEventListener('keydown', onActivity)
+      window.removeEventListener('mousedown', onActivity)
+    }
   }, [])
 
+  if (startupError) {
+    return <StartupErrorScreen error={startupError} />
+  }
+
+  // Wait for the lock state so nothing loads entries before the journal is unlocked.
+  // The app lock comes first: the backend refuses to unlock the journal behind it.
+  if (!encryption || appLocked === null) {
+    return null
+  }
+
+  if (appLocked) {
+    return (
+      <UnlockScreen
+        title="Echo Daily is locked"
+        unlock={(passphrase) => unlockApp(passphrase)}
+        onUnlocked={() => setAppLocked(false)}
+      />
+    )
+  }
+
+  if (encryption.enabled && !encryption.unlocked) {
+    return (
+      <UnlockScreen
+        title="Your journal is locked"
+        unlock={unlockJournal}
+        onUnlocked={() => setEncryption({ ...encryption, unlocked: true })}
+      />
+    )
+  }
+
   return (
     <div className="h-screen flex flex-col paper-texture bg-paper-bg">
       <Header />
@@ -0,0 +1,206 @@
+import { useCallback, useEffect, useRef, useState } from 'react'
+import { open } from '@tauri-apps/plugin-dialog'
+import { convertFileSrc } from '@tauri-apps/api/core'
+import { FileText, Loader2, Paperclip, X } from 'lucide-react'
+import {
+  addAttachment,
+  listAttachments,
+  pasteAttachment,
+  removeAttachment,
+  resolveAttachment,
+} from '../lib/api'
+import type { Attachment } from '../types'
+
+interface Props {
+  entryDate: string
+  entryId: string
+}
+
+function formatSize(bytes: number): string {
+  if (bytes < 1024) return `${bytes} B`
+  if (bytes < 1024 * 1024) return `${(bytes / 1024).toFixed(0)} KB`
+  return `${(bytes / (1024 * 1024)).toFixed(1)} MB`
+}
+
+// Read a pasted file as base64 without the data URL prefix
+function readAsBase64(file: File): Promise<string> {
+  return new Promise((resolve, reject) => {
+    const reader = new FileReader()
+    reader.onload = () => resolve(String(reader.result).split(',', 2)[1] ?? '')
+    reader.onerror = () => reject(reader.error)
+    reader.readAsDataURL(file)
+  })
+}
+
+export function Attachments({ entryDate, entryId }: Props) {
+  const [attachments, setAttachments] = useState<Attachment[]>([])
+  const [thumbnails, setThumbnails] = useState<Record<string, string>>({})
+  const [isAdding, setIsAdding] = useState(false)
+  const [error, setError] = useState<string | null>(null)
+  const requestedThumbnails = useRef(new Set<string>())
+
+  useEffect(() => {
+    let cancelled = false
+    setThumbnails({})
+    requestedThumbnails.current.clear()
+    listAttachments(entryDate, entryId)
+      .then((list) => {
+        if (!cancelled) setAttachments(list)
+      })
+      .catch((err) => console.error('Failed to load attachments:', err))
+    return () => {
+      cancelled = true
+    }
+  }, [entryDate, entryId])
+
+  // Thumbnails are loaded lazily, once per attachment
+  useEffect(() => {
+    for (const attachment of attachments) {
+      if (!attachment.has_thumbnail || requestedThumbnails.current.has(attachment.id)) continue
+      requestedThumbnails.current.add(attachment.id)
+      resolveAttachment(attachment.id, true)
+        .then((content) => {
+          if (!content.data_base64) return
+          const src = `data:${content.mime_type};base64,${content.data_base64}`
+          setThumbnails((prev) => ({ ...prev, [attachment.id]: src }))
+        })
+        .catch((err) => console.error('Failed to load thumbnail:', err))
+    }
+  }, [attachments])
+
+  const appendAttachment = useCallback((attachment: Attachment) => {
+    setAttachments((prev) => [...prev, attachment])
+  }, [])
+
+  const handleAdd = async () => {
+    setError(null)
+    const selected = await open({ multiple: true })
+    if (!selected) return
+    const paths = Array.isArray(selected) ? selected : [selected]
+
+    setIsAdding(true)
+    try {
+      for (const path of paths) {
+        appendAttachment(await addAttachment(entryDate, path, entryId))
+      }
+    } catch (err) {
+      setError(String(err))
+    } finally {
+      setIsAdding(false)
+    }
+  }
+
+  // Pasted files (e.g. screenshots) are attached to the current entry
+  const handlePaste = useCallback(
+    async (event: ClipboardEvent) => {
+      const files = Array.from(event.clipboardData?.files ?? [])
+      if (files.length === 0) return
+      event.preventDefault()
+
+      setError(null)
+      setIsAdding(true)
+      try {
+        for (const file of files) {
+          const name = file.name || `pasted-${Date.now()}.${file.type.split('/')[1] || 'bin'}`
+          const attachment = await pasteAttachment(
+            entryDate,
+            name,
+            await readAsBase64(file),
+            entryId
+          )
+          appendAttachment(attachment)
+        }
+      } catch (err) {
+        setError(String(err))
+      } finally {
+        setIsAdding(false)
+      }
+    },
+    [appendAttachment, entryDate, entryId]
+  )
+
+  useEffect(() => {
+    const listener = (event: ClipboardEvent) => void handlePaste(event)
+    document.addEventListener('paste', listener)
+    return () => document.removeEventListener('paste', listener)
+  }, [handlePaste])
+
+  const handleOpen = async (attachment: Attachment) => {
+    try {
+      const content = await resolveAttachment(attachment.id, false)
+      const src = content.data_base64
+        ? `data:${content.mime_type};base64,${content.data_base64}`
+        : content.file_path && convertFileSrc(content.file_path)
+      if (src) window.open(src, '_blank')
+    } catch (err) {
+      setError(String(err))
+    }
+  }
+
+  const handleRemove = async (attachment: Attachment) => {
+    try {
+      await removeAttachment(attachment.id)
+      setAttachments((prev) => prev.filter((a) => a.id !== attachment.id))
+    } catch (err) {
+      setError(String(err))
+    }
+  }
+
+  return (
+    <div className="px-6 pb-6">
+      {attachments.length > 0 && (
+        <div className="flex flex-wrap gap-3 mb-3">
+          {attachments.map((attachment) => (
+            <div
+              key={attachment.id}
+              className="group relative rounded-lg border border-stone-200 bg-white/60 overflow-hidden"
+            >
+              <button
+                onClick={() => void handleOpen(attachment)}
+                className="block"
+                title={`${attachment.file_name} (${formatSize(attachment.size_bytes)})`}
+              >
+                {thumbnails[attachment.id] ? (
+                  <img
+                    src={thumbnails[attachment.id]}
+                    alt={attachment.file_name}
+                    className="h-24 w-auto max-w-[12rem] object-cover"
+                  />
+                ) : (
+                  <div className="h-24 w-32 flex flex-col items-center justify-center gap-1 px-2 text-stone-500">
+                    <FileText className="w-6 h-6" />
+                    <span className="text-xs truncate max-w-full">{attachment.file_name}</span>
+                  </div>
+                )}
+              </button>
+              <button
+                onClick={() => void handleRemove(attachment)}
+                className="absolute top-1 right-1 p-0.5 rounded-full bg-white/90 text-stone-500 opacity-0 group-hover:opacity-100 hover:text-red-600 transition-opacity"
+                title="Remove attachment"
+              >
+                <X className="w-3.5 h-3.5" />
+              </button>
+            </div>
+          ))}
+        </div>
+      )}
+
+      <div className="flex items-center gap-3">
+        <button
+          onClick={() => void handleAdd()}
+          disabled={isAdding}
+          className="flex items-center gap-1.5 text-sm text-stone-500 hover:text-stone-700 transition-colors disabled:opacity-50"
+          title="Attach files (or paste an image)"
+        >
+          {isAdding ? (
+            <Loader2 className="w-4 h-4 animate-spin" />
+          ) : (
+            <Paperclip className="w-4 h-4" />
+          )}
+          Attach
+        </button>
+        {error && <span className="text-xs text-red-600">{error}</span>}
+      </div>
+    </div>
+  )
+}
@@ -0,0 +1,138 @@
+import { useEffect, useState } from 'react'
+import { Lightbulb, PenLine, SkipForward, Star } from 'lucide-react'
+import {
+  getDailyPrompt,
+  setEntryPrompt,
+  setPromptFavorite,
+  skipPrompt,
+  upsertEntry,
+} from '../lib/api'
+import type { DiaryEntry, WritingPrompt } from '../types'
+import { PromptsDialog } from './PromptsDialog'
+
+interface DailyPromptProps {
+  date: string
+  onPromptChange?: (prompt: WritingPrompt | null) => void
+  onStart: (entry: DiaryEntry) => void
+}
+
+// The prompt quoted at the top of a new entry, followed by an empty paragraph
+function promptDocument(prompt: WritingPrompt): string {
+  return JSON.stringify({
+    type: 'doc',
+    content: [
+      {
+        type: 'blockquote',
+        content: [{ type: 'paragraph', content: [{ type: 'text', text: prompt.text }] }],
+      },
+      { type: 'paragraph' },
+    ],
+  })
+}
+
+export function DailyPrompt({ date, onPromptChange, onStart }: DailyPromptProps) {
+  const [prompt, setPrompt] = useState<WritingPrompt | null>(null)
+  const [showLibrary, setShowLibrary] = useState(false)
+  const [reloadKey, setReloadKey] = useState(0)
+
+  useEffect(() => {
+    let cancelled = false
+    getDailyPrompt(date)
+      .then((daily) => {
+        if (!cancelled) setPrompt(daily)
+      })
+      .catch((error) => console.error('Failed to load prompt:', error))
+    return () => {
+      cancelled = true
+    }
+  }, [date, reloadKey])
+
+  useEffect(() => {
+    onPromptChange?.(prompt)
+  }, [onPromptChange, prompt])
+
+  if (!prompt) return null
+
+  const handleSkip = async () => {
+    try {
+      setPrompt(await skipPrompt(prompt.id, date))
+    } catch (error) {
+      console.error('Failed to skip prompt:', error)
+    }
+  }
+
+  const handleFavorite = async () => {
+    try {
+      setPrompt(await setPromptFavorite(prompt.id, !prompt.is_favorite))
+    } catch (error) {
+      console.error('Failed to update favorite:', error)
+    }
+  }
+
+  const handleWrite = async () => {
+    try {
+      const entry = await upsertEntry(date, promptDocument(prompt))
+      await setEntryPrompt(entry.id, prompt.id)
+      onStart(entry)
+    } catch (error) {
+      console.error('Failed to start from prompt:', error)
+    }
+  }
+
+  return (
+    <div className="mt-6 w-full max-w-md px-4 py-3 rounded-lg bg-amber-50/80 border border-amber-200">
+      <div className="flex items-center gap-1.5 text-xs text-amber-700 mb-1">
+        <Lightbulb className="w-3.5 h-3.5" />
+        <span className="font-medium">Prompt of the day</span>
+        {prompt.level && (
+          <span className="px-1.5 rounded bg-amber-100 text-amber-800">{prompt.level}</span>
+        )}
+        {prompt.topic && <span className="text-amber-600">{prompt.topic}</span>}
+        <button
+          onClick={() => setShowLibrary(true)}
+          className="ml-auto px-1.5 py-0.5 rounded hover:bg-amber-100 transition-colors"
+        >
+          Library
+        </button>
+      </div>
+      <p className="text-sm text-ink-primary">{prompt.text}</p>
+      <div className="flex items-center gap-2 mt-2">
+        <button
+          onClick={() => void handleWrite()}
+          className="flex items-center gap-1 px-2.5 py-1 text-xs text-white bg-accent-blue rounded-md hover:opacity-90"
+        >
+          <PenLine className="w-3.5 h-3.5" />
+          Write about it
+        </button>
+        <button
+          onClick={() => void handleSkip()}
+          className="flex items-center gap-1 px-2 py-1 text-xs text-stone-600 rounded-md hover:bg-amber-100"
+          title="Show another prompt"
+        >
+          <SkipForward className="w-3.5 h-3.5" />
+          Skip
+        </button>
+        <button
+          onClick={() => void handleFavorite()}
+          className="p-1 rounded-md hover:bg-amber-100"
+          title={prompt.is_favorite ? 'Remove from favorites' : 'Add to favorites'}
+        >
+          <Star
+            className={`w-3.5 h-3.5 ${
+              prompt.is_favorite ? 'fill-amber-400 text-amber-400' : 'text-stone-400'
+            }`}
+          />
+        </button>
+      </div>
+
+      <PromptsDialog
+        isOpen={showLibrary}
+        onClose={() => {
+          setShowLibrary(false)
+          // The level or the prompts may have changed
+          setReloadKey((key) => key + 1)
+        }}
+      />
+    </div>
+  )
+}
@@ -1,9 +1,33 @@
-import { useState } from 'react'
-import { Download, Upload, FileText, X } from 'lucide-react'
+import { useEffect, useState } from 'react'
+import { Download, FileDown, Upload, FileText, Lock, Sunrise, X } from 'lucide-react'
 import { save, open } from '@tauri-apps/plugin-dialog'
 import { writeTextFile, readTextFile } from '@tauri-apps/plugin-fs'
-import { exportData, importData } from '../lib/api'
-import type { ImportOptions } from '../types'
+import {
+  exportData,
+  exportMarkdown,
+  exportHtmlSite,
+  importData,
+  getEncryptionStatus,
+  enableEncryption,
+  lockJournal,
+  changePassphrase,
+  getLockStatus,
+  setLockPassphrase,
+  disableAppLock,
+  saveLockSettings,
+  getDaySettings,
+  saveDaySettings,
+  getJournalToday,
+} from '../lib/api'
+import { useAppStore } from '../store/useAppStore'
+import type {
+  ArchiveFormat,
+  ExportSummary,
+  DaySettings,
+  EncryptionStatus,
+  ImportOptions,
+  LockStatus,
+} from '../types'
 
 interface DataSettingsDialogProps {
   isOpen: boolean
@@ -12,9 +36,28 @@ interface DataSettingsDialogProps {
 
 export function DataSettingsDialog({ isOpen, onClose }: DataSettingsDialogProps) {
   const [isExporting, setIsExporting] = useState(false)
+  const [isExportingCopy, setIsExportingCopy] = useState(false)
+  const [includeMedia, setIncludeMedia] = useState(true)
   const [isImporting, setIsImporting] = useState(false)
   const [overwrite, setOverwrite] = useState(false)
   const [includeAiOps, setIncludeAiOps] = useState(true)
+  const [encryption, setEncryption] = useState<EncryptionStatus | null>(null)
+  const [passphrase, setPassphrase] = useState('')
+  const [newPassphrase, setNewPassphrase] = useState('')
+  const [isEncrypting, setIsEncrypting] = useState(false)
+  const [lockStatus, setLockStatus] = useState<LockStatus | null>(null)
+  const [lockCurrent, setLockCurrent] = useState('')
+  const [lockPassphrase, setLockPassphrase] = useState('')
+  const [daySettings, setDaySettings] = useState<DaySettings | null>(null)
+  const setToday = useAppStore((state) => state.setToday)
+
+  useEffect(() => {
+    if (isOpen) {
+      getEncryptionStatus().then(setEncryption).catch(console.error)
+      getLockStatus().then(setLockStatus).catch(console.error)
+      getDaySettings().then(setDaySettings).catch(console.error)
+    }
+  }, [isOpen])
 
   const handleExport = async () => {
     setIsExporting(true)
@@ -36,6 +79,57 @@ export function DataSettingsDialog({ isOpen, onClose }: DataSettingsDialogProps)
     }
   }
 
+  const today = () => new Date().toISOString().split('T')[0]
+
+  // Ask for a folder; the export goes into a new folder named `name` inside it
+  const pickExportFolder = async (name: string) => {
+    const parent = await open({ directory: true, multiple: false })
+    return typeof parent === 'string' ? `${parent}/${name}` : null
+  }
+
+  const reportExport = (summary: ExportSummary) => {
+    const missing = summary.skipped_media
+    const skipped = missing
+      ? ` (${missing} missing media ${missing === 1 ? 'file' : 'files'} skipped)`
+      : ''
+    alert(`Exported ${summary.entries} entries to ${summary.path}${skipped}`)
+  }
+
+  const handleExportMarkdown = async (format: ArchiveFormat) => {
+    const name = `echo-daily-markdown-${today()}`
+    const path =
+      format === 'zip'
+        ? await save({
+            defaultPath: `${name}.zip`,
+            filters: [{ name: 'Zip', extensions: ['zip'] }],
+          })
+        : await pickExportFolder(name)
+    if (!path) return
+
+    setIsExportingCopy(true)
+    try {
+      reportExport(await exportMarkdown(path, format, includeMedia))
+    } catch (error) {
+      alert(`Markdown export failed: ${error}`)
+    } finally {
+      setIsExportingCopy(false)
+    }
+  }
+
+  const handleExportSite = async () => {
+    const path = await pickExportFolder(`echo-daily-site-${today()}`)
+    if (!path) return
+
+    setIsExportingCopy(true)
+    try {
+      reportExport(await exportHtmlSite(path))
+    } catch (error) {
+      alert(`Website export failed: ${error}`)
+    } finally {
+      setIsExportingCopy(false)
+    }
+  }
+
   const handleImport = async () => {
     setIsImporting(true)
     try {
@@ -68,6 +162,81 @@ export function DataSettingsDialog({ isOpen, onClose }: DataSettingsDialogProps)
     }
   }
 
+  const handleEnableEncryption = async () => {
+    if (passphrase !== newPassphrase) {
+      alert('Passphrases do not match')
+      return
+    }
+    setIsEncrypting(true)
+    try {
+      await enableEncryption(passphrase)
+      setEncryption(await getEncryptionStatus())
+      setPassphrase('')
+      setNewPassphrase('')
+    } catch (error) {
+      alert(`Encryption failed: ${error}`)
+    } finally {
+      setIsEncrypting(false)
+    }
+  }
+
+  const handleChangePassphrase = async () => {
+    try {
+      await changePassphrase(passphrase, newPassphrase)
+      setPassphrase('')
+      setNewPassphrase('')
+      alert('Passphrase changed')
+    } catch (error) {
+      alert(`Passphrase change failed: ${error}`)
+    }
+  }
+
+  const handleLock = async () => {
+    await lockJournal()
+    window.location.reload()
+  }
+
+  const handleSetLockPassphrase = async () => {
+    try {
+      await setLockPassphrase(lockPassphrase, lockStatus?.enabled ? lockCurrent : undefined)
+      setLockStatus(await getLockStatus())
+      setLockCurrent('')
+      setLockPassphrase('')
+    } catch (error) {
+      alert(`App lock failed: ${error}`)
+    }
+  }
+
+  const handleDisableAppLock = async () => {
+    try {
+      await disableAppLock(lockCurrent)
+      setLockStatus(await getLockStatus())
+      setLockCurrent('')
+    } catch (error) {
+      alert(`App lock failed: ${error}`)
+    }
+  }
+
+  const handleIdleMinutesChange = async (idleMinutes: number) => {
+    try {
+      await saveLockSettings({ idle_minutes: idleMinutes })
+      setLockStatus(await getLockStatus())
+    } catch (error) {
+      alert(`App lock failed: ${error}`)
+    }
+  }
+
+  const handleDayStartChange = async (dayStartHour: number) => {
+    try {
+      const settings = { day_start_hour: dayStartHour }
+      await saveDaySettings(settings)
+      setDaySettings(settings)
+      setToday(await getJournalToday())
+    } catch (error) {
+      alert(`Saving settings failed: ${error}`)
+    }
+  }
+
   if (!isOpen) return null
 
   return (
@@ -120,6 +289,47 @@ export function DataSettingsDialog({ isOpen, onClose }: DataSettingsDialogProps)
             </button>
           </div>
 
+          {/* Markdown Export Section */}
+          <div className="space-y-2">
+            <div className="flex items-center gap-2">
+              <FileDown className="w-4 h-4 text-accent-blue" />
+              <h3 className="font-medium text-sm text-ink-primary">Readable copy</h3>
+            </div>
+            <label className="flex items-center gap-1.5 cursor-pointer text-xs text-stone-500">
+              <input
+                type="checkbox"
+                checked={includeMedia}
+                onChange={(e) => setIncludeMedia(e.target.checked)}
+                className="w-3.5 h-3.5 rounded border-stone-300 text-accent-blue focus:ring-accent-blue"
+              />
+              Markdown includes attachments and audio
+            </label>
+            <div className="flex gap-2">
+              <button
+                onClick={() => void handleExportMarkdown('directory')}
+                disabled={isExportingCopy}
+                className="flex-1 px-3 py-2 text-sm border border-stone-200 rounded-lg hover:bg-stone-50 disabled:opacity-50 disabled:cursor-not-allowed transition-colors"
+              >
+                Folder
+              </button>
+              <button
+                onClick={() => void handleExportMarkdown('zip')}
+                disabled={isExportingCopy}
+                className="flex-1 px-3 py-2 text-sm border border-stone-200 rounded-lg hover:bg-stone-50 disabled:opacity-50 disabled:cursor-not-allowed transition-colors"
+              >
+                {isExportingCopy ? 'Exporting...' : 'Zip'}
+              </button>
+            </div>
+            <button
+              onClick={() => void handleExportSite()}
+              disabled={isExportingCopy}
+              className="w-full px-3 py-2 text-sm border border-stone-200 rounded-lg hover:bg-stone-50 disabled:opacity-50 disabled:cursor-not-allowed transition-colors"
+              title="A website with every entry, attachments, audio and search"
+            >
+              Website
+            </button>
+          </div>
+
           {/* Divider */}
           <div className="col-span-2 h-px bg-stone-200 -mx-4" />
 
@@ -177,6 +387,143 @@ export function DataSettingsDialog({ isOpen, onClose }: DataSettingsDialogProps)
               </button>
             </div>
           </div>
+
+          {/* Divider */}
+          <div className="col-span-2 h-px bg-stone-200 -mx-4" />
+
+          {/* Encryption Section */}
+          {encryption && (
+            <div className="col-span-2 space-y-2">
+              <div className="flex items-center gap-2">
+                <Lock className="w-4 h-4 text-stone-600" />
+                <h3 className="font-medium text-sm text-ink-primary">Encryption</h3>
+              </div>
+              <p className="text-xs text-stone-500 leading-relaxed">
+                {encryption.enabled
+                  ? 'Entries, AI history and audio are encrypted with your passphrase.'
+                  : 'Encrypt entries, AI history and audio with a passphrase. It cannot be recovered if forgotten. Existing backups are replaced by an encrypted one.'}
+              </p>
+              <div className="flex gap-2">
+                <input
+                  type="password"
+                  value={passphrase}
+                  onChange={(e) => setPassphrase(e.target.value)}
+                  placeholder={encryption.enabled ? 'Current passphrase' : 'Passphrase'}
+                  className="flex-1 min-w-0 px-2 py-1.5 text-xs border border-stone-200 rounded-lg"
+                />
+                <input
+                  type="password"
+                  value={newPassphrase}
+                  onChange={(e) => setNewPassphrase(e.target.value)}
+                  placeholder={encryption.enabled ? 'New passphrase' : 'Repeat passphrase'}
+                  className="flex-1 min-w-0 px-2 py-1.5 text-xs border border-stone-200 rounded-lg"
+                />
+              </div>
+              <div className="flex items-center justify-between gap-2">
+                {encryption.enabled && (
+                  <button
+                    onClick={handleLock}
+                    className="px-3 py-1.5 text-xs bg-white border-2 border-stone-200 text-stone-700 rounded-lg hover:bg-stone-50"
+                  >
+                    Lock now
+                  </button>
+                )}
+                <button
+                  onClick={encryption.enabled ? handleChangePassphrase : handleEnableEncryption}
+                  disabled={isEncrypting || !passphrase || !newPassphrase}
+                  className="ml-auto px-3 py-1.5 text-xs bg-accent-blue text-white rounded-lg hover:bg-blue-600 disabled:opacity-50 disabled:cursor-not-allowed transition-colors"
+                >
+                  {encryption.enabled
+                    ? 'Change passphrase'
+                    : isEncrypting
+                      ? 'Encrypting...'
+                      : 'Encrypt journal'}
+                </button>
+              </div>
+            </div>
+          )}
+
+          {/* App Lock Section */}
+          {lockStatus && (
+            <div className="col-span-2 space-y-2">
+              <div className="flex items-center justify-between gap-2">
+                <div className="flex items-center gap-2">
+                  <Lock className="w-4 h-4 text-stone-600" />
+                  <h3 className="font-medium text-sm text-ink-primary">App lock</h3>
+                </div>
+                <select
+                  value={lockStatus.idle_minutes}
+                  onChange={(e) => handleIdleMinutesChange(Number(e.target.value))}
+                  className="px-2 py-1 text-xs border border-stone-200 rounded-lg"
+                >
+                  {[1, 5, 15, 30, 60].map((minutes) => (
+                    <option key={minutes} value={minutes}>
+                      Lock after {minutes} min idle
+                    </option>
+                  ))}
+                  <option value={0}>Never lock when idle</option>
+                </select>
+              </div>
+              <div className="flex gap-2">
+                {lockStatus.enabled && (
+                  <input
+                    type="password"
+                    value={lockCurrent}
+                    onChange={(e) => setLockCurrent(e.target.value)}
+                    placeholder="Current passphrase"
+                    className="flex-1 min-w-0 px-2 py-1.5 text-xs border border-stone-200 rounded-lg"
+                  />
+                )}
+                <input
+                  type="password"
+                  value={lockPassphrase}
+                  onChange={(e) => setLockPassphrase(e.target.value)}
+                  placeholder={lockStatus.enabled ? 'New passphrase' : 'Passphrase'}
+                  className="flex-1 min-w-0 px-2 py-1.5 text-xs border border-stone-200 rounded-lg"
+                />
+              </div>
+              <div className="flex justify-end gap-2">
+                {lockStatus.enabled && (
+                  <button
+                    onClick={handleDisableAppLock}
+                    disabled={!lockCurrent}
+                    className="px-3 py-1.5 text-xs bg-white border-2 border-stone-200 text-stone-700 rounded-lg hover:bg-stone-50 disabled:opacity-50 disabled:cursor-not-allowed"
+                  >
+                    Turn off
+                  </button>
+                )}
+                <button
+                  onClick={handleSetLockPassphrase}
+                  disabled={!lockPassphrase}
+                  className="px-3 py-1.5 text-xs bg-accent-blue text-white rounded-lg hover:bg-blue-600 disabled:opacity-50 disabled:cursor-not-allowed transition-colors"
+                >
+                  {lockStatus.enabled ? 'Change passphrase' : 'Turn on'}
+                </button>
+              </div>
+            </div>
+          )}
+
+          {/* Day Boundary Section */}
+          {daySettings && (
+            <div className="col-span-2 flex items-center justify-between gap-2">
+              <div className="flex items-center gap-2">
+                <Sunrise className="w-4 h-4 text-stone-600" />
+                <h3 className="font-medium text-sm text-ink-primary">New day starts at</h3>
+              </div>
+              <select
+                value={daySettings.day_start_hour}
+                onChange={(e) => handleDayStartChange(Number(e.target.value))}
+                className="px-2 py-1 text-xs border border-stone-200 rounded-lg"
+                title="Writing before this hour counts towards the previous day"
+              >
+                {Array.from({ length: 7 }, (_, hour) => (
+                  <option key={hour} value={hour}>
+                    {hour === 0 ? 'Midnight' : `${hour}:00 AM`}
+                  </option>
+                ))}
+              </select>
+            </div>
+          )}
         </div>
       </div>
     </div>
@@ -5,7 +5,18 @@ import { useEffect, useState } from 'react'
 import { useAppStore } from '../store/useAppStore'
 import { format } from 'date-fns'
 import { useAutosave } from '../hooks/useAutosave'
-import { getEntry, deleteEntry, upsertEntryMood } from '../lib/api'
+import {
+  getDayEntries,
+  createEntry,
+  updateEntryDetails,
+  aiSuggestTitle,
+  deleteEntry,
+  upsertEntryMood,
+  listTemplates,
+  getTemplateSettings,
+  createEntryFromTemplate,
+  getEntryPrompt,
+} from '../lib/api'
 import {
   Trash2,
   Heading1,
@@ -16,11 +27,23 @@ import {
   Bold,
   Italic,
   Sparkles,
+  Plus,
+  Wand2,
+  LayoutTemplate,
+  Lightbulb,
 } from 'lucide-react'
 import { SelectionMenu } from './SelectionMenu'
 import { TTSPlayer } from './TTSPlayer'
 import { MoodSelector } from './MoodSelector'
-import { type MoodType } from '../types'
+import { Attachments } from './Attachments'
+import { DailyPrompt } from './DailyPrompt'
+import {
+  type DiaryEntry,
+  type EntrySuggestion,
+  type MoodType,
+  type Template,
+  type WritingPrompt,
+} from '../types'
 
 /**
  * Recursively filters out unsupported TipTap nodes from content.
@@ -168,24 +191,40 @@ export function Editor() {
     setSaveStatus,
     currentEntry,
     setCurrentEntry,
+    dayEntries,
+    setDayEntries,
     markDirty,
     clearDirty,
+    today,
   } = useAppStore()
   const [showDeleteConfirm, setShowDeleteConfirm] = useState(false)
   const [isDeleting, setIsDeleting] = useState(false)
   const [entryText, setEntryText] = useState('')
   const [currentMood, setCurrentMood] = useState<MoodType | undefined>(undefined)
+  const [suggestion, setSuggestion] = useState<EntrySuggestion | null>(null)
+  const [isSuggesting, setIsSuggesting] = useState(false)
+  const [suggestError, setSuggestError] = useState<string | null>(null)
+  const [templates, setTemplates] = useState<Template[]>([])
+  const [defaultTemplateId, setDefaultTemplateId] = useState<string | undefined>(undefined)
+  const [dailyPrompt, setDailyPrompt] = useState<WritingPrompt | null>(null)
+  const [entryPrompt, setEntryPrompt] = useState<WritingPrompt | null>(null)
 
   // Update local mood state when entry changes
   useEffect(() => {
     setCurrentMood(currentEntry?.mood as MoodType | undefined)
   }, [currentEntry])
 
+  // A title suggestion belongs to the entry it was made for
+  useEffect(() => {
+    setSuggestion(null)
+    setSuggestError(null)
+  }, [currentEntry?.id])
+
   // Handle mood change
   const handleMoodChange = async (mood: MoodType | undefined) => {
     setCurrentMood(mood)
     try {
-      const updatedEntry = await upsertEntryMood(selectedDate, mood, undefined)
+      const updatedEntry = await upsertEntryMood(selectedDate, mood, undefined, currentEntry?.id)
       setCurrentEntry(updatedEntry)
     } catch (error) {
       console.error('Failed to save mood:', error)
@@ -222,16 +261,124 @@ export function Editor() {
     void switchDate()
   }, [clearPendingSelectDate, flushNow, pendingSelectedDate, selectedDate, setSelectedDate])
 
+  // Switch to another entry of the same day, saving the current one first
+  const handleSelectEntry = async (entry: DiaryEntry) => {
+    if (entry.id === currentEntry?.id) return
+    const ok = await flushNow()
+    if (ok) setCurrentEntry(entry)
+  }
+
+  // Start another entry for the day, stamped with the current time when writing today
+  const handleNewEntry = async () => {
+    const ok = await flushNow()
+    if (!ok) return
+    try {
+      const entry = await createEntry(selectedDate, {
+        entryTime: selectedDate === today ? format(new Date(), 'HH:mm') : undefined,
+      })
+      setCurrentEntry(entry)
+    } catch (error) {
+      console.error('Failed to create entry:', error)
+    }
+  }
+
+  // Templates are offered while the day is still empty, the default one first
+  useEffect(() => {
+    if (currentEntry) return
+    Promise.all([listTemplates(), getTemplateSettings()])
+      .then(([list, settings]) => {
+        const defaultId = settings.default_template_id
+        setTemplates(
+          [...list].sort((a, b) => Number(b.id === defaultId) - Number(a.id === defaultId))
+        )
+        setDefaultTemplateId(defaultId)
+      })
+      .catch((error) => console.error('Failed to load templates:', error))
+  }, [currentEntry, selectedDate])
+
+  // The prompt an entry answers is shown above it
+  useEffect(() => {
+    setEntryPrompt(null)
+    if (!currentEntry) return
+    getEntryPrompt(currentEntry.id)
+      .then(setEntryPrompt)
+      .catch((error) => console.error('Failed to load entry prompt:', error))
+  }, [currentEntry?.id])
+
+  // {{prompt}} is filled with the prompt shown in the empty state
+  const handleStartFromTemplate = async (templateId: string) => {
+    try {
+      setCurrentEntry(await createEntryFromTemplate(selectedDate, templateId, dailyPrompt?.id))
+    } catch (error) {
+      console.error('Failed to start from template:', error)
+    }
+  }
+
+  const handleDetailsChange = async (changes: {
+    title?: string | null
+    summary?: string | null
+    entryTime?: string | null
+  }) => {
+    if (!currentEntry) return
+    const details = {
+      title: changes.title !== undefined ? changes.title : (currentEntry.title ?? null),
+      summary: changes.summary !== undefined ? changes.summary : (currentEntry.summary ?? null),
+      entryTime:
+        changes.entryTime !== undefined ? changes.entryTime : (currentEntry.entry_time ?? null),
+    }
+    const unchanged =
+      details.title === (currentEntry.title ?? null) &&
+      details.summary === (currentEntry.summary ?? null) &&
+      details.entryTime === (currentEntry.entry_time ?? null)
+    if (unchanged) return
+    try {
+      const updated = await updateEntryDetails(currentEntry.id, details)
+      setCurrentEntry({
+        ...currentEntry,
+        title: updated.title,
+        summary: updated.summary,
+        entry_time: updated.entry_time,
+      })
+    } catch (error) {
+      console.error('Failed to update entry details:', error)
+    }
+  }
+
+  // Ask the AI for a title and summary; nothing changes until the user applies it
+  const handleSuggestTitle = async () => {
+    if (!currentEntry) return
+    const ok = await flushNow()
+    if (!ok) return
+    setIsSuggesting(true)
+    setSuggestError(null)
+    try {
+      setSuggestion(await aiSuggestTitle(selectedDate, currentEntry.id))
+    } catch (error) {
+      setSuggestError(String(error))
+    } finally {
+      setIsSuggesting(false)
+    }
+  }
+
+  const handleApplySuggestion = async () => {
+    if (!suggestion) return
+    await handleDetailsChange({ title: suggestion.title, summary: suggestion.summary })
+    setSuggestion(null)
+  }
+
   const handleDelete = async () => {
+    if (!currentEntry) return
     setIsDeleting(true)
     try {
-      await deleteEntry(selectedDate)
-      // Clear editor and state
-      if (editor) {
+      await deleteEntry(currentEntry.id)
+      // Move on to the day's next entry, or clear the editor if it was the last one
+      const remaining = dayEntries.filter((e) => e.id !== currentEntry.id)
+      setDayEntries(remaining)
+      if (editor && remaining.length === 0) {
         editor.commands.clearContent(false)
       }
-      setEditorContent({ type: 'doc', content: [] })
-      setCurrentEntry(null)
+      if (remaining.length === 0) setEditorContent({ type: 'doc', content: [] })
+      setCurrentEntry(remaining[0] ?? null)
       clearDirty()
       setSaveStatus('idle')
       setShowDeleteConfirm(false)
@@ -324,17 +471,18 @@ export function Editor() {
   })
 
   useEffect(() => {
-    const loadEntry = async () => {
+    const loadEntries = async () => {
       try {
-        const entry = await getEntry(selectedDate)
-        setCurrentEntry(entry)
+        const entries = await getDayEntries(selectedDate)
+        setDayEntries(entries)
+        setCurrentEntry(entries[0] ?? null)
       } catch (error) {
-        console.error('Failed to load entry:', error)
+        console.error('Failed to load entries:', error)
       }
     }
 
-    loadEntry()
-  }, [selectedDate, setCurrentEntry])
+    loadEntries()
+  }, [selectedDate, setCurrentEntry, setDayEntries])
 
   useEffect(() => {
     if (editor && currentEntry?.content_json) {
@@ -472,6 +620,7 @@ export function Editor() {
             <div className="h-8 w-px bg-border/40" />
             <MoodSelector
               entryDate={selectedDate}
+              entryId={currentEntry?.id}
               currentMood={currentMood}
               onMoodChange={handleMoodChange}
             />
@@ -481,7 +630,21 @@ export function Editor() {
           {currentEntry && (
             <div className="flex items-center gap-2">
               {/* TTS Player */}
-              <TTSPlayer text={entryText} language="auto" />
+              <TTSPlayer
+                entryDate={selectedDate}
+                entryId={currentEntry.id}
+                text={entryText}
+                language="auto"
+              />
+
+              {/* New entry for the same day */}
+              <button
+                onClick={() => void handleNewEntry()}
+                className="p-2 rounded-lg hover:bg-white/60 text-stone-500 transition-colors"
+                title="New entry for this day"
+              >
+                <Plus className="w-4 h-4" />
+              </button>
 
               {/* Delete button */}
               <button
@@ -525,6 +688,111 @@ export function Editor() {
                       <span>bold</span>
                     </span>
                   </div>
+                  <DailyPrompt
+                    date={selectedDate}
+                    onPromptChange={setDailyPrompt}
+                    onStart={setCurrentEntry}
+                  />
+                  {templates.length > 0 && (
+                    <div className="mt-6 flex flex-wrap justify-center gap-2">
+                      {templates.map((template) => (
+                        <button
+                          key={template.id}
+                          onClick={() => void handleStartFromTemplate(template.id)}
+                          className={`flex items-center gap-1.5 px-3 py-1.5 text-xs rounded-lg border transition-colors ${
+                            template.id === defaultTemplateId
+                              ? 'text-white bg-accent-blue border-accent-blue hover:opacity-90'
+                              : 'text-stone-600 bg-white/80 border-stone-200 hover:border-accent-blue hover:text-accent-blue'
+                          }`}
+                        >
+                          <LayoutTemplate className="w-3.5 h-3.5" />
+                          {template.name}
+                        </button>
+                      ))}
+                    </div>
+                  )}
+                </div>
+              )}
+
+              {/* Entries of the day, with the current entry's title and time */}
+              {currentEntry && (
+                <div className="flex items-center gap-2 px-3 pt-3">
+                  {dayEntries.length > 1 &&
+                    dayEntries.map((entry, index) => (
+                      <button
+                        key={entry.id}
+                        onClick={() => void handleSelectEntry(entry)}
+                        className={`px-2.5 py-1 rounded-md text-xs transition-colors ${
+                          entry.id === currentEntry.id
+                            ? 'bg-accent-blue text-white'
+                            : 'bg-stone-100 text-stone-600 hover:bg-stone-200'
+                        }`}
+                      >
+                        {entry.title || entry.entry_time || `Entry ${index + 1}`}
+                      </button>
+                    ))}
+                  <input
+                    key={`title-${currentEntry.id}-${currentEntry.title ?? ''}`}
+                    defaultValue={currentEntry.title ?? ''}
+                    placeholder="Untitled"
+                    onBlur={(e) => void handleDetailsChange({ title: e.target.value.trim() || null })}
+                    className="flex-1 min-w-0 bg-transparent text-sm text-ink-primary placeholder:text-stone-400 focus:outline-none"
+                  />
+                  <button
+                    onClick={() => void handleSuggestTitle()}
+                    disabled={isSuggesting}
+                    className="p-1.5 rounded-md text-stone-500 hover:bg-stone-100 hover:text-accent-blue transition-colors disabled:opacity-50"
+                    title="Suggest a title and summary"
+                  >
+                    <Wand2 className={`w-3.5 h-3.5 ${isSuggesting ? 'animate-pulse' : ''}`} />
+                  </button>
+                  <input
+                    key={`time-${currentEntry.id}`}
+                    type="time"
+                    defaultValue={currentEntry.entry_time ?? ''}
+                    onBlur={(e) => void handleDetailsChange({ entryTime: e.target.value || null })}
+                    className="bg-transparent text-xs text-stone-500 focus:outline-none"
+                  />
+                </div>
+              )}
+              {currentEntry && entryPrompt && (
+                <div className="flex items-center gap-1.5 px-3 pt-2 text-xs text-amber-700">
+                  <Lightbulb className="w-3.5 h-3.5 shrink-0" />
+                  <span className="truncate">{entryPrompt.text}</span>
+                </div>
+              )}
+              {currentEntry && (
+                <div className="px-3 pt-1">
+                  <input
+                    key={`summary-${currentEntry.id}-${currentEntry.summary ?? ''}`}
+                    defaultValue={currentEntry.summary ?? ''}
+                    placeholder="Add a one-sentence summary"
+                    onBlur={(e) =>
+                      void handleDetailsChange({ summary: e.target.value.trim() || null })
+                    }
+                    className="w-full bg-transparent text-xs italic text-stone-500 placeholder:text-stone-300 focus:outline-none"
+                  />
+                  {suggestError && <p className="text-xs text-red-500 mt-1">{suggestError}</p>}
+                  {suggestion && (
+                    <div className="mt-2 p-3 rounded-lg bg-blue-50 border border-blue-100 text-sm">
+                      <div className="font-medium text-ink-primary">{suggestion.title}</div>
+                      <div className="text-xs text-stone-600 mt-0.5">{suggestion.summary}</div>
+                      <div className="flex justify-end gap-2 mt-2">
+                        <button
+                          onClick={() => setSuggestion(null)}
+                          className="px-2.5 py-1 text-xs text-stone-600 hover:bg-white rounded-md"
+                        >
+                          Dismiss
+                        </button>
+                        <button
+                          onClick={() => void handleApplySuggestion()}
+                          className="px-2.5 py-1 text-xs text-white bg-accent-blue hover:opacity-90 rounded-md"
+                        >
+                          Apply
+                        </button>
+                      </div>
+                    </div>
+                  )}
                 </div>
               )}
 
@@ -602,6 +870,11 @@ export function Editor() {
               <div className="p-6">
                 <EditorContent editor={editor} />
               </div>
+
+              {/* Files attached to the entry */}
+              {currentEntry && (
+                <Attachments entryDate={selectedDate} entryId={currentEntry.id} />
+              )}
             </div>
           </div>
         </div>
@@ -624,8 +897,8 @@ export function Editor() {
           <div className="bg-white rounded-xl shadow-xl w-full max-w-md mx-4 paper-shadow p-6">
             <h3 className="text-lg font-semibold text-stone-800 mb-2">Delete entry</h3>
             <p className="text-stone-600 mb-6">
-              Delete the entry for {format(displayDate, 'MMM d, yyyy')}? This action cannot be
-              undone.
+              Delete {currentEntry?.title ? `"${currentEntry.title}"` : 'the entry'} for{' '}
+              {format(displayDate, 'MMM d, yyyy')}? It will be moved to the trash.
             </p>
             <div className="flex items-center justify-end gap-2">
               <button
@@ -1,13 +1,15 @@
 import { useState } from 'react'
-import { Calendar, Wand2, Volume2, Database } from 'lucide-react'
+import { Calendar, Wand2, Volume2, Database, LayoutTemplate } from 'lucide-react'
 import { AISettingsDialog } from './AISettings'
 import { TTSSettingsDialog } from './TTSSettings'
 import { DataSettingsDialog } from './DataSettings'
+import { TemplatesDialog } from './TemplatesDialog'
 
 export function Header() {
   const [showAISettings, setShowAISettings] = useState(false)
   const [showTTSSettings, setShowTTSSettings] = useState(false)
   const [showDataSettings, setShowDataSettings] = useState(false)
+  const [showTemplates, setShowTemplates] = useState(false)
 
   return (
     <>
@@ -17,6 +19,13 @@ export function Header() {
           <h1 className="text-lg font-semibold text-ink-primary">Echo Daily</h1>
         </div>
         <div className="flex items-center gap-2">
+          <button
+            className="p-2 rounded-lg hover:bg-white/40 transition-colors"
+            title="Templates"
+            onClick={() => setShowTemplates(true)}
+          >
+            <LayoutTemplate className="w-4 h-4 text-accent-blue" />
+          </button>
           <button
             className="p-2 rounded-lg hover:bg-white/40 transition-colors"
             title="AI Settings"
@@ -44,6 +53,7 @@ export function Header() {
       <AISettingsDialog isOpen={showAISettings} onClose={() => setShowAISettings(false)} />
       <TTSSettingsDialog isOpen={showTTSSettings} onClose={() => setShowTTSSettings(false)} />
       <DataSettingsDialog isOpen={showDataSettings} onClose={() => setShowDataSettings(false)} />
+      <TemplatesDialog isOpen={showTemplates} onClose={() => setShowTemplates(false)} />
     </>
   )
 }
@@ -14,12 +14,19 @@ const MOOD_COLORS = {
 
 interface Props {
   entryDate: string
+  entryId?: string // defaults to the first entry of the date
   currentMood?: MoodType
   onMoodChange?: (mood: MoodType | undefined) => void
   compact?: boolean
 }
 
-export function MoodSelector({ entryDate, currentMood, onMoodChange, compact = false }: Props) {
+export function MoodSelector({
+  entryDate,
+  entryId,
+  currentMood,
+  onMoodChange,
+  compact = false,
+}: Props) {
   const [selectedMood, setSelectedMood] = useState<MoodType | undefined>(currentMood)
   const [isSaving, setIsSaving] = useState(false)
   const [isExpanded, setIsExpanded] = useState(false)
@@ -38,7 +45,7 @@ export function MoodSelector({ entryDate, currentMood, onMoodChange, compact = f
     // Save to database
     setIsSaving(true)
     try {
-      await upsertEntryMood(entryDate, newMood, moodOption?.emoji)
+      await upsertEntryMood(entryDate, newMood, moodOption?.emoji, entryId)
       onMoodChange?.(newMood)
       setIsExpanded(false)
     } catch (err) {
@@ -0,0 +1,63 @@
+import { useEffect, useState } from 'react'
+import { History } from 'lucide-react'
+import { getOnThisDay } from '../lib/api'
+import { useAppStore } from '../store/useAppStore'
+import type { OnThisDayEntry } from '../types'
+
+// Label of when a resurfaced entry was written, relative to the selected date
+function whenLabel(entry: OnThisDayEntry): string {
+  if (entry.kind === 'week') return 'A week ago'
+  if (entry.kind === 'month') return 'A month ago'
+  return entry.years_ago === 1 ? 'A year ago' : `${entry.years_ago} years ago`
+}
+
+interface OnThisDayProps {
+  date: string
+}
+
+export function OnThisDay({ date }: OnThisDayProps) {
+  const requestSelectDate = useAppStore((state) => state.requestSelectDate)
+  const [entries, setEntries] = useState<OnThisDayEntry[]>([])
+
+  useEffect(() => {
+    let cancelled = false
+    getOnThisDay(date)
+      .then((found) => {
+        if (!cancelled) setEntries(found)
+      })
+      .catch((error) => console.error('Failed to load on this day:', error))
+    return () => {
+      cancelled = true
+    }
+  }, [date])
+
+  if (entries.length === 0) return null
+
+  return (
+    <div className="mt-4 pt-4 border-t border-stone-200/60">
+      <div className="flex items-center gap-1.5 text-xs text-stone-500 mb-2">
+        <History className="w-3 h-3" />
+        <span className="font-medium">On This Day</span>
+      </div>
+      <div className="space-y-1">
+        {entries.map((entry) => (
+          <button
+            key={entry.entry_id}
+            onClick={() => requestSelectDate(entry.entry_date)}
+            className="w-full text-left px-3 py-2 rounded-lg hover:bg-white/60 transition-colors"
+          >
+            <div className="flex items-center gap-1.5 text-xs text-stone-500">
+              <span className="font-medium">{whenLabel(entry)}</span>
+              <span>· {entry.entry_date}</span>
+              {entry.mood_emoji && <span className="ml-auto">{entry.mood_emoji}</span>}
+            </div>
+            {entry.title && (
+              <div className="text-sm font-medium text-stone-700 truncate">{entry.title}</div>
+            )}
+            <div className="text-xs text-stone-600 line-clamp-2">{entry.preview}</div>
+          </button>
+        ))}
+      </div>
+    </div>
+  )
+}
@@ -0,0 +1,203 @@
+import { useEffect, useState } from 'react'
+import { Lightbulb, Star, Trash2, X } from 'lucide-react'
+import {
+  addCustomPrompt,
+  deleteCustomPrompt,
+  getPromptSettings,
+  listPrompts,
+  savePromptSettings,
+  setPromptFavorite,
+} from '../lib/api'
+import { PROMPT_LEVELS } from '../types'
+import type { WritingPrompt } from '../types'
+
+interface PromptsDialogProps {
+  isOpen: boolean
+  onClose: () => void
+}
+
+export function PromptsDialog({ isOpen, onClose }: PromptsDialogProps) {
+  const [prompts, setPrompts] = useState<WritingPrompt[]>([])
+  const [level, setLevel] = useState<string | undefined>(undefined)
+  const [favoritesOnly, setFavoritesOnly] = useState(false)
+  const [newText, setNewText] = useState('')
+  const [newTopic, setNewTopic] = useState('')
+  const [error, setError] = useState<string | null>(null)
+
+  useEffect(() => {
+    if (!isOpen) return
+    setError(null)
+    getPromptSettings()
+      .then((settings) => setLevel(settings.level))
+      .catch(console.error)
+  }, [isOpen])
+
+  useEffect(() => {
+    if (!isOpen) return
+    listPrompts({ level, favoritesOnly })
+      .then(setPrompts)
+      .catch((err) => setError(String(err)))
+  }, [favoritesOnly, isOpen, level])
+
+  if (!isOpen) return null
+
+  // The level filters the list and is the level daily prompts come from
+  const handleLevelChange = async (value: string) => {
+    const next = value || undefined
+    setError(null)
+    try {
+      await savePromptSettings({ level: next })
+      setLevel(next)
+    } catch (err) {
+      setError(String(err))
+    }
+  }
+
+  const handleFavorite = async (prompt: WritingPrompt) => {
+    try {
+      const updated = await setPromptFavorite(prompt.id, !prompt.is_favorite)
+      setPrompts((prev) =>
+        favoritesOnly && !updated.is_favorite
+          ? prev.filter((p) => p.id !== updated.id)
+          : prev.map((p) => (p.id === updated.id ? updated : p))
+      )
+    } catch (err) {
+      setError(String(err))
+    }
+  }
+
+  const handleAdd = async () => {
+    setError(null)
+    try {
+      const prompt = await addCustomPrompt(newText, level, newTopic.trim() || undefined)
+      if (!favoritesOnly) setPrompts((prev) => [...prev, prompt])
+      setNewText('')
+      setNewTopic('')
+    } catch (err) {
+      setError(String(err))
+    }
+  }
+
+  const handleDelete = async (prompt: WritingPrompt) => {
+    try {
+      await deleteCustomPrompt(prompt.id)
+      setPrompts((prev) => prev.filter((p) => p.id !== prompt.id))
+    } catch (err) {
+      setError(String(err))
+    }
+  }
+
+  return (
+    <div
+      className="fixed inset-0 z-50 flex items-center justify-center bg-black/50"
+      onClick={onClose}
+    >
+      <div
+        className="bg-white rounded-xl shadow-2xl w-[520px] max-h-[85vh] mx-4 overflow-hidden flex flex-col"
+        onClick={(e) => e.stopPropagation()}
+      >
+        <div className="flex items-center justify-between px-4 py-3 border-b border-stone-200">
+          <div className="flex items-center gap-2">
+            <Lightbulb className="w-4 h-4 text-accent-blue" />
+            <h2 className="text-base font-semibold text-ink-primary">Writing Prompts</h2>
+          </div>
+          <button onClick={onClose} className="p-1 rounded hover:bg-stone-100 transition-colors">
+            <X className="w-4 h-4 text-stone-500" />
+          </button>
+        </div>
+
+        <div className="flex items-center gap-3 px-4 py-2 border-b border-stone-200 text-sm">
+          <label className="flex items-center gap-2 text-stone-600">
+            Level
+            <select
+              value={level ?? ''}
+              onChange={(e) => void handleLevelChange(e.target.value)}
+              className="px-2 py-1 text-sm border border-stone-200 rounded-md bg-white"
+            >
+              <option value="">Any</option>
+              {PROMPT_LEVELS.map((l) => (
+                <option key={l} value={l}>
+                  {l}
+                </option>
+              ))}
+            </select>
+          </label>
+          <label className="flex items-center gap-1.5 text-stone-600">
+            <input
+              type="checkbox"
+              checked={favoritesOnly}
+              onChange={(e) => setFavoritesOnly(e.target.checked)}
+            />
+            Favorites only
+          </label>
+        </div>
+
+        <div className="p-4 space-y-1 overflow-y-auto">
+          {prompts.length === 0 && <div className="text-sm text-stone-500">No prompts</div>}
+          {prompts.map((prompt) => (
+            <div
+              key={prompt.id}
+              className="group flex items-start gap-2 px-2 py-1.5 rounded-lg hover:bg-stone-50"
+            >
+              <button
+                onClick={() => void handleFavorite(prompt)}
+                className="p-1 rounded hover:bg-stone-100"
+                title={prompt.is_favorite ? 'Remove from favorites' : 'Add to favorites'}
+              >
+                <Star
+                  className={`w-3.5 h-3.5 ${
+                    prompt.is_favorite ? 'fill-amber-400 text-amber-400' : 'text-stone-400'
+                  }`}
+                />
+              </button>
+              <div className="flex-1 min-w-0">
+                <div className="text-sm text-ink-primary">{prompt.text}</div>
+                <div className="flex gap-2 text-xs text-stone-500">
+                  {prompt.level && <span>{prompt.level}</span>}
+                  {prompt.topic && <span>{prompt.topic}</span>}
+                  {prompt.is_custom && <span>yours</span>}
+                  {prompt.last_answered_on && <span>answered {prompt.last_answered_on}</span>}
+                </div>
+              </div>
+              {prompt.is_custom && (
+                <button
+                  onClick={() => void handleDelete(prompt)}
+                  className="p-1 rounded text-stone-400 opacity-0 group-hover:opacity-100 hover:bg-red-50 hover:text-red-600"
+                  title="Delete prompt"
+                >
+                  <Trash2 className="w-3.5 h-3.5" />
+                </button>
+              )}
+            </div>
+          ))}
+        </div>
+
+        <div className="px-4 py-3 border-t border-stone-200 space-y-2">
+          <textarea
+            value={newText}
+            onChange={(e) => setNewText(e.target.value)}
+            placeholder="Write your own prompt"
+            rows={2}
+            className="w-full px-3 py-1.5 text-sm border border-stone-200 rounded-lg resize-none focus:outline-none focus:border-accent-blue"
+          />
+          <div className="flex items-center gap-2">
+            <input
+              value={newTopic}
+              onChange={(e) => setNewTopic(e.target.value)}
+              placeholder="Topic (optional)"
+              className="flex-1 px-3 py-1.5 text-sm border border-stone-200 rounded-lg focus:outline-none focus:border-accent-blue"
+            />
+            <button
+              onClick={() => void handleAdd()}
+              disabled={!newText.trim()}
+              className="px-3 py-1.5 text-sm text-white bg-accent-blue rounded-lg hover:opacity-90 disabled:opacity-50"
+            >
+              Add prompt
+            </button>
+          </div>
+          {error && <div className="text-xs text-red-600">{error}</div>}
+        </div>
+      </div>
+    </div>
+  )
+}
@@ -52,12 +52,12 @@ export function SelectionMenu({ isVisible, position, selectedText, onReplace, on
       setTranslation(null)
 
       try {
-        const { selectedDate } = useAppStore.getState()
+        const { selectedDate, currentEntry } = useAppStore.getState()
         console.log('[SelectionMenu] Translate for:', selectedDate, 'target:', targetLang)
 
         // Use specific translation operation based on target language
         const opTypeForLang = targetLang === 'zh' ? 'translate_to_zh' : 'translate_to_en'
-        const result = await aiPolish(selectedDate, selectedText, opTypeForLang)
+        const result = await aiPolish(selectedDate, selectedText, opTypeForLang, currentEntry?.id)
 
         console.log('[SelectionMenu] Translation result:', result)
         setTranslation(result.result_text)
@@ -82,11 +82,11 @@ export function SelectionMenu({ isVisible, position, selectedText, onReplace, on
     setError(null)
 
     try {
-      const { selectedDate } = useAppStore.getState()
+      const { selectedDate, currentEntry } = useAppStore.getState()
       console.log('[SelectionMenu] AI Action:', opType, 'for date:', selectedDate)
       console.log('[SelectionMenu] Selected text:', selectedText)
 
-      const result = await aiPolish(selectedDate, selectedText)
+      const result = await aiPolish(selectedDate, selectedText, undefined, currentEntry?.id)
       console.log('[SelectionMenu] AI result:', result)
 
       // Replace selected text with AI result
@@ -7,13 +7,16 @@ import {
   BookOpen,
   Flame,
   TrendingUp,
+  BarChart3,
 } from 'lucide-react'
 import { useAppStore } from '../store/useAppStore'
 import { addMonths, format, getDaysInMonth } from 'date-fns'
 import { useEffect, useState } from 'react'
-import { listEntries, searchEntries, getWritingStats } from '../lib/api'
+import { getCalendarDays, searchEntries, getWritingStats } from '../lib/api'
 import { MOOD_OPTIONS } from '../types'
-import type { DiaryEntry, WritingStats } from '../types'
+import type { SearchHit, WritingStats } from '../types'
+import { OnThisDay } from './OnThisDay'
+import { StatsDialog } from './StatsDialog'
 
 // Mood colors for calendar badges - standardized with Tailwind colors
 const MOOD_COLORS = {
@@ -30,35 +33,41 @@ export function Sidebar() {
     setCurrentMonth,
     selectedDate,
     requestSelectDate,
-    monthEntries,
-    setMonthEntries,
+    monthDays,
+    setMonthDays,
+    today,
   } = useAppStore()
   const [searchQuery, setSearchQuery] = useState('')
-  const [searchResults, setSearchResults] = useState<DiaryEntry[]>([])
+  const [searchResults, setSearchResults] = useState<SearchHit[]>([])
+  const [searchTotal, setSearchTotal] = useState(0)
+  const [searchError, setSearchError] = useState<string | null>(null)
   const [isSearching, setIsSearching] = useState(false)
   const [showSearch, setShowSearch] = useState(false)
   const [stats, setStats] = useState<WritingStats | null>(null)
   const [statsLoading, setStatsLoading] = useState(true)
+  const [showStatsDialog, setShowStatsDialog] = useState(false)
 
-  // Load entries for the current month
+  // Load per-day totals for the current month
   useEffect(() => {
-    const loadMonthEntries = async () => {
+    const loadMonthDays = async () => {
       try {
-        const entries = await listEntries(currentMonth)
-        setMonthEntries(entries)
+        const [year, month] = currentMonth.split('-').map((part) => Number(part))
+        const lastDay = getDaysInMonth(new Date(year, month - 1, 1))
+        setMonthDays(await getCalendarDays(`${currentMonth}-01`, `${currentMonth}-${lastDay}`))
       } catch (error) {
         console.error('Failed to load month entries:', error)
       }
     }
 
-    loadMonthEntries()
-  }, [currentMonth, setMonthEntries])
+    loadMonthDays()
+  }, [currentMonth, setMonthDays])
 
   // Handle search
   useEffect(() => {
     const handleSearch = async () => {
       if (!searchQuery.trim()) {
         setSearchResults([])
+        setSearchError(null)
         setIsSearching(false)
         return
       }
@@ -66,10 +75,14 @@ export function Sidebar() {
       setIsSearching(true)
       try {
         const results = await searchEntries(searchQuery)
-        setSearchResults(results)
+        setSearchResults(results.hits)
+        setSearchTotal(results.total)
+        setSearchError(null)
       } catch (error) {
         console.error('Failed to search entries:', error)
         setSearchResults([])
+        setSearchTotal(0)
+        setSearchError(String(error))
       } finally {
         setIsSearching(false)
       }
@@ -113,17 +126,18 @@ export function Sidebar() {
   }
 
   const isSelected = (date: string) => date === selectedDate
-  const today = format(new Date(), 'yyyy-MM-dd')
   const isToday = (date: string) => date === today
 
-  // Get mood info for a specific date
+  const hasEntry = (date: string) => monthDays.some((d) => d.date === date && d.has_entry)
+
+  // Get mood info for a specific date (from its first entry with a mood)
   const getMoodForDate = (date: string) => {
-    const entry = monthEntries.find((e) => e.entry_date === date)
-    if (!entry?.mood) return null
-    const moodOption = MOOD_OPTIONS.find((m) => m.type === entry.mood)
+    const day = monthDays.find((d) => d.date === date && d.mood)
+    if (!day?.mood) return null
+    const moodOption = MOOD_OPTIONS.find((m) => m.type === day.mood)
     return {
       emoji: moodOption?.emoji,
-      color: MOOD_COLORS[entry.mood as keyof typeof MOOD_COLORS],
+      color: MOOD_COLORS[day.mood as keyof typeof MOOD_COLORS],
     }
   }
 
@@ -165,7 +179,7 @@ export function Sidebar() {
               type="text"
               value={searchQuery}
               onChange={(e) => setSearchQuery(e.target.value)}
-              placeholder="Search entries..."
+              placeholder='Search, e.g. "long walk" tag:travel -mood:sad'
               className="w-full pl-9 pr-8 py-2 text-sm border border-stone-300 rounded-lg focus:outline-none focus:ring-2 focus:ring-accent-blue"
               autoFocus
             />
@@ -189,7 +203,10 @@ export function Sidebar() {
         {/* Search Results */}
         {showSearch && (searchQuery || searchResults.length > 0) && (
           <div className="mb-4">
-            {searchQuery && searchResults.length === 0 && !isSearching && (
+            {searchError && !isSearching && (
+              <div className="text-center py-8 text-red-500 text-sm">{searchError}</div>
+            )}
+            {searchQuery && !searchError && searchResults.length === 0 && !isSearching && (
               <div className="text-center py-8 text-stone-400 text-sm">
                 No results found for &quot;{searchQuery}&quot;
               </div>
@@ -197,11 +214,11 @@ export function Sidebar() {
             {searchResults.length > 0 && (
               <div className="space-y-2">
                 <div className="text-xs font-medium text-stone-500 mb-2">
-                  {searchResults.length} {searchResults.length === 1 ? 'result' : 'results'}
+                  {searchTotal} {searchTotal === 1 ? 'result' : 'results'}
                 </div>
                 {searchResults.map((entry) => (
                   <button
-                    key={entry.id}
+                    key={entry.entry_id}
                     onClick={() => {
                       requestSelectDate(entry.entry_date)
                       setShowSearch(false)
@@ -215,8 +232,9 @@ export function Sidebar() {
                     }`}
                   >
                     <div className="flex items-center justify-between mb-1">
-                      <span className="text-sm font-medium">
+                      <span className="text-sm font-medium truncate">
                         {format(new Date(entry.entry_date + 'T00:00:00'), 'MMM d, yyyy')}
+                        {entry.title && <span className="font-normal"> · {entry.title}</span>}
                       </span>
                       {entry.mood_emoji && <span className="text-xs">{entry.mood_emoji}</span>}
                     </div>
@@ -225,10 +243,17 @@ export function Sidebar() {
                         entry.entry_date === selectedDate ? 'text-white/80' : 'text-stone-500'
                       }`}
                     >
-                      {entry.content_json
-                        ? JSON.parse(entry.content_json).content?.[0]?.content?.[0]?.text ||
-                          'No content'
-                        : 'Empty entry'}
+                      {entry.snippet.length > 0
+                        ? entry.snippet.map((segment, index) =>
+                            segment.highlight ? (
+                              <mark key={index} className="bg-yellow-200/80 rounded-sm">
+                                {segment.text}
+                              </mark>
+                            ) : (
+                              <span key={index}>{segment.text}</span>
+                            )
+                          )
+                        : 'No content'}
                     </div>
                   </button>
                 ))}
@@ -288,17 +313,24 @@ export function Sidebar() {
                   </span>
                 )}
 
-                {/* Today dot */}
-                {isTodayDate && !selected && !moodInfo && (
-                  <span className="absolute bottom-1 left-1/2 -translate-x-1/2 w-1 h-1 bg-accent-blue rounded-full" />
+                {/* Today and written-day dots */}
+                {(isTodayDate || hasEntry(dateStr)) && !selected && !moodInfo && (
+                  <span
+                    className={`absolute bottom-1 left-1/2 -translate-x-1/2 w-1 h-1 rounded-full ${
+                      isTodayDate ? 'bg-accent-blue' : 'bg-stone-400'
+                    }`}
+                  />
                 )}
               </button>
             )
           })}
         </div>
 
+        {/* Earlier entries from the selected day */}
+        <OnThisDay date={selectedDate} />
+
         {/* Month mood summary */}
-        {monthEntries.length > 0 && (
+        {monthDays.some((d) => d.mood) && (
           <div className="mt-4 pt-4 border-t border-stone-200/60">
             <div className="flex items-center gap-1.5 text-xs text-stone-500 mb-2">
               <Sparkles className="w-3 h-3" />
@@ -306,7 +338,7 @@ export function Sidebar() {
             </div>
             <div className="flex flex-wrap gap-1">
               {MOOD_OPTIONS.map((mood) => {
-                const count = monthEntries.filter((e) => e.mood === mood.type).length
+                const count = monthDays.filter((d) => d.mood === mood.type).length
                 if (count === 0) return null
                 return (
                   <div
@@ -334,6 +366,14 @@ export function Sidebar() {
             <div className="flex items-center gap-1.5 text-xs text-stone-500 mb-2">
               <TrendingUp className="w-3 h-3" />
               <span className="font-medium">Writing Stats</span>
+              <button
+                onClick={() => setShowStatsDialog(true)}
+                className="ml-auto flex items-center gap-1 px-1.5 py-0.5 rounded hover:bg-white/60 transition-colors"
+                title="Word counts, history and AI usage"
+              >
+                <BarChart3 className="w-3 h-3" />
+                More
+              </button>
             </div>
             <div className="space-y-2">
               {/* Total Entries */}
@@ -341,7 +381,12 @@ export function Sidebar() {
                 <BookOpen className="w-4 h-4 text-accent-blue" />
                 <div className="flex-1">
                   <div className="text-xs text-stone-500">Total Entries</div>
-                  <div className="text-lg font-semibold text-stone-800">{stats.total_entries}</div>
+                  <div className="text-lg font-semibold text-stone-800">
+                    {stats.total_entries}
+                    <span className="ml-1.5 text-xs font-normal text-stone-500">
+                      on {stats.total_days} {stats.total_days === 1 ? 'day' : 'days'}
+                    </span>
+                  </div>
                 </div>
               </div>
 
@@ -370,6 +415,8 @@ export function Sidebar() {
           </div>
         )}
       </div>
+
+      <StatsDialog isOpen={showStatsDialog} onClose={() => setShowStatsDialog(false)} />
     </aside>
   )
 }
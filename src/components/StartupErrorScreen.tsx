@@ -0,0 +1,42 @@
+import { AlertCircle } from 'lucide-react'
+import type { StartupError } from '../types'
+
+interface StartupErrorScreenProps {
+  error: StartupError
+}
+
+// Shown instead of the app when the database could not be opened at startup
+export function StartupErrorScreen({ error }: StartupErrorScreenProps) {
+  const isMigration = error.kind === 'migration_failed'
+
+  return (
+    <div className="h-screen flex items-center justify-center paper-texture bg-paper-bg p-6">
+      <div className="max-w-lg w-full bg-white/80 border border-border/40 rounded-lg shadow-sm p-6 space-y-4">
+        <div className="flex items-center gap-2 text-accent-red">
+          <AlertCircle className="w-5 h-5" />
+          <h1 className="text-lg font-medium">
+            {isMigration ? 'Database upgrade failed' : 'Could not open your journal'}
+          </h1>
+        </div>
+
+        {isMigration && (
+          <p className="text-sm text-ink-secondary">
+            {error.rolled_back
+              ? `Your journal was restored to how it was before the upgrade (schema v${error.schema_version}). No entries were lost.`
+              : 'The upgrade could not be undone automatically. Please keep a copy of your data folder before trying again.'}
+          </p>
+        )}
+
+        <pre className="text-xs bg-stone-100 rounded p-3 whitespace-pre-wrap break-words text-ink-secondary">
+          {error.message}
+        </pre>
+
+        {error.snapshot_path && (
+          <p className="text-xs text-ink-secondary break-all">
+            A copy taken before the upgrade is kept at {error.snapshot_path}
+          </p>
+        )}
+      </div>
+    </div>
+  )
+}
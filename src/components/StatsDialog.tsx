@@ -0,0 +1,201 @@
+import { useEffect, useState } from 'react'
+import { BarChart3, X } from 'lucide-react'
+import { getWritingAnalytics } from '../lib/api'
+import { useAppStore } from '../store/useAppStore'
+import type { PeriodStats, WritingAnalytics } from '../types'
+import { YearHeatmap } from './YearHeatmap'
+
+interface StatsDialogProps {
+  isOpen: boolean
+  onClose: () => void
+}
+
+type Period = 'weeks' | 'months' | 'years'
+
+const WEEKDAYS = ['Mon', 'Tue', 'Wed', 'Thu', 'Fri', 'Sat', 'Sun']
+
+const AI_LABELS: Record<string, string> = {
+  polish: 'Polish',
+  expand: 'Expand',
+  fix_grammar: 'Fix grammar',
+  title_summary: 'Title & summary',
+}
+
+// How many of the most recent periods are listed
+const RECENT_PERIODS = 12
+
+export function StatsDialog({ isOpen, onClose }: StatsDialogProps) {
+  const [analytics, setAnalytics] = useState<WritingAnalytics | null>(null)
+  const [period, setPeriod] = useState<Period>('months')
+  const [error, setError] = useState<string | null>(null)
+  const requestSelectDate = useAppStore((state) => state.requestSelectDate)
+
+  useEffect(() => {
+    if (!isOpen) return
+    setError(null)
+    getWritingAnalytics()
+      .then(setAnalytics)
+      .catch((err) => setError(String(err)))
+  }, [isOpen])
+
+  if (!isOpen) return null
+
+  const periods: PeriodStats[] = analytics
+    ? analytics[period].slice(-RECENT_PERIODS).reverse()
+    : []
+  const maxPeriodWords = Math.max(1, ...periods.map((p) => p.words))
+  const maxWeekdayWords = Math.max(1, ...(analytics?.weekdays.map((d) => d.words) ?? []))
+  const busiestWeekday = analytics?.weekdays.reduce(
+    (best, day) => (day.words > best.words ? day : best),
+    analytics.weekdays[0]
+  )
+
+  return (
+    <div
+      className="fixed inset-0 z-50 flex items-center justify-center bg-black/50"
+      onClick={onClose}
+    >
+      <div
+        className="bg-white rounded-xl shadow-2xl w-[520px] max-h-[85vh] mx-4 overflow-hidden flex flex-col"
+        onClick={(e) => e.stopPropagation()}
+      >
+        <div className="flex items-center justify-between px-4 py-3 border-b border-stone-200">
+          <div className="flex items-center gap-2">
+            <BarChart3 className="w-4 h-4 text-accent-blue" />
+            <h2 className="text-base font-semibold text-ink-primary">Writing Statistics</h2>
+          </div>
+          <button onClick={onClose} className="p-1 rounded hover:bg-stone-100 transition-colors">
+            <X className="w-4 h-4 text-stone-500" />
+          </button>
+        </div>
+
+        <div className="p-4 space-y-5 overflow-y-auto">
+          {error && <div className="text-sm text-red-600">{error}</div>}
+          {!analytics && !error && <div className="text-sm text-stone-500">Loading...</div>}
+
+          {analytics && (
+            <>
+              {/* Year heatmap */}
+              <YearHeatmap
+                onSelectDate={(date) => {
+                  requestSelectDate(date)
+                  onClose()
+                }}
+              />
+
+              {/* Totals */}
+              <div className="grid grid-cols-3 gap-3">
+                <div className="px-3 py-2 bg-stone-50 rounded-lg">
+                  <div className="text-xs text-stone-500">Words</div>
+                  <div className="text-lg font-semibold text-stone-800">
+                    {analytics.total_words.toLocaleString()}
+                  </div>
+                </div>
+                <div className="px-3 py-2 bg-stone-50 rounded-lg">
+                  <div className="text-xs text-stone-500">Characters</div>
+                  <div className="text-lg font-semibold text-stone-800">
+                    {analytics.total_characters.toLocaleString()}
+                  </div>
+                </div>
+                <div className="px-3 py-2 bg-stone-50 rounded-lg">
+                  <div className="text-xs text-stone-500">Words per entry</div>
+                  <div className="text-lg font-semibold text-stone-800">
+                    {Math.round(analytics.average_words)}
+                  </div>
+                </div>
+              </div>
+
+              {/* Per period */}
+              <div className="space-y-2">
+                <div className="flex items-center justify-between">
+                  <h3 className="font-medium text-sm text-ink-primary">History</h3>
+                  <div className="flex gap-1">
+                    {(['weeks', 'months', 'years'] as Period[]).map((p) => (
+                      <button
+                        key={p}
+                        onClick={() => setPeriod(p)}
+                        className={`px-2 py-0.5 text-xs rounded ${
+                          period === p
+                            ? 'bg-accent-blue text-white'
+                            : 'text-stone-500 hover:bg-stone-100'
+                        }`}
+                      >
+                        {p.charAt(0).toUpperCase() + p.slice(1)}
+                      </button>
+                    ))}
+                  </div>
+                </div>
+                {periods.length === 0 && (
+                  <div className="text-xs text-stone-500">No entries yet</div>
+                )}
+                {periods.map((p) => (
+                  <div key={p.period} className="flex items-center gap-2 text-xs">
+                    <span className="w-16 text-stone-600">{p.period}</span>
+                    <div className="flex-1 h-2 bg-stone-100 rounded">
+                      <div
+                        className="h-2 bg-accent-blue/70 rounded"
+                        style={{ width: `${(p.words / maxPeriodWords) * 100}%` }}
+                      />
+                    </div>
+                    <span
+                      className="w-40 text-right text-stone-500"
+                      title={`${p.characters.toLocaleString()} characters on ${p.days} days`}
+                    >
+                      {p.entries} {p.entries === 1 ? 'entry' : 'entries'} ·{' '}
+                      {p.words.toLocaleString()} words · ⌀ {Math.round(p.average_words)}
+                    </span>
+                  </div>
+                ))}
+              </div>
+
+              {/* Weekdays */}
+              <div className="space-y-2">
+                <h3 className="font-medium text-sm text-ink-primary">
+                  Weekdays
+                  {busiestWeekday && busiestWeekday.words > 0 && (
+                    <span className="ml-2 text-xs font-normal text-stone-500">
+                      most productive: {WEEKDAYS[busiestWeekday.weekday]}
+                    </span>
+                  )}
+                </h3>
+                <div className="flex items-end gap-2 h-20">
+                  {analytics.weekdays.map((day) => (
+                    <div
+                      key={day.weekday}
+                      className="flex-1 flex flex-col items-center justify-end gap-1 h-full"
+                      title={`${day.entries} entries · ${day.words.toLocaleString()} words`}
+                    >
+                      <div
+                        className="w-full bg-amber-300 rounded-t"
+                        style={{ height: `${(day.words / maxWeekdayWords) * 100}%` }}
+                      />
+                      <span className="text-[10px] text-stone-500">{WEEKDAYS[day.weekday]}</span>
+                    </div>
+                  ))}
+                </div>
+              </div>
+
+              {/* AI usage */}
+              <div className="space-y-2">
+                <h3 className="font-medium text-sm text-ink-primary">AI assists</h3>
+                {analytics.ai_usage.length === 0 && (
+                  <div className="text-xs text-stone-500">No AI assists used yet</div>
+                )}
+                <div className="flex flex-wrap gap-2">
+                  {analytics.ai_usage.map((usage) => (
+                    <span
+                      key={usage.op_type}
+                      className="px-2 py-1 text-xs bg-purple-50 text-purple-700 rounded-lg"
+                    >
+                      {AI_LABELS[usage.op_type] ?? usage.op_type}: {usage.count}
+                    </span>
+                  ))}
+                </div>
+              </div>
+            </>
+          )}
+        </div>
+      </div>
+    </div>
+  )
+}
@@ -4,6 +4,8 @@ import { textToSpeech } from '../lib/api'
 import { convertFileSrc } from '@tauri-apps/api/core'
 
 interface TTSPlayerProps {
+  entryDate: string
+  entryId?: string // defaults to the first entry of the date
   text: string
   language?: string
   onPlayingChange?: (isPlaying: boolean) => void
@@ -82,7 +84,13 @@ async function warmUpAudioSystem() {
   }
 }
 
-export function TTSPlayer({ text, language = 'auto', onPlayingChange }: TTSPlayerProps) {
+export function TTSPlayer({
+  entryDate,
+  entryId,
+  text,
+  language = 'auto',
+  onPlayingChange,
+}: TTSPlayerProps) {
   const [isPlaying, setIsPlaying] = useState(false)
   const [isLoading, setIsLoading] = useState(false)
   const [isMuted, setIsMuted] = useState(false)
@@ -136,6 +144,8 @@ export function TTSPlayer({ text, language = 'auto', onPlayingChange }: TTSPlaye
     try {
       console.log('TTS: Calling API...')
       const response = await textToSpeech({
+        entryDate,
+        entryId,
         text,
         language: language === 'auto' ? undefined : language,
       })
@@ -0,0 +1,188 @@
+import { useEffect, useState } from 'react'
+import { LayoutTemplate, Star, Trash2, X } from 'lucide-react'
+import {
+  createTemplate,
+  deleteTemplate,
+  getTemplateSettings,
+  listTemplates,
+  saveTemplateSettings,
+  updateTemplate,
+} from '../lib/api'
+import { useAppStore } from '../store/useAppStore'
+import type { Template } from '../types'
+
+// Filled in when a day is started from a template
+const PLACEHOLDERS = ['{{date}}', '{{weekday}}', '{{yesterday_mood}}', '{{prompt}}']
+
+interface TemplatesDialogProps {
+  isOpen: boolean
+  onClose: () => void
+}
+
+export function TemplatesDialog({ isOpen, onClose }: TemplatesDialogProps) {
+  const editorContent = useAppStore((state) => state.editorContent)
+  const [templates, setTemplates] = useState<Template[]>([])
+  const [defaultId, setDefaultId] = useState<string | undefined>(undefined)
+  const [newName, setNewName] = useState('')
+  const [error, setError] = useState<string | null>(null)
+
+  useEffect(() => {
+    if (!isOpen) return
+    setError(null)
+    listTemplates().then(setTemplates).catch(console.error)
+    getTemplateSettings()
+      .then((settings) => setDefaultId(settings.default_template_id))
+      .catch(console.error)
+  }, [isOpen])
+
+  if (!isOpen) return null
+
+  // The entry being edited becomes the template, placeholders included
+  const handleCreate = async () => {
+    setError(null)
+    try {
+      const template = await createTemplate(newName, JSON.stringify(editorContent))
+      setTemplates((prev) => [...prev, template].sort((a, b) => a.name.localeCompare(b.name)))
+      setNewName('')
+    } catch (err) {
+      setError(String(err))
+    }
+  }
+
+  const handleRename = async (template: Template, name: string) => {
+    if (name.trim() === template.name) return
+    setError(null)
+    try {
+      const updated = await updateTemplate(template.id, name, template.content_json)
+      setTemplates((prev) => prev.map((t) => (t.id === updated.id ? updated : t)))
+    } catch (err) {
+      setError(String(err))
+    }
+  }
+
+  const handleReplaceContent = async (template: Template) => {
+    setError(null)
+    try {
+      const updated = await updateTemplate(
+        template.id,
+        template.name,
+        JSON.stringify(editorContent)
+      )
+      setTemplates((prev) => prev.map((t) => (t.id === updated.id ? updated : t)))
+    } catch (err) {
+      setError(String(err))
+    }
+  }
+
+  const handleToggleDefault = async (template: Template) => {
+    const next = defaultId === template.id ? undefined : template.id
+    setError(null)
+    try {
+      await saveTemplateSettings({ default_template_id: next })
+      setDefaultId(next)
+    } catch (err) {
+      setError(String(err))
+    }
+  }
+
+  const handleDelete = async (template: Template) => {
+    setError(null)
+    try {
+      await deleteTemplate(template.id)
+      setTemplates((prev) => prev.filter((t) => t.id !== template.id))
+      if (defaultId === template.id) setDefaultId(undefined)
+    } catch (err) {
+      setError(String(err))
+    }
+  }
+
+  return (
+    <div
+      className="fixed inset-0 z-50 flex items-center justify-center bg-black/50"
+      onClick={onClose}
+    >
+      <div
+        className="bg-white rounded-xl shadow-2xl w-[440px] max-h-[85vh] mx-4 overflow-hidden flex flex-col"
+        onClick={(e) => e.stopPropagation()}
+      >
+        <div className="flex items-center justify-between px-4 py-3 border-b border-stone-200">
+          <div className="flex items-center gap-2">
+            <LayoutTemplate className="w-4 h-4 text-accent-blue" />
+            <h2 className="text-base font-semibold text-ink-primary">Templates</h2>
+          </div>
+          <button onClick={onClose} className="p-1 rounded hover:bg-stone-100 transition-colors">
+            <X className="w-4 h-4 text-stone-500" />
+          </button>
+        </div>
+
+        <div className="p-4 space-y-4 overflow-y-auto">
+          <p className="text-xs text-stone-500">
+            Templates are saved from the entry you are editing. These placeholders are filled in
+            when a day starts from one: {PLACEHOLDERS.join(', ')}.
+          </p>
+
+          {templates.length === 0 && <div className="text-sm text-stone-500">No templates yet</div>}
+          <div className="space-y-1">
+            {templates.map((template) => (
+              <div
+                key={template.id}
+                className="flex items-center gap-2 px-2 py-1.5 rounded-lg hover:bg-stone-50"
+              >
+                <button
+                  onClick={() => void handleToggleDefault(template)}
+                  className="p-1 rounded hover:bg-stone-100"
+                  title={defaultId === template.id ? 'Default template' : 'Use for new days'}
+                >
+                  <Star
+                    className={`w-3.5 h-3.5 ${
+                      defaultId === template.id
+                        ? 'fill-amber-400 text-amber-400'
+                        : 'text-stone-400'
+                    }`}
+                  />
+                </button>
+                <input
+                  key={`${template.id}-${template.name}`}
+                  defaultValue={template.name}
+                  onBlur={(e) => void handleRename(template, e.target.value)}
+                  className="flex-1 min-w-0 bg-transparent text-sm text-ink-primary focus:outline-none"
+                />
+                <button
+                  onClick={() => void handleReplaceContent(template)}
+                  className="px-2 py-0.5 text-xs text-stone-500 rounded hover:bg-stone-100"
+                  title="Replace with the entry being edited"
+                >
+                  Update
+                </button>
+                <button
+                  onClick={() => void handleDelete(template)}
+                  className="p-1 rounded text-stone-400 hover:bg-red-50 hover:text-red-600"
+                  title="Delete template"
+                >
+                  <Trash2 className="w-3.5 h-3.5" />
+                </button>
+              </div>
+            ))}
+          </div>
+
+          <div className="flex items-center gap-2 pt-3 border-t border-stone-200">
+            <input
+              value={newName}
+              onChange={(e) => setNewName(e.target.value)}
+              placeholder="Template name"
+              className="flex-1 px-3 py-1.5 text-sm border border-stone-200 rounded-lg focus:outline-none focus:border-accent-blue"
+            />
+            <button
+              onClick={() => void handleCreate()}
+              disabled={!newName.trim()}
+              className="px-3 py-1.5 text-sm text-white bg-accent-blue rounded-lg hover:opacity-90 disabled:opacity-50"
+            >
+              Save current entry
+            </button>
+          </div>
+          {error && <div className="text-xs text-red-600">{error}</div>}
+        </div>
+      </div>
+    </div>
+  )
+}
@@ -0,0 +1,62 @@
+import { useState } from 'react'
+import { Lock } from 'lucide-react'
+
+interface UnlockScreenProps {
+  title: string
+  unlock: (passphrase: string) => Promise<void>
+  onUnlocked: () => void
+}
+
+// Shown instead of the app while the journal (encryption) or the app (app lock) is locked
+export function UnlockScreen({ title, unlock, onUnlocked }: UnlockScreenProps) {
+  const [passphrase, setPassphrase] = useState('')
+  const [error, setError] = useState<string | null>(null)
+  const [isUnlocking, setIsUnlocking] = useState(false)
+
+  const handleUnlock = async (e: React.FormEvent) => {
+    e.preventDefault()
+    setIsUnlocking(true)
+    setError(null)
+    try {
+      await unlock(passphrase)
+      onUnlocked()
+    } catch (err) {
+      setError(String(err))
+    } finally {
+      setIsUnlocking(false)
+    }
+  }
+
+  return (
+    <div className="h-screen flex items-center justify-center paper-texture bg-paper-bg p-6">
+      <form
+        onSubmit={handleUnlock}
+        className="max-w-sm w-full bg-white/80 border border-border/40 rounded-lg shadow-sm p-6 space-y-4"
+      >
+        <div className="flex items-center gap-2 text-ink-primary">
+          <Lock className="w-5 h-5" />
+          <h1 className="text-lg font-medium">{title}</h1>
+        </div>
+
+        <input
+          type="password"
+          autoFocus
+          value={passphrase}
+          onChange={(e) => setPassphrase(e.target.value)}
+          placeholder="Passphrase"
+          className="w-full px-3 py-2 text-sm border border-stone-200 rounded-lg focus:outline-none focus:ring-2 focus:ring-accent-blue/30"
+        />
+
+        {error && <p className="text-xs text-accent-red">{error}</p>}
+
+        <button
+          type="submit"
+          disabled={isUnlocking || !passphrase}
+          className="w-full px-3 py-2 bg-accent-blue text-white text-sm rounded-lg hover:bg-blue-600 disabled:opacity-50 disabled:cursor-not-allowed transition-colors"
+        >
+          {isUnlocking ? 'Unlocking...' : 'Unlock'}
+        </button>
+      </form>
+    </div>
+  )
+}
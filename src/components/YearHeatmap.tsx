@@ -0,0 +1,96 @@
+import { useEffect, useState } from 'react'
+import { ChevronLeft, ChevronRight } from 'lucide-react'
+import { getCalendarDays } from '../lib/api'
+import { useAppStore } from '../store/useAppStore'
+import type { CalendarDay } from '../types'
+
+// Cell colors from no writing to the most words
+const LEVEL_COLORS = ['#F5F5F4', '#D1FAE5', '#6EE7B7', '#10B981', '#047857']
+
+// Shade of a day relative to the year's busiest day
+function levelOf(day: CalendarDay, maxWords: number): number {
+  if (!day.has_entry) return 0
+  if (maxWords === 0) return 1
+  return Math.min(4, 1 + Math.floor((day.word_count / maxWords) * 4))
+}
+
+interface YearHeatmapProps {
+  onSelectDate?: (date: string) => void
+}
+
+export function YearHeatmap({ onSelectDate }: YearHeatmapProps) {
+  const today = useAppStore((state) => state.today)
+  const [year, setYear] = useState(() => Number(today.slice(0, 4)))
+  const [days, setDays] = useState<CalendarDay[]>([])
+
+  useEffect(() => {
+    getCalendarDays(`${year}-01-01`, `${year}-12-31`)
+      .then(setDays)
+      .catch((error) => console.error('Failed to load year heatmap:', error))
+  }, [year])
+
+  const maxWords = Math.max(0, ...days.map((d) => d.word_count))
+  const writtenDays = days.filter((d) => d.has_entry).length
+
+  // Columns are weeks starting on Sunday; pad the first week up to Jan 1
+  const leading = new Date(year, 0, 1).getDay()
+  const cells: (CalendarDay | null)[] = [...Array(leading).fill(null), ...days]
+  const weeks: (CalendarDay | null)[][] = []
+  for (let i = 0; i < cells.length; i += 7) {
+    weeks.push(cells.slice(i, i + 7))
+  }
+
+  return (
+    <div className="space-y-2">
+      <div className="flex items-center justify-between">
+        <h3 className="font-medium text-sm text-ink-primary">
+          {year}
+          <span className="ml-2 text-xs font-normal text-stone-500">
+            {writtenDays} {writtenDays === 1 ? 'day' : 'days'} written
+          </span>
+        </h3>
+        <div className="flex gap-1">
+          <button
+            onClick={() => setYear(year - 1)}
+            className="p-1 rounded hover:bg-stone-100 transition-colors"
+            title="Previous year"
+          >
+            <ChevronLeft className="w-3.5 h-3.5 text-stone-500" />
+          </button>
+          <button
+            onClick={() => setYear(year + 1)}
+            className="p-1 rounded hover:bg-stone-100 transition-colors"
+            title="Next year"
+          >
+            <ChevronRight className="w-3.5 h-3.5 text-stone-500" />
+          </button>
+        </div>
+      </div>
+      <div className="flex gap-[2px] overflow-x-auto">
+        {weeks.map((week, index) => (
+          <div key={index} className="flex flex-col gap-[2px]">
+            {week.map((day, dayIndex) =>
+              day ? (
+                <button
+                  key={day.date}
+                  onClick={() => onSelectDate?.(day.date)}
+                  className="w-[7px] h-[7px] rounded-[1px]"
+                  style={{ backgroundColor: LEVEL_COLORS[levelOf(day, maxWords)] }}
+                  title={
+                    day.has_entry
+                      ? `${day.date}: ${day.entry_count} ${
+                          day.entry_count === 1 ? 'entry' : 'entries'
+                        }, ${day.word_count} words${day.mood_emoji ? ` ${day.mood_emoji}` : ''}`
+                      : day.date
+                  }
+                />
+              ) : (
+                <div key={`pad-${dayIndex}`} className="w-[7px] h-[7px]" />
+              )
+            )}
+          </div>
+        ))}
+      </div>
+    </div>
+  )
+}
@@ -33,7 +33,7 @@ export function useAutosave() {
         state.setLastSaveError(null)
         state.setSaveStatus('saving')
         const contentJson = JSON.stringify(state.editorContent as ProseMirrorNode)
-        const entry = await upsertEntry(state.selectedDate, contentJson)
+        const entry = await upsertEntry(state.selectedDate, contentJson, state.currentEntry?.id)
         useAppStore.getState().setCurrentEntry(entry)
 
         const after = useAppStore.getState()
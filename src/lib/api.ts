@@ -1,13 +1,42 @@
 import { invoke } from '@tauri-apps/api/core'
 import type {
+  ArchiveFormat,
   DiaryEntry,
+  ExportSummary,
+  CalendarDay,
+  OnThisDayEntry,
   AIOperation,
   AISettings,
+  EntrySuggestion,
   TTSVoice,
   TTSSettings,
   TTSResponse,
   WritingStats,
+  WritingAnalytics,
   ImportOptions,
+  EntryRevision,
+  EntryRevisionSummary,
+  TrashSettings,
+  DaySettings,
+  Tag,
+  TagUsage,
+  Template,
+  TemplateSettings,
+  WritingPrompt,
+  PromptSettings,
+  AudioRecord,
+  AudioPlayback,
+  Attachment,
+  AttachmentContent,
+  TTSCacheInfo,
+  SearchResults,
+  SchemaInfo,
+  BackupInfo,
+  BackupSettings,
+  StartupError,
+  EncryptionStatus,
+  LockStatus,
+  LockSettings,
 } from '../types'
 
 // Initialize the database (kept for compatibility; backend initializes on startup)
@@ -15,27 +44,406 @@ export async function initDb(): Promise<void> {
   return invoke('init_db')
 }
 
-// Create or update a diary entry
-export async function upsertEntry(entryDate: string, contentJson: string): Promise<DiaryEntry> {
+// Get why the database failed to open at startup (null when it opened fine)
+export async function getStartupError(): Promise<StartupError | null> {
+  return invoke('get_startup_error')
+}
+
+// Get the database schema version and migration history (for diagnostics)
+export async function getSchemaVersion(): Promise<SchemaInfo> {
+  return invoke('get_schema_version')
+}
+
+// Save a diary entry (the first entry of the date unless entryId is given)
+export async function upsertEntry(
+  entryDate: string,
+  contentJson: string,
+  entryId?: string
+): Promise<DiaryEntry> {
   return invoke('upsert_entry', {
     entryDate,
     contentJson,
+    entryId: entryId ?? null,
   })
 }
 
-// Get a diary entry by date
+// Get the first entry of a date (prefer getDayEntries)
 export async function getEntry(entryDate: string): Promise<DiaryEntry | null> {
   return invoke('get_entry', { entryDate })
 }
 
+// Get every entry of a date, untimed first, then by time
+export async function getDayEntries(entryDate: string): Promise<DiaryEntry[]> {
+  return invoke('get_day_entries', { entryDate })
+}
+
+// Add another entry to a date
+export async function createEntry(
+  entryDate: string,
+  options: { title?: string; entryTime?: string; contentJson?: string } = {}
+): Promise<DiaryEntry> {
+  return invoke('create_entry', {
+    entryDate,
+    title: options.title ?? null,
+    entryTime: options.entryTime ?? null,
+    contentJson: options.contentJson ?? null,
+  })
+}
+
+// Change the title, summary and time (HH:MM) of an entry
+export async function updateEntryDetails(
+  entryId: string,
+  details: { title: string | null; summary: string | null; entryTime: string | null }
+): Promise<DiaryEntry> {
+  return invoke('update_entry_details', { entryId, ...details })
+}
+
 // List diary entries by month
 export async function listEntries(month: string): Promise<DiaryEntry[]> {
   return invoke('list_entries', { month })
 }
 
-// Delete a diary entry by date
-export async function deleteEntry(entryDate: string): Promise<boolean> {
-  return invoke('delete_entry', { entryDate })
+// Per-day totals for every day from startDate to endDate (inclusive), without content
+export async function getCalendarDays(startDate: string, endDate: string): Promise<CalendarDay[]> {
+  return invoke('get_calendar_days', { startDate, endDate })
+}
+
+// Entries from the same day in earlier years, plus a month and a week before by default
+export async function getOnThisDay(
+  date: string,
+  includeLookbacks?: boolean
+): Promise<OnThisDayEntry[]> {
+  return invoke('get_on_this_day', { date, includeLookbacks: includeLookbacks ?? null })
+}
+
+// Move a diary entry to the trash
+export async function deleteEntry(entryId: string): Promise<boolean> {
+  return invoke('delete_entry', { entryId })
+}
+
+// ===== Trash API =====
+
+// List entries in the trash (most recently deleted first)
+export async function listTrash(): Promise<DiaryEntry[]> {
+  return invoke('list_trash')
+}
+
+// Restore a trashed entry
+export async function restoreEntry(entryId: string): Promise<DiaryEntry> {
+  return invoke('restore_entry', { entryId })
+}
+
+// Permanently delete all trashed entries, returns the number purged
+export async function emptyTrash(): Promise<number> {
+  return invoke('empty_trash')
+}
+
+// Get trash settings
+export async function getTrashSettings(): Promise<TrashSettings> {
+  return invoke('get_trash_settings')
+}
+
+// Save trash settings
+export async function saveTrashSettings(settings: TrashSettings): Promise<void> {
+  return invoke('save_trash_settings', { settings })
+}
+
+// ===== Day Boundary API =====
+
+// Get when a diary day starts
+export async function getDaySettings(): Promise<DaySettings> {
+  return invoke('get_day_settings')
+}
+
+// Save when a diary day starts
+export async function saveDaySettings(settings: DaySettings): Promise<void> {
+  return invoke('save_day_settings', { settings })
+}
+
+// Today's diary date (YYYY-MM-DD) in local time, honouring the day start hour
+export async function getJournalToday(): Promise<string> {
+  return invoke('get_journal_today')
+}
+
+// ===== Backup API =====
+
+// List database backups, newest first
+export async function listBackups(): Promise<BackupInfo[]> {
+  return invoke('list_backups')
+}
+
+// Take a backup now
+export async function createBackup(): Promise<BackupInfo> {
+  return invoke('create_backup')
+}
+
+// Restore a backup; the current database is set aside and the app restarts
+export async function restoreBackup(fileName: string): Promise<void> {
+  return invoke('restore_backup', { fileName })
+}
+
+// Get backup retention settings
+export async function getBackupSettings(): Promise<BackupSettings> {
+  return invoke('get_backup_settings')
+}
+
+// Save backup retention settings
+export async function saveBackupSettings(settings: BackupSettings): Promise<void> {
+  return invoke('save_backup_settings', { settings })
+}
+
+// ===== Encryption API =====
+
+// Get whether encryption is on and the journal is unlocked
+export async function getEncryptionStatus(): Promise<EncryptionStatus> {
+  return invoke('get_encryption_status')
+}
+
+// Encrypt the journal with a passphrase
+export async function enableEncryption(passphrase: string): Promise<void> {
+  return invoke('enable_encryption', { passphrase })
+}
+
+// Unlock the journal with its passphrase
+export async function unlockJournal(passphrase: string): Promise<void> {
+  return invoke('unlock_journal', { passphrase })
+}
+
+// Lock the journal
+export async function lockJournal(): Promise<void> {
+  return invoke('lock_journal')
+}
+
+// Change the encryption passphrase
+export async function changePassphrase(current: string, newPassphrase: string): Promise<void> {
+  return invoke('change_passphrase', { current, new: newPassphrase })
+}
+
+// ===== App Lock API =====
+
+// Get whether the app lock is on and engaged
+export async function getLockStatus(): Promise<LockStatus> {
+  return invoke('get_lock_status')
+}
+
+// Report user activity so the idle timeout starts over (fails while locked)
+export async function recordActivity(): Promise<void> {
+  return invoke('record_activity')
+}
+
+// Unlock the app with its passphrase
+export async function unlockApp(passphrase: string): Promise<void> {
+  return invoke('unlock_app', { passphrase })
+}
+
+// Lock the app now
+export async function lockApp(): Promise<void> {
+  return invoke('lock_app')
+}
+
+// Turn the app lock on, or change its passphrase (current is required when already on)
+export async function setLockPassphrase(passphrase: string, current?: string): Promise<void> {
+  return invoke('set_lock_passphrase', { passphrase, current: current ?? null })
+}
+
+// Turn the app lock off
+export async function disableAppLock(passphrase: string): Promise<void> {
+  return invoke('disable_app_lock', { passphrase })
+}
+
+// Get app lock settings
+export async function getLockSettings(): Promise<LockSettings> {
+  return invoke('get_lock_settings')
+}
+
+// Save app lock settings
+export async function saveLockSettings(settings: LockSettings): Promise<void> {
+  return invoke('save_lock_settings', { settings })
+}
+
+// ===== Tags API =====
+
+// List all tags with usage counts
+export async function listTags(): Promise<TagUsage[]> {
+  return invoke('list_tags')
+}
+
+// List tags attached to an entry (the first entry of the date unless entryId is given)
+export async function listEntryTags(entryDate: string, entryId?: string): Promise<Tag[]> {
+  return invoke('list_entry_tags', { entryDate, entryId: entryId ?? null })
+}
+
+// Add a tag (created if needed) to an entry
+export async function addEntryTag(entryDate: string, name: string, entryId?: string): Promise<Tag> {
+  return invoke('add_entry_tag', { entryDate, name, entryId: entryId ?? null })
+}
+
+// Remove a tag from an entry
+export async function removeEntryTag(
+  entryDate: string,
+  tagId: string,
+  entryId?: string
+): Promise<boolean> {
+  return invoke('remove_entry_tag', { entryDate, tagId, entryId: entryId ?? null })
+}
+
+// Rename a tag
+export async function renameTag(tagId: string, name: string): Promise<Tag> {
+  return invoke('rename_tag', { tagId, name })
+}
+
+// Merge several tags into one
+export async function mergeTags(sourceTagIds: string[], targetTagId: string): Promise<Tag> {
+  return invoke('merge_tags', { sourceTagIds, targetTagId })
+}
+
+// Delete a tag from every entry
+export async function deleteTag(tagId: string): Promise<boolean> {
+  return invoke('delete_tag', { tagId })
+}
+
+// List entries carrying a tag
+export async function listEntriesByTag(tagId: string): Promise<DiaryEntry[]> {
+  return invoke('list_entries_by_tag', { tagId })
+}
+
+// ===== Templates API =====
+
+// List all templates
+export async function listTemplates(): Promise<Template[]> {
+  return invoke('list_templates')
+}
+
+// Create a template from a ProseMirror document
+export async function createTemplate(name: string, contentJson: string): Promise<Template> {
+  return invoke('create_template', { name, contentJson })
+}
+
+// Rename a template and replace its content
+export async function updateTemplate(
+  templateId: string,
+  name: string,
+  contentJson: string
+): Promise<Template> {
+  return invoke('update_template', { templateId, name, contentJson })
+}
+
+// Delete a template
+export async function deleteTemplate(templateId: string): Promise<boolean> {
+  return invoke('delete_template', { templateId })
+}
+
+// Get which template new days start from
+export async function getTemplateSettings(): Promise<TemplateSettings> {
+  return invoke('get_template_settings')
+}
+
+// Save which template new days start from
+export async function saveTemplateSettings(settings: TemplateSettings): Promise<void> {
+  return invoke('save_template_settings', { settings })
+}
+
+// Start a day from a template (the default one unless templateId is given).
+// {{prompt}} is the given prompt or the day's prompt.
+export async function createEntryFromTemplate(
+  entryDate: string,
+  templateId?: string,
+  promptId?: string
+): Promise<DiaryEntry> {
+  return invoke('create_entry_from_template', {
+    entryDate,
+    templateId: templateId ?? null,
+    promptId: promptId ?? null,
+  })
+}
+
+// ===== Writing Prompts API =====
+
+// List prompts, optionally only one CEFR level, one topic, or favorites
+export async function listPrompts(
+  options: { level?: string; topic?: string; favoritesOnly?: boolean } = {}
+): Promise<WritingPrompt[]> {
+  return invoke('list_prompts', {
+    level: options.level ?? null,
+    topic: options.topic ?? null,
+    favoritesOnly: options.favoritesOnly ?? null,
+  })
+}
+
+// Get which level the daily prompt is picked from
+export async function getPromptSettings(): Promise<PromptSettings> {
+  return invoke('get_prompt_settings')
+}
+
+// Save which level the daily prompt is picked from
+export async function savePromptSettings(settings: PromptSettings): Promise<void> {
+  return invoke('save_prompt_settings', { settings })
+}
+
+// The prompt for a diary day; the same day gives the same prompt
+export async function getDailyPrompt(date: string, level?: string): Promise<WritingPrompt> {
+  return invoke('get_daily_prompt', { date, level: level ?? null })
+}
+
+// Skip a day's prompt and get the next one
+export async function skipPrompt(
+  promptId: string,
+  date: string,
+  level?: string
+): Promise<WritingPrompt> {
+  return invoke('skip_prompt', { promptId, date, level: level ?? null })
+}
+
+// Mark or unmark a prompt as a favorite
+export async function setPromptFavorite(
+  promptId: string,
+  favorite: boolean
+): Promise<WritingPrompt> {
+  return invoke('set_prompt_favorite', { promptId, favorite })
+}
+
+// Add a prompt of the user's own
+export async function addCustomPrompt(
+  text: string,
+  level?: string,
+  topic?: string
+): Promise<WritingPrompt> {
+  return invoke('add_custom_prompt', { text, level: level ?? null, topic: topic ?? null })
+}
+
+// Delete a prompt of the user's own
+export async function deleteCustomPrompt(promptId: string): Promise<boolean> {
+  return invoke('delete_custom_prompt', { promptId })
+}
+
+// Record the prompt an entry answers, or clear it with null
+export async function setEntryPrompt(entryId: string, promptId: string | null): Promise<void> {
+  return invoke('set_entry_prompt', { entryId, promptId })
+}
+
+// The prompt an entry answers, if any
+export async function getEntryPrompt(entryId: string): Promise<WritingPrompt | null> {
+  return invoke('get_entry_prompt', { entryId })
+}
+
+// ===== Entry Revisions API =====
+
+// List saved revisions of an entry (newest first)
+export async function listEntryRevisions(
+  entryDate: string,
+  entryId?: string
+): Promise<EntryRevisionSummary[]> {
+  return invoke('list_entry_revisions', { entryDate, entryId: entryId ?? null })
+}
+
+// Get a single revision with its content
+export async function getEntryRevision(revisionId: string): Promise<EntryRevision | null> {
+  return invoke('get_entry_revision', { revisionId })
+}
+
+// Restore a revision as the current entry content
+export async function restoreEntryRevision(revisionId: string): Promise<DiaryEntry> {
+  return invoke('restore_entry_revision', { revisionId })
 }
 
 // AI Operations
@@ -44,15 +452,25 @@ export async function deleteEntry(entryDate: string): Promise<boolean> {
 export async function aiPolish(
   entryDate: string,
   text: string,
-  opType?: string
+  opType?: string,
+  entryId?: string
 ): Promise<AIOperation> {
   return invoke('ai_polish', {
     entryDate,
     text,
     ...(opType && { opType }),
+    ...(entryId && { entryId }),
   })
 }
 
+// Ask the AI to propose a title and one-sentence summary for an entry (not applied)
+export async function aiSuggestTitle(
+  entryDate: string,
+  entryId?: string
+): Promise<EntrySuggestion> {
+  return invoke('ai_suggest_title', { entryDate, entryId: entryId ?? null })
+}
+
 // Save AI settings (provider, model, api key)
 export async function saveAISettings(settings: {
   provider: string
@@ -72,10 +490,56 @@ export async function listAIOperations(entryId: string): Promise<AIOperation[]>
   return invoke('list_ai_operations', { entryId })
 }
 
+// ===== Attachments API =====
+
+// Attach a file from disk to an entry (the first entry of the date unless entryId is given)
+export async function addAttachment(
+  entryDate: string,
+  sourcePath: string,
+  entryId?: string
+): Promise<Attachment> {
+  return invoke('add_attachment', { entryDate, sourcePath, entryId: entryId ?? null })
+}
+
+// Attach pasted or dropped data (base64) to an entry
+export async function pasteAttachment(
+  entryDate: string,
+  fileName: string,
+  dataBase64: string,
+  entryId?: string
+): Promise<Attachment> {
+  return invoke('paste_attachment', { entryDate, fileName, dataBase64, entryId: entryId ?? null })
+}
+
+// List the attachments of an entry
+export async function listAttachments(entryDate: string, entryId?: string): Promise<Attachment[]> {
+  return invoke('list_attachments', { entryDate, entryId: entryId ?? null })
+}
+
+// Resolve an attachment, or its thumbnail, for display
+export async function resolveAttachment(
+  attachmentId: string,
+  thumbnail: boolean
+): Promise<AttachmentContent> {
+  return invoke('resolve_attachment', { attachmentId, thumbnail })
+}
+
+// Remove an attachment (its file is deleted once nothing else uses it)
+export async function removeAttachment(attachmentId: string): Promise<boolean> {
+  return invoke('remove_attachment', { attachmentId })
+}
+
+// Delete attachment files nothing refers to; returns the number removed
+export async function collectAttachmentGarbage(): Promise<number> {
+  return invoke('collect_attachment_garbage')
+}
+
 // ===== TTS Operations =====
 
-// Text to speech synthesis
+// Text to speech synthesis (audio is saved as a record of the entry)
 export async function textToSpeech(options: {
+  entryDate: string
+  entryId?: string
   text: string
   voice?: string
   language?: string
@@ -83,7 +547,9 @@ export async function textToSpeech(options: {
   provider?: string
 }): Promise<TTSResponse> {
   return invoke('text_to_speech', {
+    entryDate: options.entryDate,
     text: options.text,
+    ...(options.entryId !== undefined && { entryId: options.entryId }),
     ...(options.voice !== undefined && { voice: options.voice }),
     ...(options.language !== undefined && { language: options.language }),
     ...(options.speed !== undefined && { speed: options.speed }),
@@ -91,6 +557,39 @@ export async function textToSpeech(options: {
   })
 }
 
+// List saved audio clips of an entry (the first entry of the date unless entryId is given)
+export async function listAudioRecords(
+  entryDate: string,
+  entryId?: string
+): Promise<AudioRecord[]> {
+  return invoke('list_audio_records', { entryDate, entryId: entryId ?? null })
+}
+
+// Resolve a saved audio clip for playback
+export async function loadAudioRecord(recordId: string): Promise<AudioPlayback> {
+  return invoke('load_audio_record', { recordId })
+}
+
+// Delete a saved audio clip and its file
+export async function deleteAudioRecord(recordId: string): Promise<boolean> {
+  return invoke('delete_audio_record', { recordId })
+}
+
+// Get TTS cache usage
+export async function getTTSCacheInfo(): Promise<TTSCacheInfo> {
+  return invoke('get_tts_cache_info')
+}
+
+// Set the TTS cache size cap in bytes
+export async function setTTSCacheLimit(maxBytes: number): Promise<void> {
+  return invoke('set_tts_cache_limit', { maxBytes })
+}
+
+// Remove all cached TTS clips, returns the number of bytes freed
+export async function clearTTSCache(): Promise<number> {
+  return invoke('clear_tts_cache')
+}
+
 // List available TTS voices
 export async function listTTSVoices(provider?: string): Promise<TTSVoice[]> {
   return invoke('list_tts_voices', {
@@ -117,16 +616,18 @@ export async function getTTSSettings(provider?: string): Promise<TTSSettings | n
 
 // ===== Mood Tracking API =====
 
-// Update or create an entry with mood information
+// Set the mood of an entry (without entryId, the date's first entry, created if needed)
 export async function upsertEntryMood(
   entryDate: string,
   mood?: string,
-  moodEmoji?: string
+  moodEmoji?: string,
+  entryId?: string
 ): Promise<DiaryEntry> {
   return invoke('upsert_entry_mood', {
     entryDate,
     ...(mood !== undefined && { mood }),
     ...(moodEmoji !== undefined && { moodEmoji }),
+    ...(entryId !== undefined && { entryId }),
   })
 }
 
@@ -137,9 +638,16 @@ export async function listEntriesByMood(month: string, mood: string): Promise<Di
 
 // ===== Search API =====
 
-// Search entries by full-text query
-export async function searchEntries(query: string): Promise<DiaryEntry[]> {
-  return invoke('search_entries', { query })
+// Search entries (supports "phrases", -negation, prefix*, mood:, tag:, before:, after:, has:), one page at a time
+export async function searchEntries(
+  query: string,
+  options: { limit?: number; offset?: number } = {}
+): Promise<SearchResults> {
+  return invoke('search_entries', {
+    query,
+    ...(options.limit !== undefined && { limit: options.limit }),
+    ...(options.offset !== undefined && { offset: options.offset }),
+  })
 }
 
 // ===== Statistics API =====
@@ -149,6 +657,11 @@ export async function getWritingStats(): Promise<WritingStats> {
   return invoke('get_writing_stats')
 }
 
+// Get word counts, per-period totals, weekday and AI usage statistics
+export async function getWritingAnalytics(): Promise<WritingAnalytics> {
+  return invoke('get_writing_analytics')
+}
+
 // ===== Export/Import API =====
 
 // Export all user data as JSON string
@@ -156,6 +669,20 @@ export async function exportData(): Promise<string> {
   return invoke('export_data')
 }
 
+// Export the journal as Markdown files into a new folder or a zip file
+export async function exportMarkdown(
+  path: string,
+  format: ArchiveFormat,
+  includeMedia: boolean
+): Promise<ExportSummary> {
+  return invoke('export_markdown', { path, format, includeMedia })
+}
+
+// Export the journal as a static HTML site into a new folder
+export async function exportHtmlSite(path: string): Promise<ExportSummary> {
+  return invoke('export_html_site', { path })
+}
+
 // Import user data from JSON string
 export async function importData(jsonData: string, options: ImportOptions): Promise<number> {
   return invoke('import_data', { jsonData, options })
@@ -1,8 +1,11 @@
 import { create } from 'zustand'
 import { format } from 'date-fns'
-import type { DiaryEntry, SaveStatus } from '../types'
+import type { CalendarDay, DiaryEntry, SaveStatus } from '../types'
 
 interface AppState {
+  today: string // today's diary date, following the "day starts at" setting
+  setToday: (date: string) => void
+
   selectedDate: string
   setSelectedDate: (date: string) => void
   pendingSelectedDate: string | null
@@ -12,6 +15,9 @@ interface AppState {
   currentEntry: DiaryEntry | null
   setCurrentEntry: (entry: DiaryEntry | null) => void
 
+  dayEntries: DiaryEntry[] // every entry of selectedDate
+  setDayEntries: (entries: DiaryEntry[]) => void
+
   saveStatus: SaveStatus
   setSaveStatus: (status: SaveStatus) => void
   lastSaveError: string | null
@@ -31,11 +37,24 @@ interface AppState {
   currentMonth: string // yyyy-MM
   setCurrentMonth: (month: string) => void
 
-  monthEntries: DiaryEntry[]
-  setMonthEntries: (entries: DiaryEntry[]) => void
+  monthDays: CalendarDay[] // every day of currentMonth
+  setMonthDays: (days: CalendarDay[]) => void
 }
 
 export const useAppStore = create<AppState>((set) => ({
+  today: format(new Date(), 'yyyy-MM-dd'),
+  setToday: (date) =>
+    set((state) => {
+      if (date === state.today) return state
+      // Follow the day change if the user was looking at today
+      if (state.selectedDate !== state.today) return { today: date }
+      return {
+        today: date,
+        pendingSelectedDate: date,
+        currentMonth: date.slice(0, 7),
+      }
+    }),
+
   selectedDate: format(new Date(), 'yyyy-MM-dd'),
   setSelectedDate: (date) => set({ selectedDate: date }),
   pendingSelectedDate: null,
@@ -43,7 +62,21 @@ export const useAppStore = create<AppState>((set) => ({
   clearPendingSelectDate: () => set({ pendingSelectedDate: null }),
 
   currentEntry: null,
-  setCurrentEntry: (entry) => set({ currentEntry: entry }),
+  setCurrentEntry: (entry) =>
+    set((state) => {
+      if (!entry || entry.entry_date !== state.selectedDate) return { currentEntry: entry }
+      // Keep the day's list in step with saves to the current entry
+      const known = state.dayEntries.some((e) => e.id === entry.id)
+      return {
+        currentEntry: entry,
+        dayEntries: known
+          ? state.dayEntries.map((e) => (e.id === entry.id ? entry : e))
+          : [...state.dayEntries, entry],
+      }
+    }),
+
+  dayEntries: [],
+  setDayEntries: (entries) => set({ dayEntries: entries }),
 
   saveStatus: 'idle',
   setSaveStatus: (status) => set({ saveStatus: status }),
@@ -73,6 +106,6 @@ export const useAppStore = create<AppState>((set) => ({
   currentMonth: format(new Date(), 'yyyy-MM'),
   setCurrentMonth: (month) => set({ currentMonth: month }),
 
-  monthEntries: [],
-  setMonthEntries: (entries) => set({ monthEntries: entries }),
+  monthDays: [],
+  setMonthDays: (days) => set({ monthDays: days }),
 }))
@@ -2,11 +2,130 @@
 export interface DiaryEntry {
   id: string
   entry_date: string // YYYY-MM-DD
+  entry_time?: string // HH:MM, orders entries within a day
+  title?: string
+  summary?: string // one-sentence summary shown in lists
   content_json: string // ProseMirror JSON serialized string
   mood?: string // Mood category: amazing, happy, neutral, sad, awful
   mood_emoji?: string // Emoji representation: 😄, 😊, 😐, 😢, 😭
   created_at: number // unix timestamp ms
   updated_at: number // unix timestamp ms
+  deleted_at?: number // set while the entry is in the trash
+}
+
+// When a diary day begins
+export interface DaySettings {
+  day_start_hour: number // 0-23; writing before this hour counts towards the previous day
+}
+
+// Which template new days start from
+export interface TemplateSettings {
+  default_template_id?: string
+}
+
+// Which prompts the daily prompt is picked from
+export interface PromptSettings {
+  level?: string // CEFR level; any level when unset
+}
+
+// Trash settings
+export interface TrashSettings {
+  retention_days: number // days before trashed entries are purged
+}
+
+// Database backup snapshot
+export interface BackupInfo {
+  file_name: string
+  created_at: number
+  size_bytes: number
+  kind: 'snapshot' | 'pre_restore' // pre_restore = database set aside by a restore
+}
+
+// Backup retention settings
+export interface BackupSettings {
+  keep_daily: number // most recent days that keep their newest snapshot
+  keep_weekly: number // most recent ISO weeks that keep their newest snapshot
+  keep_pre_restore: number // most recent pre-restore copies kept
+}
+
+// Encryption at rest state
+export interface EncryptionStatus {
+  enabled: boolean
+  unlocked: boolean
+}
+
+// App lock state
+export interface LockStatus {
+  enabled: boolean
+  locked: boolean
+  idle_minutes: number // 0 = never lock automatically
+}
+
+// App lock settings
+export interface LockSettings {
+  idle_minutes: number // 0 = never lock automatically
+}
+
+// Tag
+export interface Tag {
+  id: string
+  name: string
+  created_at: number
+}
+
+// A reusable starting point for entries
+export interface Template {
+  id: string
+  name: string
+  content_json: string // ProseMirror JSON; text may contain {{date}}, {{weekday}}, ...
+  created_at: number
+  updated_at: number
+}
+
+// CEFR levels of writing prompts, easiest first
+export const PROMPT_LEVELS = ['A1', 'A2', 'B1', 'B2', 'C1', 'C2'] as const
+
+// A writing prompt, bundled or written by the user
+export interface WritingPrompt {
+  id: string
+  text: string
+  level?: string // CEFR level
+  topic?: string
+  is_custom: boolean
+  is_favorite: boolean
+  last_answered_on?: string // YYYY-MM-DD
+}
+
+// Tag with the number of entries using it
+export interface TagUsage extends Tag {
+  entry_count: number
+}
+
+// Link between an entry and a tag
+export interface EntryTag {
+  entry_id: string
+  tag_id: string
+  created_at: number
+}
+
+// Snapshot of an entry's previous content
+export interface EntryRevision {
+  id: string
+  entry_id: string
+  entry_date: string // YYYY-MM-DD
+  content_json: string
+  saved_at: number // when this content was saved, unix timestamp ms
+  created_at: number // when the snapshot was taken, unix timestamp ms
+}
+
+// Revision metadata (without content) for listing
+export interface EntryRevisionSummary {
+  id: string
+  entry_id: string
+  entry_date: string
+  word_count: number
+  saved_at: number
+  created_at: number
 }
 
 // ProseMirror JSON type
@@ -38,6 +157,13 @@ export interface AIOperation {
   created_at: number
 }
 
+// Title and summary proposed by the AI for an entry
+export interface EntrySuggestion {
+  title: string
+  summary: string
+  operation: AIOperation // recorded with op_type "title_summary"
+}
+
 // AI Settings (without actual API key for security)
 export interface AISettings {
   provider: string
@@ -46,7 +172,7 @@ export interface AISettings {
 }
 
 // AI operation types
-export type AIOpType = 'polish' | 'expand' | 'fix_grammar'
+export type AIOpType = 'polish' | 'expand' | 'fix_grammar' | 'title_summary'
 
 // ===== TTS Types =====
 
@@ -72,6 +198,69 @@ export interface TTSResponse {
   provider: string
   model: string
   voice: string
+  record_id?: string // Saved audio record
+  cached: boolean // Served without calling the provider
+}
+
+// TTS cache usage
+export interface TTSCacheInfo {
+  entry_count: number
+  total_bytes: number
+  max_bytes: number
+}
+
+// Saved TTS audio clip
+export interface AudioRecord {
+  id: string
+  entry_id: string
+  text: string
+  audio_relpath: string // relative to the app data dir
+  format: string
+  provider: string
+  model: string
+  voice?: string
+  speed?: number
+  duration_ms?: number
+  size_bytes: number
+  created_at: number
+}
+
+// Audio ready for playback
+export interface AudioPlayback {
+  record_id: string
+  audio_file: string // absolute path
+  audio_base64?: string // only for small files
+  format: string
+}
+
+// ===== Attachment Types =====
+
+// A file attached to an entry
+export interface Attachment {
+  id: string
+  entry_id: string
+  sha256: string // content hash; identical files are stored once
+  file_name: string
+  mime_type: string
+  size_bytes: number
+  width?: number // images only
+  height?: number
+  has_thumbnail: boolean
+  created_at: number
+}
+
+// Attachment content ready for display
+export interface AttachmentContent {
+  attachment_id: string
+  mime_type: string
+  file_path?: string // absolute path, only for large unencrypted files
+  data_base64?: string
+}
+
+// Contents of an attached file in an export
+export interface AttachmentFile {
+  sha256: string
+  data_base64: string
 }
 
 // TTS Settings
@@ -104,15 +293,113 @@ export const MOOD_OPTIONS: MoodOption[] = [
   { type: 'awful', emoji: '😭', label: 'Awful' },
 ]
 
+// ===== Search Types =====
+
+// A piece of a search snippet; highlight marks text matching the query
+export interface SnippetSegment {
+  text: string
+  highlight: boolean
+}
+
+// A single full-text search match
+export interface SearchHit {
+  entry_id: string
+  entry_date: string // YYYY-MM-DD
+  title?: string
+  mood?: string
+  mood_emoji?: string
+  snippet: SnippetSegment[]
+  score: number // higher is more relevant
+}
+
+// One page of search hits
+export interface SearchResults {
+  hits: SearchHit[]
+  total: number // total hits across all pages
+  limit: number
+  offset: number
+}
+
 // ===== Statistics Types =====
 
 // Writing statistics
 export interface WritingStats {
   total_entries: number
+  total_days: number // days with at least one entry
   current_streak: number
   longest_streak: number
 }
 
+// Totals of one day for calendars and heatmaps, without entry content
+export interface CalendarDay {
+  date: string // YYYY-MM-DD
+  has_entry: boolean
+  entry_count: number
+  word_count: number
+  mood?: string // mood of the day's first entry with one
+  mood_emoji?: string
+  has_audio: boolean
+  ai_ops_count: number
+}
+
+// An earlier entry resurfaced for a date
+export interface OnThisDayEntry {
+  entry_id: string
+  entry_date: string // YYYY-MM-DD
+  entry_time?: string
+  title?: string
+  preview: string // start of the plain text
+  mood?: string
+  mood_emoji?: string
+  kind: 'year' | 'month' | 'week'
+  years_ago: number // 0 for the month and week lookbacks
+}
+
+// Word and character counts of one entry
+export interface EntryTextStats {
+  entry_id: string
+  entry_date: string
+  title?: string
+  words: number
+  characters: number // excluding whitespace
+}
+
+// Writing totals for a week ("2026-W10"), month ("2026-03") or year ("2026")
+export interface PeriodStats {
+  period: string
+  entries: number
+  days: number // days with at least one entry
+  words: number
+  characters: number
+  average_words: number // per entry
+}
+
+// Writing totals for a day of the week
+export interface WeekdayStats {
+  weekday: number // 0 = Monday ... 6 = Sunday
+  entries: number
+  words: number
+}
+
+// How often an AI operation type was used
+export interface AIUsage {
+  op_type: string
+  count: number
+}
+
+// Detailed writing statistics, counted from the entries' plain text
+export interface WritingAnalytics {
+  total_words: number
+  total_characters: number
+  average_words: number // per entry
+  entries: EntryTextStats[] // oldest first
+  weeks: PeriodStats[] // oldest first, periods without entries left out
+  months: PeriodStats[]
+  years: PeriodStats[]
+  weekdays: WeekdayStats[] // all seven days, Monday first
+  ai_usage: AIUsage[] // most used first
+}
+
 // ===== Export/Import Types =====
 
 // Export data structure containing all user data
@@ -121,10 +408,51 @@ export interface ExportData {
   exported_at: number
   entries: DiaryEntry[]
   ai_operations: AIOperation[]
+  tags?: Tag[]
+  entry_tags?: EntryTag[]
+  attachments?: Attachment[]
+  attachment_files?: AttachmentFile[]
 }
 
 // Import options
+// How a readable export is written: a new folder or a single .zip file
+export type ArchiveFormat = 'directory' | 'zip'
+
+// What a readable export wrote
+export interface ExportSummary {
+  path: string
+  entries: number
+  media_files: number
+  skipped_media: number
+}
+
 export interface ImportOptions {
   overwrite: boolean
   include_ai_operations: boolean
 }
+
+// ===== Diagnostics Types =====
+
+// Why the database could not be opened at startup
+export interface StartupError {
+  kind: 'database_unavailable' | 'migration_failed'
+  message: string
+  schema_version: number | null // schema version before migrating
+  rolled_back: boolean // restored from the pre-migration snapshot
+  snapshot_path: string | null
+}
+
+// A row of the schema_migrations table
+export interface AppliedMigration {
+  version: number
+  description: string | null
+  applied_at: number
+  checksum: string | null
+}
+
+// Database schema version and migration history
+export interface SchemaInfo {
+  current_version: number
+  latest_version: number
+  applied: AppliedMigration[]
+}